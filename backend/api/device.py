@@ -5,11 +5,15 @@ Handles device discovery, connection management, and emergency recovery.
 
 import asyncio
 import ipaddress
+import json
 import logging
+import re
 import socket
 from datetime import datetime
 
-from fastapi import APIRouter, HTTPException
+from api.api_keys import require_permission
+from db import get_db
+from fastapi import APIRouter, Depends, HTTPException, Query
 from pydantic import BaseModel
 
 logger = logging.getLogger(__name__)
@@ -60,6 +64,30 @@ class ConnectionStatus(BaseModel):
     reconnect_attempts: int = 0
 
 
+class LoggingConfig(BaseModel):
+    """Firmware log settings.
+
+    target: "udp://<ip>:<port>" (plain lines), "syslog://<ip>[:<port>]" (RFC 5424),
+    or None to log to serial only.
+    levels: per-module level spec, e.g. "info,scale_manager=debug".
+    """
+
+    target: str | None = None
+    levels: str = "info"
+
+
+class CrashReport(BaseModel):
+    """Report of an unexpected device reset (panic, watchdog, brownout)."""
+
+    reason: str
+    reason_code: int | None = None
+    firmware_version: str | None = None
+    panic: str | None = None
+    backtrace: str = ""
+    log: list[str] = []
+    received_at: str | None = None
+
+
 class DiscoveryResult(BaseModel):
     """Result of device discovery."""
 
@@ -224,6 +252,18 @@ async def update_device():
     return {"success": True, "message": "Update command queued"}
 
 
+@router.post("/bridge/update")
+async def update_bridge():
+    """Send NFC bridge firmware update command to connected device."""
+    from main import is_display_connected, queue_display_command
+
+    if not is_display_connected():
+        raise HTTPException(status_code=400, detail="No device connected")
+
+    queue_display_command("bridge_update")
+    return {"success": True, "message": "Bridge update command queued"}
+
+
 @router.post("/factory-reset")
 async def factory_reset_device():
     """Send factory reset command to connected device.
@@ -237,24 +277,235 @@ async def factory_reset_device():
     return {"success": True, "message": "Factory reset command sent"}
 
 
+LOG_LEVELS = {"off", "error", "warn", "info", "debug", "trace"}
+_LOG_MODULE_RE = re.compile(r"^[A-Za-z_][A-Za-z0-9_]*(::[A-Za-z_][A-Za-z0-9_]*)*$")
+
+
+def _valid_log_target(target: str) -> bool:
+    """Check a log target the firmware can parse (IP address, no DNS)."""
+    scheme, sep, addr = target.partition("://")
+    if not sep:
+        scheme, addr = "udp", target
+    if scheme not in ("udp", "syslog"):
+        return False
+    host, _, port = addr.rpartition(":")
+    if not host:
+        if scheme != "syslog":
+            return False
+        host, port = addr, "514"
+    try:
+        ipaddress.ip_address(host.strip("[]"))
+    except ValueError:
+        return False
+    return port.isdigit() and 0 < int(port) < 65536
+
+
+def _valid_log_levels(levels: str) -> bool:
+    """Check an env_logger style level spec ("info,scale_manager=debug")."""
+    for part in filter(None, (p.strip() for p in levels.split(","))):
+        module, sep, level = part.rpartition("=")
+        if sep and not _LOG_MODULE_RE.match(module.strip()):
+            return False
+        if level.strip().lower() not in LOG_LEVELS:
+            return False
+    return True
+
+
+@router.get("/logging", response_model=LoggingConfig)
+async def get_logging_config():
+    """Get firmware log settings (fetched by the display)."""
+    db = await get_db()
+    target = await db.get_setting("device_log_target")
+    levels = await db.get_setting("device_log_levels")
+    return LoggingConfig(target=target or None, levels=levels or "info")
+
+
+@router.put("/logging", response_model=LoggingConfig)
+async def set_logging_config(config: LoggingConfig):
+    """Save firmware log settings and tell a connected display to reload them."""
+    from main import is_display_connected, queue_display_command
+
+    target = (config.target or "").strip()
+    levels = config.levels.strip() or "info"
+    if target and not _valid_log_target(target):
+        raise HTTPException(status_code=400, detail=f"Invalid log target: {target}")
+    if not _valid_log_levels(levels):
+        raise HTTPException(status_code=400, detail=f"Invalid log levels: {levels}")
+
+    db = await get_db()
+    await db.set_setting("device_log_target", target)
+    await db.set_setting("device_log_levels", levels)
+
+    if is_display_connected():
+        queue_display_command("log_config")
+    return LoggingConfig(target=target or None, levels=levels)
+
+
+MAX_CRASH_REPORTS = 10
+
+
+async def _load_crash_reports(db) -> list[CrashReport]:
+    raw = await db.get_setting("device_crash_reports")
+    if not raw:
+        return []
+    try:
+        return [CrashReport(**r) for r in json.loads(raw)]
+    except (ValueError, TypeError):
+        logger.warning("Discarding unreadable crash reports")
+        return []
+
+
+@router.post("/crash-report")
+async def report_crash(report: CrashReport):
+    """Store the report of an unexpected reset (sent once by the display after reboot)."""
+    report.received_at = datetime.now().isoformat()
+    logger.warning(f"Display reset unexpectedly: {report.reason}" + (f" - {report.panic}" if report.panic else ""))
+
+    db = await get_db()
+    reports = [report] + await _load_crash_reports(db)
+    reports = reports[:MAX_CRASH_REPORTS]
+    await db.set_setting("device_crash_reports", json.dumps([r.model_dump() for r in reports]))
+    return {"success": True}
+
+
+@router.get("/crash-reports", response_model=list[CrashReport])
+async def get_crash_reports():
+    """Get the latest crash reports, newest first."""
+    db = await get_db()
+    return await _load_crash_reports(db)
+
+
+class SettingsBackup(BaseModel):
+    """Latest settings document uploaded by the display.
+
+    settings: the display's configuration as accepted by PUT /settings, without
+    WiFi passwords and the API key (the display keeps its own on import).
+    """
+
+    settings: dict
+    received_at: str
+
+
+def _strip_secrets(settings: dict) -> dict:
+    """Drop WiFi passwords and the API key, which a backup never holds."""
+    settings = json.loads(json.dumps(settings))
+    backend = settings.get("backend")
+    if isinstance(backend, dict):
+        backend.pop("api_key", None)
+    wifi = settings.get("wifi")
+    networks = wifi.get("networks") if isinstance(wifi, dict) else None
+    for network in networks if isinstance(networks, list) else []:
+        if isinstance(network, dict):
+            network.pop("password", None)
+    return settings
+
+
+async def _load_settings_json(db, key: str) -> dict | None:
+    raw = await db.get_setting(key)
+    if not raw:
+        return None
+    try:
+        return json.loads(raw)
+    except ValueError:
+        logger.warning(f"Discarding unreadable {key}")
+        return None
+
+
+@router.post("/settings", dependencies=[Depends(require_permission("write"))])
+async def backup_settings(settings: dict):
+    """Store the display's settings (uploaded by the display after each change).
+
+    Requires the display's API key, so nobody else can plant a backup that
+    points the display at another server when it is restored.
+    """
+    if not isinstance(settings.get("version"), int):
+        raise HTTPException(status_code=400, detail="Settings document without a version")
+
+    db = await get_db()
+    backup = SettingsBackup(settings=_strip_secrets(settings), received_at=datetime.now().isoformat())
+    await db.set_setting("device_settings_backup", backup.model_dump_json())
+    return {"success": True}
+
+
+@router.get("/settings", response_model=SettingsBackup, dependencies=[Depends(require_permission("read"))])
+async def get_settings_backup():
+    """Get the latest settings backup of the display."""
+    db = await get_db()
+    backup = await _load_settings_json(db, "device_settings_backup")
+    if not backup:
+        raise HTTPException(status_code=404, detail="No settings backup yet")
+    return SettingsBackup(**backup)
+
+
+@router.put("/settings", dependencies=[Depends(require_permission("write"))])
+async def import_settings(settings: dict):
+    """Queue a settings document for the display (restore a backup or clone a display).
+
+    Top-level sections left out of the document keep the display's current values,
+    e.g. omit "scales" to keep its calibrations. Networks without a password keep
+    the one the display saved for that SSID, and a missing API key keeps the current one.
+    """
+    from main import is_display_connected, queue_display_command
+
+    if not isinstance(settings.get("version"), int):
+        raise HTTPException(status_code=400, detail="Settings document without a version")
+
+    db = await get_db()
+    await db.set_setting("device_settings_pending", json.dumps(settings))
+
+    # Delivered with the next heartbeat, also if the display is offline right now
+    queue_display_command("settings_import")
+    if not is_display_connected():
+        return {"success": True, "message": "Settings import queued, waiting for the display"}
+    return {"success": True, "message": "Settings import queued"}
+
+
+@router.get("/settings/import", dependencies=[Depends(require_permission("read"))])
+async def get_pending_settings():
+    """Get the settings document queued for import (fetched once by the display).
+
+    It may carry WiFi passwords and an API key, so the display fetches it with its API key.
+    """
+    db = await get_db()
+    settings = await _load_settings_json(db, "device_settings_pending")
+    if not settings:
+        raise HTTPException(status_code=404, detail="No settings import queued")
+    await db.set_setting("device_settings_pending", "")
+    return settings
+
+
+# Stable scale ids reported by the device: "direct", "hx711"/"nau7802" or "m<addr>_<channel>" behind a mux
+SCALE_ID_PATTERN = r"^(direct|hx711|nau7802|m[0-9a-f]{2}_[0-7])$"
+
+
+def _scale_command(command: str, scale: str | None) -> str:
+    """Append the scale id to a scale command (omitted for the primary scale)."""
+    return command if scale is None else f"{command}:{scale}"
+
+
 @router.post("/scale/tare")
-async def scale_tare():
-    """Send tare (zero) command to scale."""
+async def scale_tare(scale: str | None = Query(default=None, pattern=SCALE_ID_PATTERN)):
+    """Send tare (zero) command to scale.
+
+    Args:
+        scale: Stable scale id for multi-scale devices (omitted = primary)
+    """
     from main import is_display_connected, queue_display_command
 
     if not is_display_connected():
         raise HTTPException(status_code=400, detail="No device connected")
 
-    queue_display_command("scale_tare")
+    queue_display_command(_scale_command("scale_tare", scale))
     return {"success": True, "message": "Tare command queued"}
 
 
 @router.post("/scale/calibrate")
-async def scale_calibrate(known_weight: float):
+async def scale_calibrate(known_weight: float, scale: str | None = Query(default=None, pattern=SCALE_ID_PATTERN)):
     """Send calibration command to scale with known weight.
 
     Args:
         known_weight: The known weight in grams placed on the scale
+        scale: Stable scale id for multi-scale devices (omitted = primary)
     """
     from main import is_display_connected, queue_display_command
 
@@ -262,19 +513,23 @@ async def scale_calibrate(known_weight: float):
         raise HTTPException(status_code=400, detail="No device connected")
 
     # Queue calibrate command with weight parameter
-    queue_display_command(f"scale_calibrate:{known_weight:.1f}")
+    queue_display_command(_scale_command(f"scale_calibrate:{known_weight:.1f}", scale))
     return {"success": True, "message": f"Calibrate command queued (known weight: {known_weight}g)"}
 
 
 @router.post("/scale/reset")
-async def scale_reset():
-    """Reset scale calibration to defaults."""
+async def scale_reset(scale: str | None = Query(default=None, pattern=SCALE_ID_PATTERN)):
+    """Reset scale calibration to defaults.
+
+    Args:
+        scale: Stable scale id for multi-scale devices (omitted = primary)
+    """
     from main import is_display_connected, queue_display_command
 
     if not is_display_connected():
         raise HTTPException(status_code=400, detail="No device connected")
 
-    queue_display_command("scale_reset")
+    queue_display_command(_scale_command("scale_reset", scale))
     return {"success": True, "message": "Scale calibration reset command queued"}
 
 
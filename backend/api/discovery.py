@@ -23,6 +23,10 @@ class DiscoveredPrinter(BaseModel):
     name: str | None = None
     ip_address: str
     model: str | None = None
+    model_code: str | None = None
+    connect_mode: str | None = None  # "lan", "cloud" or "unknown" (reported by the display)
+    bind_state: str | None = None  # "free", "bound" or "unknown"
+    firmware_version: str | None = None
 
 
 class DiscoveryStatus(BaseModel):
@@ -54,6 +58,8 @@ class DiscoveryState:
     running: bool = False
     printers: dict = field(default_factory=dict)  # serial -> DiscoveredPrinter
     task: asyncio.Task | None = None
+    # Printers the display currently hears on its network (replaced on each report)
+    device_printers: dict = field(default_factory=dict)  # serial -> DiscoveredPrinter
 
 
 _state = DiscoveryState()
@@ -312,5 +318,19 @@ async def stop_discovery():
 
 @router.get("/printers", response_model=list[DiscoveredPrinter])
 async def get_discovered_printers():
-    """Get list of discovered printers."""
-    return list(_state.printers.values())
+    """Get list of discovered printers.
+
+    Includes printers reported by the display, which listens continuously
+    and knows each printer's LAN/cloud mode.
+    """
+    printers = dict(_state.printers)
+    printers.update(_state.device_printers)
+    return list(printers.values())
+
+
+@router.post("/device-report")
+async def report_device_printers(printers: list[DiscoveredPrinter]):
+    """Receive the printers currently seen by the display's discovery service."""
+    _state.device_printers = {p.serial: p for p in printers}
+    logger.debug(f"Display reports {len(printers)} printer(s) on its network")
+    return {"count": len(printers)}
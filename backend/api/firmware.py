@@ -9,6 +9,7 @@ import logging
 import re
 import struct
 from datetime import datetime, timedelta
+from pathlib import Path
 
 import httpx
 from config import GITHUB_REPO, settings
@@ -42,6 +43,12 @@ class FirmwareCheck(BaseModel):
     update_available: bool = False
     download_url: str | None = None
     release_notes: str | None = None
+    # Size and SHA-256 of the image served by /ota (local releases only)
+    size: int | None = None
+    sha256: str | None = None
+    # Ed25519 signature over tag \0 version \0 SHA-256 (hex, from <image>.bin.sig,
+    # see firmware/tools/sign_firmware.py)
+    signature: str | None = None
     error: str | None = None
 
 
@@ -69,6 +76,19 @@ def _get_local_firmware() -> list[FirmwareVersion]:
     return firmware_files
 
 
+def _signature_path(filepath: Path) -> Path:
+    """Detached signature file for a firmware image."""
+    return filepath.with_name(filepath.name + ".sig")
+
+
+def _read_signature(filepath: Path) -> str | None:
+    """Read the hex signature for a firmware image, if it was signed."""
+    sig_path = _signature_path(filepath)
+    if not sig_path.exists():
+        return None
+    return sig_path.read_text().strip() or None
+
+
 def _parse_version(v: str):
     """Parse version string supporting semver and PEP 440 pre-releases.
 
@@ -129,8 +149,33 @@ async def get_latest_firmware():
     return firmware_list[0]
 
 
+# Update channels, from most to least conservative
+UPDATE_CHANNELS = ("stable", "beta", "dev")
+
+
+def _version_channel(version: str) -> str:
+    """Channel a version belongs to: releases are stable, betas and release
+    candidates are beta, alphas and anything unparseable are dev."""
+    parsed = _parse_version(version)
+    if parsed is None:
+        return "dev"
+    pre_order = parsed[3]
+    if pre_order == 3:
+        return "stable"
+    if pre_order >= 1:
+        return "beta"
+    return "dev"
+
+
+def _channel_allows(channel: str | None, version: str) -> bool:
+    """Whether a device on `channel` may be offered `version` (no channel = all)."""
+    if channel is None:
+        return True
+    return UPDATE_CHANNELS.index(_version_channel(version)) <= UPDATE_CHANNELS.index(channel)
+
+
 @router.get("/check", response_model=FirmwareCheck)
-async def check_firmware_update(current_version: str | None = None):
+async def check_firmware_update(current_version: str | None = None, channel: str | None = None):
     """
     Check for firmware updates.
 
@@ -138,9 +183,14 @@ async def check_firmware_update(current_version: str | None = None):
 
     Args:
         current_version: The device's current firmware version (if not provided, uses last known device version)
+        channel: Update channel (stable, beta or dev); only versions of that channel or a more
+            conservative one are offered. All versions if omitted.
     """
     global _firmware_cache, _firmware_cache_time
 
+    if channel is not None and channel not in UPDATE_CHANNELS:
+        raise HTTPException(status_code=400, detail=f"Unknown channel: {channel}")
+
     # Use device's reported version if not explicitly provided
     if not current_version:
         from main import get_display_firmware_version
@@ -155,7 +205,7 @@ async def check_firmware_update(current_version: str | None = None):
     best_notes: str | None = None
 
     # Check local firmware
-    local_firmware = _get_local_firmware()
+    local_firmware = [fw for fw in _get_local_firmware() if _channel_allows(channel, fw.version)]
     if local_firmware:
         latest_local = local_firmware[0]
         best_version = latest_local.version
@@ -195,7 +245,7 @@ async def check_firmware_update(current_version: str | None = None):
             # Don't set error if we have local firmware
 
     # Compare local vs GitHub and use the newest
-    if _firmware_cache:
+    if _firmware_cache and _channel_allows(channel, _firmware_cache["version"]):
         github_version = _firmware_cache["version"]
 
         if best_version is None:
@@ -218,6 +268,13 @@ async def check_firmware_update(current_version: str | None = None):
         result.download_url = best_url
         result.release_notes = best_notes
 
+        # The device streams local releases from /ota and verifies them
+        if local_firmware and best_version == local_firmware[0].version:
+            filepath = FIRMWARE_DIR / local_firmware[0].filename
+            result.size = filepath.stat().st_size
+            result.sha256 = hashlib.sha256(filepath.read_bytes()).hexdigest()
+            result.signature = _read_signature(filepath)
+
         if current_version:
             result.update_available = _compare_versions(current_version, best_version)
         else:
@@ -287,13 +344,97 @@ async def get_ota_firmware(version: str | None = None):
     if not filepath.exists():
         raise HTTPException(status_code=404, detail="Firmware file not found")
 
-    # Return binary with ESP32 OTA-compatible headers
+    # Return binary with ESP32 OTA-compatible headers. FileResponse answers
+    # Range requests, which the device uses to resume interrupted downloads.
     return FileResponse(
         filepath,
         media_type="application/octet-stream",
         filename=firmware.filename,
         headers={
-            "Content-Length": str(filepath.stat().st_size),
+            "X-Firmware-Version": firmware.version,
+        },
+    )
+
+
+# =============================================================================
+# Pico NFC bridge firmware
+# =============================================================================
+
+# Bridge images (nfc-bridge-<version>.bin plus optional .sig), pushed to the
+# bridge over I2C by the display
+BRIDGE_FIRMWARE_DIR = FIRMWARE_DIR / "bridge"
+
+
+def _get_local_bridge_firmware() -> list[FirmwareVersion]:
+    """Get list of locally available NFC bridge firmware files."""
+    if not BRIDGE_FIRMWARE_DIR.exists():
+        return []
+
+    firmware_files = [
+        FirmwareVersion(
+            version=f.stem.removeprefix("nfc-bridge-"),
+            filename=f.name,
+            size=f.stat().st_size,
+        )
+        for f in BRIDGE_FIRMWARE_DIR.glob("nfc-bridge-*.bin")
+    ]
+    firmware_files.sort(key=lambda x: _parse_version(x.version) or (0, 0, 0, 0, 0), reverse=True)
+    return firmware_files
+
+
+@router.get("/bridge/check", response_model=FirmwareCheck)
+async def check_bridge_firmware_update(current_version: str | None = None, channel: str | None = None):
+    """
+    Check for NFC bridge firmware updates (local releases only).
+
+    Args:
+        current_version: Firmware version reported by the bridge
+        channel: Update channel, same rules as /check
+    """
+    if channel is not None and channel not in UPDATE_CHANNELS:
+        raise HTTPException(status_code=400, detail=f"Unknown channel: {channel}")
+
+    result = FirmwareCheck(current_version=current_version)
+    firmware_list = [fw for fw in _get_local_bridge_firmware() if _channel_allows(channel, fw.version)]
+    if not firmware_list:
+        result.error = "No bridge firmware available"
+        return result
+
+    latest = firmware_list[0]
+    filepath = BRIDGE_FIRMWARE_DIR / latest.filename
+    result.latest_version = latest.version
+    result.download_url = f"/api/firmware/bridge/ota?version={latest.version}"
+    result.size = filepath.stat().st_size
+    result.sha256 = hashlib.sha256(filepath.read_bytes()).hexdigest()
+    result.signature = _read_signature(filepath)
+    # Bridges without version reporting are offered the latest image
+    result.update_available = _compare_versions(current_version, latest.version) if current_version else True
+    return result
+
+
+@router.get("/bridge/ota")
+async def get_bridge_ota_firmware(version: str | None = None):
+    """
+    NFC bridge firmware download for the display.
+
+    Args:
+        version: Optional specific version to download (latest if omitted)
+    """
+    firmware_list = _get_local_bridge_firmware()
+    if not firmware_list:
+        raise HTTPException(status_code=404, detail="No bridge firmware available")
+
+    firmware = firmware_list[0]
+    if version:
+        firmware = next((fw for fw in firmware_list if fw.version == version), None)
+        if not firmware:
+            raise HTTPException(status_code=404, detail=f"Version {version} not found")
+
+    return FileResponse(
+        BRIDGE_FIRMWARE_DIR / firmware.filename,
+        media_type="application/octet-stream",
+        filename=firmware.filename,
+        headers={
             "X-Firmware-Version": firmware.version,
         },
     )
@@ -423,6 +564,7 @@ class FirmwareUploadResponse(BaseModel):
 async def upload_firmware(
     file: UploadFile = File(...),
     version: str | None = Form(None),
+    signature: str | None = Form(None),
 ):
     """
     Upload a new firmware binary.
@@ -432,6 +574,7 @@ async def upload_firmware(
     Args:
         file: The firmware binary file (.bin)
         version: Optional version override (extracted from binary if not provided)
+        signature: Optional Ed25519 signature (hex) from tools/sign_firmware.py
 
     Returns:
         Upload result with version and filename
@@ -470,6 +613,11 @@ async def upload_firmware(
     # Clean version string
     firmware_version = firmware_version.lstrip("v")
 
+    if signature is not None:
+        signature = signature.strip().lower()
+        if not re.fullmatch(r"[0-9a-f]{128}", signature):
+            raise HTTPException(status_code=400, detail="Invalid signature. Must be 64 bytes as hex")
+
     # Ensure releases directory exists
     FIRMWARE_DIR.mkdir(parents=True, exist_ok=True)
 
@@ -482,6 +630,8 @@ async def upload_firmware(
     if filepath.exists():
         existing_checksum = hashlib.sha256(filepath.read_bytes()).hexdigest()[:16]
         if existing_checksum == checksum:
+            if signature:
+                _signature_path(filepath).write_text(signature + "\n")
             return FirmwareUploadResponse(
                 success=True,
                 message=f"Firmware {firmware_version} already exists (identical)",
@@ -496,9 +646,14 @@ async def upload_firmware(
             filepath.rename(FIRMWARE_DIR / backup_name)
             logger.info(f"Backed up existing firmware to {backup_name}")
 
-    # Save firmware
+    # Save firmware (a new image invalidates any old signature)
     try:
         filepath.write_bytes(content)
+        sig_path = _signature_path(filepath)
+        if signature:
+            sig_path.write_text(signature + "\n")
+        elif sig_path.exists():
+            sig_path.unlink()
     except Exception as e:
         raise HTTPException(status_code=500, detail=f"Failed to save firmware: {e}")
 
@@ -531,6 +686,7 @@ async def delete_firmware(version: str):
 
     try:
         filepath.unlink()
+        _signature_path(filepath).unlink(missing_ok=True)
         logger.info(f"Deleted firmware {version}")
         return {"success": True, "message": f"Firmware {version} deleted"}
     except Exception as e:
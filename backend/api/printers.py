@@ -14,6 +14,7 @@ from models import (
     PrinterUpdate,
     PrinterWithStatus,
     SetCalibrationRequest,
+    SpoolSlot,
 )
 from PIL import Image
 from pydantic import BaseModel
@@ -143,6 +144,10 @@ async def list_printers():
                 stg_cur=stg_cur,
                 stg_cur_name=stg_cur_name,
                 tray_reading_bits=tray_reading_bits,
+                spool_slots=[
+                    SpoolSlot(ams_id=a["ams_id"], tray_id=a["tray_id"], spool_id=a["spool_id"])
+                    for a in await db.get_slot_assignments(printer.serial)
+                ],
             )
         )
 
@@ -8,6 +8,15 @@ class SetWeightRequest(BaseModel):
     """Request to set spool weight from scale."""
 
     weight: int  # Current weight in grams (including core)
+    core_weight: int | None = None  # Core tare the display used for its net weight
+
+
+class RestoreWeightRequest(BaseModel):
+    """Weight tracking to put back, as it was before a scale sync."""
+
+    weight_current: int | None
+    weight_used: float
+    consumed_since_weight: float = 0
 
 
 class LogUsageRequest(BaseModel):
@@ -152,7 +161,8 @@ async def set_spool_weight(spool_id: str, request: SetWeightRequest):
 
     Args:
         spool_id: Spool ID
-        request: Weight data (total/gross weight in grams, including spool core)
+        request: Weight data (total/gross weight in grams, including spool core,
+            and the core tare the display resolved for it)
     """
     db = await get_db()
 
@@ -161,7 +171,28 @@ async def set_spool_weight(spool_id: str, request: SetWeightRequest):
         raise HTTPException(status_code=404, detail="Spool not found")
 
     # Store gross weight directly (weight_current is the total scale reading)
-    updated = await db.set_spool_weight(spool_id, request.weight)
+    updated = await db.set_spool_weight(spool_id, request.weight, request.core_weight)
+    return updated
+
+
+@router.patch("/{spool_id}/weight", response_model=Spool)
+async def restore_spool_weight(spool_id: str, request: RestoreWeightRequest):
+    """Restore spool weight tracking saved before a scale sync (undo).
+
+    Unlike POST, the values are stored as given instead of being derived
+    from a scale reading.
+
+    Args:
+        spool_id: Spool ID
+        request: weight_current, weight_used and consumed_since_weight to restore
+    """
+    db = await get_db()
+
+    updated = await db.restore_spool_weight(
+        spool_id, request.weight_current, request.weight_used, request.consumed_since_weight
+    )
+    if not updated:
+        raise HTTPException(status_code=404, detail="Spool not found")
     return updated
 
 
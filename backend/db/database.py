@@ -865,7 +865,7 @@ class Database:
 
         return await self.get_spool(spool_id)
 
-    async def set_spool_weight(self, spool_id: str, weight: int) -> Spool | None:
+    async def set_spool_weight(self, spool_id: str, weight: int, core_weight: int | None = None) -> Spool | None:
         """Set spool current weight from scale and recalculate weight_used to match.
 
         This syncs the tracking to match the scale reading by:
@@ -874,19 +874,22 @@ class Database:
         3. Calculating weight_used so that: gross = core_weight + (label_weight - weight_used)
            => weight_used = core_weight + label_weight - gross
 
-        Uses the Default Core Weight from settings (same as dashboard display) for consistency.
+        Uses the core weight the display tared with if given, so the stored
+        weight_used matches its net weight. Otherwise the spool's core_weight or the
+        Default Core Weight from settings (same as dashboard display).
         """
         spool = await self.get_spool(spool_id)
         if not spool:
             return None
 
-        # Use spool's core_weight if set, otherwise fall back to default from settings
-        # This matches the frontend calculation in getGrossWeight()
-        if spool.core_weight is not None and spool.core_weight > 0:
-            core_weight = spool.core_weight
-        else:
-            default_core_weight_str = await self.get_setting("spoolbuddy-default-core-weight")
-            core_weight = int(default_core_weight_str) if default_core_weight_str else 250
+        # Without the display's tare, use spool's core_weight if set, otherwise fall back
+        # to default from settings. This matches the frontend calculation in getGrossWeight()
+        if core_weight is None or core_weight <= 0:
+            if spool.core_weight is not None and spool.core_weight > 0:
+                core_weight = spool.core_weight
+            else:
+                default_core_weight_str = await self.get_setting("spoolbuddy-default-core-weight")
+                core_weight = int(default_core_weight_str) if default_core_weight_str else 250
 
         # Calculate what weight_used should be to match the scale reading
         # gross_weight = core_weight + net_weight
@@ -911,6 +914,22 @@ class Database:
         await self.conn.commit()
         return await self.get_spool(spool_id)
 
+    async def restore_spool_weight(
+        self, spool_id: str, weight_current: int | None, weight_used: float, consumed_since_weight: float
+    ) -> Spool | None:
+        """Put back weight tracking saved before a scale sync (undo of set_spool_weight)."""
+        if not await self.get_spool(spool_id):
+            return None
+
+        now = int(time.time())
+        await self.conn.execute(
+            """UPDATE spools SET weight_current = ?, weight_used = ?, consumed_since_weight = ?, updated_at = ?
+               WHERE id = ?""",
+            (weight_current, weight_used, consumed_since_weight, now, spool_id),
+        )
+        await self.conn.commit()
+        return await self.get_spool(spool_id)
+
     # ============ Settings Operations ============
 
     async def get_setting(self, key: str) -> str | None:
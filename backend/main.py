@@ -4,6 +4,7 @@ import json
 import logging
 import socket
 import time
+from collections import deque
 from contextlib import asynccontextmanager
 from pathlib import Path
 
@@ -31,6 +32,7 @@ from fastapi import FastAPI, WebSocket, WebSocketDisconnect
 from fastapi.middleware.cors import CORSMiddleware
 from fastapi.staticfiles import StaticFiles
 from models import PrinterState
+from pydantic import BaseModel
 from mqtt import PrinterManager
 from tags import TagDecoder
 from usage_tracker import UsageTracker, estimate_weight_from_percent
@@ -149,11 +151,18 @@ _device_update_available: bool = False
 # Device state (weight, tag) - updated by WebSocket messages from device
 _device_last_weight: float | None = None
 _device_weight_stable: bool = False
+# Stable id of the primary scale ("direct", "m70_0", ...) as reported by the device
+_device_scale_id: str | None = None
+# Additional scales (e.g. behind an I2C mux) - stable id -> {"weight", "stable"}
+_device_scales: dict[str, dict] = {}
 # Device WiFi status - reported by ESP32
 _device_wifi_ssid: str | None = None
 _device_wifi_ip: str | None = None
 _device_wifi_rssi: int | None = None
 _device_wifi_state: int = 0  # 0=uninitialized, 1=disconnected, 2=connecting, 3=connected, 4=error
+# Device health metrics - sent with the heartbeat every 30s (24h of history)
+HEALTH_HISTORY_SIZE = 2880
+_device_health_history: deque[dict] = deque(maxlen=HEALTH_HISTORY_SIZE)
 
 # === Tag Staging System ===
 # When a tag is detected, it goes to "staging" for 30 seconds.
@@ -773,11 +782,40 @@ app.include_router(api_keys_router, prefix="/api")
 
 @app.get("/api/time")
 async def get_server_time():
-    """Get server time for ESP32 clock sync."""
+    """Get server time for ESP32 clock sync.
+
+    Also carries the display clock settings: ``tz`` is a POSIX TZ string
+    (e.g. "CET-1CEST,M3.5.0,M10.5.0/3") and ``clock_24h`` the 12/24h format.
+    Both are null when not configured, leaving the device setting untouched.
+    """
     import datetime
 
     now = datetime.datetime.now()
-    return {"hour": now.hour, "minute": now.minute, "second": now.second, "timestamp": int(now.timestamp())}
+    db = await get_db()
+    tz = await db.get_setting("display_timezone")
+    clock_24h = await db.get_setting("display_clock_24h")
+    return {
+        "hour": now.hour,
+        "minute": now.minute,
+        "second": now.second,
+        "timestamp": int(now.timestamp()),
+        "tz": tz or None,
+        "clock_24h": clock_24h.lower() == "true" if clock_24h else None,
+    }
+
+
+class DisplayHeartbeat(BaseModel):
+    """Heartbeat body sent by the ESP32 display."""
+
+    version: str | None = None
+    update_available: bool | None = None
+    # WiFi status from device
+    wifi_state: int | None = None
+    wifi_ssid: str | None = None
+    wifi_ip: str | None = None
+    wifi_rssi: int | None = None
+    # Health metrics (heap, loop timing, I2C errors, ...), sent every 30s
+    health: dict | None = None
 
 
 @app.get("/api/display/heartbeat")
@@ -790,26 +828,46 @@ async def display_heartbeat(
     wifi_ip: str | None = None,
     wifi_rssi: int | None = None,
 ):
-    """Heartbeat endpoint for ESP32 display to indicate it's connected."""
+    """Heartbeat endpoint for ESP32 display to indicate it's connected (firmware before health metrics)."""
+    return handle_display_heartbeat(
+        DisplayHeartbeat(
+            version=version,
+            update_available=update_available,
+            wifi_state=wifi_state,
+            wifi_ssid=wifi_ssid,
+            wifi_ip=wifi_ip,
+            wifi_rssi=wifi_rssi,
+        )
+    )
+
+
+@app.post("/api/display/heartbeat")
+async def display_heartbeat_post(heartbeat: DisplayHeartbeat):
+    """Heartbeat endpoint for ESP32 display, with status and health metrics as JSON body."""
+    return handle_display_heartbeat(heartbeat)
+
+
+def handle_display_heartbeat(heartbeat: DisplayHeartbeat) -> dict:
+    """Record a display heartbeat and return the pending command, if any."""
     global _display_firmware_version, _device_update_available
-    global _device_wifi_state, _device_wifi_ssid, _device_wifi_ip, _device_wifi_rssi
+    global _device_wifi_state, _device_wifi_ssid, _device_wifi_ip, _device_wifi_rssi, _device_scale_id
 
     update_display_heartbeat()
 
-    if version:
-        _display_firmware_version = version
-    if update_available is not None:
+    if heartbeat.version:
+        _display_firmware_version = heartbeat.version
+    if heartbeat.update_available is not None:
         old_status = _device_update_available
-        _device_update_available = update_available
+        _device_update_available = heartbeat.update_available
         # Broadcast if update availability changed
-        if old_status != update_available:
+        if old_status != heartbeat.update_available:
             try:
                 loop = asyncio.get_running_loop()
                 loop.create_task(
                     broadcast_message(
                         {
                             "type": "device_update_available",
-                            "update_available": update_available,
+                            "update_available": heartbeat.update_available,
                         }
                     )
                 )
@@ -817,14 +875,17 @@ async def display_heartbeat(
                 pass
 
     # Update WiFi status if provided
-    if wifi_state is not None:
-        _device_wifi_state = wifi_state
-    if wifi_ssid is not None:
-        _device_wifi_ssid = wifi_ssid
-    if wifi_ip is not None:
-        _device_wifi_ip = wifi_ip
-    if wifi_rssi is not None:
-        _device_wifi_rssi = wifi_rssi
+    if heartbeat.wifi_state is not None:
+        _device_wifi_state = heartbeat.wifi_state
+    if heartbeat.wifi_ssid is not None:
+        _device_wifi_ssid = heartbeat.wifi_ssid
+    if heartbeat.wifi_ip is not None:
+        _device_wifi_ip = heartbeat.wifi_ip
+    if heartbeat.wifi_rssi is not None:
+        _device_wifi_rssi = heartbeat.wifi_rssi
+
+    if heartbeat.health is not None:
+        _device_health_history.append({"timestamp": int(time.time()), **heartbeat.health})
 
     cmd = pop_display_command()
     if cmd:
@@ -833,6 +894,20 @@ async def display_heartbeat(
     return {"ok": True}
 
 
+@app.get("/api/display/health")
+async def display_health(since: int | None = None):
+    """Get device health metrics, oldest first.
+
+    Args:
+        since: Only return samples newer than this Unix timestamp.
+    """
+    history = [h for h in _device_health_history if since is None or h["timestamp"] > since]
+    return {
+        "latest": _device_health_history[-1] if _device_health_history else None,
+        "history": history,
+    }
+
+
 def get_display_firmware_version() -> str | None:
     """Get the last reported firmware version from the display."""
     return _display_firmware_version
@@ -851,6 +926,11 @@ async def display_status():
         "update_available": _device_update_available,
         "weight": _device_last_weight,
         "weight_stable": _device_weight_stable,
+        # Per-scale readings by stable id (the primary mirrors weight/weight_stable above)
+        "scales": [
+            {"id": _device_scale_id, "primary": True, "weight": _device_last_weight, "stable": _device_weight_stable},
+            *({"id": i, "primary": False, **_device_scales[i]} for i in sorted(_device_scales)),
+        ],
         # WiFi status from device
         # If device is connected but hasn't reported WiFi, assume connected (it needs WiFi to reach us)
         "wifi": {
@@ -871,6 +951,8 @@ async def display_status():
 
 @app.post("/api/display/state")
 async def update_device_state(
+    scale: str | None = None,
+    scale_id: str | None = None,
     weight: float | None = None,
     stable: bool | None = None,
     tag_id: str | None = None,
@@ -888,7 +970,7 @@ async def update_device_state(
     wifi_rssi: int | None = None,
 ):
     """HTTP endpoint for device to update state (alternative to WebSocket)."""
-    global _device_wifi_state, _device_wifi_ssid, _device_wifi_ip, _device_wifi_rssi
+    global _device_wifi_state, _device_wifi_ssid, _device_wifi_ip, _device_wifi_rssi, _device_scale_id
 
     update_display_heartbeat()
 
@@ -902,6 +984,13 @@ async def update_device_state(
     if wifi_rssi is not None:
         _device_wifi_rssi = wifi_rssi
 
+    # Additional scales only report weight - they don't drive tag handling
+    if scale:
+        _device_scales[scale] = {"weight": weight, "stable": bool(stable)}
+        return {"ok": True}
+    if scale_id is not None:
+        _device_scale_id = scale_id
+
     # Build tag_data if decoded data provided
     tag_data = None
     if tag_id and tag_vendor:
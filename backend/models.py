@@ -111,6 +111,14 @@ class AmsUnit(BaseModel):
     trays: list[AmsTray] = []
 
 
+class SpoolSlot(BaseModel):
+    """Inventory spool assigned to an AMS or external slot."""
+
+    ams_id: int
+    tray_id: int
+    spool_id: str
+
+
 class PrinterWithStatus(BaseModel):
     """Printer with connection status and live state."""
 
@@ -141,6 +149,8 @@ class PrinterWithStatus(BaseModel):
     active_extruder: int | None = None  # Currently active extruder (0=right, 1=left)
     # Tray reading state (RFID scanning)
     tray_reading_bits: int | None = None  # Bitmask of trays currently being read
+    # Spools assigned to this printer's slots
+    spool_slots: list[SpoolSlot] = []
 
 
 class PrinterState(BaseModel):
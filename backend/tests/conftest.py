@@ -103,6 +103,8 @@ async def async_client(test_db, mock_printer_manager) -> AsyncGenerator[AsyncCli
         patch("api.settings.get_db", override_get_db),
         patch("api.support.get_db", override_get_db),
         patch("api.tags.get_db", override_get_db),
+        patch("api.device.get_db", override_get_db),
+        patch("main.get_db", override_get_db),
     ):
         async with AsyncClient(transport=ASGITransport(app=app), base_url="http://test") as client:
             yield client
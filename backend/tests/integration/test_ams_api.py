@@ -321,6 +321,17 @@ class TestAssignSpoolAPI:
         data = response.json()
         assert len(data) >= 1
 
+    async def test_list_printers_includes_spool_slots(self, async_client, test_db, sample_printer_data, spool_factory):
+        """Test the printer list names the spool assigned to each slot."""
+        await async_client.post("/api/printers", json=sample_printer_data)
+        spool = await spool_factory()
+        await test_db.assign_spool_to_slot(str(spool.id), sample_printer_data["serial"], 1, 2)
+
+        response = await async_client.get("/api/printers")
+
+        assert response.status_code == 200
+        assert response.json()[0]["spool_slots"] == [{"ams_id": 1, "tray_id": 2, "spool_id": str(spool.id)}]
+
     async def test_get_pending_assignments(self, async_client, sample_printer_data, mock_printer_manager):
         """Test getting pending staged assignments."""
         await async_client.post("/api/printers", json=sample_printer_data)
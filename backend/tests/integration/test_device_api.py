@@ -7,6 +7,9 @@ Tests cover:
 - Connect/disconnect
 - Scale operations (tare, calibrate, reset)
 - Device commands (reboot, update, factory reset)
+- Firmware logging settings
+- Crash reports
+- Settings backup and import
 - Recovery info
 """
 
@@ -286,6 +289,37 @@ class TestScaleAPI:
 
         assert response.status_code == 400
 
+    async def test_tare_scale_by_id(self, async_client):
+        """Test tare command for an additional scale carries its stable id."""
+        with patch("main.is_display_connected", return_value=True), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.post("/api/device/scale/tare?scale=m70_2")
+
+        assert response.status_code == 200
+        mock_queue.assert_called_once_with("scale_tare:m70_2")
+
+    async def test_calibrate_scale_by_id(self, async_client):
+        """Test calibrate command for an additional scale carries its stable id."""
+        with patch("main.is_display_connected", return_value=True), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.post("/api/device/scale/calibrate?known_weight=100.5&scale=m70_1")
+
+        assert response.status_code == 200
+        mock_queue.assert_called_once_with("scale_calibrate:100.5:m70_1")
+
+    async def test_reset_scale_by_id(self, async_client):
+        """Test reset command for an additional scale carries its stable id."""
+        with patch("main.is_display_connected", return_value=True), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.post("/api/device/scale/reset?scale=direct")
+
+        assert response.status_code == 200
+        mock_queue.assert_called_once_with("scale_reset:direct")
+
+    async def test_invalid_scale_id(self, async_client):
+        """Test scale ids that aren't a mux channel or known load cell are rejected."""
+        with patch("main.is_display_connected", return_value=True):
+            response = await async_client.post("/api/device/scale/tare?scale=m70_8")
+
+        assert response.status_code == 422
+
 
 class TestDeviceCommandsAPI:
     """Tests for device command endpoints (reboot, update, factory reset)."""
@@ -320,6 +354,21 @@ class TestDeviceCommandsAPI:
 
         assert response.status_code == 400
 
+    async def test_bridge_update_success(self, async_client):
+        """Test bridge update command."""
+        with patch("main.is_display_connected", return_value=True), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.post("/api/device/bridge/update")
+
+        assert response.status_code == 200
+        mock_queue.assert_called_once_with("bridge_update")
+
+    async def test_bridge_update_no_device(self, async_client):
+        """Test bridge update fails when no device connected."""
+        with patch("main.is_display_connected", return_value=False):
+            response = await async_client.post("/api/device/bridge/update")
+
+        assert response.status_code == 400
+
     async def test_factory_reset_no_device(self, async_client):
         """Test factory reset fails when no device connected."""
         with patch("api.device._connected_device", None):
@@ -328,6 +377,251 @@ class TestDeviceCommandsAPI:
         assert response.status_code == 400
 
 
+class TestDeviceLoggingAPI:
+    """Tests for firmware logging settings."""
+
+    async def test_get_logging_defaults(self, async_client):
+        """Test logging defaults to serial only at info level."""
+        response = await async_client.get("/api/device/logging")
+
+        assert response.status_code == 200
+        assert response.json() == {"target": None, "levels": "info"}
+
+    async def test_set_logging(self, async_client):
+        """Test saving logging settings queues a reload on the display."""
+        config = {"target": "syslog://192.168.1.10", "levels": "info,scale_manager=debug"}
+        with patch("main.is_display_connected", return_value=True), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.put("/api/device/logging", json=config)
+
+        assert response.status_code == 200
+        mock_queue.assert_called_once_with("log_config")
+
+        response = await async_client.get("/api/device/logging")
+        assert response.json() == config
+
+    async def test_set_logging_no_device(self, async_client):
+        """Test logging settings are saved while the display is offline."""
+        config = {"target": "192.168.1.10:5555", "levels": "warn"}
+        with patch("main.is_display_connected", return_value=False), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.put("/api/device/logging", json=config)
+
+        assert response.status_code == 200
+        mock_queue.assert_not_called()
+
+    @pytest.mark.parametrize(
+        "config",
+        [
+            {"target": "logs.example.com:5555"},
+            {"target": "tcp://192.168.1.10:5555"},
+            {"target": "udp://192.168.1.10"},
+            {"target": "192.168.1.10:70000"},
+            {"levels": "info,scale_manager=loud"},
+            {"levels": "verbose"},
+        ],
+    )
+    async def test_set_logging_invalid(self, async_client, config):
+        """Test invalid targets and level specs are rejected."""
+        response = await async_client.put("/api/device/logging", json=config)
+
+        assert response.status_code == 400
+
+
+class TestCrashReportAPI:
+    """Tests for crash report upload and listing."""
+
+    async def test_no_crash_reports(self, async_client):
+        """Test listing crash reports when none were uploaded."""
+        response = await async_client.get("/api/device/crash-reports")
+
+        assert response.status_code == 200
+        assert response.json() == []
+
+    async def test_upload_crash_report(self, async_client):
+        """Test an uploaded report is listed with its details."""
+        report = {
+            "reason": "panic",
+            "reason_code": 4,
+            "firmware_version": "0.2.0",
+            "panic": "panicked at src/scale_manager.rs:42:9:\nindex out of bounds",
+            "backtrace": "0x42012345 0x42023456",
+            "log": ["[INFO] scale_manager: Tare", "[WARN] backend_client: Failed to fetch printers"],
+        }
+        response = await async_client.post("/api/device/crash-report", json=report)
+        assert response.status_code == 200
+
+        response = await async_client.get("/api/device/crash-reports")
+        reports = response.json()
+        assert len(reports) == 1
+        assert reports[0]["reason"] == "panic"
+        assert reports[0]["panic"] == report["panic"]
+        assert reports[0]["log"] == report["log"]
+        assert reports[0]["received_at"] is not None
+
+    async def test_crash_reports_newest_first_and_capped(self, async_client):
+        """Test reports are listed newest first and only the latest are kept."""
+        from api.device import MAX_CRASH_REPORTS
+
+        for i in range(MAX_CRASH_REPORTS + 2):
+            await async_client.post("/api/device/crash-report", json={"reason": "watchdog", "reason_code": i})
+
+        response = await async_client.get("/api/device/crash-reports")
+        reports = response.json()
+        assert len(reports) == MAX_CRASH_REPORTS
+        assert reports[0]["reason_code"] == MAX_CRASH_REPORTS + 1
+
+    async def test_crash_report_requires_reason(self, async_client):
+        """Test reports without a reset reason are rejected."""
+        response = await async_client.post("/api/device/crash-report", json={"panic": "oops"})
+
+        assert response.status_code == 422
+
+
+class TestDeviceSettingsAPI:
+    """Tests for the display settings backup and import."""
+
+    SETTINGS = {
+        "version": 1,
+        "display": {"brightness": 60, "timeout_s": 120},
+        "backend": {"url": "http://192.168.1.10:3000"},
+        "wifi": {"networks": [{"ssid": "Workshop", "auth": "wpa2_personal"}]},
+        "scales": {"direct": {"zero_offset": 8421, "cal_factor": 412.5}},
+        "lan_mode": False,
+    }
+
+    async def _api_key_headers(self, async_client, **permissions) -> dict:
+        """Create an API key with the given permissions and return its request headers."""
+        response = await async_client.post("/api/api-keys/", json={"name": "Settings", **permissions})
+        return {"X-API-Key": response.json()["key"]}
+
+    async def test_no_backup(self, async_client):
+        """Test getting the backup before the display uploaded one."""
+        headers = await self._api_key_headers(async_client, can_read=True)
+        response = await async_client.get("/api/device/settings", headers=headers)
+
+        assert response.status_code == 404
+
+    async def test_backup_requires_api_key(self, async_client):
+        """Test the backup isn't handed out without an API key."""
+        display = await self._api_key_headers(async_client, can_read=True, can_write=True)
+        await async_client.post("/api/device/settings", json=self.SETTINGS, headers=display)
+
+        response = await async_client.get("/api/device/settings")
+
+        assert response.status_code == 401
+
+    async def test_upload_backup(self, async_client):
+        """Test the latest uploaded document is returned as the backup."""
+        display = await self._api_key_headers(async_client, can_read=True, can_write=True)
+        response = await async_client.post("/api/device/settings", json=self.SETTINGS, headers=display)
+        assert response.status_code == 200
+
+        updated = {**self.SETTINGS, "lan_mode": True}
+        await async_client.post("/api/device/settings", json=updated, headers=display)
+
+        headers = await self._api_key_headers(async_client, can_read=True)
+        response = await async_client.get("/api/device/settings", headers=headers)
+        assert response.status_code == 200
+        data = response.json()
+        assert data["settings"] == updated
+        assert data["received_at"] is not None
+
+    async def test_upload_backup_requires_api_key(self, async_client):
+        """Test a backup can't be replaced without the display's API key."""
+        response = await async_client.post("/api/device/settings", json=self.SETTINGS)
+        assert response.status_code == 401
+
+        headers = await self._api_key_headers(async_client, can_read=True, can_write=False)
+        response = await async_client.post("/api/device/settings", json=self.SETTINGS, headers=headers)
+        assert response.status_code == 403
+
+    async def test_upload_backup_drops_secrets(self, async_client):
+        """Test WiFi passwords and the API key are never stored in the backup."""
+        settings = {
+            **self.SETTINGS,
+            "backend": {"url": "http://192.168.1.10:3000", "api_key": "sb_test"},
+            "wifi": {"networks": [{"ssid": "Workshop", "password": "secret123", "auth": "wpa2_personal"}]},
+        }
+        display = await self._api_key_headers(async_client, can_read=True, can_write=True)
+        await async_client.post("/api/device/settings", json=settings, headers=display)
+
+        response = await async_client.get("/api/device/settings", headers=display)
+        assert response.json()["settings"] == self.SETTINGS
+
+    async def test_upload_backup_requires_version(self, async_client):
+        """Test documents without a schema version are rejected."""
+        display = await self._api_key_headers(async_client, can_read=True, can_write=True)
+        response = await async_client.post(
+            "/api/device/settings", json={"display": {"brightness": 60}}, headers=display
+        )
+
+        assert response.status_code == 400
+
+    async def test_import_settings(self, async_client):
+        """Test a queued document is handed to the display once."""
+        headers = await self._api_key_headers(async_client, can_write=True)
+        with patch("main.is_display_connected", return_value=True), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.put("/api/device/settings", json=self.SETTINGS, headers=headers)
+
+        assert response.status_code == 200
+        mock_queue.assert_called_once_with("settings_import")
+
+        display = await self._api_key_headers(async_client, can_read=True, can_write=True)
+        response = await async_client.get("/api/device/settings/import", headers=display)
+        assert response.status_code == 200
+        assert response.json() == self.SETTINGS
+
+        response = await async_client.get("/api/device/settings/import", headers=display)
+        assert response.status_code == 404
+
+    async def test_pending_import_requires_api_key(self, async_client):
+        """Test the queued document (which may hold secrets) isn't handed out without an API key."""
+        headers = await self._api_key_headers(async_client, can_write=True)
+        with patch("main.is_display_connected", return_value=True), patch("main.queue_display_command"):
+            await async_client.put("/api/device/settings", json=self.SETTINGS, headers=headers)
+
+        response = await async_client.get("/api/device/settings/import")
+        assert response.status_code == 401
+
+        # Still queued for the display
+        display = await self._api_key_headers(async_client, can_read=True, can_write=True)
+        response = await async_client.get("/api/device/settings/import", headers=display)
+        assert response.status_code == 200
+
+    async def test_import_settings_requires_write_permission(self, async_client):
+        """Test a read-only API key can't queue an import."""
+        headers = await self._api_key_headers(async_client, can_read=True, can_write=False)
+        with patch("main.is_display_connected", return_value=True), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.put("/api/device/settings", json=self.SETTINGS, headers=headers)
+
+        assert response.status_code == 403
+        mock_queue.assert_not_called()
+
+    async def test_import_settings_no_device(self, async_client):
+        """Test an import is queued while the display is offline."""
+        headers = await self._api_key_headers(async_client, can_write=True)
+        with patch("main.is_display_connected", return_value=False), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.put("/api/device/settings", json=self.SETTINGS, headers=headers)
+
+        assert response.status_code == 200
+        mock_queue.assert_called_once_with("settings_import")
+
+    async def test_import_settings_requires_version(self, async_client):
+        """Test documents without a schema version can't be imported."""
+        headers = await self._api_key_headers(async_client, can_write=True)
+        with patch("main.is_display_connected", return_value=True), patch("main.queue_display_command") as mock_queue:
+            response = await async_client.put("/api/device/settings", json={"lan_mode": True}, headers=headers)
+
+        assert response.status_code == 400
+        mock_queue.assert_not_called()
+
+    async def test_no_pending_import(self, async_client):
+        """Test the display's fetch when nothing is queued."""
+        display = await self._api_key_headers(async_client, can_read=True, can_write=True)
+        response = await async_client.get("/api/device/settings/import", headers=display)
+
+        assert response.status_code == 404
+
+
 class TestRecoveryInfoAPI:
     """Tests for recovery info endpoint."""
 
@@ -5,6 +5,7 @@ Tests cover:
 - Discovery status
 - Start/stop discovery
 - Get discovered printers
+- Printer reports from the display
 """
 
 import asyncio
@@ -187,3 +188,51 @@ class TestDiscoveryPrintersAPI:
         assert data[0]["ip_address"] == "192.168.1.200"
         assert data[0]["name"] is None
         assert data[0]["model"] is None
+
+
+class TestDeviceReportAPI:
+    """Tests for printers reported by the display."""
+
+    async def test_device_report_replaces_previous(self, async_client):
+        """Test each report replaces the display's previous list."""
+        old = {"OLD123": DiscoveredPrinter(serial="OLD123", ip_address="192.168.1.50")}
+        mock_state = DiscoveryState(running=False, printers={}, task=None, device_printers=old)
+
+        report = [
+            {
+                "serial": "01P00A123456789",
+                "name": "Workshop P1S",
+                "ip_address": "192.168.1.120",
+                "model": "P1S",
+                "model_code": "C12",
+                "connect_mode": "lan",
+                "bind_state": "free",
+                "firmware_version": "01.08.00.00",
+            }
+        ]
+
+        with patch("api.discovery._state", mock_state):
+            response = await async_client.post("/api/discovery/device-report", json=report)
+
+        assert response.status_code == 200
+        assert response.json()["count"] == 1
+        assert set(mock_state.device_printers) == {"01P00A123456789"}
+        assert mock_state.device_printers["01P00A123456789"].connect_mode == "lan"
+
+    async def test_get_printers_includes_device_reports(self, async_client):
+        """Test reported printers are listed with their connection details."""
+        printers = {"ABC123456": DiscoveredPrinter(serial="ABC123456", ip_address="192.168.1.100")}
+        device_printers = {
+            "ABC123456": DiscoveredPrinter(serial="ABC123456", ip_address="192.168.1.100", connect_mode="cloud"),
+            "DEF789012": DiscoveredPrinter(serial="DEF789012", ip_address="192.168.1.101", connect_mode="lan"),
+        }
+        mock_state = DiscoveryState(running=False, printers=printers, task=None, device_printers=device_printers)
+
+        with patch("api.discovery._state", mock_state):
+            response = await async_client.get("/api/discovery/printers")
+
+        assert response.status_code == 200
+        data = {p["serial"]: p for p in response.json()}
+        assert set(data) == {"ABC123456", "DEF789012"}
+        assert data["ABC123456"]["connect_mode"] == "cloud"
+        assert data["DEF789012"]["connect_mode"] == "lan"
@@ -0,0 +1,89 @@
+"""
+Integration tests for the ESP32 display heartbeat.
+
+Tests cover:
+- Heartbeat as query string (older firmware) and JSON body
+- Health metrics history
+"""
+
+from collections import deque
+from unittest.mock import patch
+
+import pytest
+
+HEALTH = {
+    "uptime_s": 3600,
+    "heap_free": 84000,
+    "heap_min_free": 61000,
+    "psram_free": 6100000,
+    "loop_avg_us": 850,
+    "loop_max_us": 21000,
+    "stack_free": {"main": 5200, "printer_mqtt": 2900},
+    "scales": [{"id": "direct", "i2c_errors": 2, "noise_g": 0.8}],
+    "nfc": {"i2c_errors": 0, "reads": 9, "read_failures": 1, "read_success_rate": 0.9},
+    "clock_drift_s": None,
+}
+
+
+@pytest.fixture
+def health_history():
+    """Empty health history for each test."""
+    history = deque(maxlen=10)
+    with patch("main._device_health_history", history):
+        yield history
+
+
+class TestDisplayHeartbeatAPI:
+    """Tests for the display heartbeat endpoint."""
+
+    async def test_heartbeat_query_params(self, async_client, health_history):
+        """Test the query string heartbeat of older firmware still works."""
+        with patch("main.pop_display_command", return_value=None):
+            response = await async_client.get("/api/display/heartbeat?version=0.1.0&wifi_state=3&wifi_rssi=-60")
+
+        assert response.status_code == 200
+        assert response.json() == {"ok": True}
+        assert len(health_history) == 0
+
+    async def test_heartbeat_json_with_health(self, async_client, health_history):
+        """Test a JSON heartbeat stores the health snapshot."""
+        body = {"version": "0.2.0", "update_available": False, "wifi_state": 3, "health": HEALTH}
+        with patch("main.pop_display_command", return_value=None):
+            response = await async_client.post("/api/display/heartbeat", json=body)
+
+        assert response.status_code == 200
+        assert len(health_history) == 1
+        assert health_history[0]["heap_free"] == 84000
+        assert "timestamp" in health_history[0]
+
+    async def test_heartbeat_json_returns_command(self, async_client, health_history):
+        """Test a pending command is delivered in the JSON heartbeat response."""
+        with patch("main.pop_display_command", return_value="reboot"):
+            response = await async_client.post("/api/display/heartbeat", json={"version": "0.2.0"})
+
+        assert response.json() == {"ok": True, "command": "reboot"}
+        assert len(health_history) == 0
+
+
+class TestDisplayHealthAPI:
+    """Tests for the health metrics endpoint."""
+
+    async def test_health_empty(self, async_client, health_history):
+        """Test health without any samples."""
+        response = await async_client.get("/api/display/health")
+
+        assert response.status_code == 200
+        assert response.json() == {"latest": None, "history": []}
+
+    async def test_health_history(self, async_client, health_history):
+        """Test samples are returned oldest first and filtered by timestamp."""
+        health_history.append({"timestamp": 100, **HEALTH, "uptime_s": 60})
+        health_history.append({"timestamp": 130, **HEALTH, "uptime_s": 90})
+
+        response = await async_client.get("/api/display/health")
+        data = response.json()
+        assert [h["uptime_s"] for h in data["history"]] == [60, 90]
+        assert data["latest"]["uptime_s"] == 90
+
+        response = await async_client.get("/api/display/health?since=100")
+        assert [h["timestamp"] for h in response.json()["history"]] == [130]
@@ -5,11 +5,12 @@ Tests cover:
 - Listing firmware versions
 - Getting latest firmware
 - Checking for updates (GitHub API)
-- Downloading firmware files
+- Downloading firmware files (including resumed OTA downloads)
 - Uploading firmware binaries
 - Deleting firmware versions
 """
 
+import hashlib
 import tempfile
 from datetime import datetime, timedelta
 from pathlib import Path
@@ -259,6 +260,87 @@ class TestFirmwareCheckAPI:
         data = response.json()
         assert data["latest_version"] == "3.0.0"
 
+    async def test_check_local_includes_sha256(self, async_client):
+        """Test that a local release is announced with its size, SHA-256 and signature."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            tmp_path = Path(tmp_dir)
+            firmware_content = b"\xe9" + b"\x01" * 255
+            (tmp_path / "spoolbuddy-2.0.0.bin").write_bytes(firmware_content)
+            (tmp_path / "spoolbuddy-2.0.0.bin.sig").write_text("ab" * 64 + "\n")
+
+            cached_data = {
+                "version": "1.5.0",
+                "filename": "spoolbuddy-1.5.0.bin",
+                "url": "https://github.com/cached/url",
+            }
+
+            with (
+                patch("api.firmware.FIRMWARE_DIR", tmp_path),
+                patch("api.firmware._firmware_cache", cached_data),
+                patch("api.firmware._firmware_cache_time", datetime.now()),
+            ):
+                response = await async_client.get("/api/firmware/check?current_version=1.0.0")
+
+        assert response.status_code == 200
+        data = response.json()
+        assert data["latest_version"] == "2.0.0"
+        assert data["size"] == 256
+        assert data["sha256"] == hashlib.sha256(firmware_content).hexdigest()
+        assert data["signature"] == "ab" * 64
+
+    async def test_check_github_release_has_no_sha256(self, async_client):
+        """Test that a GitHub-only release carries no SHA-256."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            tmp_path = Path(tmp_dir)
+
+            cached_data = {
+                "version": "2.5.0",
+                "filename": "spoolbuddy-2.5.0.bin",
+                "url": "https://github.com/cached/url",
+            }
+
+            with (
+                patch("api.firmware.FIRMWARE_DIR", tmp_path),
+                patch("api.firmware._firmware_cache", cached_data),
+                patch("api.firmware._firmware_cache_time", datetime.now()),
+            ):
+                response = await async_client.get("/api/firmware/check?current_version=1.0.0")
+
+        assert response.status_code == 200
+        assert response.json()["sha256"] is None
+
+    async def test_check_channel_filters_versions(self, async_client):
+        """Test that the stable channel is not offered pre-releases."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            tmp_path = Path(tmp_dir)
+            (tmp_path / "spoolbuddy-1.1.0.bin").write_bytes(b"\xe9" + b"\x00" * 255)
+            (tmp_path / "spoolbuddy-1.2.0b1.bin").write_bytes(b"\xe9" + b"\x00" * 255)
+
+            cached_data = {
+                "version": "1.3.0a1",
+                "filename": "spoolbuddy-1.3.0a1.bin",
+                "url": "https://github.com/cached/url",
+            }
+
+            with (
+                patch("api.firmware.FIRMWARE_DIR", tmp_path),
+                patch("api.firmware._firmware_cache", cached_data),
+                patch("api.firmware._firmware_cache_time", datetime.now()),
+            ):
+                stable = await async_client.get("/api/firmware/check?current_version=1.0.0&channel=stable")
+                beta = await async_client.get("/api/firmware/check?current_version=1.0.0&channel=beta")
+                dev = await async_client.get("/api/firmware/check?current_version=1.0.0&channel=dev")
+
+        assert stable.json()["latest_version"] == "1.1.0"
+        assert beta.json()["latest_version"] == "1.2.0b1"
+        assert dev.json()["latest_version"] == "1.3.0a1"
+
+    async def test_check_unknown_channel(self, async_client):
+        """Test that an unknown channel is rejected."""
+        response = await async_client.get("/api/firmware/check?current_version=1.0.0&channel=nightly")
+
+        assert response.status_code == 400
+
 
 class TestFirmwareDownloadAPI:
     """Tests for firmware download endpoint."""
@@ -360,6 +442,94 @@ class TestFirmwareOtaAPI:
         assert response.status_code == 200
         assert response.headers["x-firmware-version"] == "1.0.0"
 
+    async def test_ota_range_request(self, async_client):
+        """Test resuming an OTA download with a Range request."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            tmp_path = Path(tmp_dir)
+            firmware_content = b"\xe9" + bytes(range(255))
+            (tmp_path / "spoolbuddy-1.0.0.bin").write_bytes(firmware_content)
+
+            with patch("api.firmware.FIRMWARE_DIR", tmp_path):
+                response = await async_client.get("/api/firmware/ota", headers={"Range": "bytes=100-"})
+
+        assert response.status_code == 206
+        assert response.content == firmware_content[100:]
+        assert response.headers["content-range"] == "bytes 100-255/256"
+
+
+class TestBridgeFirmwareAPI:
+    """Tests for NFC bridge firmware endpoints."""
+
+    async def test_bridge_check_no_firmware(self, async_client):
+        """Test bridge check when no bridge firmware is available."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            with patch("api.firmware.BRIDGE_FIRMWARE_DIR", Path(tmp_dir) / "bridge"):
+                response = await async_client.get("/api/firmware/bridge/check?current_version=2.0.0")
+
+        assert response.status_code == 200
+        data = response.json()
+        assert data["update_available"] is False
+        assert data["error"] == "No bridge firmware available"
+
+    async def test_bridge_check_update_available(self, async_client):
+        """Test bridge check returns the newest image with hash and signature."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            tmp_path = Path(tmp_dir)
+            firmware_content = bytes(range(256))
+            (tmp_path / "nfc-bridge-2.0.0.bin").write_bytes(b"\x00" * 100)
+            (tmp_path / "nfc-bridge-2.1.0.bin").write_bytes(firmware_content)
+            (tmp_path / "nfc-bridge-2.1.0.bin.sig").write_text("ab" * 64 + "\n")
+            # ESP32 images in the same directory are not bridge firmware
+            (tmp_path / "spoolbuddy-9.0.0.bin").write_bytes(b"\xe9" + b"\x00" * 255)
+
+            with patch("api.firmware.BRIDGE_FIRMWARE_DIR", tmp_path):
+                response = await async_client.get("/api/firmware/bridge/check?current_version=2.0.0")
+
+        assert response.status_code == 200
+        data = response.json()
+        assert data["update_available"] is True
+        assert data["latest_version"] == "2.1.0"
+        assert data["download_url"] == "/api/firmware/bridge/ota?version=2.1.0"
+        assert data["size"] == 256
+        assert data["sha256"] == hashlib.sha256(firmware_content).hexdigest()
+        assert data["signature"] == "ab" * 64
+
+    async def test_bridge_check_channel(self, async_client):
+        """Test that bridge updates follow the update channel."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            tmp_path = Path(tmp_dir)
+            (tmp_path / "nfc-bridge-2.1.0.bin").write_bytes(b"\x00" * 100)
+            (tmp_path / "nfc-bridge-2.2.0b1.bin").write_bytes(b"\x00" * 100)
+
+            with patch("api.firmware.BRIDGE_FIRMWARE_DIR", tmp_path):
+                stable = await async_client.get("/api/firmware/bridge/check?current_version=2.1.0&channel=stable")
+                beta = await async_client.get("/api/firmware/bridge/check?current_version=2.1.0&channel=beta")
+                unknown = await async_client.get("/api/firmware/bridge/check?channel=nightly")
+
+        assert stable.json()["latest_version"] == "2.1.0"
+        assert stable.json()["update_available"] is False
+        assert beta.json()["latest_version"] == "2.2.0b1"
+        assert beta.json()["update_available"] is True
+        assert unknown.status_code == 400
+
+    async def test_bridge_ota(self, async_client):
+        """Test downloading a bridge image."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            tmp_path = Path(tmp_dir)
+            (tmp_path / "nfc-bridge-2.0.0.bin").write_bytes(b"\x00" * 100)
+            (tmp_path / "nfc-bridge-2.1.0.bin").write_bytes(b"\x01" * 200)
+
+            with patch("api.firmware.BRIDGE_FIRMWARE_DIR", tmp_path):
+                latest = await async_client.get("/api/firmware/bridge/ota")
+                specific = await async_client.get("/api/firmware/bridge/ota?version=2.0.0")
+                missing = await async_client.get("/api/firmware/bridge/ota?version=9.9.9")
+
+        assert latest.status_code == 200
+        assert latest.content == b"\x01" * 200
+        assert latest.headers["x-firmware-version"] == "2.1.0"
+        assert specific.headers["x-firmware-version"] == "2.0.0"
+        assert missing.status_code == 404
+
 
 class TestFirmwareUploadAPI:
     """Tests for firmware upload endpoint."""
@@ -447,6 +617,43 @@ class TestFirmwareUploadAPI:
         assert data["success"] is True
         assert data["version"] == "3.0.0"  # v prefix should be stripped
 
+    async def test_upload_with_signature(self, async_client):
+        """Test that an uploaded signature is stored next to the image."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            tmp_path = Path(tmp_dir)
+
+            valid_firmware = b"\xe9" + b"\x00" * 1023
+            signature = "CD" * 64
+
+            with patch("api.firmware.FIRMWARE_DIR", tmp_path):
+                response = await async_client.post(
+                    "/api/firmware/upload",
+                    files={"file": ("firmware.bin", valid_firmware, "application/octet-stream")},
+                    data={"version": "3.0.0", "signature": signature},
+                )
+
+            assert response.status_code == 200
+            sig_file = tmp_path / "spoolbuddy-3.0.0.bin.sig"
+            assert sig_file.read_text().strip() == signature.lower()
+
+    async def test_upload_invalid_signature(self, async_client):
+        """Test that a malformed signature is rejected."""
+        with tempfile.TemporaryDirectory() as tmp_dir:
+            tmp_path = Path(tmp_dir)
+
+            valid_firmware = b"\xe9" + b"\x00" * 1023
+
+            with patch("api.firmware.FIRMWARE_DIR", tmp_path):
+                response = await async_client.post(
+                    "/api/firmware/upload",
+                    files={"file": ("firmware.bin", valid_firmware, "application/octet-stream")},
+                    data={"version": "3.0.0", "signature": "not-hex"},
+                )
+
+            assert response.status_code == 400
+            assert "signature" in response.json()["detail"].lower()
+            assert not (tmp_path / "spoolbuddy-3.0.0.bin").exists()
+
     async def test_upload_no_version_fails(self, async_client):
         """Test upload without version information fails."""
         with tempfile.TemporaryDirectory() as tmp_dir:
@@ -484,6 +691,8 @@ class TestFirmwareDeleteAPI:
             tmp_path = Path(tmp_dir)
             firmware_file = tmp_path / "spoolbuddy-1.0.0.bin"
             firmware_file.write_bytes(b"\x00" * 100)
+            sig_file = tmp_path / "spoolbuddy-1.0.0.bin.sig"
+            sig_file.write_text("ab" * 64)
 
             with patch("api.firmware.FIRMWARE_DIR", tmp_path):
                 response = await async_client.delete("/api/firmware/1.0.0")
@@ -493,6 +702,7 @@ class TestFirmwareDeleteAPI:
         assert data["success"] is True
         assert "deleted" in data["message"].lower()
         assert not firmware_file.exists()
+        assert not sig_file.exists()
 
     async def test_delete_with_v_prefix(self, async_client):
         """Test deletion with v prefix in version."""
@@ -553,6 +763,16 @@ class TestVersionParsing:
         assert _compare_versions("1.0.0", "1.0.0") is False
         assert _compare_versions("1.0.0b1", "1.0.0") is True
 
+    def test_version_channel(self):
+        """Test mapping versions to update channels."""
+        from api.firmware import _version_channel
+
+        assert _version_channel("1.0.0") == "stable"
+        assert _version_channel("0.1.1-beta.11") == "beta"
+        assert _version_channel("1.0.0rc1") == "beta"
+        assert _version_channel("1.0.0a1") == "dev"
+        assert _version_channel("nightly") == "dev"
+
 
 class TestFirmwareValidation:
     """Tests for ESP32 firmware validation."""
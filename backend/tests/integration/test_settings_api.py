@@ -4,6 +4,7 @@ Integration tests for Settings API.
 Tests cover:
 - Get/Set/Delete individual settings
 - AMS threshold settings
+- Display clock settings in /api/time
 """
 
 import pytest
@@ -148,3 +149,29 @@ class TestAMSThresholdsAPI:
         assert data["humidity_good"] == 45
         # Others should be defaults
         assert data["humidity_fair"] == 60
+
+
+class TestDisplayClockSettings:
+    """Tests for the clock settings delivered with /api/time."""
+
+    async def test_time_without_clock_settings(self, async_client, test_db):
+        """Test unset timezone and clock format are returned as null."""
+        response = await async_client.get("/api/time")
+
+        assert response.status_code == 200
+        data = response.json()
+        assert isinstance(data["timestamp"], int)
+        assert data["tz"] is None
+        assert data["clock_24h"] is None
+
+    async def test_time_with_clock_settings(self, async_client, test_db):
+        """Test configured timezone and 12h format are returned."""
+        await async_client.put("/api/settings/display_timezone", json={"value": "EST5EDT,M3.2.0,M11.1.0"})
+        await async_client.put("/api/settings/display_clock_24h", json={"value": "false"})
+
+        response = await async_client.get("/api/time")
+
+        assert response.status_code == 200
+        data = response.json()
+        assert data["tz"] == "EST5EDT,M3.2.0,M11.1.0"
+        assert data["clock_24h"] is False
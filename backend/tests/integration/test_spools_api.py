@@ -104,6 +104,43 @@ class TestSpoolsAPI:
         data = response.json()
         assert len(data) == 3
 
+    async def test_restore_spool_weight(self, async_client, sample_spool_data):
+        """Test undoing a scale sync restores the previous tracking as is."""
+        create_response = await async_client.post("/api/spools", json=sample_spool_data)
+        spool = create_response.json()
+
+        await async_client.post(f"/api/spools/{spool['id']}/weight", json={"weight": 100})
+
+        restore = {"weight_current": None, "weight_used": 120.5, "consumed_since_weight": 30.0}
+        response = await async_client.patch(f"/api/spools/{spool['id']}/weight", json=restore)
+
+        assert response.status_code == 200
+        data = response.json()
+        assert data["weight_current"] is None
+        assert data["weight_used"] == 120.5
+        assert data["consumed_since_weight"] == 30.0
+
+    async def test_set_spool_weight_with_display_core_weight(self, async_client):
+        """Test a sync uses the core tare the display computed its net weight with."""
+        create_response = await async_client.post(
+            "/api/spools", json={"material": "PLA", "brand": "Polymaker", "label_weight": 1000}
+        )
+        spool = create_response.json()
+
+        response = await async_client.post(f"/api/spools/{spool['id']}/weight", json={"weight": 640, "core_weight": 140})
+
+        assert response.status_code == 200
+        data = response.json()
+        assert data["weight_current"] == 640
+        assert data["weight_used"] == 500  # 140 core + 1000 label - 640 gross
+
+    async def test_restore_spool_weight_not_found(self, async_client):
+        """Test restoring weight tracking of a missing spool."""
+        restore = {"weight_current": 900, "weight_used": 0}
+        response = await async_client.patch("/api/spools/nonexistent-id/weight", json=restore)
+
+        assert response.status_code == 404
+
 
 class TestSpoolsDatabase:
     """Test spool database operations directly."""
@@ -6,4 +6,48 @@ fn main() {
     // Rerun if font files change
     println!("cargo:rerun-if-changed=fonts/");
     println!("cargo:rerun-if-changed=components/custom_fonts/CMakeLists.txt");
+
+    // OTA signing key is compiled in via option_env!
+    println!("cargo:rerun-if-env-changed=SPOOLBUDDY_OTA_PUBLIC_KEY");
+    if std::env::var("SPOOLBUDDY_OTA_PUBLIC_KEY").is_err() {
+        println!("cargo:warning=SPOOLBUDDY_OTA_PUBLIC_KEY not set - this build will reject all OTA updates");
+    }
+
+    // CA of the printers' LAN MQTT certificates
+    embed_ca(
+        "SPOOLBUDDY_PRINTER_CA",
+        "printer_ca.pem",
+        "this build can't connect to printers over LAN MQTT",
+    );
+    // CA of the WPA2-Enterprise authentication server
+    embed_ca(
+        "SPOOLBUDDY_EAP_CA",
+        "eap_ca.pem",
+        "enterprise WiFi only connects to networks set to skip server verification",
+    );
+}
+
+/// Compile in the PEM file named by an env var as a NUL-terminated string
+/// (OUT_DIR/<file>); empty without it
+fn embed_ca(var: &str, file: &str, missing: &str) {
+    println!("cargo:rerun-if-env-changed={}", var);
+    let mut pem = match std::env::var(var) {
+        Ok(path) => {
+            println!("cargo:rerun-if-changed={}", path);
+            let mut pem = std::fs::read(&path)
+                .unwrap_or_else(|e| panic!("Can't read {} '{}': {}", var, path, e));
+            pem.retain(|&b| b != 0);
+            pem
+        }
+        Err(_) => {
+            println!("cargo:warning={} not set - {}", var, missing);
+            Vec::new()
+        }
+    };
+    if !pem.is_empty() {
+        pem.push(0);
+    }
+    let out_dir = std::env::var("OUT_DIR").expect("OUT_DIR not set");
+    std::fs::write(std::path::Path::new(&out_dir).join(file), pem)
+        .unwrap_or_else(|e| panic!("Failed to write {}: {}", file, e));
 }
@@ -75,6 +75,9 @@ static bool touch_pressed = false;
 static int16_t touch_x = 0;
 static int16_t touch_y = 0;
 
+// Touches are read but not passed to LVGL (screen off, see idle.rs)
+static bool touch_blocked = false;
+
 // Tick timer
 static uint32_t tick_start = 0;
 
@@ -200,7 +203,8 @@ static void touch_read_cb(lv_indev_t *indev, lv_indev_data_t *data)
     if (read_gt911_touch(&touch_x, &touch_y)) {
         data->point.x = touch_x;
         data->point.y = touch_y;
-        data->state = LV_INDEV_STATE_PRESSED;
+        // A touch that only wakes the screen must not press what is under it
+        data->state = touch_blocked ? LV_INDEV_STATE_RELEASED : LV_INDEV_STATE_PRESSED;
         touch_pressed = true;
     } else {
         data->point.x = touch_x;  // Report last known position
@@ -339,6 +343,24 @@ void display_set_backlight_hw(uint8_t brightness_percent)
     }
 }
 
+/**
+ * Whether a finger was on the panel at the last touch read
+ * Called from Rust via FFI
+ */
+bool display_touch_active(void)
+{
+    return touch_pressed;
+}
+
+/**
+ * Stop (or resume) passing touches to LVGL
+ * Called from Rust via FFI
+ */
+void display_set_touch_blocked(bool blocked)
+{
+    touch_blocked = blocked;
+}
+
 /**
  * LVGL tick callback
  */
@@ -40,6 +40,17 @@ uint32_t display_get_tick_ms(void);
  */
 void display_set_backlight_hw(uint8_t brightness_percent);
 
+/**
+ * Whether a finger was on the panel at the last touch read
+ */
+bool display_touch_active(void);
+
+/**
+ * Stop (or resume) passing touches to LVGL
+ * Touches are still read, so display_touch_active() keeps working
+ */
+void display_set_touch_blocked(bool blocked);
+
 /**
  * Shutdown display before reboot
  * Properly deinitializes the LCD panel to prevent display shift on soft restart
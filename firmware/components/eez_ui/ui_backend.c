@@ -271,7 +271,12 @@ static void update_main_screen_backend_status(BackendStatus *status) {
                     int total_min = hour * 60 + minute + printer.remaining_time_min;
                     int eta_hour = (total_min / 60) % 24;
                     int eta_min = total_min % 60;
-                    snprintf(buf, sizeof(buf), "%02d:%02d", eta_hour, eta_min);
+                    if (time_is_24h()) {
+                        snprintf(buf, sizeof(buf), "%02d:%02d", eta_hour, eta_min);
+                    } else {
+                        snprintf(buf, sizeof(buf), "%d:%02d %s", (eta_hour + 11) % 12 + 1, eta_min,
+                                 eta_hour < 12 ? "AM" : "PM");
+                    }
                     lv_label_set_text(status_eta_label, buf);
                     lv_obj_set_pos(status_eta_label, 400, 27);
                 }
@@ -384,17 +389,20 @@ static void update_clock_displays(void) {
     int time_hhmm = time_get_hhmm();
     int screen_id = currentScreen + 1;
 
-    // Only update if time changed or first valid time
-    if (time_hhmm < 0 || time_hhmm == last_time_hhmm) {
+    // Only update if time (or the 12/24h format) changed or first valid time
+    if (time_hhmm < 0) {
+        return;
+    }
+    time_hhmm |= time_is_24h() << 16;
+    if (time_hhmm == last_time_hhmm) {
         return;
     }
     last_time_hhmm = time_hhmm;
 
-    int hour = (time_hhmm >> 8) & 0xFF;
-    int minute = time_hhmm & 0xFF;
-
-    char time_str[8];
-    snprintf(time_str, sizeof(time_str), "%02d:%02d", hour, minute);
+    char time_str[12];
+    if (time_format_clock(time_str, sizeof(time_str)) < 0) {
+        return;
+    }
 
     // Update clock for CURRENT screen only - other screen objects are stale!
     lv_obj_t *clock = NULL;
@@ -878,12 +878,8 @@ void update_nfc_screen(void) {
 
     // Update clock
     if (nfc_screen_top_bar_clock) {
-        int time_hhmm = time_get_hhmm();
-        if (time_hhmm >= 0) {
-            int hour = (time_hhmm >> 8) & 0xFF;
-            int minute = time_hhmm & 0xFF;
-            char time_str[8];
-            snprintf(time_str, sizeof(time_str), "%02d:%02d", hour, minute);
+        char time_str[12];
+        if (time_format_clock(time_str, sizeof(time_str)) >= 0) {
             lv_label_set_text(nfc_screen_top_bar_clock, time_str);
         }
     }
@@ -894,12 +890,8 @@ void update_scale_calibration_screen(void) {
 
     // Update clock
     if (scale_cal_top_bar_clock) {
-        int time_hhmm = time_get_hhmm();
-        if (time_hhmm >= 0) {
-            int hour = (time_hhmm >> 8) & 0xFF;
-            int minute = time_hhmm & 0xFF;
-            char time_str[8];
-            snprintf(time_str, sizeof(time_str), "%02d:%02d", hour, minute);
+        char time_str[12];
+        if (time_format_clock(time_str, sizeof(time_str)) >= 0) {
             lv_label_set_text(scale_cal_top_bar_clock, time_str);
         }
     }
@@ -1306,12 +1298,8 @@ void update_keyboard_layout_screen(void) {
 
     // Update clock
     if (kb_layout_top_bar_clock) {
-        int time_hhmm = time_get_hhmm();
-        if (time_hhmm >= 0) {
-            int hour = (time_hhmm >> 8) & 0xFF;
-            int minute = time_hhmm & 0xFF;
-            char time_str[8];
-            snprintf(time_str, sizeof(time_str), "%02d:%02d", hour, minute);
+        char time_str[12];
+        if (time_format_clock(time_str, sizeof(time_str)) >= 0) {
             lv_label_set_text(kb_layout_top_bar_clock, time_str);
         }
     }
@@ -26,6 +26,40 @@ typedef struct {
     uint8_t auth_mode; // 0=Open, 1=WEP, 2=WPA, 3=WPA2, 4=WPA3
 } WifiScanResult;
 
+// Saved WiFi network (list index = priority, 0 = highest)
+typedef struct {
+    char ssid[33];      // SSID (null-terminated)
+    bool has_password;  // Password stored
+    bool connected;     // Currently connected network
+    uint8_t auth_mode;  // 0=Auto, 1=Open, 2=WPA2, 3=WPA3, 4=WPA2/WPA3, 5=WPA2-Enterprise
+    bool hidden;        // SSID not broadcast
+    bool bssid_pinned;  // Only joins one access point
+    bool unverified;    // Enterprise server not verified (no EAP CA in the build)
+} SavedWifiNetwork;
+
+// Full WiFi connection settings (wifi_connect_ex / wifi_saved_add_ex)
+typedef struct {
+    char ssid[33];          // SSID (null-terminated)
+    char password[65];      // PSK or EAP password (null-terminated)
+    uint8_t auth_mode;      // 0=Auto, 1=Open, 2=WPA2, 3=WPA3, 4=WPA2/WPA3, 5=WPA2-Enterprise
+    char eap_identity[64];  // EAP outer identity, empty = use username
+    char eap_username[64];  // EAP username (enterprise)
+    bool eap_allow_unverified;  // Join without an EAP CA (server not verified)
+    uint8_t bssid[6];       // Access point to pin to
+    bool pin_bssid;         // Use bssid
+    bool hidden;            // SSID not broadcast
+} WifiConnectParams;
+
+// Station IPv4 settings (applies to every network)
+typedef struct {
+    bool use_static;          // Static addresses instead of DHCP
+    uint8_t ip[4];
+    uint8_t netmask[4];
+    uint8_t gateway[4];
+    uint8_t dns_primary[4];   // 0.0.0.0 = not set
+    uint8_t dns_secondary[4];
+} WifiIpConfig;
+
 // Printer discovery result from Rust
 typedef struct {
     char name[64];      // Printer name (null-terminated)
@@ -51,16 +85,42 @@ typedef struct {
 
 // WiFi functions
 extern int wifi_connect(const char *ssid, const char *password);
+extern int wifi_connect_ex(const WifiConnectParams *params);
 extern void wifi_get_status(WifiStatus *status);
 extern int wifi_disconnect(void);
 extern int wifi_is_connected(void);
 extern int wifi_get_ssid(char *buf, int buf_len);
+extern bool wifi_is_unverified(void);
 extern int wifi_scan(WifiScanResult *results, int max_results);
 extern int8_t wifi_get_rssi(void);
 
+// Saved networks (connect_best picks the strongest one in range)
+extern int wifi_saved_count(void);
+extern int wifi_saved_get(int index, SavedWifiNetwork *out);
+extern int wifi_saved_add(const char *ssid, const char *password);
+extern int wifi_saved_add_ex(const WifiConnectParams *params);
+extern int wifi_saved_remove(int index);
+extern int wifi_saved_move(int from, int to);
+extern int wifi_connect_best(void);
+
+// Station IP settings (DHCP or static, persisted)
+extern void wifi_get_ip_config(WifiIpConfig *out);
+extern int wifi_set_ip_config(const WifiIpConfig *config);
+
+// SoftAP setup portal (WiFi + backend settings from a phone browser)
+extern int config_portal_start(void);
+extern void config_portal_stop(void);
+extern bool config_portal_is_active(void);
+extern int config_portal_get_ssid(char *buf, int buf_len);
+
 // Printer discovery
 extern int printer_discover(PrinterDiscoveryResult *results, int max_results);
 
+// Direct LAN MQTT to printers while the backend is offline
+extern int printer_lan_get_enabled(void);
+extern int printer_lan_set_enabled(int enabled);
+extern int printer_lan_active_count(void);
+
 // =============================================================================
 // Backend Client Types and Functions (for server communication)
 // =============================================================================
@@ -141,6 +201,29 @@ extern int backend_get_active_extruder(int printer_index);  // -1=unknown, 0=rig
 extern int time_get_hhmm(void);
 extern int time_is_synced(void);
 
+// Local date/time (POSIX TZ with DST), valid once the clock is set (SNTP or backend)
+typedef struct {
+    int32_t year;
+    uint8_t month;      // 1-12
+    uint8_t day;        // 1-31
+    uint8_t weekday;    // 0 = Sunday
+    uint8_t hour;
+    uint8_t minute;
+    uint8_t second;
+} TimeLocal;
+
+// Returns 0 on success, -1 if the date is unknown
+extern int time_get_local(TimeLocal *out);
+// Format current time ("14:05" or "2:05 PM") / date ("Sun 18 Oct 2026"), -1 if unknown
+extern int time_format_clock(char *buf, int buf_len);
+extern int time_format_date(char *buf, int buf_len);
+// Clock format: 1 = 24h, 0 = 12h (set returns 0 on success, -1 on error)
+extern int time_is_24h(void);
+extern int time_set_24h(int enabled);
+// POSIX TZ string, e.g. "CET-1CEST,M3.5.0,M10.5.0/3" (set returns 0 on success, -1 on error)
+extern int time_get_timezone(char *buf, int buf_len);
+extern int time_set_timezone(const char *tz);
+
 // OTA manager functions (implemented in Rust)
 // Returns 1 if update available, 0 otherwise
 extern int ota_is_update_available(void);
@@ -156,6 +239,22 @@ extern int ota_get_progress(void);
 extern int ota_check_for_update(void);
 // Start OTA update (non-blocking)
 extern int ota_start_update(void);
+// Update channel: 0=stable, 1=beta, 2=dev (set returns 0 on success, -1 on error)
+extern int ota_get_channel(void);
+extern int ota_set_channel(int channel);
+// Automatic install in the install window while no printer is printing (1=on, 0=off)
+extern int ota_get_auto_install(void);
+extern int ota_set_auto_install(int enabled);
+// Install window in minutes since midnight (start == end: any time)
+extern void ota_get_install_window(int *start, int *end);
+extern int ota_set_install_window(int start, int end);
+// NFC bridge firmware (states and progress as for ESP32 OTA, -1 if unknown/none)
+extern int bridge_ota_get_state(void);
+extern int bridge_ota_get_progress(void);
+extern int bridge_ota_get_version(char *buf, int buf_len);
+extern int bridge_ota_get_update_version(char *buf, int buf_len);
+// Start bridge update (non-blocking)
+extern int bridge_ota_start_update(void);
 
 // =============================================================================
 // Spool API Types and Functions (implemented in Rust)
@@ -173,9 +272,20 @@ typedef struct {
     int32_t label_weight;   // Label weight in grams
     int32_t weight_current; // Current weight from inventory (grams)
     char slicer_filament[32]; // Slicer filament ID
+    int32_t core_weight;    // Empty spool weight used as tare (grams)
     bool valid;             // True if spool was found
 } SpoolInfoC;
 
+// Net filament estimate for the spool on the scale
+typedef struct {
+    float gross_weight;        // Scale reading (grams)
+    int32_t core_weight;       // Core tare applied (grams)
+    float net_weight;          // Remaining filament (grams)
+    float remaining_length_m;  // Remaining filament length (meters)
+    int32_t fill_pct;          // Net weight as % of label weight
+    bool valid;                // False if no spool is active
+} FilamentEstimateC;
+
 // K-profile (pressure advance calibration) for a spool
 typedef struct {
     int32_t cali_idx;       // Calibration index (-1 if not found)
@@ -199,6 +309,55 @@ extern bool spool_get_k_profile_for_printer(const char *spool_id, const char *pr
 extern int backend_assign_spool_to_tray(const char *printer_serial, int ams_id, int tray_id, const char *spool_id);
 extern bool spool_sync_weight(const char *spool_id, int weight);
 
+// Net weight/length for the spool last matched by spool_get_by_tag
+extern bool filament_get_estimate(FilamentEstimateC *out);
+
+// Automatic weight sync (posted once when a known spool settles on the scale)
+typedef enum {
+    WEIGHT_SYNC_UNDO_NONE = 0,
+    WEIGHT_SYNC_UNDO_PENDING = 1,  // Queued for the network thread
+    WEIGHT_SYNC_UNDO_DONE = 2,
+    WEIGHT_SYNC_UNDO_FAILED = 3,
+} WeightSyncUndo;
+
+typedef struct {
+    uint32_t seq;              // Increments per sync (0 = none)
+    char spool_id[64];         // Spool UUID
+    int32_t previous_weight;   // Inventory weight before sync (grams)
+    int32_t synced_weight;     // Weight posted (grams)
+    bool had_weight;           // The spool had a weight before the sync
+    uint8_t undo_state;        // WeightSyncUndo
+} WeightSyncEventC;
+
+extern bool weight_sync_get_event(WeightSyncEventC *out);
+extern int weight_sync_undo(uint32_t seq);
+extern void weight_sync_dismiss(uint32_t seq);
+
+// Consumption rate from the per-spool weight history
+typedef struct {
+    float rate_g_per_hour;      // Consumption rate (0 if idle)
+    float net_weight;           // Net filament left (grams)
+    int32_t time_to_empty_min;  // Minutes until empty (-1 if not consuming)
+    int32_t print_remaining_min; // Remaining print time (-1 if not printing)
+    int32_t sample_count;       // Readings used for the estimate
+    bool runout_predicted;      // Spool runs out before the print ends
+    bool valid;                 // False if not enough history yet
+} ConsumptionEstimateC;
+
+extern bool weight_history_get_estimate(ConsumptionEstimateC *out);
+
+// Spool predicted to run out before its printer's job ends
+typedef struct {
+    uint32_t seq;               // Changes when a new alert is raised
+    char printer_name[32];      // Printer the spool feeds
+    char slot[16];              // Slot holding the spool ("AMS-A1", "External")
+    int32_t time_to_empty_min;  // Minutes until the spool is empty
+    int32_t print_remaining_min; // Remaining print time
+} RunoutAlertC;
+
+extern bool weight_history_get_runout_alert(RunoutAlertC *out);
+extern void weight_history_dismiss_runout(uint32_t seq);
+
 // Check if a spool with given tag_id exists in inventory
 extern bool spool_exists_by_tag(const char *tag_id);
 
@@ -79,6 +79,18 @@ static int untagged_spools_count = 0;
 static lv_obj_t *details_modal = NULL;
 static char details_modal_spool_id[64] = {0};  // For sync button
 
+// Auto weight sync confirmation toast
+static lv_obj_t *sync_toast = NULL;
+static lv_obj_t *sync_toast_msg = NULL;
+static lv_obj_t *sync_toast_undo_btn = NULL;
+static uint32_t sync_toast_seq = 0;
+static uint8_t sync_toast_undo_state = WEIGHT_SYNC_UNDO_NONE;
+
+// Runout alert toast (spool runs out before its printer's job ends)
+static lv_obj_t *runout_toast = NULL;
+static lv_obj_t *runout_toast_label = NULL;
+static uint32_t runout_toast_seq = 0;
+
 // Close handler for details modal
 static void details_modal_close_handler(lv_event_t *e) {
     (void)e;
@@ -430,24 +442,40 @@ void ui_nfc_card_show_details(void) {
         lv_obj_set_style_pad_all(fill_section, 0, 0);
         lv_obj_clear_flag(fill_section, LV_OBJ_FLAG_SCROLLABLE);
 
-        // Calculate fill percentage
+        // Calculate fill percentage from net weight (gross minus spool core)
         int fill_pct = 0;
-        if (spool_info.label_weight > 0 && scale_ok) {
-            int filament_weight = scale_weight - 200;  // ~200g core
-            if (filament_weight < 0) filament_weight = 0;
-            fill_pct = (filament_weight * 100) / spool_info.label_weight;
-            if (fill_pct > 100) fill_pct = 100;
-            if (fill_pct < 0) fill_pct = 0;
+        FilamentEstimateC estimate = {0};
+        bool has_estimate = scale_ok && filament_get_estimate(&estimate);
+        if (has_estimate) {
+            fill_pct = estimate.fill_pct;
+        }
+
+        // Time-to-empty while the spool is being consumed (red if it runs out before the print ends)
+        ConsumptionEstimateC consumption = {0};
+        bool consuming = has_estimate && weight_history_get_estimate(&consumption) &&
+                         consumption.time_to_empty_min >= 0;
+        char fill_title[48];
+        if (consuming) {
+            snprintf(fill_title, sizeof(fill_title), "Fill Level  ~%dh %02dm left",
+                     consumption.time_to_empty_min / 60, consumption.time_to_empty_min % 60);
+        } else {
+            snprintf(fill_title, sizeof(fill_title), "Fill Level");
         }
 
         lv_obj_t *fill_label = lv_label_create(fill_section);
-        lv_label_set_text(fill_label, "Fill Level");
+        lv_label_set_text(fill_label, fill_title);
         lv_obj_set_style_text_font(fill_label, &lv_font_montserrat_12, 0);
-        lv_obj_set_style_text_color(fill_label, lv_color_hex(0x777777), 0);
+        lv_obj_set_style_text_color(fill_label,
+            lv_color_hex(consuming && consumption.runout_predicted ? 0xff5555 : 0x777777), 0);
         lv_obj_align(fill_label, LV_ALIGN_TOP_LEFT, 0, 0);
 
-        char fill_str[16];
-        snprintf(fill_str, sizeof(fill_str), "%d%%", fill_pct);
+        char fill_str[48];
+        if (has_estimate) {
+            snprintf(fill_str, sizeof(fill_str), "%dg / %dm  %d%%",
+                     (int)estimate.net_weight, (int)estimate.remaining_length_m, fill_pct);
+        } else {
+            snprintf(fill_str, sizeof(fill_str), "%d%%", fill_pct);
+        }
         lv_obj_t *fill_pct_label = lv_label_create(fill_section);
         lv_label_set_text(fill_pct_label, fill_str);
         lv_obj_set_style_text_font(fill_pct_label, &lv_font_montserrat_12, 0);
@@ -1266,13 +1294,219 @@ void ui_nfc_card_set_configured_tag(const char *tag_id) {
     }
 }
 
+// ============================================================================
+// Auto weight sync toast - confirms a sync posted by firmware, offers undo
+// ============================================================================
+
+static void close_sync_toast(void) {
+    if (sync_toast) {
+        lv_obj_delete(sync_toast);
+        sync_toast = NULL;
+        sync_toast_msg = NULL;
+        sync_toast_undo_btn = NULL;
+    }
+}
+
+// Message and undo button for the event's undo progress
+static void set_sync_toast_text(const WeightSyncEventC *event) {
+    sync_toast_undo_state = event->undo_state;
+
+    char text[64];
+    switch (event->undo_state) {
+        case WEIGHT_SYNC_UNDO_PENDING:
+            snprintf(text, sizeof(text), LV_SYMBOL_REFRESH " Undoing weight sync...");
+            break;
+        case WEIGHT_SYNC_UNDO_DONE:
+            snprintf(text, sizeof(text), LV_SYMBOL_OK " Weight sync undone");
+            break;
+        case WEIGHT_SYNC_UNDO_FAILED:
+            snprintf(text, sizeof(text), LV_SYMBOL_WARNING " Undo failed, weight stays %dg",
+                     (int)event->synced_weight);
+            break;
+        default:
+            if (event->had_weight) {
+                snprintf(text, sizeof(text), LV_SYMBOL_OK " Weight synced: %dg (was %dg)",
+                         (int)event->synced_weight, (int)event->previous_weight);
+            } else {
+                snprintf(text, sizeof(text), LV_SYMBOL_OK " Weight synced: %dg",
+                         (int)event->synced_weight);
+            }
+            break;
+    }
+    lv_label_set_text(sync_toast_msg, text);
+
+    // Undo is offered once; the network thread runs it
+    if (event->undo_state == WEIGHT_SYNC_UNDO_NONE) {
+        lv_obj_clear_flag(sync_toast_undo_btn, LV_OBJ_FLAG_HIDDEN);
+    } else {
+        lv_obj_add_flag(sync_toast_undo_btn, LV_OBJ_FLAG_HIDDEN);
+    }
+}
+
+static void sync_toast_undo_handler(lv_event_t *e) {
+    (void)e;
+    if (weight_sync_undo(sync_toast_seq) == 0) {
+        ESP_LOGI(TAG, "Auto-sync %u undo queued", (unsigned int)sync_toast_seq);
+        WeightSyncEventC event = {0};
+        if (weight_sync_get_event(&event) && event.seq == sync_toast_seq) {
+            set_sync_toast_text(&event);
+        }
+    } else {
+        ESP_LOGE(TAG, "Failed to undo auto-sync %u", (unsigned int)sync_toast_seq);
+        close_sync_toast();
+    }
+}
+
+static void sync_toast_close_handler(lv_event_t *e) {
+    (void)e;
+    // Keep showing a queued undo until its outcome is known
+    if (sync_toast_undo_state == WEIGHT_SYNC_UNDO_PENDING) {
+        return;
+    }
+    weight_sync_dismiss(sync_toast_seq);
+    close_sync_toast();
+}
+
+static void create_sync_toast(const WeightSyncEventC *event) {
+    close_sync_toast();
+    sync_toast_seq = event->seq;
+
+    sync_toast = lv_obj_create(lv_layer_top());
+    lv_obj_set_size(sync_toast, 460, 56);
+    lv_obj_align(sync_toast, LV_ALIGN_BOTTOM_MID, 0, -16);
+    lv_obj_set_style_bg_color(sync_toast, lv_color_hex(0x2D2D2D), LV_PART_MAIN);
+    lv_obj_set_style_bg_opa(sync_toast, 255, LV_PART_MAIN);
+    lv_obj_set_style_border_color(sync_toast, lv_color_hex(0x4CAF50), LV_PART_MAIN);
+    lv_obj_set_style_border_width(sync_toast, 1, LV_PART_MAIN);
+    lv_obj_set_style_radius(sync_toast, 12, LV_PART_MAIN);
+    lv_obj_set_style_pad_all(sync_toast, 8, LV_PART_MAIN);
+    lv_obj_clear_flag(sync_toast, LV_OBJ_FLAG_SCROLLABLE);
+    lv_obj_add_event_cb(sync_toast, sync_toast_close_handler, LV_EVENT_CLICKED, NULL);
+
+    lv_obj_t *msg = lv_label_create(sync_toast);
+    lv_obj_set_style_text_font(msg, &lv_font_montserrat_14, 0);
+    lv_obj_set_style_text_color(msg, lv_color_hex(0xFFFFFF), 0);
+    lv_obj_align(msg, LV_ALIGN_LEFT_MID, 4, 0);
+    sync_toast_msg = msg;
+
+    lv_obj_t *btn_undo = lv_btn_create(sync_toast);
+    lv_obj_set_size(btn_undo, 80, 36);
+    lv_obj_align(btn_undo, LV_ALIGN_RIGHT_MID, 0, 0);
+    lv_obj_set_style_bg_color(btn_undo, lv_color_hex(0x555555), 0);
+    lv_obj_set_style_radius(btn_undo, 18, 0);
+    lv_obj_add_event_cb(btn_undo, sync_toast_undo_handler, LV_EVENT_CLICKED, NULL);
+
+    lv_obj_t *undo_label = lv_label_create(btn_undo);
+    lv_label_set_text(undo_label, "Undo");
+    lv_obj_set_style_text_font(undo_label, &lv_font_montserrat_12, 0);
+    lv_obj_set_style_text_color(undo_label, lv_color_hex(0xFFFFFF), 0);
+    lv_obj_center(undo_label);
+    sync_toast_undo_btn = btn_undo;
+
+    set_sync_toast_text(event);
+}
+
+// Show a new sync event and the progress of its undo, hide the toast once the
+// undo window (or the undo outcome) has passed
+static void update_sync_toast(void) {
+    WeightSyncEventC event = {0};
+    if (!weight_sync_get_event(&event)) {
+        close_sync_toast();
+        return;
+    }
+    if (!sync_toast || event.seq != sync_toast_seq) {
+        ESP_LOGI(TAG, "Auto-sync %u: %dg -> %dg", (unsigned int)event.seq,
+                 (int)event.previous_weight, (int)event.synced_weight);
+        create_sync_toast(&event);
+    } else if (event.undo_state != sync_toast_undo_state) {
+        set_sync_toast_text(&event);
+    }
+}
+
+// ============================================================================
+// Runout alert toast - spool on the scale runs out before the print ends
+// ============================================================================
+
+static void close_runout_toast(void) {
+    if (runout_toast) {
+        lv_obj_delete(runout_toast);
+        runout_toast = NULL;
+        runout_toast_label = NULL;
+    }
+}
+
+static void runout_toast_close_handler(lv_event_t *e) {
+    (void)e;
+    weight_history_dismiss_runout(runout_toast_seq);
+    close_runout_toast();
+}
+
+static void format_runout_text(const RunoutAlertC *alert, char *buf, size_t buf_len) {
+    snprintf(buf, buf_len, LV_SYMBOL_WARNING " %s %s: ~%dh %02dm left, print needs ~%dh %02dm",
+             alert->printer_name, alert->slot,
+             (int)(alert->time_to_empty_min / 60), (int)(alert->time_to_empty_min % 60),
+             (int)(alert->print_remaining_min / 60), (int)(alert->print_remaining_min % 60));
+}
+
+static void create_runout_toast(const RunoutAlertC *alert) {
+    close_runout_toast();
+    runout_toast_seq = alert->seq;
+
+    runout_toast = lv_obj_create(lv_layer_top());
+    lv_obj_set_size(runout_toast, 460, 56);
+    lv_obj_align(runout_toast, LV_ALIGN_TOP_MID, 0, 16);
+    lv_obj_set_style_bg_color(runout_toast, lv_color_hex(0x2D2D2D), LV_PART_MAIN);
+    lv_obj_set_style_bg_opa(runout_toast, 255, LV_PART_MAIN);
+    lv_obj_set_style_border_color(runout_toast, lv_color_hex(0xFF5555), LV_PART_MAIN);
+    lv_obj_set_style_border_width(runout_toast, 1, LV_PART_MAIN);
+    lv_obj_set_style_radius(runout_toast, 12, LV_PART_MAIN);
+    lv_obj_set_style_pad_all(runout_toast, 8, LV_PART_MAIN);
+    lv_obj_clear_flag(runout_toast, LV_OBJ_FLAG_SCROLLABLE);
+    lv_obj_add_event_cb(runout_toast, runout_toast_close_handler, LV_EVENT_CLICKED, NULL);
+
+    char text[128];
+    format_runout_text(alert, text, sizeof(text));
+    runout_toast_label = lv_label_create(runout_toast);
+    lv_label_set_text(runout_toast_label, text);
+    lv_label_set_long_mode(runout_toast_label, LV_LABEL_LONG_DOT);
+    lv_obj_set_width(runout_toast_label, 436);
+    lv_obj_set_style_text_font(runout_toast_label, &lv_font_montserrat_14, 0);
+    lv_obj_set_style_text_color(runout_toast_label, lv_color_hex(0xFFFFFF), 0);
+    lv_obj_align(runout_toast_label, LV_ALIGN_LEFT_MID, 4, 0);
+}
+
+// Show a new runout alert until dismissed, keep its times current
+static void update_runout_toast(void) {
+    RunoutAlertC alert = {0};
+    if (!weight_history_get_runout_alert(&alert)) {
+        close_runout_toast();
+        return;
+    }
+    if (!runout_toast || alert.seq != runout_toast_seq) {
+        ESP_LOGW(TAG, "Runout predicted: %s %s, %dm left, print needs %dm", alert.printer_name,
+                 alert.slot, (int)alert.time_to_empty_min, (int)alert.print_remaining_min);
+        create_runout_toast(&alert);
+        return;
+    }
+    char text[128];
+    format_runout_text(&alert, text, sizeof(text));
+    if (strcmp(lv_label_get_text(runout_toast_label), text) != 0) {
+        lv_label_set_text(runout_toast_label, text);
+    }
+}
+
 void ui_nfc_card_cleanup(void) {
+    close_runout_toast();
+    close_sync_toast();
     close_popup();
     last_tag_present = false;
     // Don't reset configured_tag_id - it needs to persist across screen transitions
 }
 
 void ui_nfc_card_update(void) {
+    update_sync_toast();
+    update_runout_toast();
+
     if (!nfc_is_initialized()) {
         ESP_LOGD(TAG, "NFC not initialized, skipping update");
         return;
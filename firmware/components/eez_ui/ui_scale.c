@@ -22,6 +22,12 @@ extern bool scale_is_stable(void);
 extern int32_t scale_tare(void);
 extern int32_t scale_calibrate(float known_weight_grams);
 extern int32_t scale_get_tare_offset(void);
+// Indexed variants for multi-scale setups (scale 0 = primary)
+extern int scale_get_count(void);
+extern float scale_get_weight_at(int index);
+extern bool scale_is_stable_at(int index);
+extern int32_t scale_tare_at(int index);
+extern int32_t scale_calibrate_at(int index, float known_weight_grams);
 #else
 // Simulator: Scale functions that read from backend (which gets from ESP32 device)
 // Forward declare backend functions to avoid header conflicts
@@ -52,6 +58,15 @@ int32_t scale_calibrate(float known_weight_grams) {
 }
 int32_t scale_get_tare_offset(void) { return 0; }  // Tare offset is managed by ESP32
 
+// Simulator only mirrors the primary scale
+int scale_get_count(void) { return 1; }
+float scale_get_weight_at(int index) { return index == 0 ? scale_get_weight() : 0.0f; }
+bool scale_is_stable_at(int index) { return index == 0 && scale_is_stable(); }
+int32_t scale_tare_at(int index) { return index == 0 ? scale_tare() : -1; }
+int32_t scale_calibrate_at(int index, float known_weight_grams) {
+    return index == 0 ? scale_calibrate(known_weight_grams) : -1;
+}
+
 // Simulator control functions (kept for compatibility, but now no-op)
 void sim_set_scale_weight(float weight) { (void)weight; }
 void sim_set_scale_initialized(bool initialized) { (void)initialized; }
@@ -25,6 +25,18 @@ static lv_obj_t *wifi_scan_list = NULL;
 // Static storage for scan results (must persist for button callbacks)
 static WifiScanResult wifi_scan_results_storage[16];
 
+// IP settings popup (DHCP / static)
+#define WIFI_IP_FIELD_COUNT 5
+static lv_obj_t *wifi_ip_settings_btn = NULL;
+static lv_obj_t *wifi_ip_popup = NULL;
+static lv_obj_t *wifi_ip_keyboard = NULL;
+static lv_obj_t *wifi_ip_static_switch = NULL;
+static lv_obj_t *wifi_ip_status_label = NULL;
+static lv_obj_t *wifi_ip_inputs[WIFI_IP_FIELD_COUNT];
+
+// Setup portal (SoftAP with a web page for WiFi and backend settings)
+static lv_obj_t *wifi_portal_btn = NULL;
+
 // =============================================================================
 // Internal Helpers
 // =============================================================================
@@ -363,6 +375,234 @@ static void wifi_scan_click_handler(lv_event_t *e) {
     lv_obj_center(close_label);
 }
 
+// =============================================================================
+// IP Settings Popup
+// =============================================================================
+
+static const char *wifi_ip_field_names[WIFI_IP_FIELD_COUNT] = {
+    "IP Address", "Netmask", "Gateway", "DNS Server", "Secondary DNS"
+};
+
+// Parse dotted IPv4 text ("" = 0.0.0.0). Returns false if malformed.
+static bool wifi_parse_ip(const char *text, uint8_t out[4]) {
+    memset(out, 0, 4);
+    if (!text || text[0] == '\0') return true;
+
+    unsigned int a, b, c, d;
+    char extra;
+    if (sscanf(text, "%u.%u.%u.%u%c", &a, &b, &c, &d, &extra) != 4) return false;
+    if (a > 255 || b > 255 || c > 255 || d > 255) return false;
+    out[0] = a; out[1] = b; out[2] = c; out[3] = d;
+    return true;
+}
+
+static void wifi_format_ip(const uint8_t ip[4], char *buf, size_t len) {
+    if (ip[0] == 0 && ip[1] == 0 && ip[2] == 0 && ip[3] == 0) {
+        buf[0] = '\0';
+    } else {
+        snprintf(buf, len, "%d.%d.%d.%d", ip[0], ip[1], ip[2], ip[3]);
+    }
+}
+
+static void wifi_ip_close(void) {
+    if (wifi_ip_keyboard) {
+        lv_obj_delete(wifi_ip_keyboard);
+        wifi_ip_keyboard = NULL;
+    }
+    if (wifi_ip_popup) {
+        lv_obj_delete(wifi_ip_popup);
+        wifi_ip_popup = NULL;
+    }
+    wifi_ip_static_switch = NULL;
+    wifi_ip_status_label = NULL;
+    memset(wifi_ip_inputs, 0, sizeof(wifi_ip_inputs));
+}
+
+// Address fields are only editable in static mode
+static void wifi_ip_update_fields(void) {
+    bool use_static = wifi_ip_static_switch && lv_obj_has_state(wifi_ip_static_switch, LV_STATE_CHECKED);
+    for (int i = 0; i < WIFI_IP_FIELD_COUNT; i++) {
+        if (!wifi_ip_inputs[i]) continue;
+        if (use_static) {
+            lv_obj_remove_state(wifi_ip_inputs[i], LV_STATE_DISABLED);
+        } else {
+            lv_obj_add_state(wifi_ip_inputs[i], LV_STATE_DISABLED);
+        }
+    }
+}
+
+static void wifi_ip_switch_handler(lv_event_t *e) {
+    wifi_ip_update_fields();
+}
+
+static void wifi_ip_keyboard_handler(lv_event_t *e) {
+    lv_event_code_t code = lv_event_get_code(e);
+    if (code == LV_EVENT_READY || code == LV_EVENT_CANCEL) {
+        lv_obj_add_flag(wifi_ip_keyboard, LV_OBJ_FLAG_HIDDEN);
+        if (wifi_ip_popup) lv_obj_center(wifi_ip_popup);
+    }
+}
+
+static void wifi_ip_input_click_handler(lv_event_t *e) {
+    lv_obj_t *ta = lv_event_get_target(e);
+    if (!ta || !wifi_ip_keyboard || lv_obj_has_state(ta, LV_STATE_DISABLED)) return;
+
+    lv_keyboard_set_textarea(wifi_ip_keyboard, ta);
+    lv_obj_remove_flag(wifi_ip_keyboard, LV_OBJ_FLAG_HIDDEN);
+    lv_obj_move_foreground(wifi_ip_keyboard);
+    // Keep the popup above the keyboard
+    if (wifi_ip_popup) lv_obj_align(wifi_ip_popup, LV_ALIGN_TOP_MID, 0, 5);
+}
+
+static void wifi_ip_cancel_handler(lv_event_t *e) {
+    wifi_ip_close();
+}
+
+static void wifi_ip_save_handler(lv_event_t *e) {
+    WifiIpConfig config;
+    memset(&config, 0, sizeof(config));
+    config.use_static = lv_obj_has_state(wifi_ip_static_switch, LV_STATE_CHECKED);
+
+    uint8_t *fields[WIFI_IP_FIELD_COUNT] = {
+        config.ip, config.netmask, config.gateway, config.dns_primary, config.dns_secondary
+    };
+    for (int i = 0; i < WIFI_IP_FIELD_COUNT; i++) {
+        const char *text = lv_textarea_get_text(wifi_ip_inputs[i]);
+        if (!wifi_parse_ip(text, fields[i])) {
+            char buf[48];
+            snprintf(buf, sizeof(buf), "Invalid %s", wifi_ip_field_names[i]);
+            lv_label_set_text(wifi_ip_status_label, buf);
+            return;
+        }
+    }
+
+    if (wifi_set_ip_config(&config) != 0) {
+        lv_label_set_text(wifi_ip_status_label, "Settings not valid - check address, netmask and gateway");
+        return;
+    }
+#ifdef ESP_PLATFORM
+    ESP_LOGI(TAG, "IP settings saved (%s)", config.use_static ? "static" : "DHCP");
+#endif
+    wifi_ip_close();
+}
+
+static lv_obj_t *wifi_ip_create_button(lv_obj_t *parent, const char *text, uint32_t bg, uint32_t fg,
+                                       lv_event_cb_t cb) {
+    lv_obj_t *btn = lv_button_create(parent);
+    lv_obj_set_size(btn, 150, 40);
+    lv_obj_set_style_bg_color(btn, lv_color_hex(bg), LV_PART_MAIN);
+    lv_obj_set_style_radius(btn, 6, LV_PART_MAIN);
+    lv_obj_add_event_cb(btn, cb, LV_EVENT_CLICKED, NULL);
+    lv_obj_t *label = lv_label_create(btn);
+    lv_label_set_text(label, text);
+    lv_obj_set_style_text_color(label, lv_color_hex(fg), LV_PART_MAIN);
+    lv_obj_center(label);
+    return btn;
+}
+
+static void wifi_ip_settings_click_handler(lv_event_t *e) {
+    wifi_hide_keyboard();
+    wifi_ip_close();
+
+    lv_obj_t *screen = lv_screen_active();
+    if (!screen) return;
+
+    WifiIpConfig config;
+    memset(&config, 0, sizeof(config));
+    wifi_get_ip_config(&config);
+
+    wifi_ip_popup = lv_obj_create(screen);
+    lv_obj_set_size(wifi_ip_popup, 460, 420);
+    lv_obj_center(wifi_ip_popup);
+    lv_obj_move_foreground(wifi_ip_popup);
+    lv_obj_set_style_bg_color(wifi_ip_popup, lv_color_hex(0xff1a1a1a), LV_PART_MAIN);
+    lv_obj_set_style_bg_opa(wifi_ip_popup, 255, LV_PART_MAIN);
+    lv_obj_set_style_border_color(wifi_ip_popup, lv_color_hex(0xff00ff00), LV_PART_MAIN);
+    lv_obj_set_style_border_width(wifi_ip_popup, 2, LV_PART_MAIN);
+    lv_obj_set_style_radius(wifi_ip_popup, 12, LV_PART_MAIN);
+    lv_obj_set_style_pad_all(wifi_ip_popup, 15, LV_PART_MAIN);
+    lv_obj_clear_flag(wifi_ip_popup, LV_OBJ_FLAG_SCROLLABLE);
+
+    lv_obj_t *title = lv_label_create(wifi_ip_popup);
+    lv_label_set_text(title, "IP Settings");
+    lv_obj_set_style_text_color(title, lv_color_hex(0xff00ff00), LV_PART_MAIN);
+    lv_obj_set_style_text_font(title, &lv_font_montserrat_18, LV_PART_MAIN);
+    lv_obj_set_pos(title, 0, 0);
+
+    // DHCP / static toggle
+    lv_obj_t *mode_label = lv_label_create(wifi_ip_popup);
+    lv_label_set_text(mode_label, "Static IP (off = DHCP)");
+    lv_obj_set_style_text_color(mode_label, lv_color_hex(0xffffffff), LV_PART_MAIN);
+    lv_obj_set_pos(mode_label, 0, 42);
+
+    wifi_ip_static_switch = lv_switch_create(wifi_ip_popup);
+    lv_obj_set_pos(wifi_ip_static_switch, 360, 36);
+    lv_obj_set_style_bg_color(wifi_ip_static_switch, lv_color_hex(0xff00ff00), LV_PART_INDICATOR | LV_STATE_CHECKED);
+    if (config.use_static) lv_obj_add_state(wifi_ip_static_switch, LV_STATE_CHECKED);
+    lv_obj_add_event_cb(wifi_ip_static_switch, wifi_ip_switch_handler, LV_EVENT_VALUE_CHANGED, NULL);
+
+    // Address fields
+    const uint8_t *values[WIFI_IP_FIELD_COUNT] = {
+        config.ip, config.netmask, config.gateway, config.dns_primary, config.dns_secondary
+    };
+    for (int i = 0; i < WIFI_IP_FIELD_COUNT; i++) {
+        int y = 80 + i * 48;
+        lv_obj_t *label = lv_label_create(wifi_ip_popup);
+        lv_label_set_text(label, wifi_ip_field_names[i]);
+        lv_obj_set_style_text_color(label, lv_color_hex(0xffaaaaaa), LV_PART_MAIN);
+        lv_obj_set_pos(label, 0, y + 10);
+
+        lv_obj_t *ta = lv_textarea_create(wifi_ip_popup);
+        lv_obj_set_pos(ta, 170, y);
+        lv_obj_set_size(ta, 255, 40);
+        lv_textarea_set_one_line(ta, true);
+        lv_textarea_set_max_length(ta, 15);
+        lv_textarea_set_accepted_chars(ta, "0123456789.");
+        lv_textarea_set_placeholder_text(ta, i == 1 ? "255.255.255.0" : "0.0.0.0");
+        char buf[16];
+        wifi_format_ip(values[i], buf, sizeof(buf));
+        lv_textarea_set_text(ta, buf);
+        lv_obj_add_event_cb(ta, wifi_ip_input_click_handler, LV_EVENT_CLICKED, NULL);
+        wifi_ip_inputs[i] = ta;
+    }
+    wifi_ip_update_fields();
+
+    wifi_ip_status_label = lv_label_create(wifi_ip_popup);
+    lv_label_set_text(wifi_ip_status_label, "");
+    lv_obj_set_style_text_color(wifi_ip_status_label, lv_color_hex(0xffff5555), LV_PART_MAIN);
+    lv_obj_set_pos(wifi_ip_status_label, 0, 322);
+
+    lv_obj_t *cancel_btn = wifi_ip_create_button(wifi_ip_popup, "Cancel", 0xff444444, 0xffffffff, wifi_ip_cancel_handler);
+    lv_obj_align(cancel_btn, LV_ALIGN_BOTTOM_LEFT, 0, 0);
+    lv_obj_t *save_btn = wifi_ip_create_button(wifi_ip_popup, "Save", 0xff00ff00, 0xff000000, wifi_ip_save_handler);
+    lv_obj_align(save_btn, LV_ALIGN_BOTTOM_RIGHT, 0, 0);
+
+    // Numeric keyboard for the address fields
+    wifi_ip_keyboard = lv_keyboard_create(screen);
+    lv_obj_set_size(wifi_ip_keyboard, 800, 220);
+    lv_obj_align(wifi_ip_keyboard, LV_ALIGN_BOTTOM_MID, 0, 0);
+    lv_keyboard_set_mode(wifi_ip_keyboard, LV_KEYBOARD_MODE_NUMBER);
+    lv_obj_add_flag(wifi_ip_keyboard, LV_OBJ_FLAG_HIDDEN);
+    lv_obj_add_event_cb(wifi_ip_keyboard, wifi_ip_keyboard_handler, LV_EVENT_ALL, NULL);
+}
+
+// =============================================================================
+// Setup Portal
+// =============================================================================
+
+static void wifi_portal_click_handler(lv_event_t *e) {
+    (void)e;
+    if (config_portal_is_active()) {
+        config_portal_stop();
+    } else if (config_portal_start() != 0) {
+        if (objects.settings_wifi_screen_content_panel_label_status) {
+            lv_label_set_text(objects.settings_wifi_screen_content_panel_label_status, "Status: Portal failed to start");
+        }
+        return;
+    }
+    update_wifi_ui_state();
+}
+
 // =============================================================================
 // WiFi UI State Update
 // =============================================================================
@@ -391,8 +631,14 @@ void update_wifi_ui_state(void) {
                 lv_label_set_text(objects.settings_wifi_screen_content_panel_label_status, "Status: Connecting...");
                 break;
             case 3: // Connected
-                snprintf(buf, sizeof(buf), "Connected: %d.%d.%d.%d",
-                         status.ip[0], status.ip[1], status.ip[2], status.ip[3]);
+                if (wifi_is_unverified()) {
+                    // Enterprise network joined without checking its server
+                    snprintf(buf, sizeof(buf), LV_SYMBOL_WARNING " Connected: %d.%d.%d.%d (server not verified)",
+                             status.ip[0], status.ip[1], status.ip[2], status.ip[3]);
+                } else {
+                    snprintf(buf, sizeof(buf), "Connected: %d.%d.%d.%d",
+                             status.ip[0], status.ip[1], status.ip[2], status.ip[3]);
+                }
                 lv_label_set_text(objects.settings_wifi_screen_content_panel_label_status, buf);
                 break;
             case 4: // Error
@@ -404,6 +650,20 @@ void update_wifi_ui_state(void) {
         }
     }
 
+    // Portal running - tell the user which network to join
+    if (config_portal_is_active() && status.state != 3 && objects.settings_wifi_screen_content_panel_label_status) {
+        char ap_ssid[33];
+        char buf[64];
+        if (config_portal_get_ssid(ap_ssid, sizeof(ap_ssid)) > 0) {
+            snprintf(buf, sizeof(buf), "Setup portal: join %s", ap_ssid);
+            lv_label_set_text(objects.settings_wifi_screen_content_panel_label_status, buf);
+        }
+    }
+    if (wifi_portal_btn) {
+        lv_obj_t *label = lv_obj_get_child(wifi_portal_btn, 0);
+        if (label) lv_label_set_text(label, config_portal_is_active() ? "Stop Portal" : "Setup Portal");
+    }
+
     // Update Connect button via dedicated function
     update_wifi_connect_btn_state();
 
@@ -489,6 +749,13 @@ void ui_wifi_cleanup(void) {
     wifi_keyboard = NULL;
     wifi_focused_ta = NULL;
     wifi_scan_list = NULL;
+    wifi_ip_settings_btn = NULL;
+    wifi_portal_btn = NULL;
+    wifi_ip_popup = NULL;
+    wifi_ip_keyboard = NULL;
+    wifi_ip_static_switch = NULL;
+    wifi_ip_status_label = NULL;
+    memset(wifi_ip_inputs, 0, sizeof(wifi_ip_inputs));
 }
 
 // =============================================================================
@@ -527,6 +794,32 @@ void wire_wifi_settings_buttons(void) {
         lv_obj_add_event_cb(objects.settings_wifi_screen_content_panel_button_scan_, wifi_scan_click_handler, LV_EVENT_CLICKED, NULL);
     }
 
+    // IP settings button (not part of the EEZ layout - created here)
+    if (objects.settings_wifi_screen_content_panel_) {
+        wifi_ip_settings_btn = lv_button_create(objects.settings_wifi_screen_content_panel_);
+        lv_obj_set_pos(wifi_ip_settings_btn, 380, 250);
+        lv_obj_set_size(wifi_ip_settings_btn, 150, 50);
+        lv_obj_set_style_bg_color(wifi_ip_settings_btn, lv_color_hex(0xff444444), LV_PART_MAIN);
+        lv_obj_set_style_bg_color(wifi_ip_settings_btn, lv_color_hex(0xff555555), LV_PART_MAIN | LV_STATE_PRESSED);
+        lv_obj_add_event_cb(wifi_ip_settings_btn, wifi_ip_settings_click_handler, LV_EVENT_CLICKED, NULL);
+        lv_obj_t *label = lv_label_create(wifi_ip_settings_btn);
+        lv_label_set_text(label, "IP Settings");
+        lv_obj_set_style_text_color(label, lv_color_hex(0xffffffff), LV_PART_MAIN);
+        lv_obj_center(label);
+
+        // Setup portal button (labelled by update_wifi_ui_state)
+        wifi_portal_btn = lv_button_create(objects.settings_wifi_screen_content_panel_);
+        lv_obj_set_pos(wifi_portal_btn, 547, 250);
+        lv_obj_set_size(wifi_portal_btn, 170, 50);
+        lv_obj_set_style_bg_color(wifi_portal_btn, lv_color_hex(0xff444444), LV_PART_MAIN);
+        lv_obj_set_style_bg_color(wifi_portal_btn, lv_color_hex(0xff555555), LV_PART_MAIN | LV_STATE_PRESSED);
+        lv_obj_add_event_cb(wifi_portal_btn, wifi_portal_click_handler, LV_EVENT_CLICKED, NULL);
+        label = lv_label_create(wifi_portal_btn);
+        lv_label_set_text(label, "Setup Portal");
+        lv_obj_set_style_text_color(label, lv_color_hex(0xffffffff), LV_PART_MAIN);
+        lv_obj_center(label);
+    }
+
     // Update initial state
     update_wifi_ui_state();
     // Set initial connect button state
@@ -42,6 +42,17 @@ echo "Firmware: $FIRMWARE_ELF"
 echo "Port: $PORT"
 echo ""
 
+# The image must fit an OTA app slot (ota_0 size from the partition table)
+SLOT_SIZE=$(awk -F, '$1 ~ /^ota_0/ { gsub(/ /, "", $5); print $5 }' "$SCRIPT_DIR/partitions.csv")
+IMAGE=$(mktemp)
+trap 'rm -f "$IMAGE"' EXIT
+$ESPFLASH save-image --chip esp32s3 "$FIRMWARE_ELF" "$IMAGE" > /dev/null
+BYTES=$(wc -c < "$IMAGE" | tr -d ' ')
+if [ "$BYTES" -gt $((SLOT_SIZE)) ]; then
+    echo "Error: image is $BYTES bytes, larger than the $((SLOT_SIZE)) byte OTA app slot"
+    exit 1
+fi
+
 # Flash using espflash (handles bootloader automatically)
 echo "Flashing firmware..."
-sudo $ESPFLASH flash --monitor --port "$PORT" "$FIRMWARE_ELF"
+sudo $ESPFLASH flash --monitor --port "$PORT" --partition-table "$SCRIPT_DIR/partitions.csv" "$FIRMWARE_ELF"
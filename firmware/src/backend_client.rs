@@ -72,13 +72,38 @@ struct ApiPrinter {
     tray_now_left: Option<i32>,
     tray_now_right: Option<i32>,
     active_extruder: Option<i32>,  // 0=right, 1=left, None=unknown
+    #[serde(default)]
+    spool_slots: Vec<ApiSpoolSlot>,
+}
+
+/// Inventory spool assigned to an AMS or external slot, from backend API
+#[derive(Debug, Clone, Deserialize)]
+struct ApiSpoolSlot {
+    ams_id: i32,
+    tray_id: i32,
+    spool_id: String,
 }
 
 /// Time response from backend API
 #[derive(Debug, Clone, Deserialize)]
 struct ApiTime {
-    hour: u8,
-    minute: u8,
+    /// Unix epoch seconds
+    timestamp: i64,
+    /// POSIX TZ string configured on the backend (None: keep the device's)
+    #[serde(default)]
+    tz: Option<String>,
+    /// 12/24h preference configured on the backend
+    #[serde(default)]
+    clock_24h: Option<bool>,
+}
+
+/// Firmware log settings from backend API
+#[derive(Debug, Clone, Deserialize)]
+struct ApiLogConfig {
+    /// "udp://ip:port", "syslog://ip[:port]" or None (serial only)
+    target: Option<String>,
+    /// Per-module level spec, e.g. "info,scale_manager=debug"
+    levels: String,
 }
 
 /// Cached AMS tray info
@@ -134,6 +159,7 @@ struct CachedPrinter {
     tray_now_left: i32,     // -1 if not available
     tray_now_right: i32,    // -1 if not available
     active_extruder: i32,   // -1 if not available, 0=right, 1=left
+    spool_slots: Vec<ApiSpoolSlot>, // Spools assigned to this printer's slots
 }
 
 impl Default for CachedPrinter {
@@ -156,6 +182,7 @@ impl Default for CachedPrinter {
             tray_now_left: -1,
             tray_now_right: -1,
             active_extruder: -1,
+            spool_slots: Vec::new(),
         }
     }
 }
@@ -201,6 +228,7 @@ const EMPTY_PRINTER: CachedPrinter = CachedPrinter {
     tray_now_left: -1,
     tray_now_right: -1,
     active_extruder: -1,
+    spool_slots: Vec::new(),
 };
 
 impl BackendManager {
@@ -223,11 +251,26 @@ static COVER_DATA: Mutex<Vec<u8>> = Mutex::new(Vec::new());
 static COVER_VALID: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
 static LAST_COVER_URL: Mutex<String> = Mutex::new(String::new());
 
+// Consecutive failed printer fetches; the backend counts as offline after a few
+static FETCH_FAILURES: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+const OFFLINE_AFTER_FAILURES: u32 = 3;
+
+// Log settings are fetched once after boot and again on a "log_config" command
+static LOG_CONFIG_FETCHED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
 /// Initialize the backend client
 pub fn init() {
+    crate::settings::subscribe(on_settings_changed);
     info!("Backend client initialized");
 }
 
+/// Follow backend URL changes (setup screen, restored backup)
+fn on_settings_changed(old: &crate::settings::DeviceSettings, new: &crate::settings::DeviceSettings) {
+    if old.backend.url != new.backend.url {
+        set_server_url(&crate::config_portal::backend_url());
+    }
+}
+
 /// Set the backend server URL manually
 pub fn set_server_url(url: &str) {
     let mut manager = BACKEND_MANAGER.lock().unwrap();
@@ -254,8 +297,67 @@ pub fn set_server_url(url: &str) {
     warn!("Failed to parse server URL: {}", url);
 }
 
+/// Request headers plus `X-API-Key` when an API key is configured
+fn with_api_key<'a>(headers: &[(&'a str, &'a str)], api_key: &'a str) -> Vec<(&'a str, &'a str)> {
+    let mut all = headers.to_vec();
+    if !api_key.is_empty() {
+        all.push(("X-API-Key", api_key));
+    }
+    all
+}
+
+/// Print job fed by a spool: the printing printer with the spool assigned
+/// to one of its slots
+#[derive(Debug, Clone)]
+pub struct SpoolPrintJob {
+    pub printer_name: String,
+    /// Slot holding the spool, e.g. "AMS-B3" or "External"
+    pub slot: String,
+    pub remaining_time_min: u16,
+}
+
+/// Printer has a job in progress (running or paused)
+fn is_printing(printer: &CachedPrinter) -> bool {
+    printer.connected
+        && (printer.gcode_state.starts_with(b"RUNNING") || printer.gcode_state.starts_with(b"PAUSE"))
+}
+
+/// Whether any printer has a job in progress
+pub fn any_printer_printing() -> bool {
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    manager.printers[..manager.printer_count].iter().any(is_printing)
+}
+
+/// Print job the spool is assigned to, None if its printer isn't printing
+/// (or the spool isn't in any printer's slot)
+pub fn spool_print_job(spool_id: &str) -> Option<SpoolPrintJob> {
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    manager.printers[..manager.printer_count]
+        .iter()
+        .filter(|p| is_printing(p))
+        .find_map(|p| {
+            let slot = p.spool_slots.iter().find(|s| s.spool_id == spool_id)?;
+            Some(SpoolPrintJob {
+                printer_name: cstr_to_string(&p.name),
+                slot: slot_label(slot.ams_id, slot.tray_id),
+                remaining_time_min: p.remaining_time_min,
+            })
+        })
+}
+
+/// Slot label as shown in the UI: "AMS-A1", "HT-A" or "External"
+fn slot_label(ams_id: i32, tray_id: i32) -> String {
+    match ams_id {
+        255 => "External".to_string(),
+        254 => "External L".to_string(),
+        128..=135 => format!("HT-{}", (b'A' + (ams_id - 128) as u8) as char),
+        0..=7 => format!("AMS-{}{}", (b'A' + ams_id as u8) as char, tray_id + 1),
+        _ => format!("AMS {} slot {}", ams_id, tray_id + 1),
+    }
+}
+
 /// Poll the backend server for printer status and time
-/// Called from main loop every ~2 seconds
+/// Called from the network thread every ~2 seconds
 pub fn poll_backend() {
     let manager = BACKEND_MANAGER.lock().unwrap();
 
@@ -286,9 +388,18 @@ pub fn poll_backend() {
 
             let mut manager = BACKEND_MANAGER.lock().unwrap();
             update_printer_cache(&mut manager, &printers);
+            drop(manager);
+
+            FETCH_FAILURES.store(0, std::sync::atomic::Ordering::Relaxed);
+            // Reaching the backend confirms a freshly installed image
+            crate::ota_manager::confirm_image();
+            crate::crash_report::upload_pending();
+            crate::settings::upload_backup();
+            crate::printer_mqtt::remember_printers(lan_printers(&printers));
         }
         Err(e) => {
             warn!("Failed to fetch printers: {}", e);
+            FETCH_FAILURES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
         }
     }
 
@@ -299,18 +410,23 @@ pub fn poll_backend() {
 
     // Fetch time from backend
     fetch_and_set_time(&base_url);
+
+    if !LOG_CONFIG_FETCHED.load(std::sync::atomic::Ordering::Relaxed)
+        && FETCH_FAILURES.load(std::sync::atomic::Ordering::Relaxed) == 0
+    {
+        fetch_and_apply_log_config(&base_url);
+    }
+}
+
+/// True once several printer fetches in a row have failed
+pub fn backend_unreachable() -> bool {
+    FETCH_FAILURES.load(std::sync::atomic::Ordering::Relaxed) >= OFFLINE_AFTER_FAILURES
 }
 
 /// Get WiFi status parameters for backend state updates
 /// Returns URL query string fragment like "&wifi_state=3&wifi_ssid=MyNetwork&wifi_ip=192.168.1.50&wifi_rssi=-45"
 fn get_wifi_params() -> String {
-    let mut status = crate::wifi_manager::WifiStatus {
-        state: 0,
-        ip: [0, 0, 0, 0],
-        rssi: 0,
-    };
-
-    crate::wifi_manager::wifi_get_status(&mut status as *mut _);
+    let status = get_wifi_status();
 
     if status.state == 0 {
         // Uninitialized - don't send WiFi params
@@ -321,19 +437,10 @@ fn get_wifi_params() -> String {
 
     // Get SSID if connected
     if status.state == 3 {
-        let mut ssid_buf = [0u8; 33];
-        let ssid_len = crate::wifi_manager::wifi_get_ssid(ssid_buf.as_mut_ptr() as *mut c_char, 33);
-        if ssid_len > 0 {
-            // Convert buffer to string (find null terminator)
-            let ssid = ssid_buf.iter()
-                .position(|&b| b == 0)
-                .map(|end| String::from_utf8_lossy(&ssid_buf[..end]).to_string())
-                .unwrap_or_default();
-            if !ssid.is_empty() {
-                // URL encode the SSID
-                let encoded_ssid = ssid.replace(' ', "%20").replace('#', "%23");
-                params.push_str(&format!("&wifi_ssid={}", encoded_ssid));
-            }
+        if let Some(ssid) = get_wifi_ssid() {
+            // URL encode the SSID
+            let encoded_ssid = ssid.replace(' ', "%20").replace('#', "%23");
+            params.push_str(&format!("&wifi_ssid={}", encoded_ssid));
         }
 
         // Add IP address
@@ -347,6 +454,47 @@ fn get_wifi_params() -> String {
     params
 }
 
+/// WiFi status as heartbeat JSON fields (same names as the query params)
+fn get_wifi_json() -> serde_json::Value {
+    let status = get_wifi_status();
+    if status.state == 0 {
+        return serde_json::json!({});
+    }
+    if status.state != 3 {
+        return serde_json::json!({ "wifi_state": status.state });
+    }
+    serde_json::json!({
+        "wifi_state": status.state,
+        "wifi_ssid": get_wifi_ssid(),
+        "wifi_ip": format!("{}.{}.{}.{}", status.ip[0], status.ip[1], status.ip[2], status.ip[3]),
+        "wifi_rssi": status.rssi,
+    })
+}
+
+fn get_wifi_status() -> crate::wifi_manager::WifiStatus {
+    let mut status = crate::wifi_manager::WifiStatus {
+        state: 0,
+        ip: [0, 0, 0, 0],
+        rssi: 0,
+    };
+    crate::wifi_manager::wifi_get_status(&mut status as *mut _);
+    status
+}
+
+/// SSID of the connected network
+fn get_wifi_ssid() -> Option<String> {
+    let mut ssid_buf = [0u8; 33];
+    let ssid_len = crate::wifi_manager::wifi_get_ssid(ssid_buf.as_mut_ptr() as *mut c_char, 33);
+    if ssid_len <= 0 {
+        return None;
+    }
+    // Convert buffer to string (find null terminator)
+    ssid_buf.iter()
+        .position(|&b| b == 0)
+        .map(|end| String::from_utf8_lossy(&ssid_buf[..end]).to_string())
+        .filter(|ssid| !ssid.is_empty())
+}
+
 // External C function to shutdown display before reboot
 extern "C" {
     fn display_shutdown();
@@ -354,17 +502,23 @@ extern "C" {
 
 /// Send heartbeat to backend to indicate display is connected
 /// Also checks for pending commands (e.g., reboot)
-/// Includes WiFi status so backend always has current network info
+/// Includes WiFi status so backend always has current network info, and
+/// a health snapshot (see health.rs) every 30 seconds
 fn send_heartbeat(base_url: &str) {
     use esp_idf_sys::esp_restart;
 
-    let version = env!("CARGO_PKG_VERSION");
-    let update_available = crate::ota_manager::is_update_available();
-    let wifi_params = get_wifi_params();
-    let url = format!(
-        "{}/api/display/heartbeat?version={}&update_available={}{}",
-        base_url, version, update_available, wifi_params
-    );
+    let url = format!("{}/api/display/heartbeat", base_url);
+    let mut payload = serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "update_available": crate::ota_manager::is_update_available(),
+    });
+    if let (Some(fields), serde_json::Value::Object(wifi)) = (payload.as_object_mut(), get_wifi_json()) {
+        fields.extend(wifi);
+    }
+    if crate::health::due() {
+        payload["health"] = crate::health::snapshot();
+    }
+    let body = payload.to_string();
 
     let config = HttpConfig {
         timeout: Some(std::time::Duration::from_millis(2000)),
@@ -378,10 +532,20 @@ fn send_heartbeat(base_url: &str) {
 
     let mut client = HttpClient::wrap(connection);
 
-    let request = match client.get(&url) {
+    let content_length = body.len().to_string();
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
+        ("Content-Type", "application/json"),
+        ("Content-Length", &content_length),
+    ], &api_key);
+
+    let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
         Ok(r) => r,
         Err(_) => return,
     };
+    if request.write(body.as_bytes()).is_err() || request.flush().is_err() {
+        return;
+    }
 
     let mut response = match request.submit() {
         Ok(r) => r,
@@ -396,56 +560,268 @@ fn send_heartbeat(base_url: &str) {
             // Check for update command (triggers OTA)
             if body.contains("\"command\":\"update\"") || body.contains("\"command\": \"update\"") {
                 log::info!("Received update command from backend - starting OTA");
-                if let Err(e) = crate::ota_manager::perform_update(base_url) {
-                    log::error!("OTA update failed: {}", e);
+                if !crate::ota_manager::start_update(base_url) {
+                    log::warn!("Update check or install already running");
                 }
-                // perform_update reboots on success, so we only get here on failure
+            }
+            // Check for NFC bridge update command (runs in the background)
+            else if body.contains("\"command\":\"bridge_update\"") || body.contains("\"command\": \"bridge_update\"") {
+                log::info!("Received bridge_update command from backend");
+                if !crate::bridge_ota::start_update(base_url) {
+                    log::warn!("Bridge update already running");
+                }
+            }
+            // Check for log settings change
+            else if body.contains("\"command\":\"log_config\"") || body.contains("\"command\": \"log_config\"") {
+                log::info!("Received log_config command from backend");
+                fetch_and_apply_log_config(base_url);
+            }
+            // Check for settings restore/clone
+            else if body.contains("\"command\":\"settings_import\"") || body.contains("\"command\": \"settings_import\"") {
+                log::info!("Received settings_import command from backend");
+                fetch_and_import_settings(base_url);
             }
             // Check for reboot command
             else if body.contains("\"command\":\"reboot\"") || body.contains("\"command\": \"reboot\"") {
                 log::info!("Received reboot command from backend");
+                crate::settings::flush();
                 // Properly shutdown display before reboot to prevent display shift
                 unsafe { display_shutdown(); }
                 std::thread::sleep(std::time::Duration::from_millis(100));
                 unsafe { esp_restart(); }
             }
-            // Check for scale tare command
-            else if body.contains("\"command\":\"scale_tare\"") || body.contains("\"command\": \"scale_tare\"") {
-                log::info!("Received scale_tare command from backend");
-                let result = crate::scale_manager::scale_tare();
-                log::info!("Scale tare result: {}", result);
+            // Check for scale tare command (e.g., "scale_tare" or "scale_tare:m70_1" for one scale)
+            else if body.contains("\"command\":\"scale_tare") || body.contains("\"command\": \"scale_tare") {
+                if let Some(index) = scale_index(scale_command_args(&body, "scale_tare")) {
+                    log::info!("Received scale_tare command from backend (scale {})", index);
+                    let result = crate::scale_manager::tare(index);
+                    log::info!("Scale tare result: {}", result);
+                }
             }
-            // Check for scale calibrate command (e.g., "scale_calibrate:100.0")
+            // Check for scale calibrate command (e.g., "scale_calibrate:100.0" or "scale_calibrate:100.0:m70_1")
             else if body.contains("\"command\":\"scale_calibrate:") || body.contains("\"command\": \"scale_calibrate:") {
                 log::info!("Detected scale_calibrate command in response body");
-                // Extract the weight value from command
-                if let Some(start) = body.find("scale_calibrate:") {
-                    let after_cmd = &body[start + 16..];
-                    // Find end of weight value (quote or whitespace)
-                    let end = after_cmd.find(|c: char| c == '"' || c.is_whitespace()).unwrap_or(after_cmd.len());
-                    let weight_str = &after_cmd[..end];
+                // Extract the weight value (and optional scale id) from command
+                if let Some(args) = scale_command_args(&body, "scale_calibrate") {
+                    let (weight_str, id) = match args.split_once(':') {
+                        Some((w, id)) => (w, Some(id)),
+                        None => (args, None),
+                    };
                     log::info!("Parsing weight value: '{}'", weight_str);
-                    if let Ok(known_weight) = weight_str.parse::<f32>() {
-                        log::info!("Received scale_calibrate command from backend: {}g", known_weight);
-                        let result = crate::scale_manager::scale_calibrate(known_weight);
-                        log::info!("Scale calibrate result: {} (0=success, -1=error)", result);
-                    } else {
-                        log::warn!("Failed to parse weight value: '{}'", weight_str);
+                    match (weight_str.parse::<f32>(), scale_index(id)) {
+                        (Ok(known_weight), Some(index)) => {
+                            log::info!("Received scale_calibrate command from backend: {}g (scale {})", known_weight, index);
+                            let result = crate::scale_manager::calibrate(index, known_weight);
+                            log::info!("Scale calibrate result: {} (0=success, -1=error)", result);
+                        }
+                        (Err(_), _) => log::warn!("Failed to parse weight value: '{}'", weight_str),
+                        (Ok(_), None) => {}
                     }
                 } else {
                     log::warn!("Could not find scale_calibrate: in body");
                 }
             }
-            // Check for scale reset command
-            else if body.contains("\"command\":\"scale_reset\"") || body.contains("\"command\": \"scale_reset\"") {
-                log::info!("Received scale_reset command from backend");
-                let result = crate::scale_manager::scale_reset_calibration();
-                log::info!("Scale reset result: {}", result);
+            // Check for scale reset command (e.g., "scale_reset" or "scale_reset:m70_1")
+            else if body.contains("\"command\":\"scale_reset") || body.contains("\"command\": \"scale_reset") {
+                if let Some(index) = scale_index(scale_command_args(&body, "scale_reset")) {
+                    log::info!("Received scale_reset command from backend (scale {})", index);
+                    let result = crate::scale_manager::reset_calibration(index);
+                    log::info!("Scale reset result: {}", result);
+                }
+            }
+        }
+    }
+}
+
+/// Extract the arguments after "<command>:" in a heartbeat response
+/// (e.g. "100.0:1" for "scale_calibrate:100.0:1"), up to the closing quote
+fn scale_command_args<'a>(body: &'a str, command: &str) -> Option<&'a str> {
+    let start = body.find(command)? + command.len();
+    let after_cmd = body[start..].strip_prefix(':')?;
+    let end = after_cmd.find(|c: char| c == '"' || c.is_whitespace()).unwrap_or(after_cmd.len());
+    Some(&after_cmd[..end])
+}
+
+/// Resolve the scale id of a command (None = primary scale) to its index
+fn scale_index(id: Option<&str>) -> Option<usize> {
+    match id {
+        None | Some("") => Some(0),
+        Some(id) => {
+            let index = crate::scale_manager::index_of(id);
+            if index.is_none() {
+                log::warn!("Scale command for unknown scale '{}'", id);
             }
+            index
         }
     }
 }
 
+/// Send the reading of an additional scale (not the primary one) to backend
+pub fn send_scale_state(id: &str, weight: f32, stable: bool) -> bool {
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    if manager.server_url.is_empty() {
+        return false;
+    }
+    let url = format!(
+        "{}/api/display/state?scale={}&weight={:.1}&stable={}",
+        manager.server_url, id, weight, stable
+    );
+    drop(manager);
+
+    let config = HttpConfig {
+        timeout: Some(std::time::Duration::from_millis(3000)),
+        ..Default::default()
+    };
+
+    let connection = match EspHttpConnection::new(&config) {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+
+    let mut client = HttpClient::wrap(connection);
+
+    // POST request
+    let request = match client.post(&url, &[]) {
+        Ok(r) => r,
+        Err(_) => return false,
+    };
+
+    match request.submit() {
+        Ok(response) => response.status() == 200,
+        Err(_) => false,
+    }
+}
+
+/// Report the printers found by SSDP discovery (full list, replaces the
+/// previous report)
+pub fn send_discovered_printers(printers: &[crate::printer_discovery::DiscoveredPrinter]) -> bool {
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    if manager.server_url.is_empty() {
+        return false;
+    }
+    let url = format!("{}/api/discovery/device-report", manager.server_url);
+    drop(manager);
+
+    let list: Vec<serde_json::Value> = printers
+        .iter()
+        .map(|p| {
+            serde_json::json!({
+                "serial": p.serial,
+                "name": p.name,
+                "ip_address": p.ip.to_string(),
+                "model": p.model_name(),
+                "model_code": p.model_code,
+                "connect_mode": p.connect.as_str(),
+                "bind_state": p.bind.as_str(),
+                "firmware_version": p.firmware,
+            })
+        })
+        .collect();
+    let body = serde_json::Value::Array(list).to_string();
+
+    let config = HttpConfig {
+        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
+        ..Default::default()
+    };
+
+    let connection = match EspHttpConnection::new(&config) {
+        Ok(c) => c,
+        Err(_) => return false,
+    };
+
+    let mut client = HttpClient::wrap(connection);
+
+    let content_length = body.len().to_string();
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
+        ("Content-Type", "application/json"),
+        ("Content-Length", &content_length),
+    ], &api_key);
+
+    let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
+        Ok(r) => r,
+        Err(_) => return false,
+    };
+    if request.write(body.as_bytes()).is_err() || request.flush().is_err() {
+        return false;
+    }
+
+    match request.submit() {
+        Ok(response) => response.status() == 200,
+        Err(e) => {
+            warn!("Failed to report discovered printers: {:?}", e);
+            false
+        }
+    }
+}
+
+/// Upload the report of an unexpected reset (see crash_report)
+pub fn send_crash_report(report: &serde_json::Value) -> bool {
+    match post_json("/api/device/crash-report", report) {
+        Ok(status) => status == 200,
+        Err(e) => {
+            warn!("Failed to upload crash report: {}", e);
+            false
+        }
+    }
+}
+
+/// Upload the settings document as the backend's backup (see settings)
+pub fn send_settings_backup(settings: &serde_json::Value) -> bool {
+    match post_json("/api/device/settings", settings) {
+        Ok(status) => status == 200,
+        Err(e) => {
+            warn!("Failed to upload settings backup: {}", e);
+            false
+        }
+    }
+}
+
+/// POST a JSON document to the backend, returns the HTTP status
+fn post_json(path: &str, document: &serde_json::Value) -> Result<u16, String> {
+    send_json(embedded_svc::http::Method::Post, path, document)
+}
+
+/// Send a JSON document to the backend, returns the HTTP status
+fn send_json(method: embedded_svc::http::Method, path: &str, document: &serde_json::Value) -> Result<u16, String> {
+    let manager = BACKEND_MANAGER.lock().unwrap();
+    if manager.server_url.is_empty() {
+        return Err("No backend server configured".to_string());
+    }
+    let url = format!("{}{}", manager.server_url, path);
+    drop(manager);
+
+    let body = document.to_string();
+
+    let config = HttpConfig {
+        timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
+        ..Default::default()
+    };
+
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
+
+    let mut client = HttpClient::wrap(connection);
+
+    let content_length = body.len().to_string();
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
+        ("Content-Type", "application/json"),
+        ("Content-Length", &content_length),
+    ], &api_key);
+
+    let mut request = client.request(method, &url, &headers)
+        .map_err(|e| format!("Request to {} failed: {:?}", path, e))?;
+    request.write(body.as_bytes())
+        .map_err(|e| format!("Write error: {:?}", e))?;
+    request.flush()
+        .map_err(|e| format!("Flush error: {:?}", e))?;
+
+    let response = request.submit()
+        .map_err(|e| format!("Request submit failed: {:?}", e))?;
+    Ok(response.status())
+}
+
 /// Send device state to backend (weight, tag, WiFi) and receive decoded tag data
 /// Returns true if tag data was received and set
 pub fn send_device_state(tag_uid_hex: Option<&str>, weight: f32, stable: bool) -> bool {
@@ -456,8 +832,11 @@ pub fn send_device_state(tag_uid_hex: Option<&str>, weight: f32, stable: bool) -
     let base_url = manager.server_url.clone();
     drop(manager);
 
-    // Get WiFi status to include in state update
-    let wifi_params = get_wifi_params();
+    // Get WiFi status (and the primary scale's id) to include in state update
+    let mut wifi_params = get_wifi_params();
+    if let Some(id) = crate::scale_manager::ids().first() {
+        wifi_params.push_str(&format!("&scale_id={}", id));
+    }
 
     // Build URL with query params, including decoded tag data if available
     let url = if let Some(tag_id) = tag_uid_hex {
@@ -613,9 +992,19 @@ fn fetch_decoded_tag_data(base_url: &str) {
 /// Can be called independently for quick time sync
 pub fn fetch_and_set_time(base_url: &str) {
     let time_url = format!("{}/api/time", base_url);
-    match fetch_time(&time_url) {
+    match fetch_json::<ApiTime>(&time_url) {
         Ok(time) => {
-            crate::time_manager::set_backend_time(time.hour, time.minute);
+            crate::time_manager::set_backend_time(time.timestamp);
+            if let Some(ref tz) = time.tz {
+                if let Err(e) = crate::time_manager::set_timezone(tz) {
+                    warn!("{}", e);
+                }
+            }
+            if let Some(clock_24h) = time.clock_24h {
+                if let Err(e) = crate::time_manager::set_clock_24h(clock_24h) {
+                    warn!("{}", e);
+                }
+            }
         }
         Err(_) => {
             // Silently ignore time fetch errors
@@ -623,6 +1012,36 @@ pub fn fetch_and_set_time(base_url: &str) {
     }
 }
 
+/// Fetch log target and levels from backend and apply them (persisted in NVS)
+fn fetch_and_apply_log_config(base_url: &str) {
+    let url = format!("{}/api/device/logging", base_url);
+    match fetch_json::<ApiLogConfig>(&url) {
+        Ok(config) => {
+            LOG_CONFIG_FETCHED.store(true, std::sync::atomic::Ordering::Relaxed);
+            if let Err(e) = crate::udp_logger::set_target(config.target.as_deref().unwrap_or("")) {
+                warn!("{}", e);
+            }
+            if let Err(e) = crate::udp_logger::set_levels(&config.levels) {
+                warn!("{}", e);
+            }
+        }
+        Err(e) => warn!("Failed to fetch log settings: {}", e),
+    }
+}
+
+/// Fetch a settings document queued on the backend and apply it
+fn fetch_and_import_settings(base_url: &str) {
+    let url = format!("{}/api/device/settings/import", base_url);
+    match fetch_json::<serde_json::Value>(&url) {
+        Ok(document) => {
+            if let Err(e) = crate::settings::import(document) {
+                warn!("Failed to import settings: {}", e);
+            }
+        }
+        Err(e) => warn!("Failed to fetch settings: {}", e),
+    }
+}
+
 /// Quick time sync - call after setting server URL
 pub fn sync_time() {
     let manager = BACKEND_MANAGER.lock().unwrap();
@@ -678,10 +1097,11 @@ fn fetch_printers(url: &str) -> Result<Vec<ApiPrinter>, String> {
     Ok(printers)
 }
 
-/// Fetch time from backend API
-fn fetch_time(url: &str) -> Result<ApiTime, String> {
+/// Fetch a small JSON document from backend API (time, settings)
+/// Sends the API key, which the queued settings import requires
+fn fetch_json<T: serde::de::DeserializeOwned>(url: &str) -> Result<T, String> {
     let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_millis(2000)), // Short timeout for time
+        timeout: Some(std::time::Duration::from_millis(2000)), // Short timeout for small documents
         ..Default::default()
     };
 
@@ -690,7 +1110,9 @@ fn fetch_time(url: &str) -> Result<ApiTime, String> {
 
     let mut client = HttpClient::wrap(connection);
 
-    let request = client.get(url)
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[], &api_key);
+    let request = client.request(embedded_svc::http::Method::Get, url, &headers)
         .map_err(|e| format!("GET request failed: {:?}", e))?;
 
     let mut response = request.submit()
@@ -711,10 +1133,8 @@ fn fetch_time(url: &str) -> Result<ApiTime, String> {
         }
     }
 
-    let time: ApiTime = serde_json::from_slice(&body)
-        .map_err(|e| format!("JSON parse error: {:?}", e))?;
-
-    Ok(time)
+    serde_json::from_slice(&body)
+        .map_err(|e| format!("JSON parse error: {:?}", e))
 }
 
 /// Update the cached printer data
@@ -802,6 +1222,7 @@ fn update_printer_cache(manager: &mut BackendManager, printers: &[ApiPrinter]) {
         cached.tray_now_left = printer.tray_now_left.unwrap_or(-1);
         cached.tray_now_right = printer.tray_now_right.unwrap_or(-1);
         cached.active_extruder = printer.active_extruder.unwrap_or(-1);
+        cached.spool_slots = printer.spool_slots.clone();
 
         // Copy AMS units
         cached.ams_unit_count = printer.ams_units.len().min(MAX_AMS_UNITS) as u8;
@@ -845,6 +1266,117 @@ fn update_printer_cache(manager: &mut BackendManager, printers: &[ApiPrinter]) {
 
 }
 
+/// LAN connection details of the printers that have an IP and access code
+fn lan_printers(printers: &[ApiPrinter]) -> Vec<crate::printer_mqtt::LanPrinter> {
+    printers
+        .iter()
+        .filter_map(|p| {
+            Some(crate::printer_mqtt::LanPrinter {
+                serial: p.serial.clone(),
+                name: p.name.clone().unwrap_or_default(),
+                ip: p.ip_address.clone().filter(|ip| !ip.is_empty())?,
+                access_code: p.access_code.clone().filter(|code| !code.is_empty())?,
+            })
+        })
+        .collect()
+}
+
+/// Merge a report received directly from the printer over LAN MQTT into the
+/// cache. Printers the cache doesn't know yet (e.g. after a reboot with the
+/// backend down) are added.
+pub fn apply_lan_report(printer: &crate::printer_mqtt::LanPrinter, report: &crate::printer_mqtt::LanReport) {
+    let mut manager = BACKEND_MANAGER.lock().unwrap();
+    let count = manager.printer_count;
+    let index = match manager.printers[..count]
+        .iter()
+        .position(|p| cstr_eq(&p.serial, &printer.serial))
+    {
+        Some(i) => i,
+        None if count < MAX_PRINTERS => {
+            let cached = &mut manager.printers[count];
+            *cached = EMPTY_PRINTER;
+            copy_to_c_buf(if printer.name.is_empty() { &printer.serial } else { &printer.name }, &mut cached.name);
+            copy_to_c_buf(&printer.serial, &mut cached.serial);
+            copy_to_c_buf(&printer.ip, &mut cached.ip_address);
+            copy_to_c_buf(&printer.access_code, &mut cached.access_code);
+            manager.printer_count += 1;
+            info!("Added printer {} from LAN report", printer.serial);
+            count
+        }
+        None => return,
+    };
+
+    let cached = &mut manager.printers[index];
+    cached.connected = true;
+    if let Some(ref gcode) = report.gcode_state {
+        cached.gcode_state = [0; 16];
+        copy_to_c_buf(gcode, &mut cached.gcode_state);
+    }
+    if let Some(progress) = report.print_progress {
+        cached.print_progress = progress;
+    }
+    if let Some(time) = report.remaining_time_min {
+        cached.remaining_time_min = time;
+    }
+    if let Some(ref subtask) = report.subtask_name {
+        cached.subtask_name = [0; 64];
+        copy_to_c_buf(subtask, &mut cached.subtask_name);
+    }
+    if let Some(stage) = report.stg_cur {
+        if stage != cached.stg_cur {
+            // Stage names come from the backend; don't show a stale one
+            cached.stg_cur_name = [0; 48];
+        }
+        cached.stg_cur = stage;
+    }
+    if let Some(tray_now) = report.tray_now {
+        cached.tray_now = tray_now;
+    }
+
+    if let Some(ref units) = report.ams_units {
+        cached.ams_unit_count = units.len().min(MAX_AMS_UNITS) as u8;
+        for (j, unit) in units.iter().take(MAX_AMS_UNITS).enumerate() {
+            // Keep the extruder mapping, it isn't part of the AMS report
+            let extruder = if cached.ams_units[j].id == unit.id { cached.ams_units[j].extruder } else { -1 };
+            let cached_ams = &mut cached.ams_units[j];
+            *cached_ams = EMPTY_AMS_UNIT;
+            cached_ams.id = unit.id;
+            cached_ams.extruder = extruder;
+            cached_ams.humidity = unit.humidity.unwrap_or(-1);
+            cached_ams.temperature = unit.temperature.map(|t| (t * 10.0) as i16).unwrap_or(-1);
+
+            for tray in unit.trays.iter().filter(|t| t.id < 4) {
+                let cached_tray = &mut cached_ams.trays[tray.id];
+                copy_to_c_buf(&tray.tray_type, &mut cached_tray.tray_type);
+                cached_tray.tray_color = parse_rgba_color(&tray.tray_color);
+                cached_tray.remain = tray.remain.clamp(0, 100) as u8;
+                cached_ams.tray_count = cached_ams.tray_count.max(tray.id as u8 + 1);
+            }
+        }
+    }
+}
+
+/// Mark a printer offline after its LAN MQTT connection dropped
+pub fn set_lan_printer_offline(serial: &str) {
+    let mut manager = BACKEND_MANAGER.lock().unwrap();
+    let count = manager.printer_count;
+    if let Some(cached) = manager.printers[..count].iter_mut().find(|p| cstr_eq(&p.serial, serial)) {
+        cached.connected = false;
+    }
+}
+
+/// Compare a NUL-padded cache field with a string
+fn cstr_eq(buf: &[u8], s: &str) -> bool {
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    &buf[..len] == s.as_bytes()
+}
+
+/// Text of a NUL-padded cache field
+fn cstr_to_string(buf: &[u8]) -> String {
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8_lossy(&buf[..len]).into_owned()
+}
+
 /// Check if cover URL changed and return the new URL if so
 fn check_cover_url_changed(printers: &[ApiPrinter], base_url: &str) -> Option<String> {
     if let Some(printer) = printers.first() {
@@ -1073,7 +1605,7 @@ pub extern "C" fn backend_get_printer(index: c_int, info: *mut PrinterInfo) -> c
     0
 }
 
-/// Set backend server URL from C
+/// Set (and save) the backend server URL from C
 /// Returns 0 on success, -1 on error
 #[no_mangle]
 pub extern "C" fn backend_set_url(url: *const c_char) -> c_int {
@@ -1089,6 +1621,10 @@ pub extern "C" fn backend_set_url(url: *const c_char) -> c_int {
     };
 
     set_server_url(url_str);
+    // Keep it across reboots
+    if let Err(e) = crate::settings::update(|s| s.backend.url = url_str.to_string()) {
+        warn!("{}", e);
+    }
     0
 }
 
@@ -1360,34 +1896,14 @@ pub extern "C" fn ota_is_update_available() -> c_int {
 /// Copies version string to buffer, returns length or -1 on error
 #[no_mangle]
 pub extern "C" fn ota_get_current_version(buf: *mut c_char, buf_len: c_int) -> c_int {
-    if buf.is_null() || buf_len <= 0 {
-        return -1;
-    }
-    let version = crate::ota_manager::get_version();
-    let bytes = version.as_bytes();
-    let copy_len = std::cmp::min(bytes.len(), (buf_len - 1) as usize);
-    unsafe {
-        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, copy_len);
-        *buf.add(copy_len) = 0; // Null terminate
-    }
-    copy_len as c_int
+    copy_to_c(&crate::ota_manager::get_version(), buf, buf_len)
 }
 
 /// Get available update version
 /// Copies version string to buffer, returns length or -1 on error
 #[no_mangle]
 pub extern "C" fn ota_get_update_version(buf: *mut c_char, buf_len: c_int) -> c_int {
-    if buf.is_null() || buf_len <= 0 {
-        return -1;
-    }
-    let version = crate::ota_manager::get_update_version();
-    let bytes = version.as_bytes();
-    let copy_len = std::cmp::min(bytes.len(), (buf_len - 1) as usize);
-    unsafe {
-        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, copy_len);
-        *buf.add(copy_len) = 0; // Null terminate
-    }
-    copy_len as c_int
+    copy_to_c(&crate::ota_manager::get_update_version(), buf, buf_len)
 }
 
 /// Get OTA state
@@ -1450,7 +1966,7 @@ pub extern "C" fn ota_check_for_update() -> c_int {
 }
 
 /// Trigger OTA update (non-blocking, spawns thread)
-/// Returns 0 on success, -1 on error
+/// Returns 0 on success, -1 if not connected or an update is already running
 #[no_mangle]
 pub extern "C" fn ota_start_update() -> c_int {
     // Get backend URL
@@ -1463,13 +1979,10 @@ pub extern "C" fn ota_start_update() -> c_int {
     };
     drop(manager);
 
-    // Spawn thread to perform update
-    std::thread::spawn(move || {
-        if let Err(e) = crate::ota_manager::perform_update(&url) {
-            log::error!("OTA update failed: {}", e);
-        }
-        // Note: perform_update reboots on success, so we only get here on error
-    });
+    // Runs in the background and reboots on success
+    if !crate::ota_manager::start_update(&url) {
+        return -1;
+    }
     0
 }
 
@@ -1490,6 +2003,7 @@ pub struct SpoolInfoC {
     pub label_weight: i32,      // Label weight in grams
     pub weight_current: i32,    // Current weight from inventory (grams)
     pub slicer_filament: [u8; 32], // Slicer filament ID
+    pub core_weight: i32,       // Empty spool weight used as tare (grams)
     pub valid: bool,            // True if spool was found
 }
 
@@ -1514,7 +2028,18 @@ struct ApiSpool {
     rgba: Option<String>,
     label_weight: Option<i32>,
     weight_current: Option<i32>,
+    core_weight: Option<i32>,
     slicer_filament: Option<String>,
+    weight_used: Option<f32>,
+    consumed_since_weight: Option<f32>,
+}
+
+/// Inventory weight tracking of a spool, saved so a scale sync can be undone
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpoolWeightRecord {
+    pub weight_current: Option<i32>,
+    pub weight_used: f32,
+    pub consumed_since_weight: f32,
 }
 
 /// API response for K-profile
@@ -1534,8 +2059,22 @@ struct ApiAssignResponse {
     message: Option<String>,
 }
 
+/// Copy a string into a C buffer from an FFI caller, returns length or -1 on error
+pub fn copy_to_c(value: &str, buf: *mut c_char, buf_len: c_int) -> c_int {
+    if buf.is_null() || buf_len <= 0 {
+        return -1;
+    }
+    let bytes = value.as_bytes();
+    let copy_len = std::cmp::min(bytes.len(), (buf_len - 1) as usize);
+    unsafe {
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), buf as *mut u8, copy_len);
+        *buf.add(copy_len) = 0; // Null terminate
+    }
+    copy_len as c_int
+}
+
 /// Helper to copy string to fixed-size C buffer
-fn copy_to_c_buf(src: &str, dst: &mut [u8]) {
+pub fn copy_to_c_buf(src: &str, dst: &mut [u8]) {
     let bytes = src.as_bytes();
     let copy_len = std::cmp::min(bytes.len(), dst.len() - 1);
     dst[..copy_len].copy_from_slice(&bytes[..copy_len]);
@@ -1568,13 +2107,29 @@ pub extern "C" fn spool_get_by_tag(tag_id: *const c_char, info: *mut SpoolInfoC)
         }
     };
 
+    match find_spool_by_tag(tag_id_str) {
+        Some(spool) => {
+            unsafe { *info = spool };
+            true
+        }
+        None => false,
+    }
+}
+
+/// Look up a spool in the inventory by NFC tag ID (Rust-callable)
+pub fn find_spool_by_tag(tag_id_str: &str) -> Option<SpoolInfoC> {
+    find_spool_with_record(tag_id_str).map(|(info, _)| info)
+}
+
+/// Look up a spool by NFC tag ID, with its current weight tracking
+pub fn find_spool_with_record(tag_id_str: &str) -> Option<(SpoolInfoC, SpoolWeightRecord)> {
     // Get backend URL
     let manager = BACKEND_MANAGER.lock().unwrap();
     let base_url = manager.server_url.clone();
     drop(manager);
 
     if base_url.is_empty() {
-        return false;
+        return None;
     }
 
     // GET /api/spools to list all spools
@@ -1587,18 +2142,18 @@ pub extern "C" fn spool_get_by_tag(tag_id: *const c_char, info: *mut SpoolInfoC)
 
     let connection = match EspHttpConnection::new(&config) {
         Ok(c) => c,
-        Err(_) => return false,
+        Err(_) => return None,
     };
 
     let mut client = HttpClient::wrap(connection);
     let request = match client.get(&url) {
         Ok(r) => r,
-        Err(_) => return false,
+        Err(_) => return None,
     };
 
     let mut response = match request.submit() {
         Ok(r) => r,
-        Err(_) => return false,
+        Err(_) => return None,
     };
 
     // Read response body
@@ -1616,14 +2171,14 @@ pub extern "C" fn spool_get_by_tag(tag_id: *const c_char, info: *mut SpoolInfoC)
     }
 
     if total == 0 {
-        return false;
+        return None;
     }
 
     // Parse JSON array of spools
     let body = String::from_utf8_lossy(&buf[..total]);
     let spools: Vec<ApiSpool> = match serde_json::from_str(&body) {
         Ok(s) => s,
-        Err(_) => return false,
+        Err(_) => return None,
     };
 
     // Find spool with matching tag_id
@@ -1631,8 +2186,7 @@ pub extern "C" fn spool_get_by_tag(tag_id: *const c_char, info: *mut SpoolInfoC)
         if let Some(ref tid) = spool.tag_id {
             if tid == tag_id_str {
                 // Found - fill info struct
-                let info_ref = unsafe { &mut *info };
-                *info_ref = SpoolInfoC {
+                let mut info = SpoolInfoC {
                     id: [0; 64],
                     tag_id: [0; 32],
                     brand: [0; 32],
@@ -1643,44 +2197,66 @@ pub extern "C" fn spool_get_by_tag(tag_id: *const c_char, info: *mut SpoolInfoC)
                     label_weight: 0,
                     weight_current: 0,
                     slicer_filament: [0; 32],
+                    core_weight: 0,
                     valid: true,
                 };
 
-                copy_to_c_buf(&spool.id, &mut info_ref.id);
-                copy_to_c_buf(tid, &mut info_ref.tag_id);
+                copy_to_c_buf(&spool.id, &mut info.id);
+                copy_to_c_buf(tid, &mut info.tag_id);
                 if let Some(ref b) = spool.brand {
-                    copy_to_c_buf(b, &mut info_ref.brand);
+                    copy_to_c_buf(b, &mut info.brand);
                 }
                 if let Some(ref m) = spool.material {
-                    copy_to_c_buf(m, &mut info_ref.material);
+                    copy_to_c_buf(m, &mut info.material);
                 }
                 if let Some(ref s) = spool.subtype {
-                    copy_to_c_buf(s, &mut info_ref.subtype);
+                    copy_to_c_buf(s, &mut info.subtype);
                 }
                 if let Some(ref c) = spool.color_name {
-                    copy_to_c_buf(c, &mut info_ref.color_name);
+                    copy_to_c_buf(c, &mut info.color_name);
                 }
                 if let Some(ref rgba) = spool.rgba {
-                    info_ref.color_rgba = parse_rgba_hex(rgba);
+                    info.color_rgba = parse_rgba_hex(rgba);
                 }
                 if let Some(w) = spool.label_weight {
-                    info_ref.label_weight = w;
+                    info.label_weight = w;
                 }
                 if let Some(w) = spool.weight_current {
-                    info_ref.weight_current = w;
+                    info.weight_current = w;
                 }
                 if let Some(ref sf) = spool.slicer_filament {
-                    copy_to_c_buf(sf, &mut info_ref.slicer_filament);
+                    copy_to_c_buf(sf, &mut info.slicer_filament);
                 }
+                info.core_weight = crate::spool_weight::resolve_core_weight(
+                    spool.core_weight,
+                    spool.brand.as_deref(),
+                );
+
+                // Remember this spool so net weight can be derived from the scale
+                crate::spool_weight::set_active_spool(crate::spool_weight::SpoolProfile {
+                    spool_id: spool.id.clone(),
+                    core_weight: info.core_weight,
+                    label_weight: info.label_weight,
+                    density: crate::spool_weight::material_density(
+                        spool.material.as_deref().unwrap_or(""),
+                    ),
+                    diameter_mm: crate::spool_weight::DEFAULT_DIAMETER_MM,
+                });
+
+                let record = SpoolWeightRecord {
+                    weight_current: spool.weight_current,
+                    weight_used: spool.weight_used.unwrap_or(0.0),
+                    consumed_since_weight: spool.consumed_since_weight.unwrap_or(0.0),
+                };
 
                 info!("spool_get_by_tag: found spool {} for tag {}", spool.id, tag_id_str);
-                return true;
+                return Some((info, record));
             }
         }
     }
 
     info!("spool_get_by_tag: no spool found for tag {}", tag_id_str);
-    false
+    None
 }
 
 /// Get K-profile for a spool on a specific printer
@@ -1964,10 +2540,12 @@ pub extern "C" fn spool_add_to_inventory(
 
     let mut client = HttpClient::wrap(connection);
 
-    let headers = [
+    let content_length = body.len().to_string();
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
         ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+        ("Content-Length", &content_length),
+    ], &api_key);
 
     let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
         Ok(r) => r,
@@ -2258,10 +2836,12 @@ pub extern "C" fn spool_link_tag(
 
     let mut client = HttpClient::wrap(connection);
 
-    let headers = [
+    let content_length = body.len().to_string();
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
         ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+        ("Content-Length", &content_length),
+    ], &api_key);
 
     let mut request = match client.request(embedded_svc::http::Method::Patch, &url, &headers) {
         Ok(r) => r,
@@ -2316,6 +2896,11 @@ pub extern "C" fn spool_sync_weight(
             .to_string()
     };
 
+    sync_spool_weight(&spool_id_str, weight)
+}
+
+/// Post a gross scale weight for a spool (Rust-callable)
+pub fn sync_spool_weight(spool_id_str: &str, weight: i32) -> bool {
     if spool_id_str.is_empty() {
         return false;
     }
@@ -2328,12 +2913,19 @@ pub extern "C" fn spool_sync_weight(
         return false;
     }
 
-    // PUT /api/spools/{spool_id}
-    let url = format!("{}/api/spools/{}", base_url, spool_id_str);
-
-    let body = format!(r#"{{"weight_current":{}}}"#, weight);
+    // POST /api/spools/{spool_id}/weight - backend stores the gross reading and
+    // derives weight_used with the core tare we used for the net estimate
+    let url = format!("{}/api/spools/{}/weight", base_url, spool_id_str);
 
-    info!("spool_sync_weight: PUT {} with {}", url, body);
+    let body = match crate::spool_weight::core_weight_for(spool_id_str) {
+        Some(core) => {
+            info!("spool_sync_weight: net {}g after {}g core",
+                  crate::spool_weight::net_weight(weight as f32, core) as i32, core);
+            format!(r#"{{"weight":{},"core_weight":{}}}"#, weight, core)
+        }
+        None => format!(r#"{{"weight":{}}}"#, weight),
+    };
+    info!("spool_sync_weight: POST {} with {}", url, body);
 
     let config = HttpConfig {
         timeout: Some(std::time::Duration::from_millis(HTTP_TIMEOUT_MS)),
@@ -2350,15 +2942,17 @@ pub extern "C" fn spool_sync_weight(
 
     let mut client = HttpClient::wrap(connection);
 
-    let headers = [
+    let content_length = body.len().to_string();
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
         ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+        ("Content-Length", &content_length),
+    ], &api_key);
 
-    let mut request = match client.request(embedded_svc::http::Method::Put, &url, &headers) {
+    let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
         Ok(r) => r,
         Err(e) => {
-            warn!("Failed to create PUT request: {:?}", e);
+            warn!("Failed to create POST request: {:?}", e);
             return false;
         }
     };
@@ -2391,6 +2985,28 @@ pub extern "C" fn spool_sync_weight(
     true
 }
 
+/// Put back a spool's weight tracking saved before a scale sync (undo)
+pub fn restore_spool_weight(spool_id: &str, record: &SpoolWeightRecord) -> bool {
+    let path = format!("/api/spools/{}/weight", spool_id);
+    let document = serde_json::json!({
+        "weight_current": record.weight_current,
+        "weight_used": record.weight_used,
+        "consumed_since_weight": record.consumed_since_weight,
+    });
+    info!("spool_restore_weight: PATCH {} with {}", path, document);
+    match send_json(embedded_svc::http::Method::Patch, &path, &document) {
+        Ok(200) => true,
+        Ok(status) => {
+            warn!("spool_restore_weight failed with status {}", status);
+            false
+        }
+        Err(e) => {
+            warn!("spool_restore_weight failed: {}", e);
+            false
+        }
+    }
+}
+
 /// Assign result enum (matches simulator)
 /// 0 = Error, 1 = Configured, 2 = Staged, 3 = StagedReplace
 #[no_mangle]
@@ -2455,10 +3071,12 @@ pub extern "C" fn backend_assign_spool_to_tray(
 
     // POST request with JSON body
     // Use request() method with headers that include Content-Length
-    let headers = [
+    let content_length = body.len().to_string();
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
         ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+        ("Content-Length", &content_length),
+    ], &api_key);
 
     let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
         Ok(r) => r,
@@ -3048,6 +3666,19 @@ pub extern "C" fn backend_set_slot_filament(
     let tray_sub_brands_str = c_str_to_string(tray_sub_brands);
     let tray_color_str = c_str_to_string(tray_color);
 
+    // Backend offline but the printer is reachable directly
+    if crate::printer_mqtt::is_active(serial_str) {
+        return crate::printer_mqtt::set_slot_filament(serial_str, ams_id, tray_id, &crate::printer_mqtt::SlotFilament {
+            tray_info_idx: &tray_info_idx_str,
+            setting_id: &setting_id_str,
+            tray_type: &tray_type_str,
+            tray_sub_brands: &tray_sub_brands_str,
+            tray_color: &tray_color_str,
+            nozzle_temp_min,
+            nozzle_temp_max,
+        });
+    }
+
     let manager = BACKEND_MANAGER.lock().unwrap();
     let base_url = manager.server_url.clone();
     drop(manager);
@@ -3084,10 +3715,12 @@ pub extern "C" fn backend_set_slot_filament(
 
     let mut client = HttpClient::wrap(connection);
 
-    let headers = [
+    let content_length = body.len().to_string();
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
         ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+        ("Content-Length", &content_length),
+    ], &api_key);
 
     let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
         Ok(r) => r,
@@ -3206,10 +3839,12 @@ pub extern "C" fn backend_set_slot_calibration(
 
     let mut client = HttpClient::wrap(connection);
 
-    let headers = [
+    let content_length = body.len().to_string();
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
         ("Content-Type", "application/json"),
-        ("Content-Length", &body.len().to_string()),
-    ];
+        ("Content-Length", &content_length),
+    ], &api_key);
 
     let mut request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
         Ok(r) => r,
@@ -3296,10 +3931,11 @@ pub extern "C" fn backend_reset_slot(
     let mut client = HttpClient::wrap(connection);
 
     // Empty body POST
-    let headers = [
+    let api_key = crate::config_portal::api_key();
+    let headers = with_api_key(&[
         ("Content-Type", "application/json"),
         ("Content-Length", "0"),
-    ];
+    ], &api_key);
 
     let request = match client.request(embedded_svc::http::Method::Post, &url, &headers) {
         Ok(r) => r,
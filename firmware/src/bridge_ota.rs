@@ -0,0 +1,425 @@
+//! NFC Bridge Firmware Updates
+//!
+//! Updates the Pico NFC bridge without USB access:
+//! 1. Ask the backend for the latest bridge image on the device's update
+//!    channel (`/api/firmware/bridge/check`)
+//! 2. Download it into memory and check its SHA-256 and Ed25519 signature,
+//!    the same way ESP32 images are checked
+//! 3. Push it to the bridge over I2C; the bridge checks the SHA-256 again,
+//!    stages the image and reboots into it
+//! 4. Read the version back from the bridge
+//!
+//! Bridge updates follow the ESP32 update policy (channel, auto-install
+//! window, no printer printing). NFC polling pauses while an update runs.
+//! Bridges with firmware older than the update command set have to be
+//! flashed once over USB.
+
+use esp_idf_hal::i2c::I2cDriver;
+use esp_idf_svc::http::client::{Configuration as HttpConfig, EspHttpConnection};
+use embedded_svc::http::client::Client as HttpClient;
+use log::{error, info, warn};
+use sha2::{Digest, Sha256};
+use std::ffi::{c_char, c_int};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::backend_client::copy_to_c;
+use crate::nfc::i2c_bridge::{self, UPDATE_CHUNK_SIZE};
+use crate::ota_manager::{spawn_worker, OtaState, UpdateInfo, Verifier, Version};
+use crate::shared_i2c;
+
+/// Largest bridge image accepted (sketch partition of a 2 MB Pico)
+const MAX_IMAGE_SIZE: usize = 1024 * 1024;
+
+/// Download attempts before giving up
+const DOWNLOAD_RETRIES: u32 = 3;
+
+/// Failed I2C writes in a row before the update is aborted
+const WRITE_RETRIES: u32 = 5;
+
+/// How long the bridge may take to apply the image and come back
+const REBOOT_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often to look for bridge updates in the background
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Signature tag of NFC bridge images (see `ota_manager::Verifier`)
+const BRIDGE_SIGNATURE_TAG: &str = "spoolbuddy-nfc-bridge";
+
+// Global state
+static STATE: Mutex<OtaState> = Mutex::new(OtaState::Idle);
+/// Version reported by the bridge (None: not read yet or too old to report)
+static BRIDGE_VERSION: Mutex<Option<(u8, u8, u8)>> = Mutex::new(None);
+/// Newer version offered by the backend
+static UPDATE_VERSION: Mutex<Option<String>> = Mutex::new(None);
+static LAST_CHECK: Mutex<Option<Instant>> = Mutex::new(None);
+/// Version the last automatic install was started for (not retried)
+static AUTO_INSTALL_ATTEMPTED: Mutex<String> = Mutex::new(String::new());
+/// An update is being pushed (NFC polling pauses)
+static UPDATING: AtomicBool = AtomicBool::new(false);
+/// A background check or install thread is running
+static WORKER_BUSY: AtomicBool = AtomicBool::new(false);
+
+/// Get bridge update state
+pub fn get_state() -> OtaState {
+    STATE.lock().unwrap().clone()
+}
+
+fn set_state(state: OtaState) {
+    *STATE.lock().unwrap() = state;
+}
+
+/// Whether an update is being pushed to the bridge
+pub fn is_updating() -> bool {
+    UPDATING.load(Ordering::Relaxed)
+}
+
+/// Bridge firmware version as "major.minor.patch", if known
+pub fn get_bridge_version() -> Option<String> {
+    BRIDGE_VERSION
+        .lock()
+        .unwrap()
+        .map(|(major, minor, patch)| format!("{}.{}.{}", major, minor, patch))
+}
+
+/// Newer bridge firmware offered by the backend, if any
+pub fn get_update_version() -> Option<String> {
+    UPDATE_VERSION.lock().unwrap().clone()
+}
+
+/// Run a bridge command on the shared I2C bus
+fn with_bridge<R>(f: impl FnOnce(&mut I2cDriver<'static>) -> Result<R, &'static str>) -> Result<R, String> {
+    shared_i2c::with_i2c(f)
+        .ok_or("I2C not initialized")?
+        .map_err(str::to_string)
+}
+
+/// Read the firmware version from the bridge (call after the bridge was found)
+/// Returns None (and keeps the last known version) if the bridge didn't answer
+pub fn refresh_version() -> Option<(u8, u8, u8)> {
+    match with_bridge(i2c_bridge::get_firmware_version) {
+        Ok((major, minor, patch)) => {
+            info!("NFC bridge firmware: v{}.{}.{}", major, minor, patch);
+            *BRIDGE_VERSION.lock().unwrap() = Some((major, minor, patch));
+            Some((major, minor, patch))
+        }
+        Err(e) => {
+            // Firmware from before OTA support never answers; it needs one USB flash
+            warn!("Failed to read bridge firmware version: {} (busy, or firmware too old for OTA updates)", e);
+            None
+        }
+    }
+}
+
+/// Check for bridge firmware updates on the configured channel
+pub fn check_for_update(server_url: &str) -> Result<UpdateInfo, String> {
+    *LAST_CHECK.lock().unwrap() = Some(Instant::now());
+
+    let current = get_bridge_version();
+    let mut url = format!("{}/api/firmware/bridge/check?channel={}",
+                          server_url, crate::ota_manager::channel().as_str());
+    if let Some(ref current) = current {
+        url.push_str(&format!("&current_version={}", current));
+    }
+    info!("Checking for bridge updates: {}", url);
+
+    let update = crate::ota_manager::query_update(&url, current.as_deref())?;
+    *UPDATE_VERSION.lock().unwrap() = update.available.then(|| update.version.clone());
+    Ok(update)
+}
+
+/// Update the bridge to the latest image on the channel (blocking; use
+/// `start_update` from other modules)
+fn perform_update(server_url: &str) -> Result<(), String> {
+    if UPDATING.swap(true, Ordering::Relaxed) {
+        return Err("Bridge update already running".to_string());
+    }
+    let result = update_bridge(server_url);
+    UPDATING.store(false, Ordering::Relaxed);
+
+    match result {
+        Ok(()) => {
+            set_state(OtaState::Complete);
+            *UPDATE_VERSION.lock().unwrap() = None;
+            Ok(())
+        }
+        Err(e) => {
+            set_state(OtaState::Error(e.clone()));
+            Err(e)
+        }
+    }
+}
+
+/// Start a bridge update in the background
+/// Returns false if an update is already running
+pub fn start_update(server_url: &str) -> bool {
+    if is_updating() {
+        return false;
+    }
+    spawn_worker("bridge_update", &WORKER_BUSY, server_url.to_string(), |url| {
+        if let Err(e) = perform_update(&url) {
+            error!("Bridge update failed: {}", e);
+        }
+    })
+}
+
+fn update_bridge(server_url: &str) -> Result<(), String> {
+    set_state(OtaState::Checking);
+    let (major, minor, patch) = refresh_version()
+        .ok_or_else(|| "Bridge firmware version unknown (busy, or too old for OTA updates)".to_string())?;
+
+    let update = check_for_update(server_url)?;
+    if !update.available {
+        return Err(format!("No bridge update available (running v{}.{}.{})", major, minor, patch));
+    }
+    if update.sha256.len() != 64 {
+        return Err("Bridge update check returned no SHA-256".to_string());
+    }
+    let expected = Version::parse(&update.version)
+        .ok_or_else(|| format!("Invalid bridge version: {}", update.version))?;
+    if expected.core() < (major as u64, minor as u64, patch as u64) {
+        return Err(format!("Bridge v{} is older than the running v{}.{}.{}",
+                           update.version, major, minor, patch));
+    }
+    let verifier = Verifier::new(&update.signature, BRIDGE_SIGNATURE_TAG)?;
+
+    // Verify the whole image before touching the bridge
+    let image = download_image(server_url, &update)?;
+    set_state(OtaState::Validating);
+    let mut digest = [0u8; 32];
+    digest.copy_from_slice(&Sha256::digest(&image));
+    crate::ota_manager::verify_digest(&digest, &update.sha256, &update.version, &verifier)?;
+
+    push_image(&image, &digest)?;
+
+    // The bridge copies the staged image into place while it reboots
+    info!("Waiting for the NFC bridge to restart...");
+    let deadline = Instant::now() + REBOOT_TIMEOUT;
+    std::thread::sleep(Duration::from_secs(2));
+    loop {
+        if let Ok(version) = with_bridge(i2c_bridge::get_firmware_version) {
+            *BRIDGE_VERSION.lock().unwrap() = Some(version);
+            let (major, minor, patch) = version;
+            if expected.core() == (major as u64, minor as u64, patch as u64) {
+                info!("NFC bridge updated to v{}", update.version);
+                return Ok(());
+            }
+            return Err(format!("Bridge still reports v{}.{}.{} after the update", major, minor, patch));
+        }
+        if Instant::now() >= deadline {
+            return Err("NFC bridge did not come back after the update".to_string());
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// Download the bridge image into memory
+fn download_image(server_url: &str, update: &UpdateInfo) -> Result<Vec<u8>, String> {
+    let size = update.size as usize;
+    if size == 0 || size > MAX_IMAGE_SIZE {
+        return Err(format!("Invalid bridge image size: {} bytes", size));
+    }
+
+    let url = format!("{}/api/firmware/bridge/ota?version={}", server_url, update.version);
+    info!("Downloading bridge firmware v{} from: {}", update.version, url);
+
+    let mut retries = 0;
+    loop {
+        set_state(OtaState::Downloading { progress: 0 });
+        match download_from(&url, size) {
+            Ok(image) => return Ok(image),
+            Err(e) => {
+                retries += 1;
+                if retries >= DOWNLOAD_RETRIES {
+                    return Err(e);
+                }
+                warn!("{}, retrying ({}/{})", e, retries, DOWNLOAD_RETRIES);
+                std::thread::sleep(Duration::from_secs(2));
+            }
+        }
+    }
+}
+
+fn download_from(url: &str, size: usize) -> Result<Vec<u8>, String> {
+    let config = HttpConfig {
+        timeout: Some(Duration::from_secs(30)),
+        ..Default::default()
+    };
+    let connection = EspHttpConnection::new(&config)
+        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
+    let mut client = HttpClient::wrap(connection);
+
+    let request = client.get(url)
+        .map_err(|e| format!("HTTP request failed: {:?}", e))?;
+    let mut response = request.submit()
+        .map_err(|e| format!("HTTP submit failed: {:?}", e))?;
+
+    let status = response.status();
+    if status != 200 {
+        return Err(format!("HTTP error: {}", status));
+    }
+
+    let mut image = Vec::new();
+    image.try_reserve_exact(size)
+        .map_err(|_| format!("Not enough memory for a {} byte image", size))?;
+
+    let mut buf = vec![0u8; 4096];
+    while image.len() < size {
+        let n = response.read(&mut buf)
+            .map_err(|e| format!("Download error: {:?}", e))?;
+        if n == 0 {
+            return Err(format!("Connection closed at {} / {} bytes", image.len(), size));
+        }
+        if image.len() + n > size {
+            return Err(format!("Bridge image larger than announced ({} bytes)", size));
+        }
+        image.extend_from_slice(&buf[..n]);
+        set_state(OtaState::Downloading { progress: ((image.len() * 100) / size) as u8 });
+    }
+
+    info!("Bridge image downloaded: {} bytes", image.len());
+    Ok(image)
+}
+
+/// Write the image to the bridge in chunks and let it verify and stage it
+fn push_image(image: &[u8], sha256: &[u8; 32]) -> Result<(), String> {
+    info!("Pushing {} bytes to the NFC bridge", image.len());
+    set_state(OtaState::Flashing { progress: 0 });
+    with_bridge(|i2c| i2c_bridge::update_begin(i2c, image.len() as u32, sha256))?;
+
+    let mut offset = 0;
+    let mut failures = 0;
+    while offset < image.len() {
+        let end = (offset + UPDATE_CHUNK_SIZE).min(image.len());
+        match with_bridge(|i2c| i2c_bridge::update_write(i2c, offset as u32, &image[offset..end])) {
+            // Continue where the bridge is (it skips chunks it already has)
+            Ok(written) if written as usize <= image.len() => {
+                offset = written as usize;
+                failures = 0;
+            }
+            Ok(written) => {
+                return Err(abort_push(format!("Bridge reports {} bytes written", written)));
+            }
+            Err(e) => {
+                failures += 1;
+                if failures > WRITE_RETRIES {
+                    return Err(abort_push(e));
+                }
+                warn!("Bridge write at {} failed: {}, retrying", offset, e);
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        }
+        set_state(OtaState::Flashing { progress: ((offset * 100) / image.len()) as u8 });
+    }
+
+    with_bridge(i2c_bridge::update_end)
+}
+
+/// Discard the partial image on the bridge and pass the error on
+fn abort_push(e: String) -> String {
+    if let Err(abort_err) = with_bridge(i2c_bridge::update_abort) {
+        warn!("Bridge update abort failed: {}", abort_err);
+    }
+    e
+}
+
+// ============================================================================
+// Background checks and automatic installs
+// ============================================================================
+
+/// Periodic bridge update check and scheduled install
+/// Called from the network thread every ~2 seconds
+pub fn poll(server_url: &str) {
+    if WORKER_BUSY.load(Ordering::Relaxed)
+        || is_updating()
+        || !crate::nfc_bridge_manager::is_initialized()
+        || !matches!(crate::ota_manager::get_state(), OtaState::Idle | OtaState::Error(_))
+    {
+        return;
+    }
+
+    let check_due = LAST_CHECK
+        .lock()
+        .unwrap()
+        .map_or(true, |last| last.elapsed() >= CHECK_INTERVAL);
+    if check_due {
+        spawn_worker("bridge_check", &WORKER_BUSY, server_url.to_string(), |url| {
+            // Not known yet if the bridge was busy when it was found
+            if get_bridge_version().is_none() && refresh_version().is_none() {
+                *LAST_CHECK.lock().unwrap() = Some(Instant::now());
+                return;
+            }
+            match check_for_update(&url) {
+                Ok(info) if info.available => info!("NFC bridge update available: v{}", info.version),
+                Ok(_) => {}
+                Err(e) => warn!("Bridge update check failed: {}", e),
+            }
+        });
+    } else if let Some(version) = get_update_version() {
+        if *AUTO_INSTALL_ATTEMPTED.lock().unwrap() == version
+            || !crate::ota_manager::auto_install_allowed()
+        {
+            return;
+        }
+        info!("Installing NFC bridge v{} automatically", version);
+        *AUTO_INSTALL_ATTEMPTED.lock().unwrap() = version;
+        start_update(server_url);
+    }
+}
+
+// ============================================================================
+// C-callable interface
+// ============================================================================
+
+/// Get bridge update state
+/// Returns: 0=Idle, 1=Checking, 2=Downloading, 3=Validating, 4=Flashing, 5=Complete, 6=Error
+#[no_mangle]
+pub extern "C" fn bridge_ota_get_state() -> c_int {
+    match get_state() {
+        OtaState::Idle => 0,
+        OtaState::Checking => 1,
+        OtaState::Downloading { .. } => 2,
+        OtaState::Validating => 3,
+        OtaState::Flashing { .. } => 4,
+        OtaState::Complete => 5,
+        OtaState::Error(_) => 6,
+    }
+}
+
+/// Get bridge download/flash progress (0-100)
+/// Returns -1 if not in a progress state
+#[no_mangle]
+pub extern "C" fn bridge_ota_get_progress() -> c_int {
+    match get_state() {
+        OtaState::Downloading { progress } | OtaState::Flashing { progress } => progress as c_int,
+        _ => -1,
+    }
+}
+
+/// Get the bridge firmware version
+/// Copies version string to buffer, returns length or -1 if unknown
+#[no_mangle]
+pub extern "C" fn bridge_ota_get_version(buf: *mut c_char, buf_len: c_int) -> c_int {
+    match get_bridge_version() {
+        Some(version) => copy_to_c(&version, buf, buf_len),
+        None => -1,
+    }
+}
+
+/// Get the available bridge update version
+/// Copies version string to buffer, returns length or -1 if none
+#[no_mangle]
+pub extern "C" fn bridge_ota_get_update_version(buf: *mut c_char, buf_len: c_int) -> c_int {
+    match get_update_version() {
+        Some(version) => copy_to_c(&version, buf, buf_len),
+        None => -1,
+    }
+}
+
+/// Start a bridge update (non-blocking)
+/// Returns 0 on success, -1 if an update is already running
+#[no_mangle]
+pub extern "C" fn bridge_ota_start_update() -> c_int {
+    if start_update(&crate::config_portal::backend_url()) { 0 } else { -1 }
+}
@@ -0,0 +1,485 @@
+//! SoftAP Setup Portal
+//!
+//! For headless units (or when the touchscreen keyboard is a pain), the
+//! device opens an open access point "SpoolBuddy-XXXX" with a small HTTP
+//! server and a catch-all DNS responder, so phones show the setup page as a
+//! captive portal. The page sets WiFi SSID/password, backend URL and API key;
+//! everything is saved to the settings store and the device reboots to apply it.
+//! The AP is open, so the page never shows the saved API key: an empty field
+//! keeps it.
+//!
+//! Also provides the backend settings used by the rest of the firmware.
+
+use embedded_svc::http::Method;
+use embedded_svc::io::{Read, Write};
+use esp_idf_svc::http::server::{Configuration as HttpServerConfig, EspHttpServer};
+use log::{error, info, warn};
+use std::ffi::{c_char, c_int};
+use std::net::UdpSocket;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::backend_client::copy_to_c;
+use crate::{settings, wifi_manager};
+
+/// Backend URL used until one is configured
+pub const DEFAULT_BACKEND_URL: &str = "http://192.168.255.16:3000";
+
+/// Largest form submission accepted (bytes)
+const MAX_FORM_LEN: usize = 1024;
+
+/// Running portal (server and DNS responder stop when dropped/flagged)
+struct Portal {
+    _server: EspHttpServer<'static>,
+    dns_stop: Arc<AtomicBool>,
+    ssid: String,
+}
+
+static PORTAL: Mutex<Option<Portal>> = Mutex::new(None);
+
+// =============================================================================
+// Backend settings
+// =============================================================================
+
+/// Configured backend URL (falls back to the built-in default)
+pub fn backend_url() -> String {
+    settings::read(|s| {
+        if s.backend.url.is_empty() {
+            DEFAULT_BACKEND_URL.to_string()
+        } else {
+            s.backend.url.clone()
+        }
+    })
+}
+
+/// Configured backend API key (empty if none)
+pub fn api_key() -> String {
+    settings::read(|s| s.backend.api_key.clone())
+}
+
+/// Save backend settings (api_key None keeps the current key)
+fn save_backend_config(url: &str, api_key: Option<&str>) -> Result<(), String> {
+    settings::update(|s| {
+        s.backend.url = url.to_string();
+        if let Some(api_key) = api_key {
+            s.backend.api_key = api_key.to_string();
+        }
+    })?;
+    info!("Backend settings saved: {}", url);
+    Ok(())
+}
+
+// =============================================================================
+// Portal lifecycle
+// =============================================================================
+
+/// Start the SoftAP, HTTP setup page and captive DNS
+pub fn start() -> Result<(), String> {
+    if PORTAL.lock().unwrap().is_some() {
+        return Ok(());
+    }
+
+    // Suffix from the AP MAC so several units can be set up side by side
+    let mac = wifi_manager::ap_mac().unwrap_or_default();
+    let ssid = format!("SpoolBuddy-{:02X}{:02X}", mac[4], mac[5]);
+    let ip = wifi_manager::start_access_point(&ssid)?;
+
+    let server = start_http_server(ip)?;
+
+    let dns_stop = Arc::new(AtomicBool::new(false));
+    let stop = dns_stop.clone();
+    std::thread::Builder::new()
+        .name("portal_dns".into())
+        .stack_size(4096)
+        .spawn(move || run_dns(ip, stop))
+        .map_err(|e| format!("Failed to start DNS thread: {:?}", e))?;
+
+    info!("Setup portal running: join '{}' and open http://{}.{}.{}.{}/",
+          ssid, ip[0], ip[1], ip[2], ip[3]);
+    *PORTAL.lock().unwrap() = Some(Portal {
+        _server: server,
+        dns_stop,
+        ssid,
+    });
+    Ok(())
+}
+
+/// Stop the portal and the SoftAP
+pub fn stop() {
+    let Some(portal) = PORTAL.lock().unwrap().take() else {
+        return;
+    };
+    portal.dns_stop.store(true, Ordering::Relaxed);
+    drop(portal);
+    if let Err(e) = wifi_manager::stop_access_point() {
+        warn!("{}", e);
+    }
+    info!("Setup portal stopped");
+}
+
+/// Whether the portal is running
+pub fn is_active() -> bool {
+    PORTAL.lock().unwrap().is_some()
+}
+
+// =============================================================================
+// HTTP server
+// =============================================================================
+
+fn start_http_server(ip: [u8; 4]) -> Result<EspHttpServer<'static>, String> {
+    let config = HttpServerConfig {
+        uri_match_wildcard: true,
+        stack_size: 8192,
+        ..Default::default()
+    };
+    let mut server = EspHttpServer::new(&config)
+        .map_err(|e| format!("Failed to start HTTP server: {:?}", e))?;
+
+    server
+        .fn_handler::<anyhow::Error, _>("/", Method::Get, |req| {
+            let page = setup_page();
+            req.into_response(200, None, &[("Content-Type", "text/html; charset=utf-8")])?
+                .write_all(page.as_bytes())?;
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to register handler: {:?}", e))?;
+
+    server
+        .fn_handler::<anyhow::Error, _>("/save", Method::Post, |mut req| {
+            let mut body = Vec::new();
+            let mut buf = [0u8; 256];
+            loop {
+                let n = req.read(&mut buf)?;
+                if n == 0 || body.len() + n > MAX_FORM_LEN {
+                    break;
+                }
+                body.extend_from_slice(&buf[..n]);
+            }
+
+            let (status, message) = match apply_form(&String::from_utf8_lossy(&body)) {
+                Ok(ssid) => (200, format!("Saved. SpoolBuddy will restart and join \"{}\".", html_escape(&ssid))),
+                Err(e) => (400, format!("Not saved: {}", html_escape(&e))),
+            };
+            req.into_response(status, None, &[("Content-Type", "text/html; charset=utf-8")])?
+                .write_all(result_page(&message).as_bytes())?;
+
+            if status == 200 {
+                schedule_restart();
+            }
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to register handler: {:?}", e))?;
+
+    // Any other URL (OS connectivity checks included) redirects to the setup
+    // page, which makes phones and laptops open it as a captive portal
+    let location = format!("http://{}.{}.{}.{}/", ip[0], ip[1], ip[2], ip[3]);
+    server
+        .fn_handler::<anyhow::Error, _>("/*", Method::Get, move |req| {
+            req.into_response(302, Some("Found"), &[("Location", location.as_str())])?;
+            Ok(())
+        })
+        .map_err(|e| format!("Failed to register handler: {:?}", e))?;
+
+    Ok(server)
+}
+
+/// Validate and persist a submitted setup form
+/// Returns the new SSID on success
+fn apply_form(body: &str) -> Result<String, String> {
+    let mut ssid = String::new();
+    let mut password = String::new();
+    let mut backend_url = String::new();
+    let mut api_key = String::new();
+    let mut clear_api_key = false;
+    let mut ip_fields: [String; 6] = Default::default();
+    let mut printer_lan = false;
+
+    for pair in body.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = url_decode(value);
+        match key {
+            "ssid" => ssid = value.trim().to_string(),
+            "password" => password = value,
+            "backend_url" => backend_url = value.trim().trim_end_matches('/').to_string(),
+            "api_key" => api_key = value.trim().to_string(),
+            "clear_api_key" => clear_api_key = value == "on",
+            "ip_mode" => ip_fields[0] = value,
+            "ip" => ip_fields[1] = value,
+            "netmask" => ip_fields[2] = value,
+            "gateway" => ip_fields[3] = value,
+            "dns1" => ip_fields[4] = value,
+            "dns2" => ip_fields[5] = value,
+            "printer_lan" => printer_lan = value == "on",
+            _ => {}
+        }
+    }
+
+    if ssid.is_empty() || ssid.len() > 32 {
+        return Err("SSID must be 1-32 characters".to_string());
+    }
+    if !password.is_empty() && !(8..=63).contains(&password.len()) {
+        return Err("Password must be 8-63 characters (or empty for open networks)".to_string());
+    }
+    let ip_config = parse_ip_fields(&ip_fields)?;
+    ip_config.validate()?;
+    if !backend_url.is_empty() {
+        if !backend_url.starts_with("http://") && !backend_url.starts_with("https://") {
+            return Err("Backend URL must start with http:// or https://".to_string());
+        }
+        // Empty field keeps the saved key, it is never sent to the page
+        let api_key = match (clear_api_key, api_key.is_empty()) {
+            (true, _) => Some(""),
+            (false, false) => Some(api_key.as_str()),
+            (false, true) => None,
+        };
+        save_backend_config(&backend_url, api_key)?;
+    }
+    wifi_manager::set_ip_config(ip_config)?;
+    crate::printer_mqtt::set_enabled(printer_lan)?;
+
+    wifi_manager::store_credentials(&ssid, &password);
+    info!("Setup portal: saved WiFi '{}'", ssid);
+    Ok(ssid)
+}
+
+/// Build the IP settings from the form's ip_mode, ip, netmask, gateway,
+/// dns1 and dns2 fields (in that order)
+fn parse_ip_fields(fields: &[String; 6]) -> Result<wifi_manager::IpConfig, String> {
+    let addr = |i: usize, name: &str| {
+        wifi_manager::parse_ipv4(&fields[i]).ok_or_else(|| format!("Invalid {}", name))
+    };
+    Ok(wifi_manager::IpConfig {
+        use_static: fields[0] == "static",
+        ip: addr(1, "IP address")?,
+        netmask: addr(2, "netmask")?,
+        gateway: addr(3, "gateway")?,
+        dns_primary: addr(4, "DNS server")?,
+        dns_secondary: addr(5, "DNS server")?,
+    })
+}
+
+/// Format an address for a form field (empty if unset)
+fn ip_field(ip: [u8; 4]) -> String {
+    if ip == [0; 4] {
+        String::new()
+    } else {
+        format!("{}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3])
+    }
+}
+
+/// Reboot shortly after answering so the browser gets the confirmation page
+fn schedule_restart() {
+    let _ = std::thread::Builder::new()
+        .name("portal_restart".into())
+        .stack_size(2048)
+        .spawn(|| {
+            std::thread::sleep(Duration::from_secs(2));
+            info!("Restarting to apply portal settings");
+            unsafe { esp_idf_sys::esp_restart(); }
+        });
+}
+
+fn setup_page() -> String {
+    let networks = wifi_manager::scan_networks();
+    let options: String = networks
+        .iter()
+        .map(|(ssid, rssi)| format!("<option value=\"{0}\">{0} ({1} dBm)</option>", html_escape(ssid), rssi))
+        .collect();
+    let ip = wifi_manager::ip_config();
+    let printer_lan = crate::printer_mqtt::is_enabled();
+    let url = backend_url();
+    let has_api_key = settings::read(|s| !s.backend.api_key.is_empty());
+    let api_key_fields = if has_api_key {
+        "<label>API key (leave empty to keep the saved key)<input name=\"api_key\" autocomplete=\"off\"></label>\
+<label><input name=\"clear_api_key\" type=\"checkbox\" style=\"width:auto\"> Remove saved API key</label>"
+    } else {
+        "<label>API key (optional)<input name=\"api_key\" autocomplete=\"off\"></label>"
+    };
+
+    format!(
+        "<!DOCTYPE html><html><head><meta name=\"viewport\" content=\"width=device-width,initial-scale=1\">\
+<title>SpoolBuddy Setup</title><style>{}</style></head><body><h1>SpoolBuddy Setup</h1>\
+<form method=\"post\" action=\"/save\">\
+<label>WiFi network<input name=\"ssid\" list=\"nets\" required maxlength=\"32\"></label>\
+<datalist id=\"nets\">{}</datalist>\
+<label>Password<input name=\"password\" type=\"password\" maxlength=\"63\"></label>\
+<label>Backend URL<input name=\"backend_url\" value=\"{}\"></label>\
+{}\
+<label>IP address<select name=\"ip_mode\"><option value=\"dhcp\"{}>DHCP</option>\
+<option value=\"static\"{}>Static</option></select></label>\
+<label>Static IP<input name=\"ip\" value=\"{}\" placeholder=\"192.168.1.50\"></label>\
+<label>Netmask<input name=\"netmask\" value=\"{}\" placeholder=\"255.255.255.0\"></label>\
+<label>Gateway<input name=\"gateway\" value=\"{}\"></label>\
+<label>DNS server<input name=\"dns1\" value=\"{}\"></label>\
+<label>Secondary DNS<input name=\"dns2\" value=\"{}\"></label>\
+<label>Direct printer connection when backend is offline<select name=\"printer_lan\">\
+<option value=\"off\"{}>Off</option><option value=\"on\"{}>On</option></select></label>\
+<button type=\"submit\">Save &amp; restart</button></form></body></html>",
+        PAGE_STYLE,
+        options,
+        html_escape(&url),
+        api_key_fields,
+        if ip.use_static { "" } else { " selected" },
+        if ip.use_static { " selected" } else { "" },
+        ip_field(ip.ip),
+        ip_field(ip.netmask),
+        ip_field(ip.gateway),
+        ip_field(ip.dns_primary),
+        ip_field(ip.dns_secondary),
+        if printer_lan { "" } else { " selected" },
+        if printer_lan { " selected" } else { "" },
+    )
+}
+
+fn result_page(message: &str) -> String {
+    format!(
+        "<!DOCTYPE html><html><head><meta name=\"viewport\" content=\"width=device-width,initial-scale=1\">\
+<title>SpoolBuddy Setup</title><style>{}</style></head><body><h1>SpoolBuddy Setup</h1><p>{}</p>\
+<a href=\"/\">Back</a></body></html>",
+        PAGE_STYLE, message
+    )
+}
+
+const PAGE_STYLE: &str = "body{font-family:sans-serif;background:#1a1a1a;color:#fafafa;max-width:420px;margin:auto;padding:16px}\
+label{display:block;margin:12px 0 4px;color:#aaa}input,select{width:100%;padding:10px;box-sizing:border-box;\
+background:#2a2a2a;color:#fafafa;border:1px solid #444;border-radius:6px}\
+button{margin-top:20px;width:100%;padding:12px;background:#00ae42;color:#fff;border:0;border-radius:6px}a{color:#00ae42}";
+
+/// Decode an application/x-www-form-urlencoded value
+fn url_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => out.push(b' '),
+            b'%' if i + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).ok();
+                match hex.and_then(|h| u8::from_str_radix(h, 16).ok()) {
+                    Some(b) => {
+                        out.push(b);
+                        i += 2;
+                    }
+                    None => out.push(b'%'),
+                }
+            }
+            b => out.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// =============================================================================
+// Captive DNS
+// =============================================================================
+
+/// Answer every DNS A query with the AP address until stopped
+fn run_dns(ip: [u8; 4], stop: Arc<AtomicBool>) {
+    let socket = match UdpSocket::bind("0.0.0.0:53") {
+        Ok(s) => s,
+        Err(e) => {
+            error!("Captive DNS bind failed: {:?}", e);
+            return;
+        }
+    };
+    let _ = socket.set_read_timeout(Some(Duration::from_millis(500)));
+
+    let mut buf = [0u8; 512];
+    while !stop.load(Ordering::Relaxed) {
+        let Ok((len, src)) = socket.recv_from(&mut buf) else {
+            continue;
+        };
+        if let Some(reply) = dns_reply(&buf[..len], ip) {
+            let _ = socket.send_to(&reply, src);
+        }
+    }
+    info!("Captive DNS stopped");
+}
+
+/// Build a reply pointing the (single) question at `ip`
+fn dns_reply(query: &[u8], ip: [u8; 4]) -> Option<Vec<u8>> {
+    // Header is 12 bytes; only handle standard queries with one question
+    if query.len() < 12 || query[2] & 0x80 != 0 || u16::from_be_bytes([query[4], query[5]]) != 1 {
+        return None;
+    }
+
+    // Walk the QNAME labels to find the end of the question
+    let mut pos = 12;
+    while pos < query.len() && query[pos] != 0 {
+        pos += query[pos] as usize + 1;
+    }
+    let question_end = pos + 5; // zero byte + QTYPE + QCLASS
+    if question_end > query.len() {
+        return None;
+    }
+    let qtype = u16::from_be_bytes([query[pos + 1], query[pos + 2]]);
+
+    let mut reply = Vec::with_capacity(question_end + 16);
+    reply.extend_from_slice(&query[..2]); // ID
+    reply.extend_from_slice(&[0x81, 0x80]); // Response, recursion available, no error
+    reply.extend_from_slice(&[0x00, 0x01]); // QDCOUNT
+    reply.extend_from_slice(&[0x00, if qtype == 1 { 1 } else { 0 }]); // ANCOUNT
+    reply.extend_from_slice(&[0x00, 0x00, 0x00, 0x00]); // NSCOUNT, ARCOUNT
+    reply.extend_from_slice(&query[12..question_end]);
+
+    // Only A records get an answer; AAAA etc. get an empty reply
+    if qtype == 1 {
+        reply.extend_from_slice(&[0xC0, 0x0C]); // Pointer to QNAME
+        reply.extend_from_slice(&[0x00, 0x01, 0x00, 0x01]); // Type A, class IN
+        reply.extend_from_slice(&60u32.to_be_bytes()); // TTL
+        reply.extend_from_slice(&[0x00, 0x04]);
+        reply.extend_from_slice(&ip);
+    }
+    Some(reply)
+}
+
+// =============================================================================
+// C-callable FFI Functions
+// =============================================================================
+
+/// Start the setup portal
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn config_portal_start() -> c_int {
+    match start() {
+        Ok(()) => 0,
+        Err(e) => {
+            error!("Setup portal failed to start: {}", e);
+            -1
+        }
+    }
+}
+
+/// Stop the setup portal
+#[no_mangle]
+pub extern "C" fn config_portal_stop() {
+    stop();
+}
+
+/// Check if the setup portal is running
+#[no_mangle]
+pub extern "C" fn config_portal_is_active() -> bool {
+    is_active()
+}
+
+/// Get the portal's AP name
+/// Copies the SSID to the provided buffer, returns length or -1 if not running
+#[no_mangle]
+pub extern "C" fn config_portal_get_ssid(buf: *mut c_char, buf_len: c_int) -> c_int {
+    match PORTAL.lock().unwrap().as_ref() {
+        Some(portal) => copy_to_c(&portal.ssid, buf, buf_len),
+        None => {
+            copy_to_c("", buf, buf_len);
+            -1
+        }
+    }
+}
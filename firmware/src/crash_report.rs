@@ -0,0 +1,212 @@
+//! Crash and reset-reason reporting
+//!
+//! A panic hook stores the panic message, a backtrace and the last log lines
+//! in RTC memory, which survives the reset that follows. On the next boot the
+//! reset reason from `esp_reset_reason()` is combined with that record, and
+//! unexpected resets (panics, watchdogs, brownouts) are uploaded once to the
+//! backend as soon as it can be reached again.
+//!
+//! Watchdog and brownout resets bypass the panic hook, so those reports only
+//! carry the reset reason.
+
+use log::{info, warn};
+use serde_json::{json, Value};
+use std::sync::Mutex;
+
+/// Marks a valid record in RTC memory ("SBCR")
+const CRASH_MAGIC: u32 = 0x5342_4352;
+
+/// Stored panic message length
+const MESSAGE_LEN: usize = 256;
+
+/// Stored backtrace frames
+const BACKTRACE_DEPTH: usize = 16;
+
+/// Stored log tail length
+const LOG_LEN: usize = 1536;
+
+/// Panic record kept across the reset (not initialized by the bootloader)
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct RtcCrash {
+    magic: u32,
+    message_len: u16,
+    log_len: u16,
+    backtrace_len: u32,
+    message: [u8; MESSAGE_LEN],
+    backtrace: [u32; BACKTRACE_DEPTH],
+    log: [u8; LOG_LEN],
+}
+
+#[link_section = ".rtc_noinit"]
+static mut RTC_CRASH: RtcCrash = RtcCrash {
+    magic: 0,
+    message_len: 0,
+    log_len: 0,
+    backtrace_len: 0,
+    message: [0; MESSAGE_LEN],
+    backtrace: [0; BACKTRACE_DEPTH],
+    log: [0; LOG_LEN],
+};
+
+/// Report of the previous reset, waiting for upload
+#[derive(Debug, Clone)]
+pub struct CrashReport {
+    pub reason: &'static str,
+    pub reason_code: esp_idf_sys::esp_reset_reason_t,
+    pub panic: Option<String>,
+    /// Return addresses, decode with `xtensa-esp32s3-elf-addr2line`
+    pub backtrace: Vec<u32>,
+    pub log: Vec<String>,
+}
+
+impl CrashReport {
+    fn to_json(&self) -> Value {
+        let backtrace: Vec<String> = self.backtrace.iter().map(|pc| format!("0x{:08x}", pc)).collect();
+        json!({
+            "reason": self.reason,
+            "reason_code": self.reason_code,
+            "firmware_version": env!("CARGO_PKG_VERSION"),
+            "panic": self.panic,
+            "backtrace": backtrace.join(" "),
+            "log": self.log,
+        })
+    }
+}
+
+static PENDING: Mutex<Option<CrashReport>> = Mutex::new(None);
+
+/// Read the previous reset and install the panic hook (call early at startup)
+pub fn init() {
+    let code = unsafe { esp_idf_sys::esp_reset_reason() };
+    let (reason, expected) = reset_reason(code);
+
+    let record = unsafe { std::ptr::read_volatile(std::ptr::addr_of!(RTC_CRASH)) };
+    unsafe { std::ptr::write_volatile(std::ptr::addr_of_mut!(RTC_CRASH.magic), 0) };
+
+    let mut report = CrashReport {
+        reason,
+        reason_code: code,
+        panic: None,
+        backtrace: Vec::new(),
+        log: Vec::new(),
+    };
+    if record.magic == CRASH_MAGIC {
+        let message_len = (record.message_len as usize).min(MESSAGE_LEN);
+        let log_len = (record.log_len as usize).min(LOG_LEN);
+        let backtrace_len = (record.backtrace_len as usize).min(BACKTRACE_DEPTH);
+        report.panic = Some(String::from_utf8_lossy(&record.message[..message_len]).into_owned());
+        report.backtrace = record.backtrace[..backtrace_len].to_vec();
+        report.log = String::from_utf8_lossy(&record.log[..log_len])
+            .lines()
+            .map(str::to_string)
+            .collect();
+    }
+
+    if expected && report.panic.is_none() {
+        info!("Reset reason: {}", reason);
+    } else {
+        warn!("Unexpected reset: {}", reason);
+        if let Some(ref panic) = report.panic {
+            warn!("Last panic: {}", panic);
+        }
+        *PENDING.lock().unwrap() = Some(report);
+    }
+
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic_info| {
+        store_panic(&panic_info.to_string());
+        default_hook(panic_info);
+    }));
+}
+
+/// Name of a reset reason and whether it is a normal one (not reported)
+fn reset_reason(code: esp_idf_sys::esp_reset_reason_t) -> (&'static str, bool) {
+    match code {
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_POWERON => ("power_on", true),
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_EXT => ("external", true),
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_SW => ("software", true),
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_DEEPSLEEP => ("deep_sleep", true),
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_PANIC => ("panic", false),
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_INT_WDT => ("interrupt_watchdog", false),
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_TASK_WDT => ("task_watchdog", false),
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_WDT => ("watchdog", false),
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_BROWNOUT => ("brownout", false),
+        esp_idf_sys::esp_reset_reason_t_ESP_RST_SDIO => ("sdio", false),
+        _ => ("unknown", false),
+    }
+}
+
+/// Save the panic message, backtrace and log tail to RTC memory
+fn store_panic(message: &str) {
+    let mut backtrace = [0u32; BACKTRACE_DEPTH];
+    let backtrace_len = capture_backtrace(&mut backtrace);
+
+    // Newest lines win if they don't all fit
+    let lines = crate::udp_logger::recent_lines();
+    let mut log = String::new();
+    for line in lines.iter().rev() {
+        if log.len() + line.len() + 1 > LOG_LEN {
+            break;
+        }
+        log.insert_str(0, "\n");
+        log.insert_str(0, line);
+    }
+
+    let mut record = RtcCrash {
+        magic: CRASH_MAGIC,
+        message_len: 0,
+        log_len: log.len() as u16,
+        backtrace_len: backtrace_len as u32,
+        message: [0; MESSAGE_LEN],
+        backtrace,
+        log: [0; LOG_LEN],
+    };
+    let message = message.as_bytes();
+    let message_len = message.len().min(MESSAGE_LEN);
+    record.message[..message_len].copy_from_slice(&message[..message_len]);
+    record.message_len = message_len as u16;
+    record.log[..log.len()].copy_from_slice(log.as_bytes());
+
+    unsafe { std::ptr::write_volatile(std::ptr::addr_of_mut!(RTC_CRASH), record) };
+}
+
+/// Walk the Xtensa call stack (same addresses as the ESP-IDF "Backtrace:" line)
+#[cfg(target_arch = "xtensa")]
+fn capture_backtrace(out: &mut [u32]) -> usize {
+    let mut frame: esp_idf_sys::esp_backtrace_frame_t = unsafe { std::mem::zeroed() };
+    unsafe {
+        esp_idf_sys::esp_backtrace_get_start(&mut frame.pc, &mut frame.sp, &mut frame.next_pc);
+    }
+
+    let mut depth = 0;
+    while depth < out.len() {
+        // Strip the window increment bits from the return address
+        out[depth] = if frame.pc & 0x8000_0000 != 0 {
+            ((frame.pc & 0x3fff_ffff) | 0x4000_0000) - 3
+        } else {
+            frame.pc
+        };
+        depth += 1;
+        if frame.next_pc == 0 || !unsafe { esp_idf_sys::esp_backtrace_get_next_frame(&mut frame) } {
+            break;
+        }
+    }
+    depth
+}
+
+#[cfg(not(target_arch = "xtensa"))]
+fn capture_backtrace(_out: &mut [u32]) -> usize {
+    0
+}
+
+/// Upload the report of the previous reset, once (call when the backend answers)
+pub fn upload_pending() {
+    let Some(report) = PENDING.lock().unwrap().clone() else {
+        return;
+    };
+    if crate::backend_client::send_crash_report(&report.to_json()) {
+        info!("Crash report uploaded ({})", report.reason);
+        *PENDING.lock().unwrap() = None;
+    }
+}
@@ -0,0 +1,116 @@
+//! Device health metrics for the heartbeat
+//!
+//! Collects heap and PSRAM usage, main-loop timing, stack high-water marks,
+//! I2C error counters, scale noise and NFC read statistics. A snapshot is
+//! sent with the heartbeat every `HEALTH_INTERVAL` so the backend can chart
+//! fleet health. Timing and noise figures cover the time since the previous
+//! snapshot; counters are totals since boot.
+
+use serde_json::{json, Value};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// How often a snapshot is attached to the heartbeat
+const HEALTH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Threads that report their stack high-water mark
+const MAX_STACKS: usize = 8;
+
+/// Main-loop iteration times since the last snapshot
+struct LoopStats {
+    count: u32,
+    total_us: u64,
+    max_us: u32,
+}
+
+static LOOP_STATS: Mutex<LoopStats> = Mutex::new(LoopStats { count: 0, total_us: 0, max_us: 0 });
+
+/// Lowest free stack seen per thread (bytes)
+static STACKS: Mutex<Vec<(&'static str, u32)>> = Mutex::new(Vec::new());
+
+static LAST_SNAPSHOT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Record one main-loop iteration (work time, without the delay)
+pub fn record_loop(elapsed: Duration) {
+    let us = elapsed.as_micros().min(u32::MAX as u128) as u32;
+    let mut stats = LOOP_STATS.lock().unwrap();
+    stats.count = stats.count.saturating_add(1);
+    stats.total_us += us as u64;
+    stats.max_us = stats.max_us.max(us);
+}
+
+/// Record the stack high-water mark of the calling thread
+pub fn record_stack(name: &'static str) {
+    // ESP-IDF FreeRTOS reports stack sizes in bytes
+    let free = unsafe { esp_idf_sys::uxTaskGetStackHighWaterMark(std::ptr::null_mut()) };
+    let mut stacks = STACKS.lock().unwrap();
+    if let Some(entry) = stacks.iter_mut().find(|(n, _)| *n == name) {
+        entry.1 = free;
+    } else if stacks.len() < MAX_STACKS {
+        stacks.push((name, free));
+    }
+}
+
+/// Whether a snapshot should go out with the next heartbeat
+pub fn due() -> bool {
+    LAST_SNAPSHOT
+        .lock()
+        .unwrap()
+        .map_or(true, |last| last.elapsed() >= HEALTH_INTERVAL)
+}
+
+/// Collect all metrics and start a new measurement window
+pub fn snapshot() -> Value {
+    *LAST_SNAPSHOT.lock().unwrap() = Some(Instant::now());
+
+    let (loop_avg_us, loop_max_us) = {
+        let mut stats = LOOP_STATS.lock().unwrap();
+        let avg = if stats.count > 0 { stats.total_us / stats.count as u64 } else { 0 };
+        let max = stats.max_us;
+        *stats = LoopStats { count: 0, total_us: 0, max_us: 0 };
+        (avg, max)
+    };
+
+    let stacks: serde_json::Map<String, Value> = STACKS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(name, free)| (name.to_string(), json!(free)))
+        .collect();
+
+    let scales: Vec<Value> = crate::scale_manager::health()
+        .iter()
+        .map(|s| json!({ "id": s.id, "i2c_errors": s.i2c_errors, "noise_g": s.noise_grams }))
+        .collect();
+
+    let nfc = crate::nfc_bridge_manager::health();
+    let attempts = nfc.reads + nfc.read_failures;
+    let read_success_rate = if attempts > 0 { Some(nfc.reads as f32 / attempts as f32) } else { None };
+
+    let (heap_free, heap_min_free, psram_free) = unsafe {
+        (
+            esp_idf_sys::heap_caps_get_free_size(esp_idf_sys::MALLOC_CAP_INTERNAL),
+            esp_idf_sys::heap_caps_get_minimum_free_size(esp_idf_sys::MALLOC_CAP_INTERNAL),
+            esp_idf_sys::heap_caps_get_free_size(esp_idf_sys::MALLOC_CAP_SPIRAM),
+        )
+    };
+    let uptime_s = unsafe { esp_idf_sys::esp_timer_get_time() } / 1_000_000;
+
+    json!({
+        "uptime_s": uptime_s,
+        "heap_free": heap_free,
+        "heap_min_free": heap_min_free,
+        "psram_free": psram_free,
+        "loop_avg_us": loop_avg_us,
+        "loop_max_us": loop_max_us,
+        "stack_free": stacks,
+        "scales": scales,
+        "nfc": {
+            "i2c_errors": nfc.i2c_errors,
+            "reads": nfc.reads,
+            "read_failures": nfc.read_failures,
+            "read_success_rate": read_success_rate,
+        },
+        "clock_drift_s": crate::time_manager::clock_drift(),
+    })
+}
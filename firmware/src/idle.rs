@@ -0,0 +1,141 @@
+//! Screen dimming and blanking when the display is not used
+//!
+//! The backlight dims shortly before the configured screen timeout and turns
+//! off when it expires (`timeout_s` 0 = never). A touch, a tag arriving on
+//! the reader or a weight change on any scale wakes it up again. The touch
+//! that wakes a dark screen is not passed on to the UI, so it can't press a
+//! button nobody could see.
+//!
+//! `poll()` runs in the UI loop, which owns the backlight and touch I2C bus;
+//! the sensor and NFC threads only report activity.
+
+use log::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::settings;
+
+extern "C" {
+    fn display_set_backlight_hw(brightness_percent: u8);
+    fn display_touch_active() -> bool;
+    fn display_set_touch_blocked(blocked: bool);
+}
+
+/// Dimmed for this long before the backlight turns off (at most half the timeout)
+const DIM_LEAD: Duration = Duration::from_secs(15);
+
+/// Brightness while dimmed (percent)
+const DIM_BRIGHTNESS: u8 = 10;
+
+/// Weight change that counts as activity (grams)
+const WEIGHT_WAKE_GRAMS: f32 = 5.0;
+
+/// Backlight state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Screen {
+    On,
+    Dimmed,
+    Off,
+}
+
+struct IdleState {
+    screen: Screen,
+    last_activity: Option<Instant>,
+    /// Backlight level last written to the hardware
+    applied: Option<u8>,
+    /// Touches are held back from LVGL
+    touch_blocked: bool,
+}
+
+static STATE: Mutex<IdleState> = Mutex::new(IdleState {
+    screen: Screen::On,
+    last_activity: None,
+    applied: None,
+    touch_blocked: false,
+});
+
+/// Activity reported by other threads since the last poll
+static ACTIVITY: AtomicBool = AtomicBool::new(false);
+
+/// Last settled weight per scale, for weight change detection
+static WEIGHTS: Mutex<Vec<Option<f32>>> = Mutex::new(Vec::new());
+
+/// Keep the screen on (call from any thread, e.g. on a tag arrival)
+pub fn activity() {
+    ACTIVITY.store(true, Ordering::Relaxed);
+}
+
+/// Report scale readings (weight, stable) from the sensor thread; a change
+/// from the last settled weight counts as activity
+pub fn note_weights(readings: &[(f32, bool)]) {
+    let mut weights = WEIGHTS.lock().unwrap();
+    weights.resize(readings.len(), None);
+    for (last, &(weight, stable)) in weights.iter_mut().zip(readings) {
+        let changed = last.is_some_and(|last| (weight - last).abs() >= WEIGHT_WAKE_GRAMS);
+        if changed {
+            activity();
+        }
+        // Settled readings follow slow drift; a change is only counted once
+        if stable || changed || last.is_none() {
+            *last = Some(weight);
+        }
+    }
+}
+
+/// Screen state after being idle for `idle`
+fn screen_for(idle: Duration, timeout_s: u16) -> Screen {
+    if timeout_s == 0 {
+        return Screen::On;
+    }
+    let timeout = Duration::from_secs(timeout_s as u64);
+    let dim_at = timeout - DIM_LEAD.min(timeout / 2);
+    if idle >= timeout {
+        Screen::Off
+    } else if idle >= dim_at {
+        Screen::Dimmed
+    } else {
+        Screen::On
+    }
+}
+
+/// Update the backlight (call from the UI loop after `display_tick()`)
+pub fn poll() {
+    let touched = unsafe { display_touch_active() };
+    let now = Instant::now();
+    let mut state = STATE.lock().unwrap();
+
+    let active = ACTIVITY.swap(false, Ordering::Relaxed) || touched;
+    if active || state.last_activity.is_none() {
+        state.last_activity = Some(now);
+    }
+    let idle = state.last_activity.map_or(Duration::ZERO, |at| now.duration_since(at));
+
+    let (brightness, timeout_s) = settings::read(|s| (s.display.brightness, s.display.timeout_s));
+    let screen = screen_for(idle, timeout_s);
+    if screen != state.screen {
+        match screen {
+            Screen::On => info!("Screen on"),
+            Screen::Dimmed => info!("Screen dimmed"),
+            Screen::Off => info!("Screen off after {} s idle", timeout_s),
+        }
+        state.screen = screen;
+    }
+
+    // Blocked while dark, released once the waking finger is lifted
+    let block = screen == Screen::Off || (state.touch_blocked && touched);
+    if block != state.touch_blocked {
+        unsafe { display_set_touch_blocked(block) };
+        state.touch_blocked = block;
+    }
+
+    let level = match screen {
+        Screen::On => brightness,
+        Screen::Dimmed => brightness.min(DIM_BRIGHTNESS),
+        Screen::Off => 0,
+    };
+    if state.applied != Some(level) {
+        unsafe { display_set_backlight_hw(level) };
+        state.applied = Some(level);
+    }
+}
@@ -19,6 +19,14 @@ mod scale;
 // Scale manager with C-callable interface
 mod scale_manager;
 
+// Net filament weight and length from gross scale readings
+mod spool_weight;
+
+// Automatic weight sync when a known spool settles on the scale
+mod weight_sync;
+// Per-spool weight history and consumption rate
+mod weight_history;
+
 // NFC module for PN5180 and I2C bridge
 mod nfc;
 
@@ -31,15 +39,45 @@ mod nfc_bridge_manager;
 // WiFi manager with C-callable interface
 mod wifi_manager;
 
+// SoftAP setup portal and backend settings
+mod config_portal;
+
+// Versioned settings store in NVS with backup/import
+mod settings;
+
+// Background SSDP discovery of Bambu printers
+mod printer_discovery;
+
+// Direct LAN MQTT to printers while the backend is offline
+mod printer_mqtt;
+
 // Backend client for server communication
 mod backend_client;
 
 // Time manager for NTP sync
 mod time_manager;
 
+// Log backend: serial + UDP/syslog with per-module levels
+mod udp_logger;
+
+// Panic capture and reset-reason reports
+mod crash_report;
+
+// Heap, loop timing and sensor health metrics for the heartbeat
+mod health;
+
+// Sensor, NFC and network threads
+mod tasks;
+
+// Backlight dimming and screen timeout
+mod idle;
+
 // OTA update manager
 mod ota_manager;
 
+// NFC bridge firmware updates over I2C
+mod bridge_ota;
+
 // Direct SPI NFC disabled - now using I2C bridge via Pico
 const NFC_ENABLED: bool = false;
 
@@ -47,49 +85,41 @@ const NFC_ENABLED: bool = false;
 extern "C" {
     fn display_init() -> i32;
     fn display_tick();
-    fn display_set_backlight_hw(brightness_percent: u8);
 }
 
 // =============================================================================
 // Display Settings FFI (called from C UI code)
 // =============================================================================
 
-static mut DISPLAY_BRIGHTNESS: u8 = 80;
-static mut DISPLAY_TIMEOUT: u16 = 300;
-
 #[no_mangle]
 pub extern "C" fn display_set_brightness(brightness: u8) {
     let brightness = if brightness > 100 { 100 } else { brightness };
-    unsafe {
-        DISPLAY_BRIGHTNESS = brightness;
-        // Actually set hardware backlight via I2C
-        display_set_backlight_hw(brightness);
-    }
+    // Slider steps are saved once it is released; the UI loop applies them
+    settings::stage(|s| s.display.brightness = brightness);
     info!("Display brightness set to {}%", brightness);
 }
 
 #[no_mangle]
 pub extern "C" fn display_get_brightness() -> u8 {
-    unsafe { DISPLAY_BRIGHTNESS }
+    settings::read(|s| s.display.brightness)
 }
 
 #[no_mangle]
 pub extern "C" fn display_set_timeout(timeout_seconds: u16) {
-    unsafe {
-        DISPLAY_TIMEOUT = timeout_seconds;
-    }
+    settings::stage(|s| s.display.timeout_s = timeout_seconds);
     info!("Display timeout set to {} seconds", timeout_seconds);
 }
 
 #[no_mangle]
 pub extern "C" fn display_get_timeout() -> u16 {
-    unsafe { DISPLAY_TIMEOUT }
+    settings::read(|s| s.display.timeout_s)
 }
 
 fn main() {
     // Initialize ESP-IDF
     esp_idf_svc::sys::link_patches();
-    esp_idf_svc::log::EspLogger::initialize_default();
+    udp_logger::init();
+    crash_report::init();
 
     info!("SpoolBuddy Firmware starting...");
 
@@ -166,16 +196,40 @@ fn main() {
     let sysloop = EspSystemEventLoop::take().expect("Failed to take system event loop");
     let nvs = EspDefaultNvsPartition::take().ok();
 
-    // Clone NVS partition for scale calibration persistence
-    let nvs_for_scale = nvs.clone();
+    // Load the settings store first - the modules below read from it
+    settings::init_nvs(nvs.clone());
+
+    // Arms the rollback timer if this is the first boot of a new image
+    ota_manager::init();
+
+    printer_mqtt::init_nvs(nvs.clone());
+    time_manager::load_settings();
+    udp_logger::load_settings();
 
     match wifi_manager::init_wifi_system(peripherals.modem, sysloop, nvs) {
-        Ok(_) => info!("WiFi subsystem ready"),
+        Ok(_) => {
+            info!("WiFi subsystem ready");
+            // Network stack is up - log records may go out over UDP now
+            udp_logger::start();
+        }
         Err(e) => warn!("WiFi init failed: {}", e),
     }
 
-    // Initialize scale NVS (for calibration persistence)
-    scale_manager::init_nvs(nvs_for_scale);
+    // Listens for printer announcements once WiFi is up
+    printer_discovery::start();
+
+    // Talks to printers directly when the backend can't be reached
+    printer_mqtt::start();
+
+    // No saved network yet - open the setup portal so headless units can be configured
+    if !wifi_manager::has_credentials() {
+        if let Err(e) = config_portal::start() {
+            warn!("Setup portal failed to start: {}", e);
+        }
+    }
+
+    // Follow scale calibration changes (restored backups)
+    scale_manager::load_settings();
 
     // Initialize backend client (for server communication)
     backend_client::init();
@@ -187,6 +241,9 @@ fn main() {
         let result = display_init();
         if result != 0 {
             info!("Display init failed with code: {}", result);
+        } else {
+            // Saved brightness (the driver starts at full)
+            idle::poll();
         }
     }
 
@@ -212,6 +269,7 @@ fn main() {
             info!("Scanning I2C1 bus...");
             let mut found_nau7802 = false;
             let mut found_pico = false;
+            let mut found_mux: Option<u8> = None;
             for addr in 0x08..0x78 {
                 let mut buf = [0u8; 1];
                 if i2c_static.read(addr, &mut buf, 100).is_ok() {
@@ -220,30 +278,51 @@ fn main() {
                         info!("  -> NAU7802 scale chip detected!");
                         found_nau7802 = true;
                     }
+                    if (scale::tca9548a::TCA9548A_ADDR_BASE..=scale::tca9548a::TCA9548A_ADDR_LAST).contains(&addr)
+                        && found_mux.is_none()
+                    {
+                        info!("  -> TCA9548A I2C mux detected!");
+                        found_mux = Some(addr);
+                    }
                     if addr == nfc::i2c_bridge::PICO_NFC_ADDR {
                         info!("  -> Pico NFC bridge detected!");
                         found_pico = true;
                     }
                 }
             }
-            if !found_nau7802 {
+            if !found_nau7802 && found_mux.is_none() {
                 warn!("  NAU7802 not found at 0x{:02X}", scale::nau7802::NAU7802_ADDR);
             }
             if !found_pico {
                 warn!("  Pico NFC bridge not found at 0x{:02X}", nfc::i2c_bridge::PICO_NFC_ADDR);
             }
 
-            // Initialize scale if found
-            if found_nau7802 {
-                let mut scale_state = scale::nau7802::Nau7802State::new();
+            // Initialize scales: a NAU7802 directly on the bus becomes scale 0,
+            // then one per populated mux channel. Without any NAU7802, probe for
+            // an HX711. The "hx711" feature skips the NAU7802 for boards that have both.
+            let mut scale_ready = false;
+            if found_nau7802 && !cfg!(feature = "hx711") {
+                let mut scale_state = scale::load_cell::ScaleState::new();
                 match scale::nau7802::init(i2c_static, &mut scale_state) {
                     Ok(()) => {
                         info!("NAU7802 scale initialized");
-                        scale_manager::init_scale_manager(scale_state);
+                        scale_ready = scale_manager::add_scale(
+                            scale_manager::LoadCellBackend::Nau7802 { mux: None },
+                            scale_state,
+                        )
+                        .is_some();
                     }
                     Err(e) => warn!("NAU7802 init failed: {:?}", e),
                 }
             }
+            if let Some(mux_addr) = found_mux.filter(|_| !cfg!(feature = "hx711")) {
+                let count = scale_manager::init_mux_scales(i2c_static, mux_addr);
+                info!("{} NAU7802 scale(s) found behind mux 0x{:02X}", count, mux_addr);
+                scale_ready |= count > 0;
+            }
+            if !scale_ready && scale_manager::init_hx711() {
+                info!("HX711 scale initialized");
+            }
 
             // Take ownership back and give to shared_i2c
             let i2c_owned = unsafe { Box::from_raw(i2c_static as *mut I2cDriver<'static>) };
@@ -1084,79 +1163,29 @@ fn main() {
     }
     } // end if NFC_ENABLED
 
-    info!("Entering main loop...");
+    // Sensors, NFC and network run in their own threads from here on
+    tasks::start();
+
+    info!("Entering UI loop...");
 
-    // Main loop counter for periodic tasks
+    // Loop counter for periodic bookkeeping
     let mut loop_count: u32 = 0;
 
-    // Main loop
+    // UI loop - only LVGL work here, so the touchscreen stays responsive
     loop {
+        let iteration_start = std::time::Instant::now();
         unsafe {
             display_tick();
         }
+        // Brightness changes and the screen timeout
+        idle::poll();
 
-        // Poll scale every 10 iterations (~50ms at 5ms delay)
         loop_count = loop_count.wrapping_add(1);
-        if loop_count % 10 == 0 {
-            scale_manager::poll_scale();
-        }
-
-        // Post-WiFi initialization - check frequently until WiFi connects
-        static WIFI_INIT_DONE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
-        static OTA_CHECK_DONE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
-        if !WIFI_INIT_DONE.load(std::sync::atomic::Ordering::Relaxed) {
-            if loop_count % 20 == 0 && wifi_manager::is_connected() {
-                // Initialize SNTP for time sync (may take time)
-                time_manager::init_sntp();
-                // Set backend server URL
-                backend_client::set_server_url("http://192.168.255.16:3000");
-                // Sync time immediately from backend (faster than SNTP)
-                backend_client::sync_time();
-                WIFI_INIT_DONE.store(true, std::sync::atomic::Ordering::Relaxed);
-                info!("Post-WiFi init complete (SNTP + backend URL + time sync)");
-                // Immediate first poll for printer data
-                backend_client::poll_backend();
-            }
-        } else if loop_count % 400 == 0 {
-            // Regular polling every 2 seconds (full sync: printers, commands, etc.)
-            backend_client::poll_backend();
-        } else if loop_count % 100 == 0 {
-            // Weight-only update every 500ms for faster UI feedback
-            let weight = scale_manager::scale_get_weight();
-            let stable = scale_manager::scale_is_stable();
-            backend_client::send_device_state(None, weight, stable);
-        }
-
-        // OTA check on startup (once, after WiFi init) - check but don't auto-install
-        // Updates are triggered via backend command
-        if WIFI_INIT_DONE.load(std::sync::atomic::Ordering::Relaxed)
-            && !OTA_CHECK_DONE.load(std::sync::atomic::Ordering::Relaxed)
-        {
-            OTA_CHECK_DONE.store(true, std::sync::atomic::Ordering::Relaxed);
-            info!("Firmware version: v{}", ota_manager::get_version());
-
-            // Check for updates and store result (don't auto-install)
-            match ota_manager::check_for_update("http://192.168.255.16:3000") {
-                Ok(info) => {
-                    if info.available {
-                        info!("Firmware update available: v{}", info.version);
-                        ota_manager::set_update_available(true, &info.version);
-                    } else {
-                        info!("Firmware is up to date");
-                        ota_manager::set_update_available(false, "");
-                    }
-                }
-                Err(e) => {
-                    warn!("OTA check failed: {}", e);
-                }
-            }
-        }
-
-        // Poll NFC bridge every 100 iterations (~500ms at 5ms delay)
-        if loop_count % 100 == 0 {
-            nfc_bridge_manager::poll_nfc();
+        if loop_count % 400 == 0 {
+            health::record_stack("main");
         }
 
+        health::record_loop(iteration_start.elapsed());
         FreeRtos::delay_ms(5);
     }
 }
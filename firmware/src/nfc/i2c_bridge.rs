@@ -8,8 +8,15 @@
 //! - Commands:
 //!   - 0x00: Get status (returns 2 bytes: status, tag_present)
 //!   - 0x01: Get version (returns 3 bytes: status, major, minor)
+//!   - 0x02: Get bridge firmware version (returns 4 bytes: status, major, minor, patch)
 //!   - 0x10: Scan tag (returns: status, uid_len, uid[0..uid_len])
 //!   - 0x20: Read tag data (returns: status, tag_type, uid_len, uid, block_data...)
+//!   - 0x30: Update begin [seq, size(4), sha256(32)] (returns: status)
+//!   - 0x31: Update write [seq, offset(4), data] (returns: status, written(4))
+//!   - 0x32: Update end [seq] - bridge verifies, stages and reboots (returns: status)
+//!   - 0x33: Update abort [seq] (returns: status)
+//!
+//! Multi-byte values are little endian. 0xFF means "no response ready".
 
 use esp_idf_hal::i2c::I2cDriver;
 use log::{debug, info, warn};
@@ -31,6 +38,17 @@ const CMD_GET_STATUS: u8 = 0x00;
 const CMD_GET_VERSION: u8 = 0x01;
 const CMD_SCAN_TAG: u8 = 0x10;
 const CMD_READ_TAG_DATA: u8 = 0x20;
+const CMD_GET_FW_VERSION: u8 = 0x02;
+const CMD_UPDATE_BEGIN: u8 = 0x30;
+const CMD_UPDATE_WRITE: u8 = 0x31;
+const CMD_UPDATE_END: u8 = 0x32;
+const CMD_UPDATE_ABORT: u8 = 0x33;
+
+/// Max data bytes per update write (matches UPDATE_CHUNK_SIZE on the Pico)
+pub const UPDATE_CHUNK_SIZE: usize = 128;
+
+/// Response marker while the Pico hasn't processed a command yet
+const RESP_NOT_READY: u8 = 0xFF;
 
 /// Tag types (matches Pico definitions)
 pub const TAG_TYPE_UNKNOWN: u8 = 0;
@@ -129,6 +147,107 @@ pub fn get_version(i2c: &mut I2cDriver<'_>) -> Result<(u8, u8), &'static str> {
     Ok((resp[1], resp[2]))
 }
 
+/// Get the bridge firmware version (major, minor, patch)
+/// Fails if the bridge doesn't answer in time: it is busy, or runs firmware
+/// without version reporting (which answers unknown commands with 0xFF)
+pub fn get_firmware_version(i2c: &mut I2cDriver<'_>) -> Result<(u8, u8, u8), &'static str> {
+    let cmd = [CMD_GET_FW_VERSION, next_seq()];
+
+    // Read response: [status, major, minor, patch]
+    let mut resp = [0u8; 4];
+    update_transaction(i2c, &cmd, &mut resp, std::time::Duration::from_millis(500))?;
+    if resp[0] != 0 {
+        return Err("Bridge rejected the version request");
+    }
+
+    Ok((resp[1], resp[2], resp[3]))
+}
+
+/// Send an update command and wait for the Pico to answer it
+fn update_transaction(
+    i2c: &mut I2cDriver<'_>,
+    cmd: &[u8],
+    resp: &mut [u8],
+    timeout: std::time::Duration,
+) -> Result<(), &'static str> {
+    if i2c.write(PICO_NFC_ADDR, cmd, 100).is_err() {
+        return Err("I2C write failed");
+    }
+
+    let deadline = std::time::Instant::now() + timeout;
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        if i2c.read(PICO_NFC_ADDR, resp, 100).is_ok() && resp[0] != RESP_NOT_READY {
+            return Ok(());
+        }
+        if std::time::Instant::now() >= deadline {
+            return Err("Bridge did not respond");
+        }
+    }
+}
+
+/// Map an update status code from the Pico
+fn update_status(status: u8) -> Result<(), &'static str> {
+    match status {
+        0 => Ok(()),
+        1 => Err("No update in progress on the bridge"),
+        2 => Err("Update chunk out of order"),
+        3 => Err("Bridge flash error"),
+        4 => Err("Bridge rejected the image (SHA-256 mismatch)"),
+        5 => Err("Update size mismatch"),
+        _ => Err("Unknown bridge update status"),
+    }
+}
+
+/// Start a firmware update for an image of `size` bytes
+pub fn update_begin(i2c: &mut I2cDriver<'_>, size: u32, sha256: &[u8; 32]) -> Result<(), &'static str> {
+    let mut cmd = [0u8; 38];
+    cmd[0] = CMD_UPDATE_BEGIN;
+    cmd[1] = next_seq();
+    cmd[2..6].copy_from_slice(&size.to_le_bytes());
+    cmd[6..].copy_from_slice(sha256);
+
+    // Opening the image on the Pico's filesystem can take a while
+    let mut resp = [0u8; 1];
+    update_transaction(i2c, &cmd, &mut resp, std::time::Duration::from_secs(5))?;
+    update_status(resp[0])
+}
+
+/// Write a chunk at `offset`. Returns the number of bytes the bridge has
+/// received so far (repeated chunks are acknowledged, not written twice).
+pub fn update_write(i2c: &mut I2cDriver<'_>, offset: u32, data: &[u8]) -> Result<u32, &'static str> {
+    if data.len() > UPDATE_CHUNK_SIZE {
+        return Err("Update chunk too large");
+    }
+    let mut cmd = [0u8; 6 + UPDATE_CHUNK_SIZE];
+    cmd[0] = CMD_UPDATE_WRITE;
+    cmd[1] = next_seq();
+    cmd[2..6].copy_from_slice(&offset.to_le_bytes());
+    cmd[6..6 + data.len()].copy_from_slice(data);
+
+    // Read response: [status, written(4)]
+    let mut resp = [0u8; 5];
+    update_transaction(i2c, &cmd[..6 + data.len()], &mut resp, std::time::Duration::from_secs(1))?;
+    update_status(resp[0])?;
+    Ok(u32::from_le_bytes([resp[1], resp[2], resp[3], resp[4]]))
+}
+
+/// Finish the update: the bridge checks the SHA-256, stages the image and reboots
+pub fn update_end(i2c: &mut I2cDriver<'_>) -> Result<(), &'static str> {
+    let cmd = [CMD_UPDATE_END, next_seq()];
+    let mut resp = [0u8; 1];
+    update_transaction(i2c, &cmd, &mut resp, std::time::Duration::from_secs(5))?;
+    update_status(resp[0])
+}
+
+/// Discard a partially written update
+pub fn update_abort(i2c: &mut I2cDriver<'_>) -> Result<(), &'static str> {
+    let cmd = [CMD_UPDATE_ABORT, next_seq()];
+    let mut resp = [0u8; 1];
+    update_transaction(i2c, &cmd, &mut resp, std::time::Duration::from_secs(1))?;
+    update_status(resp[0])
+}
+
 /// Scan for a tag
 pub fn scan_tag(i2c: &mut I2cDriver<'_>, state: &mut NfcBridgeState) -> Result<bool, &'static str> {
     let seq = next_seq();
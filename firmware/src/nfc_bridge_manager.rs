@@ -4,6 +4,7 @@
 //! Uses the Pico NFC bridge over I2C.
 
 use log::{info, warn};
+use std::sync::atomic::{AtomicU32, Ordering};
 use std::sync::Mutex;
 
 use crate::nfc::i2c_bridge::{self, NfcBridgeState};
@@ -12,6 +13,27 @@ use crate::shared_i2c;
 /// Global NFC state protected by mutex
 static NFC_STATE: Mutex<Option<NfcBridgeState>> = Mutex::new(None);
 
+/// Counters since boot (health metrics)
+static I2C_ERRORS: AtomicU32 = AtomicU32::new(0);
+static TAG_READS: AtomicU32 = AtomicU32::new(0);
+static TAG_READ_FAILURES: AtomicU32 = AtomicU32::new(0);
+
+/// Tag change for the network thread to report to the backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagEvent {
+    /// Tag placed on the reader, or its data decoded (UID as hex)
+    Detected(String),
+    Removed,
+}
+
+/// NFC bridge health metrics
+pub struct NfcHealth {
+    pub i2c_errors: u32,
+    /// Tags read and decoded
+    pub reads: u32,
+    pub read_failures: u32,
+}
+
 /// NFC status for C code
 #[repr(C)]
 pub struct NfcStatus {
@@ -41,18 +63,26 @@ pub fn init_nfc_manager() -> bool {
     if let Some(Some(state)) = result {
         let mut guard = NFC_STATE.lock().unwrap();
         *guard = Some(state);
+        drop(guard);
+        crate::bridge_ota::refresh_version();
         true
     } else {
         false
     }
 }
 
-/// Poll the NFC bridge (call from main loop)
-pub fn poll_nfc() {
+/// Poll the NFC bridge (call from the NFC thread)
+/// Returns the tag change to report, if any
+pub fn poll_nfc() -> Option<TagEvent> {
     static mut LAST_TAG_PRESENT: bool = false;
     static mut TAG_DATA_READ: bool = false;
 
-    // Collect data from I2C, then release locks before HTTP calls
+    // The bridge is busy receiving (or rebooting into) new firmware
+    if crate::bridge_ota::is_updating() {
+        return None;
+    }
+
+    // Collect data from I2C, then release the bus before reporting
     let mut tag_just_appeared = false;
     let mut tag_just_removed = false;
     let mut tag_data_decoded = false;
@@ -60,90 +90,107 @@ pub fn poll_nfc() {
     #[allow(unused_variables)]
     let mut decoded_info: Option<i2c_bridge::DecodedTagInfo> = None;
 
-    {
-        let mut guard = NFC_STATE.lock().unwrap();
-        if let Some(ref mut state) = *guard {
-            if state.initialized {
-                let _ = shared_i2c::with_i2c(|i2c| {
-                    match i2c_bridge::scan_tag(i2c, state) {
-                        Ok(found) => {
-                            unsafe {
-                                if found && !LAST_TAG_PRESENT {
-                                    // Tag just appeared
-                                    uid_hex = get_uid_hex_string(state);
-                                    // Log detection without full UID (security: avoid logging sensitive tag identifiers)
-                                    info!("NFC TAG DETECTED");
-                                    TAG_DATA_READ = false;
-                                    tag_just_appeared = true;
-                                }
+    // Work on a copy: the UI reads NFC_STATE and must not wait for the bridge
+    let mut bridge = match NFC_STATE.lock().unwrap().clone() {
+        Some(state) if state.initialized => state,
+        _ => return None,
+    };
 
-                                // Read tag data if we haven't yet (for local decoding)
-                                if found && !TAG_DATA_READ {
-                                    match i2c_bridge::read_tag_data(i2c, state) {
-                                        Ok(true) => {
-                                            TAG_DATA_READ = true;
-                                            tag_data_decoded = true;
-                                            decoded_info = state.decoded_info.clone();
-
-                                            // Copy decoded data to FFI storage
-                                            if let Some(ref info) = state.decoded_info {
-                                                set_decoded_tag_data(
-                                                    &info.vendor,
-                                                    &info.material,
-                                                    &info.material_subtype,
-                                                    &info.color_name,
-                                                    info.color_rgba,
-                                                    info.spool_weight,
-                                                    &info.tag_type_name,
-                                                );
-                                                info!("Tag decoded: {} {} {} ({}g)",
-                                                    info.vendor, info.material, info.color_name, info.spool_weight);
-                                            }
-
-                                            if uid_hex.is_empty() {
-                                                uid_hex = get_uid_hex_string(state);
-                                            }
-                                        }
-                                        Ok(false) => {
-                                            // No data yet, will retry
-                                        }
-                                        Err(e) => {
-                                            warn!("Tag data read error: {}", e);
-                                            TAG_DATA_READ = true; // Don't keep retrying on error
-                                        }
-                                    }
+    let state = &mut bridge;
+    let _ = shared_i2c::with_i2c(|i2c| {
+        match i2c_bridge::scan_tag(i2c, state) {
+            Ok(found) => {
+                unsafe {
+                    if found && !LAST_TAG_PRESENT {
+                        // Tag just appeared
+                        uid_hex = get_uid_hex_string(state);
+                        // Log detection without full UID (security: avoid logging sensitive tag identifiers)
+                        info!("NFC TAG DETECTED");
+                        TAG_DATA_READ = false;
+                        tag_just_appeared = true;
+                    }
+
+                    // Read tag data if we haven't yet (for local decoding)
+                    if found && !TAG_DATA_READ {
+                        match i2c_bridge::read_tag_data(i2c, state) {
+                            Ok(true) => {
+                                TAG_READS.fetch_add(1, Ordering::Relaxed);
+                                TAG_DATA_READ = true;
+                                tag_data_decoded = true;
+                                decoded_info = state.decoded_info.clone();
+
+                                // Copy decoded data to FFI storage
+                                if let Some(ref info) = state.decoded_info {
+                                    set_decoded_tag_data(
+                                        &info.vendor,
+                                        &info.material,
+                                        &info.material_subtype,
+                                        &info.color_name,
+                                        info.color_rgba,
+                                        info.spool_weight,
+                                        &info.tag_type_name,
+                                    );
+                                    info!("Tag decoded: {} {} {} ({}g)",
+                                        info.vendor, info.material, info.color_name, info.spool_weight);
                                 }
 
-                                if !found && LAST_TAG_PRESENT {
-                                    // Tag just removed
-                                    info!("NFC TAG REMOVED");
-                                    clear_decoded_tag_data();
-                                    TAG_DATA_READ = false;
-                                    tag_just_removed = true;
+                                if uid_hex.is_empty() {
+                                    uid_hex = get_uid_hex_string(state);
                                 }
-                                LAST_TAG_PRESENT = found;
+                            }
+                            Ok(false) => {
+                                // No data yet, will retry
+                            }
+                            Err(e) => {
+                                warn!("Tag data read error: {}", e);
+                                TAG_READ_FAILURES.fetch_add(1, Ordering::Relaxed);
+                                TAG_DATA_READ = true; // Don't keep retrying on error
                             }
                         }
-                        Err(e) => {
-                            warn!("NFC scan error: {}", e);
-                        }
                     }
-                });
+
+                    if !found && LAST_TAG_PRESENT {
+                        // Tag just removed
+                        info!("NFC TAG REMOVED");
+                        clear_decoded_tag_data();
+                        crate::spool_weight::clear_active_spool();
+                        TAG_DATA_READ = false;
+                        tag_just_removed = true;
+                    }
+                    LAST_TAG_PRESENT = found;
+                }
+            }
+            Err(e) => {
+                I2C_ERRORS.fetch_add(1, Ordering::Relaxed);
+                warn!("NFC scan error: {}", e);
             }
         }
-    } // Release NFC_STATE lock and I2C lock here
+    });
+
+    // Publish the new tag state for the UI
+    *NFC_STATE.lock().unwrap() = Some(bridge);
 
-    // Now make HTTP calls outside the locks
+    // The network thread makes the HTTP calls
     if tag_just_appeared || tag_data_decoded {
-        let weight = crate::scale_manager::scale_get_weight();
-        let stable = crate::scale_manager::scale_is_stable();
-        crate::backend_client::send_device_state(Some(&uid_hex), weight, stable);
+        Some(TagEvent::Detected(uid_hex))
+    } else if tag_just_removed {
+        Some(TagEvent::Removed)
+    } else {
+        None
     }
+}
+
+/// Whether the bridge was found at startup
+pub fn is_initialized() -> bool {
+    NFC_STATE.lock().unwrap().is_some()
+}
 
-    if tag_just_removed {
-        let weight = crate::scale_manager::scale_get_weight();
-        let stable = crate::scale_manager::scale_is_stable();
-        crate::backend_client::send_device_state(None, weight, stable);
+/// Health metrics (counters since boot)
+pub fn health() -> NfcHealth {
+    NfcHealth {
+        i2c_errors: I2C_ERRORS.load(Ordering::Relaxed),
+        reads: TAG_READS.load(Ordering::Relaxed),
+        read_failures: TAG_READ_FAILURES.load(Ordering::Relaxed),
     }
 }
 
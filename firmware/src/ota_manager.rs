@@ -1,28 +1,80 @@
 //! OTA Firmware Update Manager
 //!
-//! Implements PSRAM-buffered OTA for single-partition systems:
-//! 1. Download firmware to PSRAM
-//! 2. Validate checksum
-//! 3. Erase and write to factory partition
-//! 4. Reboot
+//! A/B updates on the ota_0/ota_1 partitions:
+//! 1. Ask the backend for the latest version, its size and SHA-256
+//! 2. Stream the image into the inactive slot (esp_ota_begin/write/end),
+//!    resuming with HTTP ranges if the connection drops
+//! 3. Check the SHA-256, the Ed25519 signature and the image version, make
+//!    that slot the boot partition and reboot
 //!
-//! Note: Power loss during flash write (~10s) will brick device,
-//! requiring USB recovery. Acceptable for constant-power deployments.
+//! Images are signed with `tools/sign_firmware.py`; the public key is
+//! compiled in from `SPOOLBUDDY_OTA_PUBLIC_KEY` (hex). Builds without a key
+//! reject every update. The signature covers `tag \0 version \0 sha256`, so
+//! a signature can't be reused for another kind of image or another version.
+//! The version in the image's app descriptor must be the announced one and
+//! not older than the running firmware, so an old signed image can't be
+//! replayed as a downgrade.
+//!
+//! Devices follow an update channel (stable, beta or dev, kept in the settings store)
+//! and compare versions semver-aware. Besides the install command, an update
+//! is installed automatically inside a configurable time window, but only
+//! while no printer is printing.
+//!
+//! The new image boots as pending-verify. It is marked valid once it has
+//! started up and talked to the backend; if that doesn't happen
+//! within `VERIFY_TIMEOUT` (or the image resets before), the bootloader
+//! returns to the previous slot. A failed or interrupted write leaves the
+//! running image untouched.
 
 #![allow(dead_code)]
 
 use esp_idf_svc::http::client::{Configuration as HttpConfig, EspHttpConnection};
 use esp_idf_sys::{
-    esp_partition_find, esp_partition_erase_range, esp_partition_write,
-    esp_partition_t, esp_partition_type_t_ESP_PARTITION_TYPE_APP,
-    esp_partition_subtype_t_ESP_PARTITION_SUBTYPE_APP_FACTORY,
-    esp_partition_iterator_t, esp_partition_get, esp_partition_iterator_release,
-    esp_restart,
+    esp_app_desc_t, esp_app_get_description, esp_ota_abort, esp_ota_begin, esp_ota_check_rollback_is_possible,
+    esp_ota_end, esp_ota_get_next_update_partition, esp_ota_get_partition_description,
+    esp_ota_get_running_partition, esp_ota_get_state_partition, esp_ota_handle_t,
+    esp_ota_img_states_t, esp_ota_img_states_t_ESP_OTA_IMG_PENDING_VERIFY,
+    esp_ota_mark_app_invalid_rollback_and_reboot, esp_ota_mark_app_valid_cancel_rollback,
+    esp_ota_set_boot_partition, esp_ota_write, esp_partition_t, esp_restart,
 };
 use embedded_svc::http::client::Client as HttpClient;
-use log::info;
+use embedded_svc::http::Method;
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+use ed25519_compact::{PublicKey, Signature};
+use sha2::{Digest, Sha256};
+use std::cmp::Ordering;
+use std::ffi::{c_int, CStr};
 use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering as AtomicOrdering};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::settings;
+
+/// How long a freshly installed image has to reach the backend
+const VERIFY_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Ed25519 public key (hex) that update images must be signed with
+const OTA_PUBLIC_KEY: Option<&str> = option_env!("SPOOLBUDDY_OTA_PUBLIC_KEY");
+
+/// Signature tag of ESP32 app images
+const FIRMWARE_SIGNATURE_TAG: &str = "spoolbuddy-esp32";
+
+/// Download attempts without progress before giving up
+const DOWNLOAD_RETRIES: u32 = 5;
+
+/// Pause before resuming a dropped download
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Longest wait for WiFi to reconnect before the next attempt
+const WIFI_WAIT: Duration = Duration::from_secs(60);
+
+/// How often to look for updates in the background
+const CHECK_INTERVAL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// Minutes in a day (install window bounds are minutes since midnight)
+const MINUTES_PER_DAY: u16 = 24 * 60;
 
 // External C function to shutdown display before reboot
 extern "C" {
@@ -47,7 +99,10 @@ pub struct UpdateInfo {
     pub available: bool,
     pub version: String,
     pub size: u32,
-    pub checksum: String,
+    /// SHA-256 of the image (hex), empty if the backend didn't provide one
+    pub sha256: String,
+    /// Ed25519 signature over tag, version and SHA-256 digest (hex), empty if unsigned
+    pub signature: String,
 }
 
 // Global state
@@ -55,6 +110,91 @@ static OTA_STATE: Mutex<OtaState> = Mutex::new(OtaState::Idle);
 static CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
 static UPDATE_AVAILABLE: Mutex<bool> = Mutex::new(false);
 static UPDATE_VERSION: Mutex<String> = Mutex::new(String::new());
+/// Rollback deadline while the running image is pending verification
+static VERIFY_DEADLINE: Mutex<Option<Instant>> = Mutex::new(None);
+/// Start of the last update check
+static LAST_CHECK: Mutex<Option<Instant>> = Mutex::new(None);
+/// Version the last automatic install was started for (not retried)
+static AUTO_INSTALL_ATTEMPTED: Mutex<String> = Mutex::new(String::new());
+/// A background check or install thread is running (every install goes
+/// through `start_update`, so only one can run at a time)
+static WORKER_BUSY: AtomicBool = AtomicBool::new(false);
+
+/// Partition label as a string
+fn partition_label(partition: &esp_partition_t) -> String {
+    unsafe { CStr::from_ptr(partition.label.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Check the running slot at startup and arm the rollback deadline if this
+/// is the first boot of a new image (call after settings::init_nvs)
+pub fn init() {
+    let (start, end) = install_window();
+    info!("OTA channel: {}, auto-install: {} ({:02}:{:02}-{:02}:{:02})",
+          channel().as_str(), auto_install_enabled(), start / 60, start % 60, end / 60, end % 60);
+
+    unsafe {
+        let running = esp_ota_get_running_partition();
+        if running.is_null() {
+            warn!("Running partition unknown");
+            return;
+        }
+        info!("Running from partition '{}' at 0x{:X}", partition_label(&*running), (*running).address);
+
+        let mut img_state: esp_ota_img_states_t = 0;
+        if esp_ota_get_state_partition(running, &mut img_state) == 0
+            && img_state == esp_ota_img_states_t_ESP_OTA_IMG_PENDING_VERIFY
+        {
+            warn!("New firmware pending verification, rollback in {}s unless the backend is reached",
+                  VERIFY_TIMEOUT.as_secs());
+            *VERIFY_DEADLINE.lock().unwrap() = Some(Instant::now() + VERIFY_TIMEOUT);
+        }
+    }
+}
+
+/// Mark the running image valid (called after a successful backend contact)
+pub fn confirm_image() {
+    let mut deadline = VERIFY_DEADLINE.lock().unwrap();
+    if deadline.is_none() {
+        return;
+    }
+    let ret = unsafe { esp_ota_mark_app_valid_cancel_rollback() };
+    if ret == 0 {
+        info!("Firmware v{} marked valid", CURRENT_VERSION);
+        *deadline = None;
+    } else {
+        error!("Failed to mark firmware valid: {}", ret);
+    }
+}
+
+/// Roll back to the previous image if the new one never reached the backend
+/// Called from the network thread
+pub fn poll_rollback() {
+    let expired = VERIFY_DEADLINE
+        .lock()
+        .unwrap()
+        .is_some_and(|deadline| Instant::now() >= deadline);
+    if !expired {
+        return;
+    }
+
+    if !unsafe { esp_ota_check_rollback_is_possible() } {
+        warn!("New firmware not verified, but there is no previous image to roll back to");
+        *VERIFY_DEADLINE.lock().unwrap() = None;
+        return;
+    }
+
+    error!("New firmware did not reach the backend in time, rolling back");
+    unsafe {
+        display_shutdown();
+        std::thread::sleep(Duration::from_millis(100));
+        let ret = esp_ota_mark_app_invalid_rollback_and_reboot();
+        // Only returns on failure
+        error!("Rollback failed: {}", ret);
+        esp_restart();
+    }
+}
 
 /// Get current OTA state
 pub fn get_state() -> OtaState {
@@ -87,15 +227,366 @@ pub fn get_update_version() -> String {
     UPDATE_VERSION.lock().unwrap().clone()
 }
 
-/// Check for available updates
+// ============================================================================
+// Versions and channels
+// ============================================================================
+
+/// Pre-release identifier; numeric identifiers sort before alphanumeric ones
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum PreRelease {
+    Numeric(u64),
+    Alpha(String),
+}
+
+/// Semantic version (build metadata is ignored)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Version {
+    major: u64,
+    minor: u64,
+    patch: u64,
+    pre: Vec<PreRelease>,
+}
+
+impl Version {
+    /// Parse `1.2.3`, `1.2.3-beta.11` or the PEP 440 style `1.2.3b11`
+    /// used by backend release files
+    pub fn parse(version: &str) -> Option<Self> {
+        let version = version.trim().trim_start_matches('v');
+        let version = version.split('+').next()?;
+        let (core, pre) = match version.split_once('-') {
+            Some((core, pre)) => (core, Some(pre)),
+            None => (version, None),
+        };
+
+        let mut parts = core.splitn(3, '.');
+        let major = parts.next()?.parse().ok()?;
+        let minor = parts.next()?.parse().ok()?;
+        let patch_str = parts.next()?;
+
+        // PEP 440 pre-release glued to the patch number (1.2.3b11)
+        let digits = patch_str.find(|c: char| !c.is_ascii_digit()).unwrap_or(patch_str.len());
+        let patch = patch_str[..digits].parse().ok()?;
+        let pep440_pre = &patch_str[digits..];
+
+        let pre = if !pep440_pre.is_empty() {
+            if pre.is_some() {
+                return None;
+            }
+            let split = pep440_pre.find(|c: char| c.is_ascii_digit()).unwrap_or(pep440_pre.len());
+            let label = match &pep440_pre[..split] {
+                "a" => "alpha",
+                "b" => "beta",
+                "rc" => "rc",
+                _ => return None,
+            };
+            let mut pre = vec![PreRelease::Alpha(label.to_string())];
+            if split < pep440_pre.len() {
+                pre.push(PreRelease::Numeric(pep440_pre[split..].parse().ok()?));
+            }
+            pre
+        } else if let Some(pre) = pre {
+            pre.split('.')
+                .map(|ident| {
+                    if ident.is_empty() {
+                        return None;
+                    }
+                    Some(ident.parse().map_or_else(
+                        |_| PreRelease::Alpha(ident.to_ascii_lowercase()),
+                        PreRelease::Numeric,
+                    ))
+                })
+                .collect::<Option<Vec<_>>>()?
+        } else {
+            Vec::new()
+        };
+
+        Some(Self { major, minor, patch, pre })
+    }
+
+    /// Release number without pre-release
+    pub fn core(&self) -> (u64, u64, u64) {
+        (self.major, self.minor, self.patch)
+    }
+
+    /// Channel this version is published on
+    pub fn channel(&self) -> UpdateChannel {
+        match self.pre.first() {
+            None => UpdateChannel::Stable,
+            Some(PreRelease::Alpha(label)) if label == "beta" || label == "rc" => UpdateChannel::Beta,
+            Some(_) => UpdateChannel::Dev,
+        }
+    }
+}
+
+impl Ord for Version {
+    /// SemVer precedence: a pre-release sorts before its release, pre-release
+    /// identifiers compare field by field and a shorter prefix sorts first
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Update channel, from most to least conservative. A channel also
+/// receives the versions of the more conservative ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    Stable = 0,
+    Beta = 1,
+    Dev = 2,
+}
+
+impl UpdateChannel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "stable",
+            UpdateChannel::Beta => "beta",
+            UpdateChannel::Dev => "dev",
+        }
+    }
+
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(UpdateChannel::Stable),
+            1 => Some(UpdateChannel::Beta),
+            2 => Some(UpdateChannel::Dev),
+            _ => None,
+        }
+    }
+
+    /// Whether devices on this channel may install `version`
+    pub fn accepts(self, version: &Version) -> bool {
+        version.channel() <= self
+    }
+}
+
+// ============================================================================
+// Update policy (settings store)
+// ============================================================================
+
+/// Channel and automatic install settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdatePolicy {
+    /// None until configured: follow the channel of the running firmware
+    pub channel: Option<UpdateChannel>,
+    pub auto_install: bool,
+    /// Install window in minutes since midnight; start == end means any time
+    pub window_start: u16,
+    pub window_end: u16,
+}
+
+impl Default for UpdatePolicy {
+    fn default() -> Self {
+        UpdatePolicy {
+            channel: None,
+            auto_install: true,
+            window_start: 0,
+            window_end: 0,
+        }
+    }
+}
+
+impl UpdatePolicy {
+    /// Check the install window is made of valid times of day
+    pub fn validate(&self) -> Result<(), String> {
+        if self.window_start >= MINUTES_PER_DAY || self.window_end >= MINUTES_PER_DAY {
+            return Err(format!("Invalid install window: {} - {}", self.window_start, self.window_end));
+        }
+        Ok(())
+    }
+
+    fn in_window(&self, now: Option<(u8, u8)>) -> bool {
+        if self.window_start == self.window_end {
+            return true;
+        }
+        // A restricted window needs the clock
+        let Some((hour, minute)) = now else {
+            return false;
+        };
+        let now = hour as u16 * 60 + minute as u16;
+        if self.window_start < self.window_end {
+            now >= self.window_start && now < self.window_end
+        } else {
+            // Wraps around midnight (e.g. 22:00 - 05:00)
+            now >= self.window_start || now < self.window_end
+        }
+    }
+}
+
+/// Channel the device follows
+pub fn channel() -> UpdateChannel {
+    settings::read(|s| s.ota.channel).unwrap_or_else(|| {
+        Version::parse(CURRENT_VERSION).map_or(UpdateChannel::Dev, |v| v.channel())
+    })
+}
+
+/// Change the update channel (persisted). Clears the cached check result,
+/// the next poll checks again.
+pub fn set_channel(channel: UpdateChannel) -> Result<(), String> {
+    settings::update(|s| s.ota.channel = Some(channel))?;
+    set_update_available(false, "");
+    *LAST_CHECK.lock().unwrap() = None;
+    info!("OTA channel set to {}", channel.as_str());
+    Ok(())
+}
+
+/// Whether updates are installed automatically
+pub fn auto_install_enabled() -> bool {
+    settings::read(|s| s.ota.auto_install)
+}
+
+/// Enable or disable automatic installs (persisted)
+pub fn set_auto_install(enabled: bool) -> Result<(), String> {
+    settings::update(|s| s.ota.auto_install = enabled)?;
+    info!("OTA auto-install {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+/// Automatic install window in minutes since midnight (start == end: any time)
+pub fn install_window() -> (u16, u16) {
+    settings::read(|s| (s.ota.window_start, s.ota.window_end))
+}
+
+/// Set the automatic install window (persisted)
+pub fn set_install_window(start: u16, end: u16) -> Result<(), String> {
+    let policy = UpdatePolicy {
+        window_start: start,
+        window_end: end,
+        ..settings::read(|s| s.ota.clone())
+    };
+    policy.validate()?;
+    settings::update(|s| s.ota = policy)
+}
+
+// ============================================================================
+// Background checks and automatic installs
+// ============================================================================
+
+/// Periodic update check and scheduled install
+/// Called from the network thread every ~2 seconds
+pub fn poll(server_url: &str) {
+    if WORKER_BUSY.load(AtomicOrdering::Relaxed)
+        || crate::bridge_ota::is_updating()
+        || !matches!(get_state(), OtaState::Idle | OtaState::Error(_))
+    {
+        return;
+    }
+
+    let check_due = LAST_CHECK
+        .lock()
+        .unwrap()
+        .map_or(true, |last| last.elapsed() >= CHECK_INTERVAL);
+    if check_due {
+        spawn_worker("ota_check", &WORKER_BUSY, server_url.to_string(), |url| {
+            match check_for_update(&url) {
+                Ok(info) => {
+                    set_update_available(info.available, &info.version);
+                    if info.available {
+                        info!("Firmware update available: v{}", info.version);
+                    }
+                }
+                Err(e) => warn!("OTA check failed: {}", e),
+            }
+        });
+    } else if auto_install_due() {
+        let version = get_update_version();
+        info!("Installing v{} automatically", version);
+        *AUTO_INSTALL_ATTEMPTED.lock().unwrap() = version;
+        start_update(server_url);
+    }
+}
+
+/// Start an install in the background (heartbeat command, UI and automatic
+/// installs). Returns false if a check or install is already running
+pub fn start_update(server_url: &str) -> bool {
+    if crate::bridge_ota::is_updating()
+        || !matches!(get_state(), OtaState::Idle | OtaState::Error(_))
+    {
+        return false;
+    }
+    spawn_worker("ota_update", &WORKER_BUSY, server_url.to_string(), |url| {
+        // Reboots on success
+        if let Err(e) = perform_update(&url) {
+            error!("OTA update failed: {}", e);
+        }
+    })
+}
+
+/// Whether a known update should be installed now (not tried before)
+fn auto_install_due() -> bool {
+    is_update_available()
+        && *AUTO_INSTALL_ATTEMPTED.lock().unwrap() != get_update_version()
+        && auto_install_allowed()
+}
+
+/// Whether automatic installs may run now: enabled, inside the window and
+/// no printer printing (per the cached gcode_state)
+pub fn auto_install_allowed() -> bool {
+    let now = crate::time_manager::get_time();
+    if !settings::read(|s| s.ota.auto_install && s.ota.in_window(now)) {
+        return false;
+    }
+    !crate::backend_client::any_printer_printing()
+}
+
+/// Run a check or install off the network thread (HTTP needs a larger stack)
+/// `busy` is held while it runs; returns false if it was already set
+pub fn spawn_worker(name: &str, busy: &'static AtomicBool, url: String, work: fn(String)) -> bool {
+    if busy.swap(true, AtomicOrdering::Relaxed) {
+        return false;
+    }
+    let spawned = std::thread::Builder::new()
+        .name(name.into())
+        .stack_size(16384)
+        .spawn(move || {
+            work(url);
+            busy.store(false, AtomicOrdering::Relaxed);
+        });
+    if let Err(e) = spawned {
+        warn!("Failed to start {} thread: {:?}", name, e);
+        busy.store(false, AtomicOrdering::Relaxed);
+        return false;
+    }
+    true
+}
+
+// ============================================================================
+// Check, download and install
+// ============================================================================
+
+/// Check for available updates on the configured channel
 pub fn check_for_update(server_url: &str) -> Result<UpdateInfo, String> {
     set_state(OtaState::Checking);
+    *LAST_CHECK.lock().unwrap() = Some(Instant::now());
 
-    let url = format!("{}/api/firmware/check?current_version={}", server_url, CURRENT_VERSION);
+    let url = format!("{}/api/firmware/check?current_version={}&channel={}",
+                      server_url, CURRENT_VERSION, channel().as_str());
     info!("Checking for updates: {}", url);
 
+    let result = query_update(&url, Some(CURRENT_VERSION));
+    set_state(OtaState::Idle);
+    result
+}
+
+/// Query an update check endpoint (ESP32 or NFC bridge). The update counts
+/// as available if it is newer than `current_version` and on the channel.
+pub fn query_update(url: &str, current_version: Option<&str>) -> Result<UpdateInfo, String> {
     let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_secs(10)),
+        timeout: Some(Duration::from_secs(10)),
         ..Default::default()
     };
 
@@ -110,7 +601,6 @@ pub fn check_for_update(server_url: &str) -> Result<UpdateInfo, String> {
 
     let status = response.status();
     if status != 200 {
-        set_state(OtaState::Idle);
         return Err(format!("HTTP error: {}", status));
     }
 
@@ -121,10 +611,7 @@ pub fn check_for_update(server_url: &str) -> Result<UpdateInfo, String> {
         match response.read(&mut buf) {
             Ok(0) => break,
             Ok(n) => json_data.extend_from_slice(&buf[..n]),
-            Err(e) => {
-                set_state(OtaState::Idle);
-                return Err(format!("Read error: {:?}", e));
-            }
+            Err(e) => return Err(format!("Read error: {:?}", e)),
         }
     }
 
@@ -132,51 +619,83 @@ pub fn check_for_update(server_url: &str) -> Result<UpdateInfo, String> {
     let json_str = String::from_utf8_lossy(&json_data);
 
     // Simple JSON parsing (avoid pulling in full serde for this)
-    let available = json_str.contains("\"update_available\":true") ||
+    let backend_available = json_str.contains("\"update_available\":true") ||
                    json_str.contains("\"update_available\": true");
 
     let version = extract_json_string(&json_str, "latest_version")
         .unwrap_or_else(|| "unknown".to_string());
 
+    // Decide on-device when both versions are semver, else trust the backend
+    let available = match (current_version.and_then(Version::parse), Version::parse(&version)) {
+        (Some(current), Some(latest)) => latest > current && channel().accepts(&latest),
+        _ => backend_available,
+    };
+
     let size = extract_json_number(&json_str, "size").unwrap_or(0);
 
-    let checksum = extract_json_string(&json_str, "checksum")
+    let sha256 = extract_json_string(&json_str, "sha256")
         .unwrap_or_else(|| "".to_string());
 
-    set_state(OtaState::Idle);
+    let signature = extract_json_string(&json_str, "signature")
+        .unwrap_or_else(|| "".to_string());
 
     Ok(UpdateInfo {
         available,
         version,
         size,
-        checksum,
+        sha256,
+        signature,
     })
 }
 
-/// Perform OTA update
-/// Downloads firmware to PSRAM, validates, then flashes
-pub fn perform_update(server_url: &str) -> Result<(), String> {
+/// Perform OTA update (blocking; use `start_update` from other modules)
+/// Streams the image advertised by the update check into the inactive slot,
+/// verifies its SHA-256 and boots from it
+fn perform_update(server_url: &str) -> Result<(), String> {
     info!("Starting OTA update from {}", server_url);
 
-    // Step 1: Download to PSRAM
-    set_state(OtaState::Downloading { progress: 0 });
-    let firmware_data = download_firmware(server_url)?;
+    // Step 1: Fetch the expected version, size and hash
+    let update = match check_for_update(server_url) {
+        Ok(update) => update,
+        Err(e) => {
+            set_state(OtaState::Error(e.clone()));
+            return Err(e);
+        }
+    };
+    set_update_available(update.available, &update.version);
+    if !update.available {
+        return Err(format!("No update available (running v{})", CURRENT_VERSION));
+    }
+    if update.sha256.len() != 64 {
+        let e = "Update check returned no SHA-256".to_string();
+        set_state(OtaState::Error(e.clone()));
+        return Err(e);
+    }
 
-    // Step 2: Validate
-    set_state(OtaState::Validating);
-    validate_firmware(&firmware_data)?;
+    // Reject unsigned updates before downloading anything
+    let verifier = match Verifier::new(&update.signature, FIRMWARE_SIGNATURE_TAG) {
+        Ok(verifier) => verifier,
+        Err(e) => {
+            set_state(OtaState::Error(e.clone()));
+            return Err(e);
+        }
+    };
 
-    // Step 3: Flash
-    flash_firmware(&firmware_data)?;
+    // Step 2: Stream to the inactive slot, verify and boot from it next
+    set_state(OtaState::Downloading { progress: 0 });
+    if let Err(e) = download_firmware(server_url, &update, &verifier) {
+        set_state(OtaState::Error(e.clone()));
+        return Err(e);
+    }
 
-    // Step 4: Reboot
+    // Step 3: Reboot
     set_state(OtaState::Complete);
     info!("OTA complete, rebooting in 2 seconds...");
-    std::thread::sleep(std::time::Duration::from_secs(2));
+    std::thread::sleep(Duration::from_secs(2));
 
     // Properly shutdown display before reboot to prevent display shift
     unsafe { display_shutdown(); }
-    std::thread::sleep(std::time::Duration::from_millis(100));
+    std::thread::sleep(Duration::from_millis(100));
     unsafe { esp_restart(); }
 
     // esp_restart() never returns, but Rust needs a return value
@@ -184,78 +703,222 @@ pub fn perform_update(server_url: &str) -> Result<(), String> {
     Ok(())
 }
 
-/// Download firmware to PSRAM buffer
-fn download_firmware(server_url: &str) -> Result<Vec<u8>, String> {
-    let url = format!("{}/api/firmware/ota", server_url);
-    info!("Downloading firmware from: {}", url);
+/// Download error: network errors are retried from the current offset,
+/// anything else aborts the update
+enum DownloadError {
+    Network(String),
+    Fatal(String),
+}
+
+/// Download the update straight into the OTA slot, resuming with HTTP
+/// ranges after connection drops
+fn download_firmware(server_url: &str, update: &UpdateInfo, verifier: &Verifier) -> Result<(), String> {
+    let url = format!("{}/api/firmware/ota?version={}", server_url, update.version);
+    info!("Downloading firmware v{} from: {}", update.version, url);
+
+    let mut writer: Option<OtaWriter> = None;
+    let mut retries = 0;
+
+    loop {
+        let offset = writer.as_ref().map_or(0, |w| w.written);
+        match download_from(&url, update, &mut writer) {
+            Ok(()) => break,
+            Err(DownloadError::Fatal(e)) => return Err(e),
+            Err(DownloadError::Network(e)) => {
+                // Only give up if several attempts in a row made no progress
+                if writer.as_ref().map_or(0, |w| w.written) > offset {
+                    retries = 0;
+                }
+                retries += 1;
+                if retries > DOWNLOAD_RETRIES {
+                    return Err(e);
+                }
+                warn!("{}, resuming ({}/{})", e, retries, DOWNLOAD_RETRIES);
+                wait_for_wifi();
+            }
+        }
+    }
+
+    let writer = writer.ok_or("Empty download")?;
+    if writer.written != writer.total {
+        return Err(format!("Download incomplete: {} / {} bytes", writer.written, writer.total));
+    }
+
+    set_state(OtaState::Validating);
+    writer.finish(update, verifier)
+}
+
+/// Wait (bounded) for WiFi to come back before retrying a download
+fn wait_for_wifi() {
+    let deadline = Instant::now() + WIFI_WAIT;
+    std::thread::sleep(RETRY_DELAY);
+    while !crate::wifi_manager::is_connected() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_secs(1));
+    }
+}
+
+/// One HTTP request, continuing at the writer's offset
+fn download_from(url: &str, update: &UpdateInfo, writer: &mut Option<OtaWriter>) -> Result<(), DownloadError> {
+    let offset = writer.as_ref().map_or(0, |w| w.written);
 
     let config = HttpConfig {
-        timeout: Some(std::time::Duration::from_secs(120)), // 2 min for large file
+        timeout: Some(Duration::from_secs(30)),
         ..Default::default()
     };
-
     let connection = EspHttpConnection::new(&config)
-        .map_err(|e| format!("HTTP connection failed: {:?}", e))?;
+        .map_err(|e| DownloadError::Network(format!("HTTP connection failed: {:?}", e)))?;
     let mut client = HttpClient::wrap(connection);
 
-    let request = client.get(&url)
-        .map_err(|e| format!("HTTP request failed: {:?}", e))?;
+    let range = format!("bytes={}-", offset);
+    let mut headers = Vec::new();
+    if offset > 0 {
+        headers.push(("Range", range.as_str()));
+    }
+    let request = client.request(Method::Get, url, &headers)
+        .map_err(|e| DownloadError::Network(format!("HTTP request failed: {:?}", e)))?;
     let mut response = request.submit()
-        .map_err(|e| format!("HTTP submit failed: {:?}", e))?;
+        .map_err(|e| DownloadError::Network(format!("HTTP submit failed: {:?}", e)))?;
 
-    let status = response.status();
-    if status != 200 {
-        set_state(OtaState::Error(format!("HTTP {}", status)));
-        return Err(format!("HTTP error: {}", status));
+    // A server that ignores the range sends the whole image again
+    let mut skip = match response.status() {
+        206 if offset > 0 => 0,
+        200 => offset,
+        status => return Err(DownloadError::Fatal(format!("HTTP error: {}", status))),
+    };
+
+    if writer.is_none() {
+        let content_length: usize = response.header("Content-Length")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or(0);
+        let total = if update.size > 0 { update.size as usize } else { content_length };
+        if total == 0 {
+            return Err(DownloadError::Fatal("Firmware size unknown".to_string()));
+        }
+        info!("Firmware size: {} bytes", total);
+        *writer = Some(OtaWriter::begin(total).map_err(DownloadError::Fatal)?);
+    } else {
+        info!("Resuming download at {} bytes", offset);
     }
+    let writer = writer.as_mut().unwrap();
 
-    // Get content length for progress
-    let content_length: usize = response.header("Content-Length")
-        .and_then(|s| s.parse().ok())
-        .unwrap_or(5_000_000); // Assume 5MB if not provided
+    // Use heap-allocated buffer to avoid stack overflow
+    let mut buf = vec![0u8; 4096];
+    while writer.written < writer.total {
+        let n = response.read(&mut buf)
+            .map_err(|e| DownloadError::Network(format!("Download error: {:?}", e)))?;
+        if n == 0 {
+            return Err(DownloadError::Network(format!(
+                "Connection closed at {} / {} bytes", writer.written, writer.total)));
+        }
 
-    info!("Firmware size: {} bytes", content_length);
+        let mut data = &buf[..n];
+        if skip > 0 {
+            let skipped = skip.min(data.len());
+            skip -= skipped;
+            data = &data[skipped..];
+        }
+        writer.write(data).map_err(DownloadError::Fatal)?;
 
-    // Allocate in PSRAM (Vec uses heap which is configured to use PSRAM for large allocs)
-    let mut firmware_data = Vec::with_capacity(content_length);
-    // Use heap-allocated buffer to avoid stack overflow
-    let mut buf = vec![0u8; 4096]; // 4KB chunks on heap
-    let mut total_read = 0usize;
+        let progress = ((writer.written * 100) / writer.total).min(100) as u8;
+        set_state(OtaState::Downloading { progress });
+        if writer.written % (256 * 1024) < data.len() {
+            info!("Downloaded: {} / {} bytes ({}%)", writer.written, writer.total, progress);
+        }
+    }
 
-    loop {
-        match response.read(&mut buf) {
-            Ok(0) => break,
-            Ok(n) => {
-                firmware_data.extend_from_slice(&buf[..n]);
-                total_read += n;
+    info!("Download complete: {} bytes", writer.written);
+    Ok(())
+}
 
-                let progress = ((total_read * 100) / content_length).min(100) as u8;
-                set_state(OtaState::Downloading { progress });
+/// Decode a hex string
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
 
-                if total_read % (256 * 1024) == 0 {
-                    info!("Downloaded: {} / {} bytes ({}%)", total_read, content_length, progress);
-                }
-            }
-            Err(e) => {
-                set_state(OtaState::Error(format!("Download failed: {:?}", e)));
-                return Err(format!("Download error: {:?}", e));
-            }
+/// Compiled-in public key plus the signature announced for an update
+pub struct Verifier {
+    key: PublicKey,
+    signature: Signature,
+    /// Kind of image the signature must be for
+    tag: &'static str,
+}
+
+impl Verifier {
+    pub fn new(signature_hex: &str, tag: &'static str) -> Result<Self, String> {
+        let key_hex = OTA_PUBLIC_KEY.ok_or("Firmware built without an OTA signing key")?;
+        let key = decode_hex(key_hex.trim())
+            .and_then(|bytes| PublicKey::from_slice(&bytes).ok())
+            .ok_or("Invalid compiled-in OTA signing key")?;
+
+        if signature_hex.is_empty() {
+            return Err("Update is not signed".to_string());
         }
+        let signature = decode_hex(signature_hex)
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .ok_or("Malformed update signature")?;
+
+        Ok(Self { key, signature, tag })
+    }
+
+    /// Check the signature over `tag \0 version \0 sha256`
+    pub fn verify(&self, version: &str, digest: &[u8]) -> Result<(), String> {
+        let mut message = Vec::with_capacity(self.tag.len() + version.len() + 2 + digest.len());
+        message.extend_from_slice(self.tag.as_bytes());
+        message.push(0);
+        message.extend_from_slice(version.as_bytes());
+        message.push(0);
+        message.extend_from_slice(digest);
+        self.key
+            .verify(&message, &self.signature)
+            .map_err(|e| format!("Signature verification failed for {} v{}: {:?}", self.tag, version, e))
     }
+}
 
-    info!("Download complete: {} bytes", firmware_data.len());
-    Ok(firmware_data)
+/// Check an image's SHA-256 digest against the announced hash, and the
+/// signature over it and the image version
+pub fn verify_digest(digest: &[u8], expected_sha256: &str, version: &str, verifier: &Verifier) -> Result<(), String> {
+    let digest_hex: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    if !digest_hex.eq_ignore_ascii_case(expected_sha256) {
+        return Err(format!("SHA-256 mismatch: got {}, expected {}", digest_hex, expected_sha256));
+    }
+    verifier.verify(version, digest)?;
+    info!("SHA-256 and signature verified: {} (v{})", digest_hex, version);
+    Ok(())
 }
 
-/// Validate firmware binary
-fn validate_firmware(data: &[u8]) -> Result<(), String> {
-    info!("Validating firmware ({} bytes)", data.len());
+/// Version string of an app descriptor
+fn app_desc_version(desc: &esp_app_desc_t) -> String {
+    let bytes: Vec<u8> = desc.version.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
 
-    // Check minimum size
-    if data.len() < 256 {
-        return Err("Firmware too small".to_string());
+/// Reject an image that isn't the announced version or is older than the
+/// running firmware (both as written in the app descriptors)
+fn check_image_version(image: &str, announced: &str) -> Result<(), String> {
+    let running = app_desc_version(unsafe { &*esp_app_get_description() });
+    let (Some(image_v), Some(announced_v), Some(running_v)) =
+        (Version::parse(image), Version::parse(announced), Version::parse(&running))
+    else {
+        return Err(format!("Can't compare versions: image {:?}, announced {:?}, running {:?}",
+                           image, announced, running));
+    };
+    if image_v != announced_v {
+        return Err(format!("Image is v{}, but v{} was announced", image, announced));
     }
+    if image_v < running_v {
+        return Err(format!("Image v{} is older than the running v{}", image, running));
+    }
+    Ok(())
+}
 
+/// Validate the image header (first bytes of the download)
+fn validate_header(data: &[u8]) -> Result<(), String> {
     // Check ESP32 magic byte
     if data[0] != 0xE9 {
         return Err(format!("Invalid magic byte: 0x{:02X} (expected 0xE9)", data[0]));
@@ -267,72 +930,121 @@ fn validate_firmware(data: &[u8]) -> Result<(), String> {
         return Err(format!("Invalid segment count: {}", segment_count));
     }
 
-    info!("Firmware validation passed");
     Ok(())
 }
 
-/// Flash firmware to factory partition
-fn flash_firmware(data: &[u8]) -> Result<(), String> {
-    info!("Flashing {} bytes to factory partition", data.len());
-    set_state(OtaState::Flashing { progress: 0 });
-
-    unsafe {
-        // Find factory partition
-        let iterator: esp_partition_iterator_t = esp_partition_find(
-            esp_partition_type_t_ESP_PARTITION_TYPE_APP,
-            esp_partition_subtype_t_ESP_PARTITION_SUBTYPE_APP_FACTORY,
-            ptr::null(),
-        );
+/// Sequential writer into the inactive OTA slot that hashes what it writes.
+/// Dropping it before `finish` aborts the update.
+struct OtaWriter {
+    handle: esp_ota_handle_t,
+    partition: *const esp_partition_t,
+    hasher: Sha256,
+    written: usize,
+    total: usize,
+    finished: bool,
+}
 
-        if iterator.is_null() {
-            return Err("Factory partition not found".to_string());
+impl OtaWriter {
+    /// Select and erase the inactive slot for an image of `total` bytes
+    fn begin(total: usize) -> Result<Self, String> {
+        if total < 256 {
+            return Err("Firmware too small".to_string());
         }
 
-        let partition: *const esp_partition_t = esp_partition_get(iterator);
-        esp_partition_iterator_release(iterator);
+        unsafe {
+            let partition: *const esp_partition_t = esp_ota_get_next_update_partition(ptr::null());
+            if partition.is_null() {
+                return Err("No OTA update partition".to_string());
+            }
 
-        if partition.is_null() {
-            return Err("Failed to get partition".to_string());
-        }
+            let part = &*partition;
+            info!("Writing {} bytes to '{}' (offset=0x{:X}, size={} bytes)",
+                  total, partition_label(part), part.address, part.size);
 
-        let part = &*partition;
-        let part_size = part.size as usize;
+            if total > part.size as usize {
+                return Err(format!("Firmware too large: {} > {}", total, part.size));
+            }
 
-        info!("Factory partition: offset=0x{:X}, size={} bytes", part.address, part_size);
+            // Erases the slot for the given image size
+            let mut handle: esp_ota_handle_t = 0;
+            let ret = esp_ota_begin(partition, total, &mut handle);
+            if ret != 0 {
+                return Err(format!("OTA begin failed: {}", ret));
+            }
 
-        if data.len() > part_size {
-            return Err(format!("Firmware too large: {} > {}", data.len(), part_size));
+            Ok(Self {
+                handle,
+                partition,
+                hasher: Sha256::new(),
+                written: 0,
+                total,
+                finished: false,
+            })
         }
+    }
 
-        // Erase partition (in 64KB chunks for progress)
-        let erase_size = ((data.len() + 0xFFF) / 0x1000) * 0x1000; // Round up to 4KB
-        info!("Erasing {} bytes...", erase_size);
+    fn write(&mut self, data: &[u8]) -> Result<(), String> {
+        if data.is_empty() {
+            return Ok(());
+        }
+        if self.written + data.len() > self.total {
+            return Err(format!("Firmware larger than announced ({} bytes)", self.total));
+        }
+        if self.written == 0 && data.len() >= 2 {
+            validate_header(data)?;
+        }
 
-        let ret = esp_partition_erase_range(partition, 0, erase_size);
+        let ret = unsafe { esp_ota_write(self.handle, data.as_ptr() as *const _, data.len()) };
         if ret != 0 {
-            return Err(format!("Erase failed: {}", ret));
+            return Err(format!("Write failed at offset {}: {}", self.written, ret));
         }
+        self.hasher.update(data);
+        self.written += data.len();
+        Ok(())
+    }
 
-        // Write firmware (in 4KB chunks for progress)
-        let chunk_size = 4096;
-        let total_chunks = (data.len() + chunk_size - 1) / chunk_size;
-
-        for (i, chunk) in data.chunks(chunk_size).enumerate() {
-            let offset = i * chunk_size;
-            let ret = esp_partition_write(partition, offset, chunk.as_ptr() as *const _, chunk.len());
+    /// Version in the written image's app descriptor
+    fn image_version(&self) -> Result<String, String> {
+        let mut desc: esp_app_desc_t = unsafe { std::mem::zeroed() };
+        let ret = unsafe { esp_ota_get_partition_description(self.partition, &mut desc) };
+        if ret != 0 {
+            return Err(format!("Image has no app description: {}", ret));
+        }
+        Ok(app_desc_version(&desc))
+    }
 
+    /// Check the SHA-256, signature and version, finalize the image and boot
+    /// from it next
+    fn finish(mut self, update: &UpdateInfo, verifier: &Verifier) -> Result<(), String> {
+        let version = self.image_version()?;
+        verify_digest(&self.hasher.clone().finalize(), &update.sha256, &version, verifier)?;
+        check_image_version(&version, &update.version)?;
+
+        self.finished = true;
+        unsafe {
+            // Verifies the image (segments, checksum and appended SHA-256)
+            let ret = esp_ota_end(self.handle);
             if ret != 0 {
-                return Err(format!("Write failed at offset {}: {}", offset, ret));
+                return Err(format!("Image verification failed: {}", ret));
             }
 
-            let progress = (((i + 1) * 100) / total_chunks).min(100) as u8;
-            set_state(OtaState::Flashing { progress });
+            let ret = esp_ota_set_boot_partition(self.partition);
+            if ret != 0 {
+                return Err(format!("Failed to set boot partition: {}", ret));
+            }
+            info!("Flash complete, next boot from '{}'", partition_label(&*self.partition));
         }
-
-        info!("Flash complete");
+        Ok(())
     }
+}
 
-    Ok(())
+impl Drop for OtaWriter {
+    fn drop(&mut self) {
+        if !self.finished {
+            warn!("OTA update aborted after {} bytes", self.written);
+            unsafe { esp_ota_abort(self.handle); }
+        }
+    }
 }
 
 // Simple JSON helpers (avoid serde dependency)
@@ -359,3 +1071,78 @@ fn extract_json_number(json: &str, key: &str) -> Option<u32> {
     let end = json_after.find(|c: char| !c.is_ascii_digit()).unwrap_or(json_after.len());
     json_after[..end].parse().ok()
 }
+
+// ============================================================================
+// C-callable interface
+// ============================================================================
+
+/// Get the update channel: 0 = stable, 1 = beta, 2 = dev
+#[no_mangle]
+pub extern "C" fn ota_get_channel() -> c_int {
+    channel() as c_int
+}
+
+/// Set the update channel (0 = stable, 1 = beta, 2 = dev)
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn ota_set_channel(channel: c_int) -> c_int {
+    let Some(channel) = u8::try_from(channel).ok().and_then(UpdateChannel::from_u8) else {
+        return -1;
+    };
+    match set_channel(channel) {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Returns 1 if updates are installed automatically, 0 otherwise
+#[no_mangle]
+pub extern "C" fn ota_get_auto_install() -> c_int {
+    auto_install_enabled() as c_int
+}
+
+/// Enable (1) or disable (0) automatic installs
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn ota_set_auto_install(enabled: c_int) -> c_int {
+    match set_auto_install(enabled != 0) {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Get the install window in minutes since midnight (start == end: any time)
+#[no_mangle]
+pub extern "C" fn ota_get_install_window(start: *mut c_int, end: *mut c_int) {
+    let (window_start, window_end) = install_window();
+    unsafe {
+        if !start.is_null() {
+            *start = window_start as c_int;
+        }
+        if !end.is_null() {
+            *end = window_end as c_int;
+        }
+    }
+}
+
+/// Set the install window in minutes since midnight (start == end: any time)
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn ota_set_install_window(start: c_int, end: c_int) -> c_int {
+    let (Ok(start), Ok(end)) = (u16::try_from(start), u16::try_from(end)) else {
+        return -1;
+    };
+    match set_install_window(start, end) {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
@@ -0,0 +1,509 @@
+//! Bambu Printer Discovery
+//!
+//! Bambu printers announce themselves with SSDP-style NOTIFY packets on UDP
+//! 2021 (broadcast and the 239.255.255.250 multicast group) every few
+//! seconds, and answer M-SEARCH requests with the same headers. A background
+//! thread listens for both while WiFi is up, keeps a de-duplicated list of
+//! printers that ages out silent ones, and pushes changes to the backend.
+
+use log::{info, warn, debug};
+use std::ffi::{c_char, c_int};
+use std::net::{Ipv4Addr, SocketAddr, UdpSocket};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::wifi_manager;
+
+/// Bambu SSDP port (NOTIFY and M-SEARCH)
+const SSDP_PORT: u16 = 2021;
+
+/// SSDP multicast group
+const SSDP_MULTICAST: Ipv4Addr = Ipv4Addr::new(239, 255, 255, 250);
+
+/// Search target announced by Bambu printers
+const BAMBU_ST: &str = "urn:bambulab-com:device:3dprinter:1";
+
+/// Printers not heard from for this long are dropped
+const PRINTER_MAX_AGE: Duration = Duration::from_secs(120);
+
+/// M-SEARCH interval (NOTIFYs usually arrive sooner)
+const SEARCH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Socket read timeout (also the housekeeping interval)
+const RECV_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Minimum time between backend pushes of an unchanged list
+const PUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Wait after a failed push before trying again
+const PUSH_RETRY_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Maximum printers kept
+const MAX_PRINTERS: usize = 16;
+
+/// How the printer talks to the outside world (DevConnect header)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectMode {
+    Unknown,
+    /// LAN-only mode
+    Lan,
+    /// Connected to Bambu cloud
+    Cloud,
+}
+
+impl ConnectMode {
+    fn parse(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "lan" => ConnectMode::Lan,
+            "cloud" => ConnectMode::Cloud,
+            _ => ConnectMode::Unknown,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ConnectMode::Unknown => "unknown",
+            ConnectMode::Lan => "lan",
+            ConnectMode::Cloud => "cloud",
+        }
+    }
+}
+
+/// Whether the printer is bound to a cloud account (DevBind header)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindState {
+    Unknown,
+    Free,
+    Bound,
+}
+
+impl BindState {
+    fn parse(value: &str) -> Self {
+        match value.to_ascii_lowercase().as_str() {
+            "free" => BindState::Free,
+            "occupied" => BindState::Bound,
+            _ => BindState::Unknown,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BindState::Unknown => "unknown",
+            BindState::Free => "free",
+            BindState::Bound => "bound",
+        }
+    }
+}
+
+/// Kind of SSDP packet (from the start line)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SsdpKind {
+    Notify,
+    Response,
+    Search,
+}
+
+/// Parsed SSDP packet. Bambu headers come as "DevName.bambu.com: ..." -
+/// the domain suffix is dropped and names are matched case-insensitively.
+#[derive(Debug, Default)]
+struct SsdpMessage {
+    kind: Option<SsdpKind>,
+    /// NT (NOTIFY) or ST (response)
+    target: String,
+    usn: String,
+    location: String,
+    dev_model: String,
+    dev_name: String,
+    dev_connect: String,
+    dev_bind: String,
+    dev_version: String,
+}
+
+impl SsdpMessage {
+    fn parse(data: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(data).ok()?;
+        let mut lines = text.split("\r\n").flat_map(|l| l.split('\n'));
+
+        let start = lines.next()?.trim();
+        let kind = if start.starts_with("NOTIFY ") {
+            SsdpKind::Notify
+        } else if start.starts_with("HTTP/1.1 200") {
+            SsdpKind::Response
+        } else if start.starts_with("M-SEARCH ") {
+            SsdpKind::Search
+        } else {
+            return None;
+        };
+
+        let mut msg = SsdpMessage { kind: Some(kind), ..Default::default() };
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let name = name.trim();
+            let name = name
+                .strip_suffix(".bambu.com")
+                .unwrap_or(name)
+                .to_ascii_lowercase();
+            let value = value.trim().to_string();
+            match name.as_str() {
+                "nt" | "st" => msg.target = value,
+                "usn" => msg.usn = value,
+                "location" => msg.location = value,
+                "devmodel" => msg.dev_model = value,
+                "devname" => msg.dev_name = value,
+                "devconnect" => msg.dev_connect = value,
+                "devbind" => msg.dev_bind = value,
+                "devversion" => msg.dev_version = value,
+                _ => {}
+            }
+        }
+        Some(msg)
+    }
+
+    /// Serial number from the USN ("<serial>" or "uuid:<serial>::<type>")
+    fn serial(&self) -> &str {
+        let usn = self.usn.strip_prefix("uuid:").unwrap_or(&self.usn);
+        usn.split("::").next().unwrap_or(usn).trim()
+    }
+
+    fn is_bambu_printer(&self) -> bool {
+        self.kind != Some(SsdpKind::Search) && self.target.eq_ignore_ascii_case(BAMBU_ST)
+    }
+}
+
+/// A printer seen on the network
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscoveredPrinter {
+    pub serial: String,
+    pub name: String,
+    /// Bambu model code (e.g. "C12")
+    pub model_code: String,
+    pub ip: Ipv4Addr,
+    pub connect: ConnectMode,
+    pub bind: BindState,
+    pub firmware: String,
+    pub last_seen: Instant,
+}
+
+impl DiscoveredPrinter {
+    /// Friendly model name for the model code
+    pub fn model_name(&self) -> &str {
+        model_name(&self.model_code)
+    }
+
+    /// Same printer details, ignoring when it was last heard from
+    fn same_details(&self, other: &DiscoveredPrinter) -> bool {
+        self.name == other.name
+            && self.model_code == other.model_code
+            && self.ip == other.ip
+            && self.connect == other.connect
+            && self.bind == other.bind
+            && self.firmware == other.firmware
+    }
+}
+
+/// Map Bambu model codes to friendly names
+/// Reference: https://github.com/bambulab/BambuStudio/tree/master/resources/printers
+pub fn model_name(code: &str) -> &str {
+    match code {
+        // X1 Series
+        "BL-P001" => "X1 Carbon",
+        "BL-P002" => "X1",
+        "C13" => "X1E",
+        // P1 Series
+        "C11" => "P1P",
+        "C12" => "P1S",
+        // A1 Series
+        "N1" => "A1 Mini",
+        "N2S" => "A1",
+        // P2 Series
+        "N7" => "P2S",
+        // H2 Series
+        "O1C" | "O1C2" => "H2C",
+        "O1D" => "H2D",
+        "O1E" => "H2D Pro",
+        "O1S" => "H2S",
+        "" => "Bambu Printer",
+        other => other, // Keep unknown codes as-is
+    }
+}
+
+/// Build a printer entry from a packet. The Location header carries the
+/// printer's IP; the sender address is the fallback.
+fn printer_from_message(msg: &SsdpMessage, from: Ipv4Addr) -> Option<DiscoveredPrinter> {
+    if !msg.is_bambu_printer() {
+        return None;
+    }
+    let serial = msg.serial();
+    if serial.is_empty() {
+        return None;
+    }
+    let ip = msg.location.parse().unwrap_or(from);
+
+    let name = if msg.dev_name.is_empty() {
+        let short = &serial[serial.len().saturating_sub(6)..];
+        format!("{} ({})", model_name(&msg.dev_model), short)
+    } else {
+        msg.dev_name.clone()
+    };
+
+    Some(DiscoveredPrinter {
+        serial: serial.to_string(),
+        name,
+        model_code: msg.dev_model.clone(),
+        ip,
+        connect: ConnectMode::parse(&msg.dev_connect),
+        bind: BindState::parse(&msg.dev_bind),
+        firmware: msg.dev_version.clone(),
+        last_seen: Instant::now(),
+    })
+}
+
+// =============================================================================
+// Discovery Service
+// =============================================================================
+
+static PRINTERS: Mutex<Vec<DiscoveredPrinter>> = Mutex::new(Vec::new());
+
+/// Set when the list changed and the backend hasn't been told yet
+static PUSH_PENDING: Mutex<bool> = Mutex::new(false);
+
+/// Set to request an immediate M-SEARCH from the service thread
+static SEARCH_REQUESTED: Mutex<bool> = Mutex::new(false);
+
+/// Start the background discovery thread (call once at startup)
+pub fn start() {
+    let result = std::thread::Builder::new()
+        .name("printer_discovery".into())
+        .stack_size(6144)
+        .spawn(run);
+    if let Err(e) = result {
+        warn!("Failed to start printer discovery: {:?}", e);
+    }
+}
+
+/// Printers currently on the network, most recently seen first
+pub fn printers() -> Vec<DiscoveredPrinter> {
+    let mut list = PRINTERS.lock().unwrap().clone();
+    list.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+    list
+}
+
+/// Ask the service to send an M-SEARCH now (e.g. the user opened a picker)
+pub fn request_search() {
+    *SEARCH_REQUESTED.lock().unwrap() = true;
+}
+
+/// Open the listening socket and join the multicast group
+fn open_socket() -> Result<UdpSocket, std::io::Error> {
+    let socket = UdpSocket::bind(SocketAddr::from((Ipv4Addr::UNSPECIFIED, SSDP_PORT)))?;
+    socket.set_broadcast(true)?;
+    socket.set_read_timeout(Some(RECV_TIMEOUT))?;
+    if let Err(e) = socket.join_multicast_v4(&SSDP_MULTICAST, &Ipv4Addr::UNSPECIFIED) {
+        // Broadcast NOTIFYs still arrive without the group
+        warn!("Could not join SSDP multicast group: {:?}", e);
+    }
+    Ok(socket)
+}
+
+fn send_search(socket: &UdpSocket) {
+    let msg = format!(
+        "M-SEARCH * HTTP/1.1\r\nHOST: {}:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: {}\r\n\r\n",
+        SSDP_MULTICAST, BAMBU_ST
+    );
+    for target in [Ipv4Addr::BROADCAST, SSDP_MULTICAST] {
+        if let Err(e) = socket.send_to(msg.as_bytes(), (target, SSDP_PORT)) {
+            debug!("M-SEARCH to {} failed: {:?}", target, e);
+        }
+    }
+}
+
+fn run() {
+    let mut socket: Option<UdpSocket> = None;
+    let mut last_search: Option<Instant> = None;
+    let mut last_push: Option<Instant> = None;
+    let mut push_failed = false;
+    let mut buf = [0u8; 1024];
+
+    loop {
+        if !wifi_manager::is_connected() {
+            // Socket is bound to the old interface address - reopen on reconnect
+            if socket.take().is_some() {
+                info!("Printer discovery paused (WiFi down)");
+            }
+            std::thread::sleep(Duration::from_secs(1));
+            continue;
+        }
+
+        if socket.is_none() {
+            match open_socket() {
+                Ok(s) => {
+                    info!("Printer discovery listening on UDP {}", SSDP_PORT);
+                    socket = Some(s);
+                    last_search = None;
+                }
+                Err(e) => {
+                    warn!("Printer discovery socket failed: {:?}", e);
+                    std::thread::sleep(Duration::from_secs(5));
+                    continue;
+                }
+            }
+        }
+        let Some(sock) = socket.as_ref() else {
+            continue;
+        };
+
+        let search_requested = std::mem::take(&mut *SEARCH_REQUESTED.lock().unwrap());
+        if search_requested || !last_search.is_some_and(|t| t.elapsed() < SEARCH_INTERVAL) {
+            send_search(sock);
+            last_search = Some(Instant::now());
+        }
+
+        match sock.recv_from(&mut buf) {
+            Ok((len, SocketAddr::V4(from))) => {
+                if let Some(printer) = SsdpMessage::parse(&buf[..len])
+                    .and_then(|msg| printer_from_message(&msg, *from.ip()))
+                {
+                    record(printer);
+                }
+            }
+            Ok(_) => {}
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {}
+            Err(e) => {
+                warn!("Printer discovery receive failed: {:?}", e);
+                socket = None;
+                continue;
+            }
+        }
+
+        expire();
+
+        // Push changes right away, and the full list periodically so the
+        // backend can age entries out too. After a failure (backend down)
+        // pending changes wait for the retry interval.
+        let pending = *PUSH_PENDING.lock().unwrap();
+        let interval = match (pending, push_failed) {
+            (true, false) => Duration::ZERO,
+            (true, true) => PUSH_RETRY_INTERVAL,
+            (false, _) => PUSH_INTERVAL,
+        };
+        if !last_push.is_some_and(|t| t.elapsed() < interval) {
+            push_failed = !crate::backend_client::send_discovered_printers(&printers());
+            if !push_failed {
+                *PUSH_PENDING.lock().unwrap() = false;
+            }
+            last_push = Some(Instant::now());
+        }
+    }
+}
+
+/// Add or refresh a printer, flagging a push if anything besides the
+/// timestamp changed
+fn record(printer: DiscoveredPrinter) {
+    let mut list = PRINTERS.lock().unwrap();
+    match list.iter_mut().find(|p| p.serial == printer.serial) {
+        Some(existing) => {
+            if !existing.same_details(&printer) {
+                info!("Printer {} updated: {} at {} ({})",
+                      printer.serial, printer.name, printer.ip, printer.connect.as_str());
+                *PUSH_PENDING.lock().unwrap() = true;
+            }
+            *existing = printer;
+        }
+        None => {
+            if list.len() >= MAX_PRINTERS {
+                // Drop the one heard from least recently
+                if let Some(oldest) = list
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, p)| p.last_seen)
+                    .map(|(i, _)| i)
+                {
+                    list.swap_remove(oldest);
+                }
+            }
+            info!("Discovered printer {} '{}' ({}) at {} - {} mode",
+                  printer.serial, printer.name, printer.model_name(), printer.ip, printer.connect.as_str());
+            list.push(printer);
+            *PUSH_PENDING.lock().unwrap() = true;
+        }
+    }
+}
+
+/// Drop printers that have gone quiet
+fn expire() {
+    let mut list = PRINTERS.lock().unwrap();
+    let before = list.len();
+    list.retain(|p| {
+        let alive = p.last_seen.elapsed() < PRINTER_MAX_AGE;
+        if !alive {
+            info!("Printer {} '{}' no longer announcing", p.serial, p.name);
+        }
+        alive
+    });
+    if list.len() != before {
+        *PUSH_PENDING.lock().unwrap() = true;
+    }
+}
+
+// =============================================================================
+// C-callable FFI Functions
+// =============================================================================
+
+/// Discovered printer info for C interface
+#[repr(C)]
+pub struct PrinterDiscoveryResult {
+    /// Printer name (null-terminated)
+    pub name: [c_char; 64],
+    /// Serial number (null-terminated)
+    pub serial: [c_char; 32],
+    /// IP address as string (null-terminated)
+    pub ip: [c_char; 16],
+    /// Model name (null-terminated)
+    pub model: [c_char; 32],
+}
+
+/// Copy a string into a fixed C buffer (truncated, null-terminated)
+fn copy_c_str(dst: &mut [c_char], src: &str) {
+    let len = src.len().min(dst.len() - 1);
+    for (d, &s) in dst.iter_mut().zip(&src.as_bytes()[..len]) {
+        *d = s as c_char;
+    }
+    dst[len] = 0;
+}
+
+/// Discover Bambu printers on the network
+/// Returns the printers the background service knows about; if none are
+/// known yet, sends an M-SEARCH and waits up to 2 seconds for answers.
+/// Fills the results array with up to max_results entries
+/// Returns the number of printers found, or -1 on error
+#[no_mangle]
+pub extern "C" fn printer_discover(results: *mut PrinterDiscoveryResult, max_results: c_int) -> c_int {
+    if results.is_null() || max_results <= 0 {
+        return -1;
+    }
+
+    if !wifi_manager::is_connected() {
+        warn!("printer_discover: WiFi not connected");
+        return -1;
+    }
+
+    request_search();
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while PRINTERS.lock().unwrap().is_empty() && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(100));
+    }
+
+    let printers = printers();
+    let count = printers.len().min(max_results as usize);
+    for (i, printer) in printers.iter().take(count).enumerate() {
+        let result = unsafe { &mut *results.add(i) };
+        copy_c_str(&mut result.name, &printer.name);
+        copy_c_str(&mut result.serial, &printer.serial);
+        copy_c_str(&mut result.ip, &printer.ip.to_string());
+        copy_c_str(&mut result.model, printer.model_name());
+    }
+    info!("printer_discover: {} printer(s)", count);
+    count as c_int
+}
@@ -0,0 +1,562 @@
+//! Direct LAN MQTT to Bambu printers
+//!
+//! Every Bambu printer runs an MQTT broker on port 8883 (TLS with a
+//! certificate issued by Bambu Lab's printer CA, user "bblp", password = LAN
+//! access code). Each connection verifies the broker against that CA, which
+//! is compiled in from the PEM file in `SPOOLBUDDY_PRINTER_CA`; builds
+//! without it don't open LAN connections. When
+//! enabled, this module connects to the printers the backend last reported
+//! whenever the backend itself is unreachable. It feeds
+//! `device/<serial>/report` pushes into the printer cache and publishes
+//! `ams_filament_setting` commands directly, so the AMS view and slot setup
+//! keep working without the server.
+//!
+//! Printers only accept a few LAN clients, so the connections are closed
+//! again as soon as the backend answers.
+
+use esp_idf_svc::mqtt::client::{Details, EspMqttClient, EventPayload, MqttClientConfiguration, QoS};
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
+use esp_idf_svc::tls::X509;
+use log::{debug, info, warn};
+use serde_json::{json, Value};
+use std::ffi::c_int;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// NVS keys for remembered printers (LAN mode itself is in the settings store)
+const NVS_NAMESPACE: &str = "printer_lan";
+const NVS_KEY_COUNT: &str = "count";
+
+/// Printer MQTT broker port (TLS)
+const MQTT_PORT: u16 = 8883;
+
+/// Fixed LAN MQTT user name
+const MQTT_USER: &str = "bblp";
+
+/// Printers remembered for LAN mode (matches the backend printer cache)
+const MAX_PRINTERS: usize = 4;
+
+/// MQTT receive buffer; larger reports arrive in chunks and are reassembled
+const MQTT_BUFFER_SIZE: usize = 4096;
+
+/// Largest report reassembled (a full push with four AMS units is ~12 KB)
+const MAX_REPORT_SIZE: usize = 32 * 1024;
+
+/// CA certificate of the printers' brokers (PEM, NUL-terminated; empty if
+/// the build had no `SPOOLBUDDY_PRINTER_CA`)
+static PRINTER_CA: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/printer_ca.pem"));
+
+/// Housekeeping interval of the connection thread
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Printer credentials needed for a LAN connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanPrinter {
+    pub serial: String,
+    pub name: String,
+    pub ip: String,
+    pub access_code: String,
+}
+
+/// Fields of a `device/<serial>/report` push. Printers send partial
+/// updates, so anything missing from a push is `None` and left unchanged.
+#[derive(Debug, Default)]
+pub struct LanReport {
+    pub gcode_state: Option<String>,
+    pub print_progress: Option<u8>,
+    pub remaining_time_min: Option<u16>,
+    pub subtask_name: Option<String>,
+    pub stg_cur: Option<i8>,
+    pub tray_now: Option<i32>,
+    pub ams_units: Option<Vec<LanAmsUnit>>,
+}
+
+/// AMS unit from a report
+#[derive(Debug)]
+pub struct LanAmsUnit {
+    pub id: i32,
+    pub humidity: Option<i32>,
+    pub temperature: Option<f32>,
+    pub trays: Vec<LanAmsTray>,
+}
+
+/// AMS tray from a report (empty slots only carry their id)
+#[derive(Debug)]
+pub struct LanAmsTray {
+    pub id: usize,
+    pub tray_type: String,
+    pub tray_color: String,
+    pub remain: i32,
+}
+
+/// Event forwarded from the MQTT client tasks to the connection thread
+enum LanEvent {
+    Connected(String),
+    Disconnected(String),
+    Report(String, Vec<u8>),
+}
+
+/// Open connection to one printer
+struct Connection {
+    serial: String,
+    client: EspMqttClient<'static>,
+    connected: bool,
+}
+
+struct LanState {
+    printers: Vec<LanPrinter>,
+    connections: Vec<Connection>,
+    sequence: u32,
+}
+
+static NVS_PARTITION: Mutex<Option<EspDefaultNvsPartition>> = Mutex::new(None);
+static STATE: Mutex<LanState> = Mutex::new(LanState {
+    printers: Vec::new(),
+    connections: Vec::new(),
+    sequence: 0,
+});
+
+// =============================================================================
+// Report parsing
+// =============================================================================
+
+/// Integer that printers send either as a number or as a string
+fn as_int(value: &Value) -> Option<i64> {
+    value.as_i64().or_else(|| value.as_str()?.trim().parse().ok())
+}
+
+/// Float that printers send either as a number or as a string
+fn as_float(value: &Value) -> Option<f32> {
+    value
+        .as_f64()
+        .map(|v| v as f32)
+        .or_else(|| value.as_str()?.trim().parse().ok())
+}
+
+impl LanReport {
+    /// Parse a report payload; returns None for non-print messages
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let root: Value = serde_json::from_slice(data).ok()?;
+        let print = root.get("print")?;
+
+        let mut report = LanReport {
+            gcode_state: print.get("gcode_state").and_then(Value::as_str).map(str::to_string),
+            print_progress: print.get("mc_percent").and_then(as_int).map(|v| v.clamp(0, 100) as u8),
+            remaining_time_min: print
+                .get("mc_remaining_time")
+                .and_then(as_int)
+                .map(|v| v.clamp(0, u16::MAX as i64) as u16),
+            subtask_name: print.get("subtask_name").and_then(Value::as_str).map(str::to_string),
+            stg_cur: print.get("stg_cur").and_then(as_int).map(|v| v.clamp(-1, i8::MAX as i64) as i8),
+            ..Default::default()
+        };
+
+        if let Some(ams) = print.get("ams") {
+            report.tray_now = ams.get("tray_now").and_then(as_int).map(|v| v as i32);
+            if let Some(units) = ams.get("ams").and_then(Value::as_array) {
+                report.ams_units = Some(units.iter().map(LanAmsUnit::parse).collect());
+            }
+        }
+        Some(report)
+    }
+}
+
+impl LanAmsUnit {
+    fn parse(unit: &Value) -> Self {
+        // Prefer the actual percentage over the 1-5 humidity index
+        let humidity = unit
+            .get("humidity_raw")
+            .and_then(as_int)
+            .or_else(|| unit.get("humidity").and_then(as_int))
+            .map(|v| v as i32);
+        let trays = unit
+            .get("tray")
+            .and_then(Value::as_array)
+            .map(|trays| {
+                trays
+                    .iter()
+                    .map(|tray| LanAmsTray {
+                        id: tray.get("id").and_then(as_int).unwrap_or(0).max(0) as usize,
+                        tray_type: tray.get("tray_type").and_then(Value::as_str).unwrap_or("").to_string(),
+                        tray_color: tray.get("tray_color").and_then(Value::as_str).unwrap_or("").to_string(),
+                        remain: tray.get("remain").and_then(as_int).unwrap_or(0) as i32,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        LanAmsUnit {
+            id: unit.get("id").and_then(as_int).unwrap_or(0) as i32,
+            humidity,
+            temperature: unit.get("temp").and_then(as_float),
+            trays,
+        }
+    }
+}
+
+// =============================================================================
+// Settings and remembered printers
+// =============================================================================
+
+/// Load the remembered printers from NVS (call once at startup)
+pub fn init_nvs(nvs: Option<EspDefaultNvsPartition>) {
+    let mut state = STATE.lock().unwrap();
+    if let Some(nvs) = nvs.as_ref().and_then(|p| EspNvs::new(p.clone(), NVS_NAMESPACE, true).ok()) {
+        let count = nvs.get_u8(NVS_KEY_COUNT).ok().flatten().unwrap_or(0) as usize;
+        for i in 0..count.min(MAX_PRINTERS) {
+            let mut buf = [0u8; 64];
+            let mut read = |key: &str| {
+                nvs.get_str(&format!("{}{}", key, i), &mut buf)
+                    .ok()
+                    .flatten()
+                    .map(str::to_string)
+                    .unwrap_or_default()
+            };
+            let printer = LanPrinter {
+                serial: read("sn"),
+                name: read("nm"),
+                ip: read("ip"),
+                access_code: read("ac"),
+            };
+            if !printer.serial.is_empty() {
+                state.printers.push(printer);
+            }
+        }
+        info!("LAN mode {} ({} remembered printers)",
+              if is_enabled() { "enabled" } else { "disabled" }, state.printers.len());
+    }
+    *NVS_PARTITION.lock().unwrap() = nvs;
+}
+
+fn open_nvs() -> Result<EspNvs<esp_idf_svc::nvs::NvsDefault>, String> {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let nvs_partition = nvs_guard.as_ref().ok_or("No NVS partition available")?;
+    EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true)
+        .map_err(|e| format!("Failed to open NVS: {:?}", e))
+}
+
+fn save_printers(printers: &[LanPrinter]) -> Result<(), String> {
+    let nvs = open_nvs()?;
+    for (i, printer) in printers.iter().enumerate() {
+        for (key, value) in [
+            ("sn", &printer.serial),
+            ("nm", &printer.name),
+            ("ip", &printer.ip),
+            ("ac", &printer.access_code),
+        ] {
+            nvs.set_str(&format!("{}{}", key, i), value)
+                .map_err(|e| format!("Failed to save printer: {:?}", e))?;
+        }
+    }
+    nvs.set_u8(NVS_KEY_COUNT, printers.len() as u8)
+        .map_err(|e| format!("Failed to save printer count: {:?}", e))?;
+    Ok(())
+}
+
+/// Remember the printers (with IP and access code) the backend reported,
+/// so LAN mode also works after a reboot while the server is down
+pub fn remember_printers(printers: Vec<LanPrinter>) {
+    let printers: Vec<LanPrinter> = printers.into_iter().take(MAX_PRINTERS).collect();
+    let mut state = STATE.lock().unwrap();
+    if state.printers == printers {
+        return;
+    }
+    state.printers = printers;
+    if let Err(e) = save_printers(&state.printers) {
+        warn!("{}", e);
+    } else {
+        info!("Remembered {} printers for LAN mode", state.printers.len());
+    }
+}
+
+/// Whether LAN mode is enabled
+pub fn is_enabled() -> bool {
+    crate::settings::read(|s| s.lan_mode)
+}
+
+/// Enable or disable LAN mode (persisted)
+pub fn set_enabled(enabled: bool) -> Result<(), String> {
+    crate::settings::update(|s| s.lan_mode = enabled)?;
+    info!("LAN mode {}", if enabled { "enabled" } else { "disabled" });
+    Ok(())
+}
+
+// =============================================================================
+// Connection thread
+// =============================================================================
+
+/// Start the LAN MQTT connection thread
+pub fn start() {
+    if PRINTER_CA.is_empty() {
+        warn!("Built without the printer CA certificate - LAN MQTT disabled");
+        return;
+    }
+    let (tx, rx) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("printer_mqtt".into())
+        .stack_size(8192)
+        .spawn(move || run(tx, rx));
+    if let Err(e) = spawned {
+        warn!("Failed to start LAN MQTT thread: {:?}", e);
+    }
+}
+
+fn run(tx: Sender<LanEvent>, rx: Receiver<LanEvent>) {
+    let mut last_update = Instant::now();
+    loop {
+        match rx.recv_timeout(POLL_INTERVAL) {
+            Ok(event) => handle_event(event),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        if last_update.elapsed() >= POLL_INTERVAL {
+            update_connections(&tx);
+            crate::health::record_stack("printer_mqtt");
+            last_update = Instant::now();
+        }
+    }
+}
+
+/// Open or close printer connections to match the current conditions
+fn update_connections(tx: &Sender<LanEvent>) {
+    let wanted = is_enabled()
+        && crate::wifi_manager::is_connected()
+        && crate::backend_client::backend_unreachable();
+    let mut state = STATE.lock().unwrap();
+
+    if !wanted {
+        if state.connections.is_empty() {
+            return;
+        }
+        info!("Closing LAN MQTT connections");
+        let closed: Vec<String> = state.connections.drain(..).map(|c| c.serial).collect();
+        drop(state);
+        for serial in closed {
+            crate::backend_client::set_lan_printer_offline(&serial);
+        }
+        return;
+    }
+
+    let LanState { printers, connections, .. } = &mut *state;
+    connections.retain(|c| printers.iter().any(|p| p.serial == c.serial));
+    for printer in printers.iter() {
+        if printer.ip.is_empty() || connections.iter().any(|c| c.serial == printer.serial) {
+            continue;
+        }
+        match connect(printer, tx.clone()) {
+            Ok(client) => {
+                info!("LAN MQTT: connecting to {} at {}", printer.serial, printer.ip);
+                connections.push(Connection {
+                    serial: printer.serial.clone(),
+                    client,
+                    connected: false,
+                });
+            }
+            Err(e) => warn!("LAN MQTT: {} ({})", e, printer.serial),
+        }
+    }
+}
+
+/// Create the MQTT client for one printer; events go to the connection thread
+fn connect(printer: &LanPrinter, tx: Sender<LanEvent>) -> Result<EspMqttClient<'static>, String> {
+    let url = format!("mqtts://{}:{}", printer.ip, MQTT_PORT);
+    let client_id = format!("spoolbuddy-{}", printer.serial);
+    let config = MqttClientConfiguration {
+        client_id: Some(&client_id),
+        username: Some(MQTT_USER),
+        password: Some(&printer.access_code),
+        // Verified against the printer CA; the certificate is issued to the
+        // serial number rather than the IP address we connect to
+        server_certificate: Some(X509::pem_until_nul(PRINTER_CA)),
+        skip_cert_common_name_check: true,
+        buffer_size: MQTT_BUFFER_SIZE,
+        keep_alive_interval: Some(Duration::from_secs(30)),
+        reconnect_timeout: Some(Duration::from_secs(10)),
+        network_timeout: Duration::from_secs(10),
+        ..Default::default()
+    };
+
+    let serial = printer.serial.clone();
+    let mut chunks: Vec<u8> = Vec::new();
+    EspMqttClient::new_cb(&url, &config, move |event| match event.payload() {
+        EventPayload::Connected(_) => {
+            let _ = tx.send(LanEvent::Connected(serial.clone()));
+        }
+        EventPayload::Disconnected => {
+            let _ = tx.send(LanEvent::Disconnected(serial.clone()));
+        }
+        EventPayload::Received { data, details, .. } => match details {
+            Details::Complete => {
+                let _ = tx.send(LanEvent::Report(serial.clone(), data.to_vec()));
+            }
+            Details::InitialChunk(chunk) => {
+                chunks.clear();
+                if chunk.total_data_size <= MAX_REPORT_SIZE {
+                    chunks.reserve(chunk.total_data_size);
+                    chunks.extend_from_slice(data);
+                } else {
+                    warn!("LAN MQTT: dropping {} byte report from {}", chunk.total_data_size, serial);
+                }
+            }
+            Details::SubsequentChunk(chunk) => {
+                if chunks.is_empty() {
+                    return;
+                }
+                chunks.extend_from_slice(data);
+                if chunks.len() >= chunk.total_data_size {
+                    let _ = tx.send(LanEvent::Report(serial.clone(), std::mem::take(&mut chunks)));
+                }
+            }
+        },
+        EventPayload::Error(e) => debug!("LAN MQTT {}: {:?}", serial, e),
+        _ => {}
+    })
+    .map_err(|e| format!("Failed to create MQTT client: {:?}", e))
+}
+
+fn handle_event(event: LanEvent) {
+    match event {
+        LanEvent::Connected(serial) => {
+            let mut state = STATE.lock().unwrap();
+            state.sequence = state.sequence.wrapping_add(1);
+            let sequence = state.sequence;
+            let Some(conn) = state.connections.iter_mut().find(|c| c.serial == serial) else {
+                return;
+            };
+            conn.connected = true;
+            info!("LAN MQTT: connected to {}", serial);
+
+            if let Err(e) = conn.client.subscribe(&format!("device/{}/report", serial), QoS::AtMostOnce) {
+                warn!("LAN MQTT: subscribe failed for {}: {:?}", serial, e);
+            }
+            // Ask for a full status push; P1/A1 printers otherwise only send deltas
+            let pushall = json!({"pushing": {"command": "pushall", "sequence_id": sequence.to_string()}});
+            if let Err(e) = publish(&mut conn.client, &serial, &pushall) {
+                warn!("LAN MQTT: pushall failed for {}: {}", serial, e);
+            }
+        }
+        LanEvent::Disconnected(serial) => {
+            let mut state = STATE.lock().unwrap();
+            let Some(conn) = state.connections.iter_mut().find(|c| c.serial == serial) else {
+                return;
+            };
+            conn.connected = false;
+            drop(state);
+            info!("LAN MQTT: disconnected from {}", serial);
+            crate::backend_client::set_lan_printer_offline(&serial);
+        }
+        LanEvent::Report(serial, data) => {
+            let Some(report) = LanReport::parse(&data) else {
+                return;
+            };
+            let printer = STATE.lock().unwrap().printers.iter().find(|p| p.serial == serial).cloned();
+            if let Some(printer) = printer {
+                crate::backend_client::apply_lan_report(&printer, &report);
+            }
+        }
+    }
+}
+
+fn publish(client: &mut EspMqttClient<'static>, serial: &str, payload: &Value) -> Result<(), String> {
+    client
+        .enqueue(&format!("device/{}/request", serial), QoS::AtMostOnce, false, payload.to_string().as_bytes())
+        .map(|_| ())
+        .map_err(|e| format!("{:?}", e))
+}
+
+// =============================================================================
+// Commands
+// =============================================================================
+
+/// Whether a LAN connection to this printer is currently up
+pub fn is_active(serial: &str) -> bool {
+    STATE
+        .lock()
+        .unwrap()
+        .connections
+        .iter()
+        .any(|c| c.connected && c.serial == serial)
+}
+
+/// Filament settings for one AMS slot (fields of `ams_filament_setting`)
+pub struct SlotFilament<'a> {
+    pub tray_info_idx: &'a str,
+    pub setting_id: &'a str,
+    pub tray_type: &'a str,
+    pub tray_sub_brands: &'a str,
+    pub tray_color: &'a str,
+    pub nozzle_temp_min: i32,
+    pub nozzle_temp_max: i32,
+}
+
+/// Send an `ams_filament_setting` command straight to the printer
+pub fn set_slot_filament(serial: &str, ams_id: i32, tray_id: i32, filament: &SlotFilament) -> bool {
+    let mut state = STATE.lock().unwrap();
+    state.sequence = state.sequence.wrapping_add(1);
+    let sequence = state.sequence;
+    let Some(conn) = state.connections.iter_mut().find(|c| c.connected && c.serial == serial) else {
+        warn!("LAN MQTT: no connection to {}", serial);
+        return false;
+    };
+
+    // AMS-HT (128+) and external spools (254/255) have a single slot
+    let slot_id = if ams_id <= 3 { tray_id } else { 0 };
+    let mut command = json!({"print": {
+        "command": "ams_filament_setting",
+        "ams_id": ams_id,
+        "tray_id": tray_id,
+        "slot_id": slot_id,
+        "tray_info_idx": filament.tray_info_idx,
+        "tray_type": filament.tray_type,
+        "tray_sub_brands": filament.tray_sub_brands,
+        "tray_color": filament.tray_color,
+        "nozzle_temp_min": filament.nozzle_temp_min,
+        "nozzle_temp_max": filament.nozzle_temp_max,
+        "sequence_id": sequence.to_string(),
+    }});
+    if !filament.setting_id.is_empty() {
+        command["print"]["setting_id"] = Value::from(filament.setting_id);
+    }
+
+    match publish(&mut conn.client, serial, &command) {
+        Ok(()) => {
+            info!("LAN MQTT: set filament on {} AMS {} tray {}", serial, ams_id, tray_id);
+            true
+        }
+        Err(e) => {
+            warn!("LAN MQTT: ams_filament_setting failed for {}: {}", serial, e);
+            false
+        }
+    }
+}
+
+// =============================================================================
+// C-callable interface
+// =============================================================================
+
+/// Check if LAN mode is enabled
+/// Returns 1 if enabled, 0 otherwise
+#[no_mangle]
+pub extern "C" fn printer_lan_get_enabled() -> c_int {
+    is_enabled() as c_int
+}
+
+/// Enable (1) or disable (0) LAN mode
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn printer_lan_set_enabled(enabled: c_int) -> c_int {
+    match set_enabled(enabled != 0) {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Number of printers currently connected over LAN MQTT
+#[no_mangle]
+pub extern "C" fn printer_lan_active_count() -> c_int {
+    STATE.lock().unwrap().connections.iter().filter(|c| c.connected).count() as c_int
+}
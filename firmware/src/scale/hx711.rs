@@ -18,9 +18,14 @@
 //! - SCK high time: min 0.2µs, typ 1µs
 //! - SCK low time: min 0.2µs, typ 1µs
 //! - SCK high >60µs puts chip in power down mode
+//!
+//! Many existing SpoolBuddy builds use HX711 boards; wire DOUT to GPIO4 and
+//! PD_SCK to GPIO5 on the J9 header (free since NFC moved to the Pico bridge).
 
 use embedded_hal::digital::{InputPin, OutputPin};
-use embassy_time::{Duration, Instant, Timer};
+use std::time::{Duration, Instant};
+
+use super::load_cell::{LoadCell, LoadCellError};
 
 /// HX711 gain/channel selection
 #[derive(Debug, Clone, Copy, Default)]
@@ -76,7 +81,7 @@ where
     }
 
     /// Wait for data to be ready with timeout.
-    pub async fn wait_ready(&mut self, timeout_ms: u32) -> Result<(), Hx711Error> {
+    pub fn wait_ready(&mut self, timeout_ms: u32) -> Result<(), Hx711Error> {
         let start = Instant::now();
         let timeout = Duration::from_millis(timeout_ms as u64);
 
@@ -84,7 +89,7 @@ where
             if start.elapsed() > timeout {
                 return Err(Hx711Error::Timeout);
             }
-            Timer::after(Duration::from_micros(100)).await;
+            std::thread::sleep(Duration::from_millis(1));
         }
 
         Ok(())
@@ -93,9 +98,9 @@ where
     /// Read a single raw value from the HX711.
     ///
     /// Returns a 24-bit signed value (in i32).
-    pub async fn read(&mut self) -> Result<i32, Hx711Error> {
+    pub fn read(&mut self) -> Result<i32, Hx711Error> {
         // Wait for data ready
-        self.wait_ready(1000).await?;
+        self.wait_ready(1000)?;
 
         let mut value: u32 = 0;
 
@@ -139,14 +144,14 @@ where
     }
 
     /// Read averaged value (multiple samples).
-    pub async fn read_average(&mut self, samples: usize) -> Result<i32, Hx711Error> {
+    pub fn read_average(&mut self, samples: usize) -> Result<i32, Hx711Error> {
         if samples == 0 {
             return Err(Hx711Error::InvalidParameter);
         }
 
         let mut sum: i64 = 0;
         for _ in 0..samples {
-            sum += self.read().await? as i64;
+            sum += self.read()? as i64;
         }
 
         Ok((sum / samples as i64) as i32)
@@ -167,9 +172,45 @@ where
     }
 }
 
+impl<DOUT, SCK> LoadCell for Hx711<DOUT, SCK>
+where
+    DOUT: InputPin,
+    SCK: OutputPin,
+{
+    fn name(&self) -> &'static str {
+        "HX711"
+    }
+
+    fn ready(&mut self) -> Result<bool, LoadCellError> {
+        Ok(self.is_ready())
+    }
+
+    fn read_raw(&mut self) -> Result<i32, LoadCellError> {
+        self.read().map_err(Into::into)
+    }
+
+    fn set_power(&mut self, on: bool) -> Result<(), LoadCellError> {
+        if on {
+            self.power_up();
+        } else {
+            self.power_down();
+        }
+        Ok(())
+    }
+}
+
 /// HX711 errors
 #[derive(Debug, Clone, Copy)]
 pub enum Hx711Error {
     Timeout,
     InvalidParameter,
 }
+
+impl From<Hx711Error> for LoadCellError {
+    fn from(e: Hx711Error) -> Self {
+        match e {
+            Hx711Error::Timeout => LoadCellError::Timeout,
+            Hx711Error::InvalidParameter => LoadCellError::BusError,
+        }
+    }
+}
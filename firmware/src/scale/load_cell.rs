@@ -0,0 +1,269 @@
+//! Load cell abstraction shared by all ADC front-ends.
+//!
+//! A `LoadCell` only knows how to produce raw 24-bit conversions. Everything
+//! above that - calibration, filtering, stability detection, tare and
+//! known-weight calibration - lives here and works with any implementation.
+
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+
+/// Raw ADC access implemented by each load cell amplifier
+pub trait LoadCell {
+    /// Short chip name for logs
+    fn name(&self) -> &'static str;
+
+    /// Whether a new conversion is available
+    fn ready(&mut self) -> Result<bool, LoadCellError>;
+
+    /// Read the latest conversion (24-bit, sign-extended)
+    fn read_raw(&mut self) -> Result<i32, LoadCellError>;
+
+    /// Power the ADC up or down
+    fn set_power(&mut self, on: bool) -> Result<(), LoadCellError>;
+}
+
+/// Load cell error types
+#[derive(Debug)]
+pub enum LoadCellError {
+    BusError,
+    NotInitialized,
+    Timeout,
+    CalibrationFailed,
+}
+
+/// Scale calibration data
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Zero offset (tare)
+    pub zero_offset: i32,
+    /// Calibration factor (raw units per gram)
+    pub cal_factor: f32,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            zero_offset: 0,
+            // Default calibration factor - needs actual calibration
+            cal_factor: 1000.0,
+        }
+    }
+}
+
+/// Scale state (calibration, filtered weight, stability)
+#[derive(Clone)]
+pub struct ScaleState {
+    /// Calibration data
+    pub calibration: Calibration,
+    /// Whether the scale has been initialized
+    pub initialized: bool,
+    /// Last raw reading
+    pub last_raw: i32,
+    /// Filtered weight in grams
+    pub weight_grams: f32,
+    /// Filter alpha (0-1, higher = less filtering)
+    pub filter_alpha: f32,
+    /// Weight stability flag
+    pub stable: bool,
+    /// Consecutive stable readings counter
+    pub stable_count: u8,
+}
+
+impl ScaleState {
+    /// Create a new scale state
+    pub fn new() -> Self {
+        Self {
+            calibration: Calibration::default(),
+            initialized: false,
+            last_raw: 0,
+            weight_grams: 0.0,
+            filter_alpha: 0.25, // Moderate filtering - balance between smoothness and response
+            stable: false,
+            stable_count: 0,
+        }
+    }
+}
+
+impl Default for ScaleState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read weight in grams (with filtering and stability detection)
+pub fn read_weight<C: LoadCell + ?Sized>(cell: &mut C, state: &mut ScaleState) -> Result<f32, LoadCellError> {
+    if !state.initialized {
+        return Err(LoadCellError::NotInitialized);
+    }
+
+    // Check if data is ready
+    if !cell.ready()? {
+        return Ok(state.weight_grams); // Return last value
+    }
+
+    let raw = cell.read_raw()?;
+    state.last_raw = raw;
+
+    // Convert to grams using calibration
+    let weight = (raw - state.calibration.zero_offset) as f32 / state.calibration.cal_factor;
+
+    // Store previous weight for stability check
+    let prev_weight = state.weight_grams;
+
+    // Quick settle: if weight changed significantly (>50g), jump closer to new value
+    let weight_change = (weight - state.weight_grams).abs();
+    if weight_change > 50.0 {
+        // Large change detected - use stronger alpha for faster response
+        state.weight_grams = state.weight_grams * 0.3 + weight * 0.7;
+    } else {
+        // Normal filtering
+        state.weight_grams = state.weight_grams * (1.0 - state.filter_alpha) + weight * state.filter_alpha;
+    }
+
+    // Check stability (within 10g of previous reading)
+    // Increased threshold due to noisy hardware
+    let diff = (state.weight_grams - prev_weight).abs();
+    if diff < 10.0 {
+        state.stable_count = state.stable_count.saturating_add(1);
+        if state.stable_count >= 10 {
+            state.stable = true;
+        }
+    } else {
+        state.stable_count = 0;
+        state.stable = false;
+    }
+
+    Ok(state.weight_grams)
+}
+
+/// Tare the scale (set current weight as zero)
+pub fn tare<C: LoadCell + ?Sized>(cell: &mut C, state: &mut ScaleState) -> Result<(), LoadCellError> {
+    info!("=== SCALE TARE START ({}) ===", cell.name());
+    info!("  Current zero_offset: {}", state.calibration.zero_offset);
+    info!("  Current cal_factor: {}", state.calibration.cal_factor);
+
+    let (new_zero_offset, count) = sample_trimmed_mean(cell, state)?;
+    info!("  NEW zero_offset: {} (from {} middle samples)", new_zero_offset, count);
+
+    state.calibration.zero_offset = new_zero_offset;
+
+    // Reset filtered state
+    state.weight_grams = 0.0;
+    state.stable = false;
+    state.stable_count = 0;
+
+    info!("=== TARE COMPLETE ===");
+    info!("  Final zero_offset: {}", state.calibration.zero_offset);
+    info!("  Final cal_factor: {}", state.calibration.cal_factor);
+    Ok(())
+}
+
+/// Calibrate with a known weight
+pub fn calibrate<C: LoadCell + ?Sized>(
+    cell: &mut C,
+    state: &mut ScaleState,
+    known_weight_grams: f32,
+) -> Result<(), LoadCellError> {
+    info!("=== SCALE CALIBRATION START ({}) ===", cell.name());
+    info!("  Known weight: {} grams", known_weight_grams);
+    info!("  Current zero_offset: {}", state.calibration.zero_offset);
+    info!("  Current cal_factor: {}", state.calibration.cal_factor);
+
+    let (avg_raw, count) = sample_trimmed_mean(cell, state)?;
+    info!("  Average raw value (trimmed): {} (from {} middle samples)", avg_raw, count);
+
+    let delta = avg_raw - state.calibration.zero_offset;
+    info!("  Delta from zero: {} (avg_raw {} - zero_offset {})",
+          delta, avg_raw, state.calibration.zero_offset);
+
+    // Delta must be positive and significant
+    // A 797g weight should produce ~195,000 units of delta with proper calibration
+    // Require at least 10,000 to ensure meaningful signal
+    if delta < 10000 {
+        if delta < 0 {
+            warn!("  Calibration FAILED: negative delta ({}) - weight decreased readings!", delta);
+            warn!("  This usually means: load cell wiring issue, defective load cell, or not mounted correctly");
+        } else {
+            warn!("  Calibration FAILED: delta too small ({}) - no significant weight detected", delta);
+        }
+        return Err(LoadCellError::CalibrationFailed);
+    }
+
+    let new_cal_factor = delta as f32 / known_weight_grams;
+    info!("  NEW cal_factor: {} = {} / {}", new_cal_factor, delta, known_weight_grams);
+
+    // Sanity check cal_factor - should be reasonable (50-500 for typical 5kg load cell)
+    if new_cal_factor < 10.0 || new_cal_factor > 2000.0 {
+        warn!("  Calibration FAILED: cal_factor {} is out of reasonable range (10-2000)", new_cal_factor);
+        return Err(LoadCellError::CalibrationFailed);
+    }
+
+    state.calibration.cal_factor = new_cal_factor;
+
+    // Reset filtered state
+    state.weight_grams = known_weight_grams;
+    state.stable = false;
+    state.stable_count = 0;
+
+    info!("=== CALIBRATION COMPLETE ===");
+    info!("  Final zero_offset: {}", state.calibration.zero_offset);
+    info!("  Final cal_factor: {}", state.calibration.cal_factor);
+    info!("  Expected weight with current raw: {} grams",
+          (avg_raw - state.calibration.zero_offset) as f32 / state.calibration.cal_factor);
+    Ok(())
+}
+
+// --- Private helpers ---
+
+/// Longest wait for one sample during tare/calibration (10 SPS needs 100 ms)
+const SAMPLE_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Take 30 samples after a settle delay and return the trimmed mean
+/// (discarding the 5 highest and lowest) and the number of samples used
+fn sample_trimmed_mean<C: LoadCell + ?Sized>(
+    cell: &mut C,
+    state: &mut ScaleState,
+) -> Result<(i32, i64), LoadCellError> {
+    // Wait for scale to settle before sampling
+    info!("  Waiting for scale to settle (1 second)...");
+    std::thread::sleep(std::time::Duration::from_millis(1000));
+
+    // Take samples for averaging (reduced to avoid watchdog)
+    const SAMPLES: usize = 30;
+    let mut readings = [0i32; SAMPLES];
+
+    for reading in readings.iter_mut() {
+        // Wait for data ready; a disconnected HX711 (DOUT floating high) or a
+        // dead NAU7802 never gets there
+        let deadline = std::time::Instant::now() + SAMPLE_TIMEOUT;
+        while !cell.ready()? {
+            if std::time::Instant::now() >= deadline {
+                warn!("  No sample within {} ms", SAMPLE_TIMEOUT.as_millis());
+                return Err(LoadCellError::Timeout);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        *reading = cell.read_raw()?;
+        state.last_raw = *reading;
+    }
+
+    let min = *readings.iter().min().unwrap();
+    let max = *readings.iter().max().unwrap();
+    info!("  Raw readings: min={}, max={}, range={}", min, max, max - min);
+
+    // Sanity check: range shouldn't be too extreme
+    if max - min > 100000 {
+        warn!("  Warning: readings are very noisy (range={}), result may be inaccurate", max - min);
+    }
+
+    // Sort readings for trimmed mean (discard highest and lowest 5 values)
+    readings.sort();
+    let trim = 5;
+    let trimmed = &readings[trim..SAMPLES - trim];
+
+    // Calculate average of trimmed values
+    let sum: i64 = trimmed.iter().map(|&x| x as i64).sum();
+    let count = trimmed.len() as i64;
+
+    Ok(((sum / count) as i32, count))
+}
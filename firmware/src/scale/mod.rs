@@ -2,6 +2,11 @@
 //!
 //! Supports:
 //! - NAU7802 (SparkFun Qwiic Scale) - I2C interface, recommended
+//! - HX711 - two-wire GPIO interface, for existing HX711 builds
+//!
+//! Both implement the `LoadCell` trait; calibration, filtering and
+//! stability detection are shared in `load_cell`. Several NAU7802 boards
+//! can sit behind a TCA9548A I2C mux (`tca9548a`), one per channel.
 //!
 //! The NAU7802 is a 24-bit ADC with I2C interface at address 0x2A.
 //!
@@ -14,4 +19,7 @@
 #![allow(dead_code)]
 #![allow(unused)]
 
+pub mod hx711;
+pub mod load_cell;
 pub mod nau7802;
+pub mod tca9548a;
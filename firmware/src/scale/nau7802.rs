@@ -31,6 +31,8 @@
 use esp_idf_hal::i2c::I2cDriver;
 use log::{info, warn};
 
+use super::load_cell::{LoadCell, LoadCellError, ScaleState};
+
 /// NAU7802 I2C address
 pub const NAU7802_ADDR: u8 = 0x2A;
 
@@ -109,66 +111,40 @@ pub enum LdoVoltage {
     V4_5 = 0b000,
 }
 
-/// Scale calibration data
-#[derive(Debug, Clone, Copy)]
-pub struct Calibration {
-    /// Zero offset (tare)
-    pub zero_offset: i32,
-    /// Calibration factor (raw units per gram)
-    pub cal_factor: f32,
+/// NAU7802 on the shared I2C bus, borrowed for the duration of one operation
+pub struct Nau7802<'a, 'd> {
+    i2c: &'a mut I2cDriver<'d>,
 }
 
-impl Default for Calibration {
-    fn default() -> Self {
-        Self {
-            zero_offset: 0,
-            // Default calibration factor - needs actual calibration
-            cal_factor: 1000.0,
-        }
+impl<'a, 'd> Nau7802<'a, 'd> {
+    pub fn new(i2c: &'a mut I2cDriver<'d>) -> Self {
+        Self { i2c }
     }
 }
 
-/// NAU7802 Scale driver state
-pub struct Nau7802State {
-    /// Calibration data
-    pub calibration: Calibration,
-    /// Whether the scale has been initialized
-    pub initialized: bool,
-    /// Last raw reading
-    pub last_raw: i32,
-    /// Filtered weight in grams
-    pub weight_grams: f32,
-    /// Filter alpha (0-1, higher = less filtering)
-    pub filter_alpha: f32,
-    /// Weight stability flag
-    pub stable: bool,
-    /// Consecutive stable readings counter
-    pub stable_count: u8,
-}
+impl LoadCell for Nau7802<'_, '_> {
+    fn name(&self) -> &'static str {
+        "NAU7802"
+    }
 
-impl Nau7802State {
-    /// Create a new scale state
-    pub fn new() -> Self {
-        Self {
-            calibration: Calibration::default(),
-            initialized: false,
-            last_raw: 0,
-            weight_grams: 0.0,
-            filter_alpha: 0.25, // Moderate filtering - balance between smoothness and response
-            stable: false,
-            stable_count: 0,
-        }
+    fn ready(&mut self) -> Result<bool, LoadCellError> {
+        data_ready(self.i2c).map_err(Into::into)
+    }
+
+    fn read_raw(&mut self) -> Result<i32, LoadCellError> {
+        read_raw(self.i2c).map_err(Into::into)
     }
-}
 
-impl Default for Nau7802State {
-    fn default() -> Self {
-        Self::new()
+    fn set_power(&mut self, on: bool) -> Result<(), LoadCellError> {
+        let pu = read_reg(self.i2c, reg::PU_CTRL)?;
+        let bits = pu_ctrl::PUD | pu_ctrl::PUA;
+        let new_pu = if on { pu | bits } else { pu & !bits };
+        write_reg(self.i2c, reg::PU_CTRL, new_pu).map_err(Into::into)
     }
 }
 
 /// Initialize the NAU7802
-pub fn init(i2c: &mut I2cDriver<'_>, state: &mut Nau7802State) -> Result<(), Nau7802Error> {
+pub fn init(i2c: &mut I2cDriver<'_>, state: &mut ScaleState) -> Result<(), Nau7802Error> {
     info!("Initializing NAU7802 scale at 0x{:02X}", NAU7802_ADDR);
 
     // Check if device is present
@@ -250,7 +226,7 @@ pub fn data_ready(i2c: &mut I2cDriver<'_>) -> Result<bool, Nau7802Error> {
 }
 
 /// Read raw ADC value (24-bit signed)
-pub fn read_raw(i2c: &mut I2cDriver<'_>, state: &mut Nau7802State) -> Result<i32, Nau7802Error> {
+pub fn read_raw(i2c: &mut I2cDriver<'_>) -> Result<i32, Nau7802Error> {
     // Read 3 bytes of ADC data
     let b2 = read_reg(i2c, reg::ADCO_B2)? as i32;
     let b1 = read_reg(i2c, reg::ADCO_B1)? as i32;
@@ -264,193 +240,9 @@ pub fn read_raw(i2c: &mut I2cDriver<'_>, state: &mut Nau7802State) -> Result<i32
         raw |= 0xFF000000u32 as i32;
     }
 
-    state.last_raw = raw;
     Ok(raw)
 }
 
-/// Read weight in grams (with filtering and stability detection)
-pub fn read_weight(i2c: &mut I2cDriver<'_>, state: &mut Nau7802State) -> Result<f32, Nau7802Error> {
-    if !state.initialized {
-        return Err(Nau7802Error::NotInitialized);
-    }
-
-    // Check if data is ready
-    if !data_ready(i2c)? {
-        return Ok(state.weight_grams); // Return last value
-    }
-
-    let raw = read_raw(i2c, state)?;
-
-    // Convert to grams using calibration
-    let weight = (raw - state.calibration.zero_offset) as f32 / state.calibration.cal_factor;
-
-    // Store previous weight for stability check
-    let prev_weight = state.weight_grams;
-
-    // Quick settle: if weight changed significantly (>50g), jump closer to new value
-    let weight_change = (weight - state.weight_grams).abs();
-    if weight_change > 50.0 {
-        // Large change detected - use stronger alpha for faster response
-        state.weight_grams = state.weight_grams * 0.3 + weight * 0.7;
-    } else {
-        // Normal filtering
-        state.weight_grams = state.weight_grams * (1.0 - state.filter_alpha) + weight * state.filter_alpha;
-    }
-
-    // Check stability (within 10g of previous reading)
-    // Increased threshold due to noisy hardware
-    let diff = (state.weight_grams - prev_weight).abs();
-    if diff < 10.0 {
-        state.stable_count = state.stable_count.saturating_add(1);
-        if state.stable_count >= 10 {
-            state.stable = true;
-        }
-    } else {
-        state.stable_count = 0;
-        state.stable = false;
-    }
-
-    Ok(state.weight_grams)
-}
-
-/// Tare the scale (set current weight as zero)
-pub fn tare(i2c: &mut I2cDriver<'_>, state: &mut Nau7802State) -> Result<(), Nau7802Error> {
-    info!("=== SCALE TARE START ===");
-    info!("  Current zero_offset: {}", state.calibration.zero_offset);
-    info!("  Current cal_factor: {}", state.calibration.cal_factor);
-
-    // Wait for scale to settle before sampling
-    info!("  Waiting for scale to settle (1 second)...");
-    std::thread::sleep(std::time::Duration::from_millis(1000));
-
-    // Take samples for averaging (reduced to avoid watchdog)
-    let samples = 30;
-    let mut readings = [0i32; 30];
-
-    for i in 0..samples {
-        // Wait for data ready
-        while !data_ready(i2c)? {
-            std::thread::sleep(std::time::Duration::from_millis(10));
-        }
-        readings[i] = read_raw(i2c, state)?;
-    }
-
-    info!("  Raw readings: min={}, max={}, range={}",
-          readings.iter().min().unwrap(),
-          readings.iter().max().unwrap(),
-          readings.iter().max().unwrap() - readings.iter().min().unwrap());
-
-    // Sort readings for trimmed mean (discard highest and lowest 5 values)
-    readings.sort();
-    let trim = 5;
-    let trimmed = &readings[trim..samples - trim];
-
-    // Calculate average of trimmed values
-    let sum: i64 = trimmed.iter().map(|&x| x as i64).sum();
-    let count = trimmed.len() as i64;
-
-    let new_zero_offset = (sum / count) as i32;
-    info!("  NEW zero_offset: {} (from {} middle samples)", new_zero_offset, count);
-
-    // Sanity check: range shouldn't be too extreme
-    let range = readings.iter().max().unwrap() - readings.iter().min().unwrap();
-    if range > 100000 {
-        warn!("  Warning: readings are very noisy (range={}), tare may be inaccurate", range);
-    }
-
-    state.calibration.zero_offset = new_zero_offset;
-
-    // Reset filtered state
-    state.weight_grams = 0.0;
-    state.stable = false;
-    state.stable_count = 0;
-
-    info!("=== TARE COMPLETE ===");
-    info!("  Final zero_offset: {}", state.calibration.zero_offset);
-    info!("  Final cal_factor: {}", state.calibration.cal_factor);
-    Ok(())
-}
-
-/// Calibrate with a known weight
-pub fn calibrate(i2c: &mut I2cDriver<'_>, state: &mut Nau7802State, known_weight_grams: f32) -> Result<(), Nau7802Error> {
-    info!("=== SCALE CALIBRATION START ===");
-    info!("  Known weight: {} grams", known_weight_grams);
-    info!("  Current zero_offset: {}", state.calibration.zero_offset);
-    info!("  Current cal_factor: {}", state.calibration.cal_factor);
-
-    // Wait for scale to settle before sampling
-    info!("  Waiting for scale to settle (1 second)...");
-    std::thread::sleep(std::time::Duration::from_millis(1000));
-
-    // Take samples for averaging (reduced to avoid watchdog)
-    let samples = 30;
-    let mut readings = [0i32; 30];
-
-    for i in 0..samples {
-        while !data_ready(i2c)? {
-            std::thread::sleep(std::time::Duration::from_millis(10));
-        }
-        readings[i] = read_raw(i2c, state)?;
-    }
-
-    info!("  Raw readings: min={}, max={}, range={}",
-          readings.iter().min().unwrap(),
-          readings.iter().max().unwrap(),
-          readings.iter().max().unwrap() - readings.iter().min().unwrap());
-
-    // Sort readings for trimmed mean (discard highest and lowest 5 values)
-    readings.sort();
-    let trim = 5;
-    let trimmed = &readings[trim..samples - trim];
-
-    // Calculate average of trimmed values
-    let sum: i64 = trimmed.iter().map(|&x| x as i64).sum();
-    let count = trimmed.len() as i64;
-    let avg_raw = (sum / count) as i32;
-
-    info!("  Average raw value (trimmed): {} (from {} middle samples)", avg_raw, count);
-
-    let delta = avg_raw - state.calibration.zero_offset;
-    info!("  Delta from zero: {} (avg_raw {} - zero_offset {})",
-          delta, avg_raw, state.calibration.zero_offset);
-
-    // Delta must be positive and significant
-    // A 797g weight should produce ~195,000 units of delta with proper calibration
-    // Require at least 10,000 to ensure meaningful signal
-    if delta < 10000 {
-        if delta < 0 {
-            warn!("  Calibration FAILED: negative delta ({}) - weight decreased readings!", delta);
-            warn!("  This usually means: load cell wiring issue, defective load cell, or not mounted correctly");
-        } else {
-            warn!("  Calibration FAILED: delta too small ({}) - no significant weight detected", delta);
-        }
-        return Err(Nau7802Error::CalibrationFailed);
-    }
-
-    let new_cal_factor = delta as f32 / known_weight_grams;
-    info!("  NEW cal_factor: {} = {} / {}", new_cal_factor, delta, known_weight_grams);
-
-    // Sanity check cal_factor - should be reasonable (50-500 for typical 5kg load cell)
-    if new_cal_factor < 10.0 || new_cal_factor > 2000.0 {
-        warn!("  Calibration FAILED: cal_factor {} is out of reasonable range (10-2000)", new_cal_factor);
-        return Err(Nau7802Error::CalibrationFailed);
-    }
-
-    state.calibration.cal_factor = new_cal_factor;
-
-    // Reset filtered state
-    state.weight_grams = known_weight_grams;
-    state.stable = false;
-    state.stable_count = 0;
-
-    info!("=== CALIBRATION COMPLETE ===");
-    info!("  Final zero_offset: {}", state.calibration.zero_offset);
-    info!("  Final cal_factor: {}", state.calibration.cal_factor);
-    info!("  Expected weight with current raw: {} grams",
-          (avg_raw - state.calibration.zero_offset) as f32 / state.calibration.cal_factor);
-    Ok(())
-}
-
 // --- Private helpers ---
 
 fn read_reg(i2c: &mut I2cDriver<'_>, reg: u8) -> Result<u8, Nau7802Error> {
@@ -474,3 +266,14 @@ pub enum Nau7802Error {
     Timeout,
     CalibrationFailed,
 }
+
+impl From<Nau7802Error> for LoadCellError {
+    fn from(e: Nau7802Error) -> Self {
+        match e {
+            Nau7802Error::I2cError => LoadCellError::BusError,
+            Nau7802Error::NotInitialized => LoadCellError::NotInitialized,
+            Nau7802Error::Timeout => LoadCellError::Timeout,
+            Nau7802Error::CalibrationFailed => LoadCellError::CalibrationFailed,
+        }
+    }
+}
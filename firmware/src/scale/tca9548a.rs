@@ -0,0 +1,33 @@
+//! TCA9548A 8-channel I2C multiplexer
+//!
+//! Lets several NAU7802 boards (all fixed at 0x2A) share the bus, e.g. one
+//! per bay of a drying box. Only one channel is enabled at a time, so devices
+//! upstream of the mux (like the Pico NFC bridge) stay reachable.
+//!
+//! I2C Address: 0x70-0x77 (A0-A2 strapping)
+
+use esp_idf_hal::i2c::I2cDriver;
+
+/// Base I2C address (A0-A2 low)
+pub const TCA9548A_ADDR_BASE: u8 = 0x70;
+
+/// Highest I2C address (A0-A2 high)
+pub const TCA9548A_ADDR_LAST: u8 = 0x77;
+
+/// Number of downstream channels
+pub const CHANNEL_COUNT: u8 = 8;
+
+/// Enable a single downstream channel (disables all others)
+pub fn select_channel(i2c: &mut I2cDriver<'_>, addr: u8, channel: u8) -> Result<(), &'static str> {
+    if channel >= CHANNEL_COUNT {
+        return Err("Invalid mux channel");
+    }
+    i2c.write(addr, &[1 << channel], 100)
+        .map_err(|_| "Mux channel select failed")
+}
+
+/// Disable all downstream channels
+pub fn disable_all(i2c: &mut I2cDriver<'_>, addr: u8) -> Result<(), &'static str> {
+    i2c.write(addr, &[0x00], 100)
+        .map_err(|_| "Mux disable failed")
+}
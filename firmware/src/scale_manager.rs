@@ -1,25 +1,88 @@
 //! Scale Manager with C-callable interface
 //!
 //! Provides FFI functions for the C UI code to access scale data.
-//! Uses shared I2C bus.
-//! Calibration data is persisted to NVS flash.
+//! Works with any `LoadCell` backend: NAU7802 on the shared I2C bus (directly
+//! or behind a TCA9548A mux) or HX711 on GPIO. Several scales can run at once.
+//! Each has a stable id derived from where it is attached ("direct", "hx711"
+//! or "m<mux addr>_<channel>"), which keys its calibration in the settings
+//! store and names it to the backend, so a missing load cell doesn't shift
+//! the others. Indexes are registration order; scale 0 is the primary scale
+//! used by the unindexed FFI functions.
+//!
+//! The sensor thread holds the scales while it samples them on the I2C bus,
+//! so readers (the UI, the network thread) use a copy published after every
+//! change instead.
 
-use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
 use log::{info, warn};
+use std::ffi::c_int;
 use std::sync::Mutex;
 
-use crate::scale::nau7802::{self, Calibration, Nau7802State};
+use crate::scale::load_cell::{self, Calibration, LoadCell, ScaleState};
+use crate::scale::nau7802::Nau7802;
+use crate::scale::tca9548a;
+use crate::settings::{self, DeviceSettings};
 use crate::shared_i2c;
 
-/// NVS namespace for scale calibration
-const NVS_NAMESPACE: &str = "scale";
-const NVS_KEY_CALIBRATION: &str = "cal";
+/// Maximum number of scales (one per mux channel)
+pub const MAX_SCALES: usize = tca9548a::CHANNEL_COUNT as usize;
+
+/// Id of the NAU7802 attached directly to the shared I2C bus
+pub const DIRECT_SCALE_ID: &str = "direct";
+
+/// Load cell backend driving a scale
+pub enum LoadCellBackend {
+    /// NAU7802 on the shared I2C bus (borrowed per operation),
+    /// optionally behind a TCA9548A channel: (mux address, channel)
+    Nau7802 { mux: Option<(u8, u8)> },
+    /// Load cell that owns its pins (e.g. HX711 on GPIO)
+    Owned(Box<dyn LoadCell + Send>),
+}
+
+impl LoadCellBackend {
+    fn name(&self) -> &'static str {
+        match self {
+            LoadCellBackend::Nau7802 { .. } => "NAU7802",
+            LoadCellBackend::Owned(cell) => cell.name(),
+        }
+    }
+
+    /// Stable scale id from where the load cell is attached
+    fn id(&self) -> String {
+        match self {
+            LoadCellBackend::Nau7802 { mux: None } => DIRECT_SCALE_ID.to_string(),
+            LoadCellBackend::Nau7802 { mux: Some((addr, channel)) } => format!("m{:02x}_{}", addr, channel),
+            LoadCellBackend::Owned(cell) => cell.name().to_ascii_lowercase(),
+        }
+    }
+}
+
+/// One load cell with its calibration and filtered reading
+struct Scale {
+    /// Stable id, see `LoadCellBackend::id`
+    id: String,
+    state: ScaleState,
+    backend: LoadCellBackend,
+    /// Counter for rate-limiting error logs
+    error_count: u32,
+    /// Read errors since boot (health metrics)
+    total_errors: u32,
+    /// Raw (min, max) while stable since the last health snapshot
+    noise: Option<(i32, i32)>,
+}
+
+/// Per-scale health metrics
+pub struct ScaleHealth {
+    pub id: String,
+    pub i2c_errors: u32,
+    /// Peak-to-peak noise while stable, in grams (None if never stable)
+    pub noise_grams: Option<f32>,
+}
 
-/// Global scale state protected by mutex
-static SCALE_STATE: Mutex<Option<Nau7802State>> = Mutex::new(None);
+/// All registered scales, indexed by scale number
+static SCALES: Mutex<Vec<Scale>> = Mutex::new(Vec::new());
 
-/// Global NVS partition for calibration persistence
-static NVS_PARTITION: Mutex<Option<EspDefaultNvsPartition>> = Mutex::new(None);
+/// (id, state) of every scale as last published, indexed by scale number
+static SNAPSHOT: Mutex<Vec<(String, ScaleState)>> = Mutex::new(Vec::new());
 
 /// Scale status for C code
 #[repr(C)]
@@ -32,288 +95,475 @@ pub struct ScaleStatus {
     pub cal_factor: f32,
 }
 
-/// Initialize NVS for scale calibration persistence
-pub fn init_nvs(nvs: Option<EspDefaultNvsPartition>) {
-    let mut guard = NVS_PARTITION.lock().unwrap();
-    *guard = nvs;
-    info!("Scale NVS initialized");
+/// Follow calibration changes from the settings store (call once at startup)
+pub fn load_settings() {
+    settings::subscribe(on_settings_changed);
 }
 
-/// Initialize the scale manager with state (uses shared I2C)
-pub fn init_scale_manager(mut state: Nau7802State) {
-    // Try to load saved calibration from NVS
-    if let Some(calibration) = load_calibration_from_nvs() {
-        info!("Loaded saved calibration: zero_offset={}, cal_factor={}",
-              calibration.zero_offset, calibration.cal_factor);
+/// Apply calibrations restored through the settings store
+fn on_settings_changed(old: &DeviceSettings, new: &DeviceSettings) {
+    if old.scales == new.scales {
+        return;
+    }
+    let mut scales = SCALES.lock().unwrap();
+    for scale in scales.iter_mut() {
+        let calibration = new.scales.get(&scale.id).copied().unwrap_or_default();
+        if scale.state.calibration != calibration {
+            info!("Scale {}: calibration updated from settings", scale.id);
+            scale.state.calibration = calibration;
+        }
+    }
+    publish(&scales);
+}
+
+/// Publish the scales' state for readers (call while holding SCALES)
+fn publish(scales: &[Scale]) {
+    *SNAPSHOT.lock().unwrap() = scales
+        .iter()
+        .map(|s| (s.id.clone(), s.state.clone()))
+        .collect();
+}
+
+/// Run an operation against a scale's load cell
+fn with_load_cell<F, R>(backend: &mut LoadCellBackend, f: F) -> Option<R>
+where
+    F: FnOnce(&mut dyn LoadCell) -> R,
+{
+    match backend {
+        LoadCellBackend::Nau7802 { mux: None } => {
+            shared_i2c::with_i2c(|i2c| f(&mut Nau7802::new(i2c)))
+        }
+        LoadCellBackend::Nau7802 { mux: Some((addr, channel)) } => {
+            let (addr, channel) = (*addr, *channel);
+            shared_i2c::with_i2c(|i2c| {
+                if let Err(e) = tca9548a::select_channel(i2c, addr, channel) {
+                    warn!("{} (channel {})", e, channel);
+                    return None;
+                }
+                let result = f(&mut Nau7802::new(i2c));
+                let _ = tca9548a::disable_all(i2c, addr);
+                Some(result)
+            })
+            .flatten()
+        }
+        LoadCellBackend::Owned(cell) => Some(f(cell.as_mut())),
+    }
+}
+
+/// Register a scale with its load cell backend and state
+/// Returns the scale index, or None if all slots are taken
+pub fn add_scale(backend: LoadCellBackend, mut state: ScaleState) -> Option<usize> {
+    let mut scales = SCALES.lock().unwrap();
+    if scales.len() >= MAX_SCALES {
+        warn!("Scale limit reached ({}), ignoring {}", MAX_SCALES, backend.name());
+        return None;
+    }
+    let id = backend.id();
+    if scales.iter().any(|s| s.id == id) {
+        warn!("Scale {} already registered, ignoring", id);
+        return None;
+    }
+    let index = scales.len();
+
+    // Try to load saved calibration
+    if let Some(calibration) = settings::read(|s| s.scales.get(&id).copied()) {
+        info!("Scale {}: loaded saved calibration: zero_offset={}, cal_factor={}",
+              id, calibration.zero_offset, calibration.cal_factor);
         state.calibration = calibration;
     } else {
-        info!("No saved calibration found, using defaults");
+        info!("Scale {}: no saved calibration found, using defaults", id);
     }
 
-    let mut guard = SCALE_STATE.lock().unwrap();
-    *guard = Some(state);
-    info!("Scale manager initialized");
+    info!("Scale {} initialized as scale {} ({})", id, index, backend.name());
+    scales.push(Scale {
+        id,
+        state,
+        backend,
+        error_count: 0,
+        total_errors: 0,
+        noise: None,
+    });
+    publish(&scales);
+    Some(index)
 }
 
-/// Load calibration data from NVS (8 bytes: i32 zero_offset + i32 cal_factor_x1000)
-fn load_calibration_from_nvs() -> Option<Calibration> {
-    let nvs_guard = NVS_PARTITION.lock().unwrap();
-    let nvs_partition = nvs_guard.as_ref()?;
+/// Probe every TCA9548A channel for a NAU7802 and register each one found
+/// Returns the number of scales added
+pub fn init_mux_scales(i2c: &mut esp_idf_hal::i2c::I2cDriver<'_>, mux_addr: u8) -> usize {
+    use crate::scale::nau7802;
 
-    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
-        Ok(nvs) => nvs,
-        Err(e) => {
-            warn!("Failed to open NVS namespace for scale: {:?}", e);
-            return None;
+    let mut added = 0;
+    for channel in 0..tca9548a::CHANNEL_COUNT {
+        if tca9548a::select_channel(i2c, mux_addr, channel).is_err() {
+            warn!("  Mux 0x{:02X}: channel {} select failed", mux_addr, channel);
+            continue;
         }
-    };
 
-    // Read calibration blob
-    let mut buf = [0u8; 8];
-    match nvs.get_blob(NVS_KEY_CALIBRATION, &mut buf) {
-        Ok(Some(_)) => {
-            // Parse the calibration data
-            let zero_offset = i32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]);
-            let cal_factor_x1000 = i32::from_le_bytes([buf[4], buf[5], buf[6], buf[7]]);
-            let cal_factor = cal_factor_x1000 as f32 / 1000.0;
-
-            Some(Calibration {
-                zero_offset,
-                cal_factor,
-            })
-        }
-        Ok(None) => None, // No saved calibration
-        Err(e) => {
-            warn!("Failed to read calibration from NVS: {:?}", e);
-            None
+        let mut buf = [0u8; 1];
+        if i2c.read(nau7802::NAU7802_ADDR, &mut buf, 100).is_ok() {
+            let mut state = ScaleState::new();
+            match nau7802::init(i2c, &mut state) {
+                Ok(()) => {
+                    info!("  Mux 0x{:02X}: NAU7802 on channel {}", mux_addr, channel);
+                    let backend = LoadCellBackend::Nau7802 { mux: Some((mux_addr, channel)) };
+                    if add_scale(backend, state).is_some() {
+                        added += 1;
+                    }
+                }
+                Err(e) => warn!("  Mux channel {}: NAU7802 init failed: {:?}", channel, e),
+            }
         }
     }
+    let _ = tca9548a::disable_all(i2c, mux_addr);
+    added
 }
 
-/// Save calibration data to NVS
-fn save_calibration_to_nvs(calibration: &Calibration) -> bool {
-    let nvs_guard = NVS_PARTITION.lock().unwrap();
-    let Some(nvs_partition) = nvs_guard.as_ref() else {
-        warn!("No NVS partition available for saving calibration");
-        return false;
-    };
+/// Probe for an HX711 on the J9 header (DOUT=GPIO4, PD_SCK=GPIO5) and, if it
+/// answers, register it as a scale.
+/// DOUT has a pull-up, so an unconnected pin never signals data-ready.
+pub fn init_hx711() -> bool {
+    use esp_idf_hal::gpio::{PinDriver, Pull};
+    use crate::scale::hx711::Hx711;
 
-    let nvs = match EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
-        Ok(nvs) => nvs,
-        Err(e) => {
-            warn!("Failed to open NVS namespace for scale: {:?}", e);
+    let dout_pin = unsafe { esp_idf_hal::gpio::Gpio4::steal() };
+    let sck_pin = unsafe { esp_idf_hal::gpio::Gpio5::steal() };
+
+    let (dout, sck) = match (PinDriver::input(dout_pin, Pull::Up), PinDriver::output(sck_pin)) {
+        (Ok(dout), Ok(sck)) => (dout, sck),
+        _ => {
+            warn!("HX711 GPIO setup failed");
             return false;
         }
     };
 
-    // Pack calibration data into bytes
-    let cal_factor_x1000 = (calibration.cal_factor * 1000.0) as i32;
-    let mut buf = [0u8; 8];
-    buf[0..4].copy_from_slice(&calibration.zero_offset.to_le_bytes());
-    buf[4..8].copy_from_slice(&cal_factor_x1000.to_le_bytes());
-
-    // Save as blob
-    if let Err(e) = nvs.set_blob(NVS_KEY_CALIBRATION, &buf) {
-        warn!("Failed to save calibration to NVS: {:?}", e);
+    let mut hx711 = Hx711::new(dout, sck);
+    hx711.power_up();
+    // First conversion takes up to 400ms after power-up at 10 SPS
+    if hx711.wait_ready(500).is_err() {
+        info!("HX711 not detected on GPIO4/GPIO5");
         return false;
     }
 
-    info!("Calibration saved to NVS: zero_offset={}, cal_factor={}",
-          calibration.zero_offset, calibration.cal_factor);
-    true
+    let mut state = ScaleState::new();
+    state.initialized = true;
+    add_scale(LoadCellBackend::Owned(Box::new(hx711)), state).is_some()
 }
 
-/// Counter for rate-limiting error logs
-static ERROR_LOG_COUNTER: Mutex<u32> = Mutex::new(0);
+/// Save a scale's calibration (None clears it)
+fn save_calibration(id: &str, calibration: Option<Calibration>) -> bool {
+    let result = settings::update(|s| match calibration {
+        Some(calibration) => {
+            s.scales.insert(id.to_string(), calibration);
+        }
+        None => {
+            s.scales.remove(id);
+        }
+    });
+    match result {
+        Ok(()) => {
+            if let Some(calibration) = calibration {
+                info!("Scale {}: calibration saved: zero_offset={}, cal_factor={}",
+                      id, calibration.zero_offset, calibration.cal_factor);
+            }
+            true
+        }
+        Err(e) => {
+            warn!("Failed to save calibration: {}", e);
+            false
+        }
+    }
+}
 
-/// Poll the scale (call from main loop)
+/// Poll all scales (call from the sensor thread)
 pub fn poll_scale() {
-    let mut guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref mut state) = *guard {
-        if state.initialized {
-            let result = shared_i2c::with_i2c(|i2c| {
-                nau7802::read_weight(i2c, state)
-            });
-            match result {
-                Some(Ok(_)) => {
-                    // Reset error counter on success
-                    let mut counter = ERROR_LOG_COUNTER.lock().unwrap();
-                    *counter = 0;
+    let mut scales = SCALES.lock().unwrap();
+    for scale in scales.iter_mut() {
+        if !scale.state.initialized {
+            continue;
+        }
+        let state = &mut scale.state;
+        let result = with_load_cell(&mut scale.backend, |cell| {
+            load_cell::read_weight(cell, state)
+        });
+        match result {
+            Some(Ok(_)) => {
+                // Reset error counter on success
+                scale.error_count = 0;
+                if scale.state.stable {
+                    let raw = scale.state.last_raw;
+                    let (min, max) = scale.noise.unwrap_or((raw, raw));
+                    scale.noise = Some((min.min(raw), max.max(raw)));
                 }
-                Some(Err(e)) => {
-                    let mut counter = ERROR_LOG_COUNTER.lock().unwrap();
-                    *counter += 1;
-                    // Log first error and then every 50th error
-                    if *counter == 1 || *counter % 50 == 0 {
-                        warn!("Scale read error: {:?} (count: {})", e, *counter);
-                    }
+            }
+            Some(Err(e)) => {
+                scale.error_count += 1;
+                scale.total_errors = scale.total_errors.saturating_add(1);
+                // Log first error and then every 50th error
+                if scale.error_count == 1 || scale.error_count % 50 == 0 {
+                    warn!("Scale {} read error: {:?} (count: {})", scale.id, e, scale.error_count);
                 }
-                None => {
-                    let mut counter = ERROR_LOG_COUNTER.lock().unwrap();
-                    *counter += 1;
-                    if *counter == 1 || *counter % 50 == 0 {
-                        warn!("Scale read failed: I2C not initialized (count: {})", *counter);
-                    }
+            }
+            None => {
+                scale.error_count += 1;
+                scale.total_errors = scale.total_errors.saturating_add(1);
+                if scale.error_count == 1 || scale.error_count % 50 == 0 {
+                    warn!("Scale {} read failed: load cell not available (count: {})",
+                          scale.id, scale.error_count);
                 }
             }
         }
     }
+    publish(&scales);
+}
+
+/// Number of registered scales
+pub fn scale_count() -> usize {
+    SNAPSHOT.lock().unwrap().len()
+}
+
+/// Current (weight, stable) of every scale, by index
+pub fn readings() -> Vec<(f32, bool)> {
+    SNAPSHOT
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(_, state)| (state.weight_grams, state.stable))
+        .collect()
+}
+
+/// Stable id of every scale, by index
+pub fn ids() -> Vec<String> {
+    SNAPSHOT.lock().unwrap().iter().map(|(id, _)| id.clone()).collect()
+}
+
+/// Index of the scale with a stable id
+pub fn index_of(id: &str) -> Option<usize> {
+    SNAPSHOT.lock().unwrap().iter().position(|(scale_id, _)| scale_id == id)
+}
+
+/// Health metrics of every scale; starts a new noise window
+pub fn health() -> Vec<ScaleHealth> {
+    let mut scales = SCALES.lock().unwrap();
+    scales
+        .iter_mut()
+        .map(|scale| ScaleHealth {
+            id: scale.id.clone(),
+            i2c_errors: scale.total_errors,
+            noise_grams: scale.noise.take().map(|(min, max)| {
+                (max - min) as f32 / scale.state.calibration.cal_factor.abs()
+            }),
+        })
+        .collect()
+}
+
+// =============================================================================
+// Indexed operations (shared by both FFI flavors and backend commands)
+// =============================================================================
+
+/// Tare a scale; returns 0 on success, -1 on error
+pub fn tare(index: usize) -> i32 {
+    let mut scales = SCALES.lock().unwrap();
+    let Some(scale) = scales.get_mut(index) else {
+        return -1;
+    };
+    let state = &mut scale.state;
+    let result = with_load_cell(&mut scale.backend, |cell| {
+        load_cell::tare(cell, state)
+    });
+    let Some(Ok(())) = result else {
+        return -1;
+    };
+    let (id, calibration) = (scale.id.clone(), scale.state.calibration);
+    publish(&scales);
+    drop(scales); // Settings subscribers lock the scales again
+
+    // Save calibration (includes tare offset)
+    save_calibration(&id, Some(calibration));
+    0
+}
+
+/// Calibrate a scale with a known weight; returns 0 on success, -1 on error
+pub fn calibrate(index: usize, known_weight_grams: f32) -> i32 {
+    let mut scales = SCALES.lock().unwrap();
+    let Some(scale) = scales.get_mut(index) else {
+        return -1;
+    };
+    let state = &mut scale.state;
+    let result = with_load_cell(&mut scale.backend, |cell| {
+        load_cell::calibrate(cell, state, known_weight_grams)
+    });
+    let Some(Ok(())) = result else {
+        return -1;
+    };
+    let (id, calibration) = (scale.id.clone(), scale.state.calibration);
+    publish(&scales);
+    drop(scales); // Settings subscribers lock the scales again
+
+    // Save calibration for persistence across restarts
+    save_calibration(&id, Some(calibration));
+    0
+}
+
+/// Reset a scale's calibration to defaults; returns 0 on success, -1 on error
+pub fn reset_calibration(index: usize) -> i32 {
+    info!("Resetting scale {} calibration to defaults...", index);
+    let mut scales = SCALES.lock().unwrap();
+    let Some(scale) = scales.get_mut(index) else {
+        warn!("Scale reset failed: no scale {}", index);
+        return -1;
+    };
+    let state = &mut scale.state;
+
+    // Reset to default calibration
+    state.calibration = Calibration::default();
+    state.weight_grams = 0.0;
+    state.stable = false;
+    state.stable_count = 0;
+
+    info!("Scale {} calibration reset: zero_offset={}, cal_factor={}",
+          scale.id, state.calibration.zero_offset, state.calibration.cal_factor);
+    let id = scale.id.clone();
+    publish(&scales);
+    drop(scales); // Settings subscribers lock the scales again
+
+    // Clear saved calibration
+    save_calibration(&id, None);
+    0
+}
+
+/// Read a field of a scale's state, or a default if the scale doesn't exist
+fn read_state<T>(index: usize, default: T, f: impl FnOnce(&ScaleState) -> T) -> T {
+    let scales = SNAPSHOT.lock().unwrap();
+    scales.get(index).map(|(_, state)| f(state)).unwrap_or(default)
 }
 
 // =============================================================================
-// C-callable FFI functions
+// C-callable FFI functions (primary scale)
 // =============================================================================
 
 /// Get current scale status
 #[no_mangle]
 pub extern "C" fn scale_get_status(status: *mut ScaleStatus) {
-    if status.is_null() {
-        return;
-    }
-
-    let guard = SCALE_STATE.lock().unwrap();
-    let status = unsafe { &mut *status };
-
-    if let Some(ref state) = *guard {
-        status.initialized = state.initialized;
-        status.weight_grams = state.weight_grams;
-        status.raw_value = state.last_raw;
-        status.stable = state.stable;
-        status.tare_offset = state.calibration.zero_offset;
-        status.cal_factor = state.calibration.cal_factor;
-    } else {
-        status.initialized = false;
-        status.weight_grams = 0.0;
-        status.raw_value = 0;
-        status.stable = false;
-        status.tare_offset = 0;
-        status.cal_factor = 1.0;
-    }
+    scale_get_status_at(0, status);
 }
 
 /// Get current weight in grams
 #[no_mangle]
 pub extern "C" fn scale_get_weight() -> f32 {
-    let guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref state) = *guard {
-        state.weight_grams
-    } else {
-        0.0
-    }
+    read_state(0, 0.0, |s| s.weight_grams)
 }
 
 /// Get raw ADC value
 #[no_mangle]
 pub extern "C" fn scale_get_raw() -> i32 {
-    let guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref state) = *guard {
-        state.last_raw
-    } else {
-        0
-    }
+    read_state(0, 0, |s| s.last_raw)
 }
 
 /// Check if scale is initialized
 #[no_mangle]
 pub extern "C" fn scale_is_initialized() -> bool {
-    let guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref state) = *guard {
-        state.initialized
-    } else {
-        false
-    }
+    read_state(0, false, |s| s.initialized)
 }
 
 /// Check if weight is stable
 #[no_mangle]
 pub extern "C" fn scale_is_stable() -> bool {
-    let guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref state) = *guard {
-        state.stable
-    } else {
-        false
-    }
+    read_state(0, false, |s| s.stable)
 }
 
 /// Tare the scale (set current weight as zero)
 #[no_mangle]
 pub extern "C" fn scale_tare() -> i32 {
-    let mut guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref mut state) = *guard {
-        let result = shared_i2c::with_i2c(|i2c| {
-            nau7802::tare(i2c, state)
-        });
-        match result {
-            Some(Ok(())) => {
-                // Save calibration (includes tare offset) to NVS
-                save_calibration_to_nvs(&state.calibration);
-                0
-            }
-            _ => -1,
-        }
-    } else {
-        -1
-    }
+    tare(0)
 }
 
 /// Calibrate with a known weight (in grams)
 #[no_mangle]
 pub extern "C" fn scale_calibrate(known_weight_grams: f32) -> i32 {
-    let mut guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref mut state) = *guard {
-        let result = shared_i2c::with_i2c(|i2c| {
-            nau7802::calibrate(i2c, state, known_weight_grams)
-        });
-        match result {
-            Some(Ok(())) => {
-                // Save calibration to NVS for persistence across restarts
-                save_calibration_to_nvs(&state.calibration);
-                0
-            }
-            _ => -1,
-        }
-    } else {
-        -1
-    }
+    calibrate(0, known_weight_grams)
 }
 
 /// Reset calibration to defaults
 #[no_mangle]
 pub extern "C" fn scale_reset_calibration() -> i32 {
-    info!("Resetting scale calibration to defaults...");
-    let mut guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref mut state) = *guard {
-        // Reset to default calibration
-        state.calibration = Calibration::default();
-        state.weight_grams = 0.0;
-        state.stable = false;
-        state.stable_count = 0;
-
-        // Clear saved calibration from NVS
-        let nvs_guard = NVS_PARTITION.lock().unwrap();
-        if let Some(ref nvs_partition) = *nvs_guard {
-            if let Ok(nvs) = EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
-                let _ = nvs.remove(NVS_KEY_CALIBRATION);
-            }
-        }
-        drop(nvs_guard);
-
-        info!("Scale calibration reset: zero_offset={}, cal_factor={}",
-              state.calibration.zero_offset, state.calibration.cal_factor);
-        0
-    } else {
-        warn!("Scale reset failed: no state");
-        -1
-    }
+    reset_calibration(0)
 }
 
 /// Get tare offset
 #[no_mangle]
 pub extern "C" fn scale_get_tare_offset() -> i32 {
-    let guard = SCALE_STATE.lock().unwrap();
-    if let Some(ref state) = *guard {
-        state.calibration.zero_offset
-    } else {
-        0
+    read_state(0, 0, |s| s.calibration.zero_offset)
+}
+
+// =============================================================================
+// C-callable FFI functions (indexed, for multi-scale setups)
+// =============================================================================
+
+/// Get number of scales
+#[no_mangle]
+pub extern "C" fn scale_get_count() -> c_int {
+    scale_count() as c_int
+}
+
+/// Get status of a scale by index
+/// Returns 0 on success, -1 if the index is invalid (status marked uninitialized)
+#[no_mangle]
+pub extern "C" fn scale_get_status_at(index: c_int, status: *mut ScaleStatus) -> c_int {
+    if status.is_null() {
+        return -1;
+    }
+
+    let scales = SNAPSHOT.lock().unwrap();
+    let status = unsafe { &mut *status };
+
+    match usize::try_from(index).ok().and_then(|i| scales.get(i)) {
+        Some((_, state)) => {
+            status.initialized = state.initialized;
+            status.weight_grams = state.weight_grams;
+            status.raw_value = state.last_raw;
+            status.stable = state.stable;
+            status.tare_offset = state.calibration.zero_offset;
+            status.cal_factor = state.calibration.cal_factor;
+            0
+        }
+        None => {
+            status.initialized = false;
+            status.weight_grams = 0.0;
+            status.raw_value = 0;
+            status.stable = false;
+            status.tare_offset = 0;
+            status.cal_factor = 1.0;
+            -1
+        }
     }
 }
+
+/// Get weight of a scale by index (0.0 if invalid)
+#[no_mangle]
+pub extern "C" fn scale_get_weight_at(index: c_int) -> f32 {
+    usize::try_from(index).map_or(0.0, |i| read_state(i, 0.0, |s| s.weight_grams))
+}
+
+/// Check if a scale's weight is stable
+#[no_mangle]
+pub extern "C" fn scale_is_stable_at(index: c_int) -> bool {
+    usize::try_from(index).is_ok_and(|i| read_state(i, false, |s| s.stable))
+}
+
+/// Tare a scale by index
+#[no_mangle]
+pub extern "C" fn scale_tare_at(index: c_int) -> i32 {
+    usize::try_from(index).map_or(-1, tare)
+}
+
+/// Calibrate a scale by index with a known weight (in grams)
+#[no_mangle]
+pub extern "C" fn scale_calibrate_at(index: c_int, known_weight_grams: f32) -> i32 {
+    usize::try_from(index).map_or(-1, |i| calibrate(i, known_weight_grams))
+}
+
+/// Reset a scale's calibration by index
+#[no_mangle]
+pub extern "C" fn scale_reset_calibration_at(index: c_int) -> i32 {
+    usize::try_from(index).map_or(-1, reset_calibration)
+}
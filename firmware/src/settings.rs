@@ -0,0 +1,444 @@
+//! Device settings store
+//!
+//! All user configuration lives in one typed `DeviceSettings` document,
+//! stored as JSON in the "settings" NVS namespace. Modules read their section
+//! with `read()` and change it with `update()`; subscribers are called after
+//! every change, so a configuration imported from the backend takes effect
+//! without a reboot.
+//!
+//! Documents carry a schema version and are upgraded when loaded or imported.
+//! The WiFi credentials and scale calibration of older firmware are collected
+//! on the first boot; those keys are left in place so an OTA rollback still
+//! finds them.
+//!
+//! After each change the whole document is uploaded to the backend as a
+//! backup. The backend can send a document back ("settings_import" command)
+//! to restore a display or clone another one.
+
+use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs, NvsDefault};
+use log::{info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::ota_manager::UpdatePolicy;
+use crate::scale::load_cell::Calibration;
+use crate::scale_manager::{DIRECT_SCALE_ID, MAX_SCALES};
+use crate::wifi_manager::{IpConfig, SavedNetwork, MAX_SAVED_NETWORKS};
+
+/// Schema version written by this firmware
+pub const SETTINGS_VERSION: u32 = 1;
+
+// NVS keys for the settings document
+const NVS_NAMESPACE: &str = "settings";
+const NVS_KEY_DOCUMENT: &str = "doc";
+
+/// Staged changes are saved once they have been quiet this long
+const SAVE_DELAY: Duration = Duration::from_secs(2);
+
+/// Lowest brightness accepted from an import (the slider stops at 10% too)
+const MIN_BRIGHTNESS: u8 = 10;
+
+/// Display backlight and timeout
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DisplaySettings {
+    /// Backlight brightness in percent
+    pub brightness: u8,
+    /// Screen timeout in seconds (0 = never)
+    pub timeout_s: u16,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> Self {
+        DisplaySettings { brightness: 80, timeout_s: 300 }
+    }
+}
+
+/// Backend server connection
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackendSettings {
+    /// Server URL (empty = built-in default)
+    pub url: String,
+    pub api_key: String,
+}
+
+/// Saved WiFi networks and station addressing
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WifiSettings {
+    /// Networks in priority order
+    pub networks: Vec<SavedNetwork>,
+    pub ip: IpConfig,
+}
+
+/// Clock display
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ClockSettings {
+    /// POSIX TZ string
+    pub timezone: String,
+    pub clock_24h: bool,
+}
+
+impl Default for ClockSettings {
+    fn default() -> Self {
+        ClockSettings {
+            timezone: crate::time_manager::DEFAULT_TZ.to_string(),
+            clock_24h: true,
+        }
+    }
+}
+
+/// Network logging (see udp_logger)
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogSettings {
+    /// "udp://ip:port", "syslog://ip[:port]" or empty for serial only
+    pub target: String,
+    /// Per-module level spec (empty = info)
+    pub levels: String,
+}
+
+/// Everything the user can configure on the display
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+    pub version: u32,
+    pub display: DisplaySettings,
+    pub backend: BackendSettings,
+    pub wifi: WifiSettings,
+    /// Calibration per scale id ("direct", "hx711" or "m<mux addr>_<channel>",
+    /// see scale_manager), missing = not calibrated
+    pub scales: BTreeMap<String, Calibration>,
+    pub clock: ClockSettings,
+    pub logging: LogSettings,
+    pub ota: UpdatePolicy,
+    /// Talk to printers over LAN MQTT while the backend is unreachable
+    pub lan_mode: bool,
+}
+
+impl Default for DeviceSettings {
+    fn default() -> Self {
+        DeviceSettings {
+            version: SETTINGS_VERSION,
+            display: DisplaySettings::default(),
+            backend: BackendSettings::default(),
+            wifi: WifiSettings::default(),
+            scales: BTreeMap::new(),
+            clock: ClockSettings::default(),
+            logging: LogSettings::default(),
+            ota: UpdatePolicy::default(),
+            lan_mode: false,
+        }
+    }
+}
+
+/// Change subscriber, called with the old and the new settings
+pub type Listener = fn(&DeviceSettings, &DeviceSettings);
+
+static NVS_PARTITION: Mutex<Option<EspDefaultNvsPartition>> = Mutex::new(None);
+static SETTINGS: Mutex<Option<DeviceSettings>> = Mutex::new(None);
+static LISTENERS: Mutex<Vec<Listener>> = Mutex::new(Vec::new());
+
+/// Last staged change that is not saved yet
+static STAGED_AT: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// The backend's copy is out of date (also true after boot)
+static BACKUP_PENDING: AtomicBool = AtomicBool::new(true);
+
+// =============================================================================
+// Loading and saving (NVS)
+// =============================================================================
+
+/// Load the settings, upgrading older layouts (call once at startup, before
+/// the modules read their settings)
+pub fn init_nvs(nvs: Option<EspDefaultNvsPartition>) {
+    let settings = match nvs.as_ref() {
+        Some(partition) => load(partition),
+        None => {
+            warn!("No NVS partition available, using default settings");
+            DeviceSettings::default()
+        }
+    };
+    *SETTINGS.lock().unwrap() = Some(settings);
+    *NVS_PARTITION.lock().unwrap() = nvs;
+}
+
+fn load(partition: &EspDefaultNvsPartition) -> DeviceSettings {
+    match read_document(partition) {
+        Ok(Some(mut doc)) => match upgrade(&mut doc).and_then(|_| parse(doc)) {
+            Ok(settings) => {
+                info!("Settings loaded (version {})", SETTINGS_VERSION);
+                return settings;
+            }
+            Err(e) => warn!("Discarding stored settings: {}", e),
+        },
+        Ok(None) => info!("No settings document yet, collecting older settings"),
+        Err(e) => warn!("{}", e),
+    }
+
+    let settings = read_legacy(partition);
+    if let Err(e) = write_document(partition, &settings) {
+        warn!("{}", e);
+    }
+    settings
+}
+
+fn read_document(partition: &EspDefaultNvsPartition) -> Result<Option<Value>, String> {
+    let nvs = EspNvs::new(partition.clone(), NVS_NAMESPACE, true)
+        .map_err(|e| format!("Failed to open NVS: {:?}", e))?;
+    let Some(len) = nvs.blob_len(NVS_KEY_DOCUMENT)
+        .map_err(|e| format!("Failed to read settings: {:?}", e))? else {
+        return Ok(None);
+    };
+    let mut buf = vec![0u8; len];
+    let Some(data) = nvs.get_blob(NVS_KEY_DOCUMENT, &mut buf)
+        .map_err(|e| format!("Failed to read settings: {:?}", e))? else {
+        return Ok(None);
+    };
+    serde_json::from_slice(data)
+        .map(Some)
+        .map_err(|e| format!("Failed to parse stored settings: {:?}", e))
+}
+
+fn write_document(partition: &EspDefaultNvsPartition, settings: &DeviceSettings) -> Result<(), String> {
+    let data = serde_json::to_vec(settings)
+        .map_err(|e| format!("Failed to serialize settings: {:?}", e))?;
+    EspNvs::new(partition.clone(), NVS_NAMESPACE, true)
+        .map_err(|e| format!("Failed to open NVS: {:?}", e))?
+        .set_blob(NVS_KEY_DOCUMENT, &data)
+        .map_err(|e| format!("Failed to save settings: {:?}", e))
+}
+
+fn save(settings: &DeviceSettings) -> Result<(), String> {
+    let nvs_guard = NVS_PARTITION.lock().unwrap();
+    let partition = nvs_guard.as_ref().ok_or("No NVS partition available")?;
+    write_document(partition, settings)
+}
+
+/// Bring a stored or imported document up to `SETTINGS_VERSION`
+fn upgrade(doc: &mut Value) -> Result<(), String> {
+    let fields = doc.as_object_mut().ok_or("Settings must be a JSON object")?;
+    let version = fields.get("version").and_then(Value::as_u64).unwrap_or(SETTINGS_VERSION as u64);
+    if version > SETTINGS_VERSION as u64 {
+        // Written by newer firmware (e.g. before a rollback): keep what we know
+        warn!("Settings version {} is newer than {}, unknown fields are dropped", version, SETTINGS_VERSION);
+    }
+    // Schema changes are applied here, one step per version
+    fields.insert("version".to_string(), SETTINGS_VERSION.into());
+    Ok(())
+}
+
+/// Typed settings from an upgraded document (missing fields take defaults)
+fn parse(doc: Value) -> Result<DeviceSettings, String> {
+    serde_json::from_value(doc).map_err(|e| format!("Invalid settings: {}", e))
+}
+
+// =============================================================================
+// Access and change notifications
+// =============================================================================
+
+/// Read from the current settings
+pub fn read<R>(f: impl FnOnce(&DeviceSettings) -> R) -> R {
+    let mut guard = SETTINGS.lock().unwrap();
+    f(guard.get_or_insert_with(DeviceSettings::default))
+}
+
+/// Change settings and save them right away
+pub fn update(f: impl FnOnce(&mut DeviceSettings)) -> Result<(), String> {
+    apply(f, true)
+}
+
+/// Change settings now and save them once they stop changing (for sliders,
+/// which report every step while dragged)
+pub fn stage(f: impl FnOnce(&mut DeviceSettings)) {
+    let _ = apply(f, false);
+}
+
+fn apply(f: impl FnOnce(&mut DeviceSettings), persist: bool) -> Result<(), String> {
+    let (old, new) = {
+        let mut guard = SETTINGS.lock().unwrap();
+        let current = guard.get_or_insert_with(DeviceSettings::default);
+        let mut new = current.clone();
+        f(&mut new);
+        if new == *current {
+            return Ok(());
+        }
+        if persist {
+            save(&new)?;
+        }
+        (std::mem::replace(current, new.clone()), new)
+    };
+
+    // A save also covers earlier staged changes
+    *STAGED_AT.lock().unwrap() = if persist { None } else { Some(Instant::now()) };
+    BACKUP_PENDING.store(true, Ordering::Relaxed);
+
+    let listeners = LISTENERS.lock().unwrap().clone();
+    for listener in listeners {
+        listener(&old, &new);
+    }
+    Ok(())
+}
+
+/// Register a function to be called after every change (also for imports)
+pub fn subscribe(listener: Listener) {
+    LISTENERS.lock().unwrap().push(listener);
+}
+
+/// Save staged changes once they have settled (call from the network thread)
+pub fn poll() {
+    let due = STAGED_AT
+        .lock()
+        .unwrap()
+        .is_some_and(|at| at.elapsed() >= SAVE_DELAY);
+    if due {
+        flush();
+    }
+}
+
+/// Save staged changes now (before a reboot)
+pub fn flush() {
+    if STAGED_AT.lock().unwrap().take().is_none() {
+        return;
+    }
+    if let Err(e) = read(save) {
+        warn!("{}", e);
+    }
+}
+
+// =============================================================================
+// Backup and import
+// =============================================================================
+
+/// Settings document for a backup. WiFi passwords and the API key are left
+/// out; an import keeps the display's own (see `import`).
+pub fn export() -> Value {
+    let mut doc = read(|settings| serde_json::to_value(settings).unwrap_or(Value::Null));
+    if let Some(backend) = doc.pointer_mut("/backend").and_then(Value::as_object_mut) {
+        backend.remove("api_key");
+    }
+    if let Some(networks) = doc.pointer_mut("/wifi/networks").and_then(Value::as_array_mut) {
+        for network in networks.iter_mut().filter_map(Value::as_object_mut) {
+            network.remove("password");
+        }
+    }
+    doc
+}
+
+/// Apply a document exported by this or another display. Sections missing
+/// from `doc` are kept, so scale calibrations can be left out when cloning
+/// a display with different load cells. A missing API key keeps the current
+/// one, and a network without a password keeps the password saved for its
+/// SSID.
+pub fn import(mut doc: Value) -> Result<(), String> {
+    upgrade(&mut doc)?;
+    let current = read(|settings| settings.clone());
+    let mut merged = serde_json::to_value(&current).map_err(|e| format!("Settings not serializable: {}", e))?;
+    if let (Some(fields), Value::Object(imported)) = (merged.as_object_mut(), doc) {
+        fields.extend(imported);
+    }
+    if let Some(backend) = merged.pointer_mut("/backend").and_then(Value::as_object_mut) {
+        backend
+            .entry("api_key")
+            .or_insert_with(|| Value::String(current.backend.api_key.clone()));
+    }
+    if let Some(networks) = merged.pointer_mut("/wifi/networks").and_then(Value::as_array_mut) {
+        for network in networks.iter_mut().filter_map(Value::as_object_mut) {
+            if network.contains_key("password") {
+                continue;
+            }
+            let ssid = network.get("ssid").and_then(Value::as_str).unwrap_or_default();
+            if let Some(saved) = current.wifi.networks.iter().find(|saved| saved.ssid == ssid) {
+                network.insert("password".to_string(), Value::String(saved.password.clone()));
+            }
+        }
+    }
+    let mut imported = parse(merged)?;
+    validate(&mut imported)?;
+    update(|settings| *settings = imported)?;
+    info!("Settings imported");
+    Ok(())
+}
+
+/// Reject settings the modules can't use (an import bypasses their setters)
+fn validate(settings: &mut DeviceSettings) -> Result<(), String> {
+    let display = &mut settings.display;
+    display.brightness = display.brightness.clamp(MIN_BRIGHTNESS, 100);
+    if settings.wifi.networks.len() > MAX_SAVED_NETWORKS {
+        return Err(format!("At most {} WiFi networks can be saved", MAX_SAVED_NETWORKS));
+    }
+    for network in &settings.wifi.networks {
+        network.validate()?;
+    }
+    settings.wifi.ip.validate()?;
+    if settings.scales.len() > MAX_SCALES {
+        return Err(format!("At most {} scale calibrations can be saved", MAX_SCALES));
+    }
+    if !crate::time_manager::valid_timezone(&settings.clock.timezone) {
+        return Err(format!("Invalid timezone: {:?}", settings.clock.timezone));
+    }
+    crate::udp_logger::validate(&settings.logging)?;
+    settings.ota.validate()
+}
+
+/// Upload the settings after a change (call when the backend answers)
+pub fn upload_backup() {
+    if !BACKUP_PENDING.swap(false, Ordering::Relaxed) {
+        return;
+    }
+    if !crate::backend_client::send_settings_backup(&export()) {
+        BACKUP_PENDING.store(true, Ordering::Relaxed);
+    }
+}
+
+// =============================================================================
+// Settings from older firmware
+// =============================================================================
+
+/// Collect the WiFi credentials and scale calibration older firmware kept
+/// in its "wifi" and "scale" namespaces
+fn read_legacy(partition: &EspDefaultNvsPartition) -> DeviceSettings {
+    let mut settings = DeviceSettings::default();
+    // Read-only, so namespaces that were never written aren't created
+    let open = |namespace: &str| EspNvs::new(partition.clone(), namespace, false).ok();
+
+    if let Some(nvs) = open("wifi") {
+        let ssid = read_str(&nvs, "ssid");
+        if !ssid.is_empty() {
+            settings.wifi.networks.push(SavedNetwork::new(&ssid, &read_str(&nvs, "password")));
+        }
+    }
+    if let Some(nvs) = open("scale") {
+        if let Some(calibration) = legacy_calibration(&nvs) {
+            settings.scales.insert(DIRECT_SCALE_ID.to_string(), calibration);
+        }
+    }
+
+    info!("Collected settings: {} WiFi network(s), {} scale calibration(s)",
+          settings.wifi.networks.len(), settings.scales.len());
+    settings
+}
+
+fn read_str(nvs: &EspNvs<NvsDefault>, key: &str) -> String {
+    let mut buf = [0u8; 256];
+    nvs.get_str(key, &mut buf).ok().flatten().unwrap_or_default().to_string()
+}
+
+/// Calibration blob of the NAU7802: i32 zero_offset + i32 cal_factor x1000
+/// under "cal"
+fn legacy_calibration(nvs: &EspNvs<NvsDefault>) -> Option<Calibration> {
+    let mut buf = [0u8; 8];
+    match nvs.get_blob("cal", &mut buf) {
+        Ok(Some(data)) if data.len() == 8 => Some(Calibration {
+            zero_offset: i32::from_le_bytes([data[0], data[1], data[2], data[3]]),
+            cal_factor: i32::from_le_bytes([data[4], data[5], data[6], data[7]]) as f32 / 1000.0,
+        }),
+        _ => None,
+    }
+}
@@ -0,0 +1,215 @@
+//! Spool Weight Calculations
+//!
+//! Converts the gross scale reading into net filament weight and remaining
+//! filament length. The core tare comes from the inventory record (SpoolEase
+//! tags carry it as `WE`) or falls back to a per-vendor table. Weight syncs
+//! send the tare along, so the backend's weight_used matches the net weight
+//! shown here.
+
+use log::info;
+use std::sync::Mutex;
+
+/// Core weight used when neither the spool record nor the vendor table knows
+/// better (matches the backend's "spoolbuddy-default-core-weight" default)
+pub const DEFAULT_CORE_WEIGHT: i32 = 250;
+
+/// Filament diameter assumed when the inventory doesn't specify one (mm)
+pub const DEFAULT_DIAMETER_MM: f32 = 1.75;
+
+/// Density assumed for unknown materials (g/cm³, PLA)
+const DEFAULT_DENSITY: f32 = 1.24;
+
+/// Typical empty spool weights by vendor (grams), matched case-insensitively
+/// against the start of the brand name
+const VENDOR_CORE_WEIGHTS: &[(&str, i32)] = &[
+    ("bambu", 250),
+    ("prusament", 201),
+    ("polymaker", 140),
+    ("esun", 224),
+    ("sunlu", 165),
+    ("elegoo", 153),
+    ("overture", 165),
+    ("eryone", 187),
+    ("jayo", 120),
+    ("creality", 140),
+];
+
+/// Material densities (g/cm³). Longer prefixes first so "PETG" wins over "PET".
+const MATERIAL_DENSITIES: &[(&str, f32)] = &[
+    ("PETG", 1.27),
+    ("PCTG", 1.23),
+    ("PLA", 1.24),
+    ("PET", 1.38),
+    ("ABS", 1.04),
+    ("ASA", 1.07),
+    ("TPU", 1.21),
+    ("HIPS", 1.04),
+    ("PVA", 1.23),
+    ("PA", 1.14),
+    ("PC", 1.20),
+];
+
+/// Parameters of the spool currently on the scale
+#[derive(Debug, Clone)]
+pub struct SpoolProfile {
+    pub spool_id: String,
+    pub core_weight: i32,
+    pub label_weight: i32,
+    pub density: f32,
+    pub diameter_mm: f32,
+}
+
+/// Net weight estimate for a gross scale reading
+#[derive(Debug, Clone, Copy)]
+pub struct FilamentEstimate {
+    pub gross_weight: f32,
+    pub core_weight: i32,
+    pub net_weight: f32,
+    pub remaining_length_m: f32,
+    pub fill_pct: i32,
+}
+
+/// Spool matched to the tag on the scale (set by spool lookup, cleared on tag removal)
+static ACTIVE_SPOOL: Mutex<Option<SpoolProfile>> = Mutex::new(None);
+
+/// Resolve the core tare: spool record first, then vendor table, then default
+pub fn resolve_core_weight(record_core_weight: Option<i32>, brand: Option<&str>) -> i32 {
+    if let Some(w) = record_core_weight.filter(|&w| w > 0) {
+        return w;
+    }
+    brand.and_then(vendor_core_weight).unwrap_or(DEFAULT_CORE_WEIGHT)
+}
+
+/// Look up the typical empty spool weight for a vendor
+pub fn vendor_core_weight(brand: &str) -> Option<i32> {
+    let brand = brand.trim().to_lowercase();
+    VENDOR_CORE_WEIGHTS
+        .iter()
+        .find(|(prefix, _)| brand.starts_with(prefix))
+        .map(|&(_, w)| w)
+}
+
+/// Look up the density for a material type (e.g. "PLA", "PETG-CF")
+pub fn material_density(material: &str) -> f32 {
+    let material = material.trim().to_uppercase();
+    MATERIAL_DENSITIES
+        .iter()
+        .find(|(prefix, _)| material.starts_with(prefix))
+        .map(|&(_, d)| d)
+        .unwrap_or(DEFAULT_DENSITY)
+}
+
+/// Net filament weight for a gross reading (never negative)
+pub fn net_weight(gross_weight: f32, core_weight: i32) -> f32 {
+    (gross_weight - core_weight as f32).max(0.0)
+}
+
+/// Remaining filament length in meters for a net weight
+pub fn remaining_length_m(net_weight: f32, density: f32, diameter_mm: f32) -> f32 {
+    let radius_cm = diameter_mm / 20.0;
+    // Grams per meter = density (g/cm³) * cross-section (cm²) * 100 cm
+    let grams_per_m = density * std::f32::consts::PI * radius_cm * radius_cm * 100.0;
+    if grams_per_m <= 0.0 {
+        return 0.0;
+    }
+    net_weight / grams_per_m
+}
+
+/// Compute the estimate for a gross reading against a spool profile
+pub fn estimate(profile: &SpoolProfile, gross_weight: f32) -> FilamentEstimate {
+    let net = net_weight(gross_weight, profile.core_weight);
+    let fill_pct = if profile.label_weight > 0 {
+        ((net * 100.0) / profile.label_weight as f32).clamp(0.0, 100.0) as i32
+    } else {
+        0
+    };
+
+    FilamentEstimate {
+        gross_weight,
+        core_weight: profile.core_weight,
+        net_weight: net,
+        remaining_length_m: remaining_length_m(net, profile.density, profile.diameter_mm),
+        fill_pct,
+    }
+}
+
+/// Set the spool currently on the scale
+pub fn set_active_spool(profile: SpoolProfile) {
+    info!(
+        "Active spool {}: core {}g, label {}g, density {:.2}",
+        profile.spool_id, profile.core_weight, profile.label_weight, profile.density
+    );
+    *ACTIVE_SPOOL.lock().unwrap() = Some(profile);
+}
+
+/// Forget the active spool (tag removed)
+pub fn clear_active_spool() {
+    *ACTIVE_SPOOL.lock().unwrap() = None;
+}
+
+/// Get the active spool profile, if any
+pub fn active_spool() -> Option<SpoolProfile> {
+    ACTIVE_SPOOL.lock().unwrap().clone()
+}
+
+/// Get the core weight of the active spool for a given spool ID
+pub fn core_weight_for(spool_id: &str) -> Option<i32> {
+    ACTIVE_SPOOL
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|p| p.spool_id == spool_id)
+        .map(|p| p.core_weight)
+}
+
+// =============================================================================
+// C-callable FFI Functions
+// =============================================================================
+
+/// C-compatible filament estimate
+#[repr(C)]
+pub struct FilamentEstimateC {
+    pub gross_weight: f32,      // Scale reading (grams)
+    pub core_weight: i32,       // Core tare applied (grams)
+    pub net_weight: f32,        // Remaining filament (grams)
+    pub remaining_length_m: f32, // Remaining filament length (meters)
+    pub fill_pct: i32,          // Net weight as % of label weight
+    pub valid: bool,            // False if no spool is active
+}
+
+/// Get the net weight estimate for the active spool at the current scale reading
+/// Returns true if a spool is active and the estimate was filled
+#[no_mangle]
+pub extern "C" fn filament_get_estimate(out: *mut FilamentEstimateC) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let out = unsafe { &mut *out };
+
+    let gross = crate::scale_manager::scale_get_weight();
+    match active_spool() {
+        Some(profile) => {
+            let est = estimate(&profile, gross);
+            *out = FilamentEstimateC {
+                gross_weight: est.gross_weight,
+                core_weight: est.core_weight,
+                net_weight: est.net_weight,
+                remaining_length_m: est.remaining_length_m,
+                fill_pct: est.fill_pct,
+                valid: true,
+            };
+            true
+        }
+        None => {
+            *out = FilamentEstimateC {
+                gross_weight: gross,
+                core_weight: 0,
+                net_weight: 0.0,
+                remaining_length_m: 0.0,
+                fill_pct: 0,
+                valid: false,
+            };
+            false
+        }
+    }
+}
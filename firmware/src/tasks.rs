@@ -0,0 +1,223 @@
+//! Background tasks for sensors, NFC and the network
+//!
+//! The main thread only runs the UI: LVGL is not thread-safe, so
+//! `display_tick()` and the FFI calls made from it stay there. Everything
+//! that blocks runs in its own FreeRTOS thread instead:
+//! - `sensors`: scale polling every 50 ms (weight changes wake the screen)
+//! - `nfc`: tag scanning every 500 ms (bridge transactions can take 1.5 s)
+//! - `network`: post-WiFi init, backend polling, OTA checks and weight
+//!   reports, plus the HTTP calls for tag events from the NFC thread and
+//!   for auto-sync undos requested by the UI
+//!
+//! The threads share state through the managers' mutex-protected statics and
+//! hand tag events to the network thread over a channel, so a slow backend
+//! never stalls the touchscreen or the sensors.
+
+use log::{info, warn};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use crate::nfc_bridge_manager::TagEvent;
+use crate::{backend_client, bridge_ota, config_portal, health, idle, nfc_bridge_manager, ota_manager};
+use crate::{scale_manager, settings, time_manager, weight_history, weight_sync, wifi_manager};
+
+/// Scale polling interval
+const SENSOR_INTERVAL: Duration = Duration::from_millis(50);
+
+/// NFC bridge polling interval
+const NFC_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Full backend sync (printers, commands, heartbeat)
+const BACKEND_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Weight reports and weight sync/history
+const WEIGHT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// WiFi check until the post-WiFi init has run
+const WIFI_CHECK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Open the setup portal after this long without WiFi
+const PORTAL_DELAY: Duration = Duration::from_secs(5 * 60);
+
+/// Start the sensor, NFC and network threads (call once before the UI loop)
+pub fn start() {
+    let (tx, rx) = mpsc::channel();
+    spawn("sensors", 4096, run_sensors);
+    spawn("nfc", 6144, move || run_nfc(tx));
+    spawn("network", 12288, move || run_network(rx));
+}
+
+fn spawn(name: &str, stack_size: usize, f: impl FnOnce() + Send + 'static) {
+    let spawned = std::thread::Builder::new()
+        .name(name.into())
+        .stack_size(stack_size)
+        .spawn(f);
+    if let Err(e) = spawned {
+        warn!("Failed to start {} thread: {:?}", name, e);
+    }
+}
+
+fn run_sensors() {
+    let mut last_stack_check = Instant::now();
+    loop {
+        scale_manager::poll_scale();
+        // A spool placed or lifted wakes the screen
+        idle::note_weights(&scale_manager::readings());
+        if last_stack_check.elapsed() >= BACKEND_INTERVAL {
+            health::record_stack("sensors");
+            last_stack_check = Instant::now();
+        }
+        std::thread::sleep(SENSOR_INTERVAL);
+    }
+}
+
+fn run_nfc(tx: Sender<TagEvent>) {
+    loop {
+        if let Some(event) = nfc_bridge_manager::poll_nfc() {
+            if matches!(event, TagEvent::Detected(_)) {
+                idle::activity();
+            }
+            if tx.send(event).is_err() {
+                warn!("Network thread gone, stopping NFC polling");
+                return;
+            }
+        }
+        health::record_stack("nfc");
+        std::thread::sleep(NFC_INTERVAL);
+    }
+}
+
+fn run_network(rx: Receiver<TagEvent>) {
+    let mut wifi_init_done = false;
+    let mut ota_check_done = false;
+    let mut last_backend = Instant::now();
+    let mut last_weight = Instant::now();
+    let mut offline_since = Instant::now();
+    let mut portal_opened = false;
+
+    loop {
+        match rx.recv_timeout(WIFI_CHECK_INTERVAL) {
+            Ok(event) => handle_tag_event(event),
+            Err(RecvTimeoutError::Timeout) => {}
+            // NFC thread not running - keep the same pace
+            Err(RecvTimeoutError::Disconnected) => std::thread::sleep(WIFI_CHECK_INTERVAL),
+        }
+        // Undo tapped on the sync confirmation
+        weight_sync::poll_undo();
+
+        // Saved network unreachable (moved, new password) - open the setup
+        // portal so the unit can be reconfigured without the touchscreen
+        if wifi_manager::is_connected() {
+            offline_since = Instant::now();
+            if portal_opened {
+                portal_opened = false;
+                config_portal::stop();
+            }
+        } else if offline_since.elapsed() >= PORTAL_DELAY && !config_portal::is_active() {
+            offline_since = Instant::now(); // Retry after another delay if it fails
+            info!("No WiFi for {} minutes, opening the setup portal", PORTAL_DELAY.as_secs() / 60);
+            match config_portal::start() {
+                Ok(()) => portal_opened = true,
+                Err(e) => warn!("Setup portal failed to start: {}", e),
+            }
+        }
+
+        // Post-WiFi initialization - check frequently until WiFi connects
+        if !wifi_init_done {
+            if wifi_manager::is_connected() {
+                post_wifi_init();
+                wifi_init_done = true;
+                last_backend = Instant::now();
+            }
+        } else if last_backend.elapsed() >= BACKEND_INTERVAL {
+            // Regular polling every 2 seconds (full sync: printers, commands, etc.)
+            last_backend = Instant::now();
+            health::record_stack("network");
+            backend_client::poll_backend();
+            // Periodic update checks and scheduled installs
+            ota_manager::poll(&config_portal::backend_url());
+            bridge_ota::poll(&config_portal::backend_url());
+        }
+
+        // OTA check on startup (once, after WiFi init) - check but don't install yet
+        // Installs happen via backend command or in the auto-install window (ota_manager::poll)
+        if wifi_init_done && !ota_check_done {
+            ota_check_done = true;
+            startup_ota_check();
+        }
+
+        if last_weight.elapsed() >= WEIGHT_INTERVAL {
+            last_weight = Instant::now();
+            ota_manager::poll_rollback();
+            report_weights();
+            // Save settings staged by the UI sliders once they settle
+            settings::poll();
+        }
+    }
+}
+
+/// SNTP, backend URL and first sync once WiFi is up
+fn post_wifi_init() {
+    // Initialize SNTP for time sync (may take time)
+    time_manager::init_sntp();
+    // Connected from the touchscreen while the setup portal was open
+    if config_portal::is_active() {
+        config_portal::stop();
+    }
+    // Set backend server URL (saved via setup portal, or default)
+    backend_client::set_server_url(&config_portal::backend_url());
+    // Sync time immediately from backend (faster than SNTP)
+    backend_client::sync_time();
+    info!("Post-WiFi init complete (SNTP + backend URL + time sync)");
+    // Immediate first poll for printer data
+    backend_client::poll_backend();
+}
+
+fn startup_ota_check() {
+    info!("Firmware version: v{}", ota_manager::get_version());
+
+    // Check for updates and store result (don't auto-install)
+    match ota_manager::check_for_update(&config_portal::backend_url()) {
+        Ok(info) => {
+            if info.available {
+                info!("Firmware update available: v{}", info.version);
+                ota_manager::set_update_available(true, &info.version);
+            } else {
+                info!("Firmware is up to date");
+                ota_manager::set_update_available(false, "");
+            }
+        }
+        Err(e) => {
+            warn!("OTA check failed: {}", e);
+        }
+    }
+}
+
+/// Weight-only update every 500ms for faster UI feedback, plus weight sync/history
+fn report_weights() {
+    let weight = scale_manager::scale_get_weight();
+    let stable = scale_manager::scale_is_stable();
+    backend_client::send_device_state(None, weight, stable);
+    // Additional scales (mux channels) report separately, by stable id
+    for (id, (weight, stable)) in scale_manager::ids().iter().zip(scale_manager::readings()).skip(1) {
+        backend_client::send_scale_state(id, weight, stable);
+    }
+    weight_sync::poll(weight, stable);
+    weight_history::poll(weight, stable);
+}
+
+/// Tell the backend about a tag placed on or removed from the reader
+fn handle_tag_event(event: TagEvent) {
+    let weight = scale_manager::scale_get_weight();
+    let stable = scale_manager::scale_is_stable();
+    match event {
+        TagEvent::Detected(uid_hex) => {
+            backend_client::send_device_state(Some(&uid_hex), weight, stable);
+            weight_sync::on_tag_detected(&uid_hex);
+        }
+        TagEvent::Removed => {
+            backend_client::send_device_state(None, weight, stable);
+            weight_sync::on_tag_removed();
+        }
+    }
+}
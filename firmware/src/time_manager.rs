@@ -1,20 +1,70 @@
 //! Time Manager with SNTP synchronization and backend fallback
 //!
 //! Provides NTP time sync and C-callable interface for UI clock display.
-//! Falls back to backend server time if SNTP is unavailable.
+//! Falls back to backend server time if SNTP is unavailable: the backend's
+//! epoch timestamp sets the system clock, which then keeps running on its own
+//! between polls. Once SNTP syncs it takes over, and the offset between the
+//! backend-derived clock and SNTP is recorded as clock drift.
+//!
+//! Local time follows a POSIX TZ string (e.g. "CET-1CEST,M3.5.0,M10.5.0/3"),
+//! so DST changes are applied by the C library. The timezone and the 12/24h
+//! preference are kept in the settings store and can be pushed by the backend.
 
 use esp_idf_svc::sntp::{EspSntp, SyncStatus, SntpConf};
 use log::{info, warn};
-use std::ffi::c_int;
+use std::ffi::{c_char, c_int, CStr};
 use std::sync::Mutex;
-use std::time::SystemTime;
+use std::time::{Instant, SystemTime};
+
+use crate::backend_client::copy_to_c;
+use crate::settings::{self, DeviceSettings};
+
+/// Default timezone: Central European Time with DST
+pub const DEFAULT_TZ: &str = "CET-1CEST,M3.5.0,M10.5.0/3";
+
+/// Longest accepted TZ string
+const MAX_TZ_LEN: usize = 63;
+
+/// Backend timestamps before 2024-01-01 are treated as bogus
+const MIN_VALID_EPOCH: i64 = 1_704_067_200;
+
+/// Backend time within this many seconds of the running clock is not re-applied
+/// (avoids stepping the clock back and forth by HTTP latency)
+const BACKEND_RESYNC_SECS: i64 = 2;
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
 
 /// Time sync state
 static TIME_SYNCED: Mutex<bool> = Mutex::new(false);
 static SNTP_HANDLE: Mutex<Option<EspSntp<'static>>> = Mutex::new(None);
 
-/// Backend time (hour, minute) - used when SNTP isn't available
-static BACKEND_TIME: Mutex<Option<(u8, u8)>> = Mutex::new(None);
+/// Backend time anchor - set when the system clock was set from the backend
+static BACKEND_ANCHOR: Mutex<Option<BackendAnchor>> = Mutex::new(None);
+
+/// Last measured offset of backend time from SNTP time (seconds, positive = backend ahead)
+static CLOCK_DRIFT: Mutex<Option<i64>> = Mutex::new(None);
+
+/// Epoch timestamp from the backend and the monotonic instant it was applied
+struct BackendAnchor {
+    epoch: i64,
+    at: Instant,
+}
+
+/// Local date and time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LocalTime {
+    pub year: i32,
+    /// 1-12
+    pub month: u8,
+    /// 1-31
+    pub day: u8,
+    /// 0 = Sunday
+    pub weekday: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
 
 /// Initialize SNTP time synchronization
 /// Call this after WiFi is connected
@@ -38,62 +88,217 @@ pub fn init_sntp() {
     }
 }
 
-/// Check if time is synchronized
+/// Check if time is synchronized via SNTP
+/// (latched: ESP-IDF reports Completed only once per sync)
 pub fn is_time_synced() -> bool {
+    let mut time_synced = TIME_SYNCED.lock().unwrap();
+    if *time_synced {
+        return true;
+    }
+
     let handle = SNTP_HANDLE.lock().unwrap();
-    if let Some(ref sntp) = *handle {
-        let synced = sntp.get_sync_status() == SyncStatus::Completed;
-        if synced {
-            let mut time_synced = TIME_SYNCED.lock().unwrap();
-            if !*time_synced {
-                info!("SNTP time synchronized");
-                *time_synced = true;
-            }
+    let Some(ref sntp) = *handle else {
+        return false;
+    };
+    if sntp.get_sync_status() != SyncStatus::Completed {
+        return false;
+    }
+
+    info!("SNTP time synchronized");
+    *time_synced = true;
+
+    // Compare the clock we were running from backend time against SNTP
+    if let Some(anchor) = BACKEND_ANCHOR.lock().unwrap().take() {
+        let expected = anchor.epoch + anchor.at.elapsed().as_secs() as i64;
+        let drift = expected - now_epoch();
+        info!("Backend clock drift vs SNTP: {}s", drift);
+        *CLOCK_DRIFT.lock().unwrap() = Some(drift);
+    }
+    true
+}
+
+/// Set time from backend server (Unix epoch seconds)
+/// Called when we receive time from the backend API. Sets the system clock
+/// unless SNTP owns it; then only the drift against SNTP is recorded.
+pub fn set_backend_time(timestamp: i64) {
+    if timestamp < MIN_VALID_EPOCH {
+        warn!("Ignoring invalid backend time: {}", timestamp);
+        return;
+    }
+
+    if is_time_synced() {
+        let drift = timestamp - now_epoch();
+        if drift.abs() > 60 {
+            warn!("Backend clock is {}s off from SNTP", drift);
         }
-        synced
-    } else {
-        false
+        *CLOCK_DRIFT.lock().unwrap() = Some(drift);
+        return;
+    }
+
+    let mut anchor = BACKEND_ANCHOR.lock().unwrap();
+    if anchor.is_some() && (timestamp - now_epoch()).abs() < BACKEND_RESYNC_SECS {
+        return; // Clock is running fine
+    }
+
+    let tv = esp_idf_sys::timeval {
+        tv_sec: timestamp as esp_idf_sys::time_t,
+        tv_usec: 0,
+    };
+    if unsafe { esp_idf_sys::settimeofday(&tv, std::ptr::null()) } != 0 {
+        warn!("Failed to set system clock from backend time");
+        return;
+    }
+    if anchor.is_none() {
+        info!("System clock set from backend time");
+    }
+    *anchor = Some(BackendAnchor { epoch: timestamp, at: Instant::now() });
+}
+
+/// Whether the system clock holds a real date (SNTP or backend time)
+pub fn clock_valid() -> bool {
+    is_time_synced() || BACKEND_ANCHOR.lock().unwrap().is_some()
+}
+
+/// Last measured offset of backend time from SNTP, in seconds (positive = backend ahead)
+pub fn clock_drift() -> Option<i64> {
+    *CLOCK_DRIFT.lock().unwrap()
+}
+
+fn now_epoch() -> i64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+// ============================================================================
+// Clock settings
+// ============================================================================
+
+/// Apply the saved timezone and follow changes (call once at startup)
+pub fn load_settings() {
+    let (tz, clock_24h) = settings::read(|s| (s.clock.timezone.clone(), s.clock.clock_24h));
+    let tz = if valid_timezone(&tz) { tz } else { DEFAULT_TZ.to_string() };
+    apply_timezone(&tz);
+    info!("Timezone: {} ({}h clock)", tz, if clock_24h { 24 } else { 12 });
+    settings::subscribe(on_settings_changed);
+}
+
+fn on_settings_changed(old: &DeviceSettings, new: &DeviceSettings) {
+    if old.clock.timezone != new.clock.timezone {
+        apply_timezone(&new.clock.timezone);
+        info!("Timezone set to {}", new.clock.timezone);
+    }
+}
+
+/// Basic POSIX TZ sanity check (the C library silently treats bad strings as UTC)
+pub fn valid_timezone(tz: &str) -> bool {
+    (3..=MAX_TZ_LEN).contains(&tz.len())
+        && tz.chars().all(|c| c.is_ascii_graphic())
+        && tz.starts_with(|c: char| c.is_ascii_alphabetic() || c == '<')
+}
+
+fn apply_timezone(tz: &str) {
+    std::env::set_var("TZ", tz);
+    unsafe { esp_idf_sys::tzset(); }
+}
+
+/// Current POSIX TZ string
+pub fn timezone() -> String {
+    settings::read(|s| s.clock.timezone.clone())
+}
+
+/// Change the timezone (persisted, applied immediately)
+pub fn set_timezone(tz: &str) -> Result<(), String> {
+    let tz = tz.trim();
+    if !valid_timezone(tz) {
+        return Err(format!("Invalid timezone: {:?}", tz));
     }
+    settings::update(|s| s.clock.timezone = tz.to_string())
 }
 
-/// Set time from backend server
-/// Called when we receive time from the backend API
-pub fn set_backend_time(hour: u8, minute: u8) {
-    let mut backend_time = BACKEND_TIME.lock().unwrap();
-    *backend_time = Some((hour, minute));
+/// Whether the clock is shown in 24h format
+pub fn clock_24h() -> bool {
+    settings::read(|s| s.clock.clock_24h)
 }
 
-// Timezone offset in seconds (CET = UTC+1 = 3600, CEST = UTC+2 = 7200)
-// TODO: Make this configurable via backend
-const TIMEZONE_OFFSET_SECS: u64 = 3600; // CET (UTC+1)
+/// Set the 12/24h preference (persisted)
+pub fn set_clock_24h(enabled: bool) -> Result<(), String> {
+    settings::update(|s| s.clock.clock_24h = enabled)
+}
+
+// ============================================================================
+// Local time
+// ============================================================================
+
+/// Get the local date and time (requires SNTP or backend time)
+pub fn local_time() -> Option<LocalTime> {
+    if !clock_valid() {
+        return None;
+    }
+
+    let now = now_epoch() as esp_idf_sys::time_t;
+    let mut tm: esp_idf_sys::tm = unsafe { std::mem::zeroed() };
+    if unsafe { esp_idf_sys::localtime_r(&now, &mut tm) }.is_null() {
+        return None;
+    }
+
+    Some(LocalTime {
+        year: tm.tm_year + 1900,
+        month: (tm.tm_mon + 1) as u8,
+        day: tm.tm_mday as u8,
+        weekday: tm.tm_wday as u8,
+        hour: tm.tm_hour as u8,
+        minute: tm.tm_min as u8,
+        second: tm.tm_sec as u8,
+    })
+}
 
 /// Get current time components (for UI display)
-/// Returns (hour, minute) - tries SNTP first, falls back to backend time
+/// Returns (hour, minute) once the clock was set by SNTP or the backend
 pub fn get_time() -> Option<(u8, u8)> {
-    // Try SNTP first
-    if is_time_synced() {
-        match SystemTime::now().duration_since(SystemTime::UNIX_EPOCH) {
-            Ok(duration) => {
-                let secs = duration.as_secs() + TIMEZONE_OFFSET_SECS;
-                // Local time calculation with timezone offset
-                let day_secs = secs % 86400;
-                let hour = (day_secs / 3600) as u8;
-                let minute = ((day_secs % 3600) / 60) as u8;
-                return Some((hour, minute));
-            }
-            Err(_) => {}
-        }
+    local_time().map(|local| (local.hour, local.minute))
+}
+
+/// Format a time of day according to the 12/24h preference
+pub fn format_clock(hour: u8, minute: u8) -> String {
+    if clock_24h() {
+        format!("{:02}:{:02}", hour, minute)
+    } else {
+        let suffix = if hour < 12 { "AM" } else { "PM" };
+        let hour12 = match hour % 12 {
+            0 => 12,
+            h => h,
+        };
+        format!("{}:{:02} {}", hour12, minute, suffix)
     }
+}
 
-    // Fall back to backend time
-    let backend_time = BACKEND_TIME.lock().unwrap();
-    *backend_time
+/// Format a date as "Sun 18 Oct 2026"
+pub fn format_date(local: &LocalTime) -> String {
+    format!("{} {} {} {}",
+            WEEKDAYS[local.weekday as usize % 7],
+            local.day,
+            MONTHS[(local.month as usize).saturating_sub(1) % 12],
+            local.year)
 }
 
 // ============================================================================
 // C-callable interface
 // ============================================================================
 
+/// Local date and time for C code
+#[repr(C)]
+pub struct TimeLocalC {
+    pub year: i32,
+    pub month: u8,      // 1-12
+    pub day: u8,        // 1-31
+    pub weekday: u8,    // 0 = Sunday
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
 /// Get current time for UI display
 /// Returns hour in upper 8 bits, minute in lower 8 bits
 /// Returns -1 if time not synced
@@ -111,3 +316,89 @@ pub extern "C" fn time_get_hhmm() -> c_int {
 pub extern "C" fn time_is_synced() -> c_int {
     if is_time_synced() { 1 } else { 0 }
 }
+
+/// Get the local date and time
+/// Returns 0 on success, -1 if the date is unknown (clock not set yet)
+#[no_mangle]
+pub extern "C" fn time_get_local(out: *mut TimeLocalC) -> c_int {
+    if out.is_null() {
+        return -1;
+    }
+    let Some(local) = local_time() else {
+        return -1;
+    };
+    unsafe {
+        *out = TimeLocalC {
+            year: local.year,
+            month: local.month,
+            day: local.day,
+            weekday: local.weekday,
+            hour: local.hour,
+            minute: local.minute,
+            second: local.second,
+        };
+    }
+    0
+}
+
+/// Format the current time ("14:05" or "2:05 PM")
+/// Returns length, or -1 if time not synced
+#[no_mangle]
+pub extern "C" fn time_format_clock(buf: *mut c_char, buf_len: c_int) -> c_int {
+    match get_time() {
+        Some((hour, minute)) => copy_to_c(&format_clock(hour, minute), buf, buf_len),
+        None => -1,
+    }
+}
+
+/// Format the current date ("Sun 18 Oct 2026")
+/// Returns length, or -1 if the date is unknown
+#[no_mangle]
+pub extern "C" fn time_format_date(buf: *mut c_char, buf_len: c_int) -> c_int {
+    match local_time() {
+        Some(local) => copy_to_c(&format_date(&local), buf, buf_len),
+        None => -1,
+    }
+}
+
+/// Returns 1 for a 24h clock, 0 for 12h
+#[no_mangle]
+pub extern "C" fn time_is_24h() -> c_int {
+    clock_24h() as c_int
+}
+
+/// Set the clock format (1 = 24h, 0 = 12h)
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn time_set_24h(enabled: c_int) -> c_int {
+    match set_clock_24h(enabled != 0) {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
+
+/// Copy the POSIX TZ string to buffer, returns length or -1 on error
+#[no_mangle]
+pub extern "C" fn time_get_timezone(buf: *mut c_char, buf_len: c_int) -> c_int {
+    copy_to_c(&timezone(), buf, buf_len)
+}
+
+/// Set the POSIX TZ string (e.g. "CET-1CEST,M3.5.0,M10.5.0/3")
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn time_set_timezone(tz: *const c_char) -> c_int {
+    if tz.is_null() {
+        return -1;
+    }
+    let tz = unsafe { CStr::from_ptr(tz) }.to_string_lossy();
+    match set_timezone(&tz) {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("{}", e);
+            -1
+        }
+    }
+}
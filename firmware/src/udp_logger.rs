@@ -1,73 +1,321 @@
-//! UDP Logger - sends log messages to backend over UDP
+//! UDP Logger - `log` backend that tees records to serial and the network
 //!
-//! This allows logging even when UART pins are used for SPI.
+//! This allows logging even when UART pins are used for SPI. Every record
+//! goes to the ESP-IDF console and, once a target is configured, over UDP:
+//! - `udp://host:port` (or bare `host:port`): plain lines, e.g. the backend
+//!   log listener on port 5555
+//! - `syslog://host[:port]`: RFC 5424 syslog messages
+//!
+//! Levels are filtered per module with an env_logger style spec such as
+//! `info,scale_manager=debug,esp_idf_svc=warn`. Target and levels are
+//! kept in the settings store and can be pushed by the backend. The last lines are
+//! kept in a ring buffer for crash reports.
 
-use std::net::UdpSocket;
-use std::sync::Mutex;
+use esp_idf_svc::log::EspLogger;
+use log::{info, warn, Level, LevelFilter, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::net::{SocketAddr, UdpSocket};
 use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, RwLock};
+use std::time::SystemTime;
 
-/// UDP logger state
-static UDP_SOCKET: Mutex<Option<UdpSocket>> = Mutex::new(None);
-static UDP_TARGET: Mutex<Option<String>> = Mutex::new(None);
-static UDP_ENABLED: AtomicBool = AtomicBool::new(false);
+use crate::settings::{self, DeviceSettings, LogSettings};
 
-/// Initialize UDP logger with target address (e.g., "192.168.1.100:5555")
-pub fn init(target: &str) -> Result<(), std::io::Error> {
-    let socket = UdpSocket::bind("0.0.0.0:0")?;
-    socket.set_nonblocking(true)?;
+/// Longest accepted target / level spec
+const MAX_SETTING_LEN: usize = 127;
 
-    *UDP_SOCKET.lock().unwrap() = Some(socket);
-    *UDP_TARGET.lock().unwrap() = Some(target.to_string());
-    UDP_ENABLED.store(true, Ordering::SeqCst);
+/// Default syslog port
+const SYSLOG_PORT: u16 = 514;
 
-    // Send init message
-    log("UDP logger initialized");
-    Ok(())
+/// Syslog facility local0
+const SYSLOG_FACILITY: u8 = 16;
+
+/// Lines kept for crash reports
+const RING_LINES: usize = 64;
+
+/// Longest line kept in the ring buffer
+const RING_LINE_LEN: usize = 160;
+
+/// Longest message sent over UDP
+const MAX_DATAGRAM_LEN: usize = 1024;
+
+/// Crate prefix stripped from log targets
+const CRATE_PREFIX: &str = "spoolbuddy_firmware::";
+
+/// Where network log lines go
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Target {
+    Udp(SocketAddr),
+    Syslog(SocketAddr),
 }
 
-/// Send a log message over UDP
-pub fn log(msg: &str) {
-    if !UDP_ENABLED.load(Ordering::Relaxed) {
-        return;
+impl Target {
+    /// Parse a target setting, `None` for an empty one (serial only)
+    fn parse(spec: &str) -> Result<Option<Self>, String> {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            return Ok(None);
+        }
+        let invalid = || format!("Invalid log target: {:?}", spec);
+
+        if let Some(addr) = spec.strip_prefix("syslog://") {
+            let addr = if addr.contains(':') { addr.to_string() } else { format!("{}:{}", addr, SYSLOG_PORT) };
+            return addr.parse().map(|a| Some(Target::Syslog(a))).map_err(|_| invalid());
+        }
+        let addr = spec.strip_prefix("udp://").unwrap_or(spec);
+        addr.parse().map(|a| Some(Target::Udp(a))).map_err(|_| invalid())
+    }
+
+    fn addr(&self) -> SocketAddr {
+        match self {
+            Target::Udp(addr) | Target::Syslog(addr) => *addr,
+        }
+    }
+}
+
+/// Per-module level filters
+#[derive(Debug, Clone, PartialEq)]
+struct LevelFilters {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LevelFilters {
+    /// Parse a spec like "info,scale_manager=debug,esp_idf_svc=warn"
+    fn parse(spec: &str) -> Result<Self, String> {
+        let mut filters = LevelFilters { default: LevelFilter::Info, modules: Vec::new() };
+        for part in spec.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+            let invalid = || format!("Invalid log level: {:?}", part);
+            match part.split_once('=') {
+                Some((module, level)) => {
+                    let module = module.trim();
+                    if module.is_empty() {
+                        return Err(invalid());
+                    }
+                    let level = level.trim().parse().map_err(|_| invalid())?;
+                    filters.modules.push((module.to_string(), level));
+                }
+                None => filters.default = part.parse().map_err(|_| invalid())?,
+            }
+        }
+        // Longest module first so the most specific filter wins
+        filters.modules.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+        Ok(filters)
     }
 
-    let socket_guard = UDP_SOCKET.lock().unwrap();
-    let target_guard = UDP_TARGET.lock().unwrap();
+    /// Level for a record target ("spoolbuddy_firmware::nfc::i2c_bridge")
+    fn level_for(&self, target: &str) -> LevelFilter {
+        let short = target.strip_prefix(CRATE_PREFIX).unwrap_or(target);
+        self.modules
+            .iter()
+            .find(|(module, _)| module_matches(target, module) || module_matches(short, module))
+            .map_or(self.default, |(_, level)| *level)
+    }
 
-    if let (Some(socket), Some(target)) = (socket_guard.as_ref(), target_guard.as_ref()) {
-        // Ignore send errors (non-blocking, best-effort)
-        let _ = socket.send_to(msg.as_bytes(), target);
+    fn max(&self) -> LevelFilter {
+        self.modules.iter().map(|(_, level)| *level).fold(self.default, std::cmp::max)
     }
 }
 
-/// Log with format (like println!)
-#[macro_export]
-macro_rules! udp_log {
-    ($($arg:tt)*) => {
-        $crate::udp_logger::log(&format!($($arg)*))
-    };
+/// "nfc" matches "nfc" and "nfc::i2c_bridge", but not "nfc_manager"
+fn module_matches(target: &str, module: &str) -> bool {
+    target
+        .strip_prefix(module)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
 }
 
-/// Log info level
-#[macro_export]
-macro_rules! udp_info {
-    ($($arg:tt)*) => {
-        $crate::udp_logger::log(&format!("[INFO] {}", format!($($arg)*)))
-    };
+/// Logger state
+static SERIAL: EspLogger = EspLogger::new();
+static FILTERS: RwLock<LevelFilters> = RwLock::new(LevelFilters { default: LevelFilter::Info, modules: Vec::new() });
+static UDP_SOCKET: Mutex<Option<UdpSocket>> = Mutex::new(None);
+static UDP_TARGET: Mutex<Option<Target>> = Mutex::new(None);
+static NETWORK_READY: AtomicBool = AtomicBool::new(false);
+static RING: Mutex<VecDeque<String>> = Mutex::new(VecDeque::new());
+
+struct TeeLogger;
+
+static LOGGER: TeeLogger = TeeLogger;
+
+impl Log for TeeLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        match FILTERS.read() {
+            Ok(filters) => metadata.level() <= filters.level_for(metadata.target()),
+            Err(_) => true,
+        }
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        SERIAL.log(record);
+
+        let target = record.target().strip_prefix(CRATE_PREFIX).unwrap_or(record.target());
+        let line = format!("[{}] {}: {}", record.level(), target, record.args());
+        remember(&line);
+
+        // Never log from here - a record while holding these locks would deadlock
+        if !NETWORK_READY.load(Ordering::Relaxed) {
+            return;
+        }
+        let Some(dest) = UDP_TARGET.lock().ok().and_then(|t| *t) else {
+            return;
+        };
+        let Ok(mut socket) = UDP_SOCKET.lock() else {
+            return;
+        };
+        if socket.is_none() {
+            *socket = UdpSocket::bind("0.0.0.0:0").ok().filter(|s| s.set_nonblocking(true).is_ok());
+        }
+        let Some(socket) = socket.as_ref() else {
+            return;
+        };
+
+        let message = match dest {
+            Target::Udp(_) => line,
+            Target::Syslog(_) => syslog_message(record.level(), target, &record.args().to_string()),
+        };
+        let bytes = message.as_bytes();
+        // Best-effort: non-blocking, errors ignored
+        let _ = socket.send_to(&bytes[..bytes.len().min(MAX_DATAGRAM_LEN)], dest.addr());
+    }
+
+    fn flush(&self) {
+        SERIAL.flush();
+    }
 }
 
-/// Log warning level
-#[macro_export]
-macro_rules! udp_warn {
-    ($($arg:tt)*) => {
-        $crate::udp_logger::log(&format!("[WARN] {}", format!($($arg)*)))
+/// Append a line to the crash report ring buffer
+fn remember(line: &str) {
+    let Ok(mut ring) = RING.lock() else {
+        return;
     };
+    if ring.len() >= RING_LINES {
+        ring.pop_front();
+    }
+    let mut end = line.len().min(RING_LINE_LEN);
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    ring.push_back(line[..end].to_string());
 }
 
-/// Log error level
-#[macro_export]
-macro_rules! udp_error {
-    ($($arg:tt)*) => {
-        $crate::udp_logger::log(&format!("[ERROR] {}", format!($($arg)*)))
+/// RFC 5424 message: `<PRI>1 TIMESTAMP HOST APP PROCID MSGID SD MSG`
+fn syslog_message(level: Level, target: &str, msg: &str) -> String {
+    let severity = match level {
+        Level::Error => 3,
+        Level::Warn => 4,
+        Level::Info => 6,
+        Level::Debug | Level::Trace => 7,
     };
+    format!("<{}>1 {} spoolbuddy firmware - - - {}: {}",
+            SYSLOG_FACILITY * 8 + severity, syslog_timestamp(), target, msg)
+}
+
+/// UTC timestamp, or the RFC 5424 nil value while the clock is unset
+fn syslog_timestamp() -> String {
+    // Reading the clock directly: time_manager logs and must not be called from here
+    let secs = SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    if secs < 1_704_067_200 {
+        return "-".to_string();
+    }
+
+    let now = secs as esp_idf_sys::time_t;
+    let mut tm: esp_idf_sys::tm = unsafe { std::mem::zeroed() };
+    if unsafe { esp_idf_sys::gmtime_r(&now, &mut tm) }.is_null() {
+        return "-".to_string();
+    }
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            tm.tm_year + 1900, tm.tm_mon + 1, tm.tm_mday, tm.tm_hour, tm.tm_min, tm.tm_sec)
+}
+
+// ============================================================================
+// Setup
+// ============================================================================
+
+/// Install the logger (call first thing in main, replaces EspLogger::initialize_default)
+pub fn init() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(LevelFilter::Info);
+    }
+}
+
+/// Apply the saved target and levels and follow changes (call once at startup)
+pub fn load_settings() {
+    apply_settings(&settings::read(|s| s.logging.clone()));
+    settings::subscribe(on_settings_changed);
+}
+
+/// Start sending over UDP (call once the network stack is up)
+pub fn start() {
+    NETWORK_READY.store(true, Ordering::Relaxed);
+}
+
+fn on_settings_changed(old: &DeviceSettings, new: &DeviceSettings) {
+    if old.logging != new.logging {
+        apply_settings(&new.logging);
+    }
+}
+
+fn apply_settings(logging: &LogSettings) {
+    match LevelFilters::parse(&logging.levels) {
+        Ok(filters) if *FILTERS.read().unwrap() != filters => {
+            log::set_max_level(filters.max());
+            *FILTERS.write().unwrap() = filters;
+            info!("Log levels set to {:?}", logging.levels);
+        }
+        Ok(_) => {}
+        Err(e) => warn!("{}", e),
+    }
+    match Target::parse(&logging.target) {
+        Ok(target) if *UDP_TARGET.lock().unwrap() != target => {
+            *UDP_TARGET.lock().unwrap() = target;
+            match target {
+                Some(target) => info!("Network logging to {:?}", target),
+                None => info!("Network logging disabled"),
+            }
+        }
+        Ok(_) => {}
+        Err(e) => warn!("{}", e),
+    }
+}
+
+fn check_len(value: &str) -> Result<(), String> {
+    if value.len() > MAX_SETTING_LEN {
+        return Err(format!("Log setting too long ({} > {})", value.len(), MAX_SETTING_LEN));
+    }
+    Ok(())
+}
+
+/// Check a target and level spec before they are saved
+pub fn validate(logging: &LogSettings) -> Result<(), String> {
+    check_len(&logging.target)?;
+    check_len(&logging.levels)?;
+    Target::parse(&logging.target)?;
+    LevelFilters::parse(&logging.levels).map(|_| ())
+}
+
+/// Set the network target ("udp://host:port", "syslog://host[:port]", "" = serial only), persisted
+pub fn set_target(spec: &str) -> Result<(), String> {
+    let spec = spec.trim();
+    check_len(spec)?;
+    Target::parse(spec)?;
+    settings::update(|s| s.logging.target = spec.to_string())
+}
+
+/// Set the level spec ("info,scale_manager=debug"), persisted
+pub fn set_levels(spec: &str) -> Result<(), String> {
+    let spec = spec.trim();
+    check_len(spec)?;
+    LevelFilters::parse(spec)?;
+    settings::update(|s| s.logging.levels = spec.to_string())
+}
+
+/// Last logged lines, oldest first (for crash reports)
+/// Empty if the buffer is busy, so a panic inside the logger can't deadlock
+pub fn recent_lines() -> Vec<String> {
+    RING.try_lock().map(|ring| ring.iter().cloned().collect()).unwrap_or_default()
 }
@@ -0,0 +1,344 @@
+//! Weight History and Consumption Rate
+//!
+//! Keeps a ring buffer of timestamped stable readings for each identified
+//! spool that has been on the scale. While a spool feeds a print from a
+//! scale-equipped holder, a least-squares fit over the recent readings gives
+//! the consumption rate and a time-to-empty estimate, which is compared with
+//! the remaining print time of the printer the spool is assigned to. A
+//! predicted runout raises an alert the UI shows as a toast.
+
+use log::{info, warn};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::backend_client::{self, SpoolPrintJob};
+use crate::spool_weight;
+
+/// Readings kept per spool (at one per SAMPLE_INTERVAL_MS: two hours)
+const HISTORY_LEN: usize = 120;
+
+/// Spools with history kept at once (least recently used is dropped)
+const MAX_TRACKED_SPOOLS: usize = 8;
+
+/// Minimum time between recorded readings (ms)
+const SAMPLE_INTERVAL_MS: u128 = 60_000;
+
+/// Only readings this recent are used for the rate fit (minutes)
+const RATE_WINDOW_MIN: f32 = 30.0;
+
+/// Readings must span at least this long before a rate is reported (minutes)
+const MIN_SPAN_MIN: f32 = 5.0;
+
+/// Minimum readings in the window before a rate is reported
+const MIN_SAMPLES: usize = 4;
+
+/// Slower consumption than this is treated as idle (grams per minute)
+const MIN_RATE_G_PER_MIN: f32 = 0.05;
+
+/// A reading this much heavier than the last one means the spool was
+/// swapped or refilled - start the history over (grams)
+const RESET_INCREASE_G: f32 = 20.0;
+
+/// Timestamped stable reading
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    at: Instant,
+    weight: f32,
+}
+
+/// Reading history for one spool
+struct SpoolHistory {
+    spool_id: String,
+    samples: VecDeque<Sample>,
+    last_used: Instant,
+}
+
+/// Consumption estimate for the spool on the scale
+#[derive(Debug, Clone)]
+pub struct ConsumptionEstimate {
+    /// Consumption rate (grams per hour)
+    pub rate_g_per_hour: f32,
+    /// Net filament left (grams)
+    pub net_weight: f32,
+    /// Minutes until the spool is empty at the current rate
+    pub time_to_empty_min: Option<u32>,
+    /// Print job of the printer the spool is assigned to
+    pub print_job: Option<SpoolPrintJob>,
+    /// Spool is predicted to run out before the print ends
+    pub runout_predicted: bool,
+    /// Readings used for the estimate
+    pub sample_count: usize,
+}
+
+/// Predicted runout shown to the user
+#[derive(Debug, Clone)]
+struct RunoutAlert {
+    seq: u32,
+    spool_id: String,
+    printer_name: String,
+    slot: String,
+    time_to_empty_min: u32,
+    print_remaining_min: u16,
+    dismissed: bool,
+}
+
+static HISTORY: Mutex<Vec<SpoolHistory>> = Mutex::new(Vec::new());
+
+/// Runout alert for the spool on the scale, if one is predicted
+static RUNOUT_ALERT: Mutex<Option<RunoutAlert>> = Mutex::new(None);
+
+/// Sequence number of the last raised alert
+static ALERT_SEQ: AtomicU32 = AtomicU32::new(0);
+
+/// Record a reading for the active spool (call from the network thread)
+pub fn poll(weight: f32, stable: bool) {
+    if !stable {
+        return;
+    }
+    let Some(profile) = spool_weight::active_spool() else {
+        clear_runout_alert();
+        return;
+    };
+
+    let now = Instant::now();
+    let mut history = HISTORY.lock().unwrap();
+
+    let index = match history.iter().position(|h| h.spool_id == profile.spool_id) {
+        Some(i) => i,
+        None => {
+            if history.len() >= MAX_TRACKED_SPOOLS {
+                if let Some(oldest) = history
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, h)| h.last_used)
+                    .map(|(i, _)| i)
+                {
+                    history.swap_remove(oldest);
+                }
+            }
+            history.push(SpoolHistory {
+                spool_id: profile.spool_id.clone(),
+                samples: VecDeque::with_capacity(HISTORY_LEN),
+                last_used: now,
+            });
+            history.len() - 1
+        }
+    };
+
+    let spool = &mut history[index];
+    spool.last_used = now;
+
+    if let Some(last) = spool.samples.back().copied() {
+        if weight - last.weight > RESET_INCREASE_G {
+            info!("Spool {} gained {:.0}g - resetting weight history",
+                  spool.spool_id, weight - last.weight);
+            spool.samples.clear();
+        } else if now.duration_since(last.at).as_millis() < SAMPLE_INTERVAL_MS {
+            return;
+        }
+    }
+
+    if spool.samples.len() >= HISTORY_LEN {
+        spool.samples.pop_front();
+    }
+    spool.samples.push_back(Sample { at: now, weight });
+    drop(history);
+
+    check_runout(&profile.spool_id);
+}
+
+/// Raise the runout alert when a runout is first predicted for the spool on
+/// the scale, keep its times current and clear it once the prediction goes away
+fn check_runout(spool_id: &str) {
+    let runout = estimate().filter(|e| e.runout_predicted);
+    let mut alert = RUNOUT_ALERT.lock().unwrap();
+    let current = alert.as_ref().is_some_and(|a| a.spool_id == spool_id);
+
+    match runout {
+        Some(ConsumptionEstimate { time_to_empty_min: Some(empty), print_job: Some(job), .. }) => {
+            if let Some(alert) = alert.as_mut().filter(|_| current) {
+                alert.time_to_empty_min = empty;
+                alert.print_remaining_min = job.remaining_time_min;
+                return;
+            }
+            warn!("Spool {} in {} {} predicted to run out in {} min, print needs {} min",
+                  spool_id, job.printer_name, job.slot, empty, job.remaining_time_min);
+            *alert = Some(RunoutAlert {
+                seq: ALERT_SEQ.fetch_add(1, Ordering::Relaxed) + 1,
+                spool_id: spool_id.to_string(),
+                printer_name: job.printer_name,
+                slot: job.slot,
+                time_to_empty_min: empty,
+                print_remaining_min: job.remaining_time_min,
+                dismissed: false,
+            });
+        }
+        _ => {
+            drop(alert);
+            clear_runout_alert();
+        }
+    }
+}
+
+/// Drop the runout alert (no prediction for the spool on the scale)
+fn clear_runout_alert() {
+    if let Some(alert) = RUNOUT_ALERT.lock().unwrap().take() {
+        info!("Runout no longer predicted for spool {}", alert.spool_id);
+    }
+}
+
+/// Fit the consumption rate (grams per minute, positive when weight drops)
+/// over the recent readings. Returns None if there isn't enough data.
+fn consumption_rate(samples: &VecDeque<Sample>, now: Instant) -> Option<(f32, usize)> {
+    // (minutes before now, weight) for readings within the window
+    let points: Vec<(f32, f32)> = samples
+        .iter()
+        .map(|s| (now.duration_since(s.at).as_secs_f32() / 60.0, s.weight))
+        .filter(|&(age, _)| age <= RATE_WINDOW_MIN)
+        .map(|(age, w)| (-age, w))
+        .collect();
+
+    if points.len() < MIN_SAMPLES {
+        return None;
+    }
+    let span = points.last()?.0 - points.first()?.0;
+    if span < MIN_SPAN_MIN {
+        return None;
+    }
+
+    // Least-squares slope of weight over time
+    let n = points.len() as f32;
+    let mean_t = points.iter().map(|p| p.0).sum::<f32>() / n;
+    let mean_w = points.iter().map(|p| p.1).sum::<f32>() / n;
+    let (mut cov, mut var) = (0.0f32, 0.0f32);
+    for &(t, w) in &points {
+        cov += (t - mean_t) * (w - mean_w);
+        var += (t - mean_t) * (t - mean_t);
+    }
+    if var <= 0.0 {
+        return None;
+    }
+
+    Some((-(cov / var), points.len()))
+}
+
+/// Consumption estimate for the spool currently on the scale
+pub fn estimate() -> Option<ConsumptionEstimate> {
+    let profile = spool_weight::active_spool()?;
+    let history = HISTORY.lock().unwrap();
+    let spool = history.iter().find(|h| h.spool_id == profile.spool_id)?;
+    let latest = spool.samples.back()?;
+
+    let net_weight = spool_weight::net_weight(latest.weight, profile.core_weight);
+    let (rate, sample_count) = consumption_rate(&spool.samples, Instant::now())?;
+    drop(history);
+
+    let time_to_empty_min = (rate >= MIN_RATE_G_PER_MIN).then(|| (net_weight / rate) as u32);
+    let print_job = backend_client::spool_print_job(&profile.spool_id);
+    let runout_predicted = match (time_to_empty_min, &print_job) {
+        (Some(empty), Some(job)) => empty < job.remaining_time_min as u32,
+        _ => false,
+    };
+
+    Some(ConsumptionEstimate {
+        rate_g_per_hour: rate.max(0.0) * 60.0,
+        net_weight,
+        time_to_empty_min,
+        print_job,
+        runout_predicted,
+        sample_count,
+    })
+}
+
+// =============================================================================
+// C-callable FFI Functions
+// =============================================================================
+
+/// C-compatible consumption estimate
+#[repr(C)]
+pub struct ConsumptionEstimateC {
+    pub rate_g_per_hour: f32,       // Consumption rate (0 if idle)
+    pub net_weight: f32,            // Net filament left (grams)
+    pub time_to_empty_min: i32,     // Minutes until empty (-1 if not consuming)
+    pub print_remaining_min: i32,   // Remaining print time (-1 if not printing)
+    pub sample_count: i32,          // Readings used for the estimate
+    pub runout_predicted: bool,     // Spool runs out before the print ends
+    pub valid: bool,                // False if not enough history yet
+}
+
+/// Get the consumption estimate for the spool on the scale
+/// Returns true if enough history was available
+#[no_mangle]
+pub extern "C" fn weight_history_get_estimate(out: *mut ConsumptionEstimateC) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let out = unsafe { &mut *out };
+
+    match estimate() {
+        Some(est) => {
+            *out = ConsumptionEstimateC {
+                rate_g_per_hour: est.rate_g_per_hour,
+                net_weight: est.net_weight,
+                time_to_empty_min: est.time_to_empty_min.map_or(-1, |m| m.min(i32::MAX as u32) as i32),
+                print_remaining_min: est.print_job.as_ref().map_or(-1, |j| j.remaining_time_min as i32),
+                sample_count: est.sample_count as i32,
+                runout_predicted: est.runout_predicted,
+                valid: true,
+            };
+            true
+        }
+        None => {
+            *out = ConsumptionEstimateC {
+                rate_g_per_hour: 0.0,
+                net_weight: 0.0,
+                time_to_empty_min: -1,
+                print_remaining_min: -1,
+                sample_count: 0,
+                runout_predicted: false,
+                valid: false,
+            };
+            false
+        }
+    }
+}
+
+/// C-compatible runout alert
+#[repr(C)]
+pub struct RunoutAlertC {
+    pub seq: u32,                   // Changes when a new alert is raised
+    pub printer_name: [u8; 32],     // Printer the spool feeds
+    pub slot: [u8; 16],             // Slot holding the spool ("AMS-A1", "External")
+    pub time_to_empty_min: i32,     // Minutes until the spool is empty
+    pub print_remaining_min: i32,   // Remaining print time
+}
+
+/// Get the runout alert to show. Returns false if none is pending
+/// (not predicted or dismissed).
+#[no_mangle]
+pub extern "C" fn weight_history_get_runout_alert(out: *mut RunoutAlertC) -> bool {
+    if out.is_null() {
+        return false;
+    }
+    let alert = RUNOUT_ALERT.lock().unwrap();
+    let Some(alert) = alert.as_ref().filter(|a| !a.dismissed) else {
+        return false;
+    };
+    let out = unsafe { &mut *out };
+    out.seq = alert.seq;
+    backend_client::copy_to_c_buf(&alert.printer_name, &mut out.printer_name);
+    backend_client::copy_to_c_buf(&alert.slot, &mut out.slot);
+    out.time_to_empty_min = alert.time_to_empty_min.min(i32::MAX as u32) as i32;
+    out.print_remaining_min = alert.print_remaining_min as i32;
+    true
+}
+
+/// Hide the runout alert until a new runout is predicted
+#[no_mangle]
+pub extern "C" fn weight_history_dismiss_runout(seq: u32) {
+    if let Some(alert) = RUNOUT_ALERT.lock().unwrap().as_mut().filter(|a| a.seq == seq) {
+        alert.dismissed = true;
+    }
+}
@@ -0,0 +1,305 @@
+//! Automatic Spool Weight Sync
+//!
+//! When a tag on the scale maps to an inventory spool and the reading settles,
+//! posts the measured weight to the backend once per placement. The UI polls
+//! for the resulting event to show a confirmation with an undo option; undo
+//! restores the inventory's weight tracking from before the sync. The UI only
+//! queues the undo: the network thread makes the HTTP call and the event
+//! carries the outcome back to the confirmation.
+
+use log::{info, warn};
+use std::ffi::c_int;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::backend_client::{self, SpoolWeightRecord};
+
+/// Reading must stay within this band of the settle reference (grams)
+const HYSTERESIS_G: f32 = 3.0;
+
+/// Reading must be stable for this long before syncing (ms)
+const SETTLE_TIME_MS: u128 = 3000;
+
+/// Minimum difference to the inventory weight worth posting (grams)
+const MIN_CHANGE_G: i32 = 5;
+
+/// Readings below this are treated as "nothing on the scale" (grams)
+const MIN_WEIGHT_G: f32 = 50.0;
+
+/// How long the UI offers to undo a sync (ms)
+const UNDO_WINDOW_MS: u128 = 15000;
+
+/// How long the UI shows the outcome of an undo (ms)
+const UNDO_RESULT_MS: u128 = 3000;
+
+/// Auto-sync progress for the spool currently on the scale
+struct Placement {
+    tag_id: String,
+    spool_id: String,
+    inventory_weight: i32,
+    /// Inventory tracking before the next sync
+    record: SpoolWeightRecord,
+    settle_ref: Option<(f32, Instant)>,
+    synced: bool,
+}
+
+/// Undo progress of a sync event
+#[derive(Clone, Copy)]
+enum Undo {
+    None,
+    /// Requested by the UI, waiting for the network thread
+    Pending,
+    Done(Instant),
+    Failed(Instant),
+}
+
+impl Undo {
+    /// Code used in the C interface (WEIGHT_SYNC_UNDO_*)
+    fn code(self) -> u8 {
+        match self {
+            Undo::None => 0,
+            Undo::Pending => 1,
+            Undo::Done(_) => 2,
+            Undo::Failed(_) => 3,
+        }
+    }
+}
+
+/// Completed sync, kept for the UI confirmation and undo
+struct SyncEvent {
+    seq: u32,
+    spool_id: String,
+    /// Tracking to restore on undo
+    previous: SpoolWeightRecord,
+    synced_weight: i32,
+    at: Instant,
+    undo: Undo,
+}
+
+impl SyncEvent {
+    /// Whether the UI should still show this event
+    fn visible(&self) -> bool {
+        match self.undo {
+            Undo::None => self.at.elapsed().as_millis() < UNDO_WINDOW_MS,
+            Undo::Pending => true,
+            Undo::Done(at) | Undo::Failed(at) => at.elapsed().as_millis() < UNDO_RESULT_MS,
+        }
+    }
+}
+
+struct WeightSyncState {
+    placement: Option<Placement>,
+    last_event: Option<SyncEvent>,
+    next_seq: u32,
+}
+
+static SYNC_STATE: Mutex<WeightSyncState> = Mutex::new(WeightSyncState {
+    placement: None,
+    last_event: None,
+    next_seq: 1,
+});
+
+/// Tag placed on the scale - look up its spool and arm auto-sync
+pub fn on_tag_detected(tag_id: &str) {
+    {
+        let state = SYNC_STATE.lock().unwrap();
+        if state.placement.as_ref().is_some_and(|p| p.tag_id == tag_id) {
+            return; // Already armed for this tag
+        }
+    }
+
+    // HTTP lookup outside the lock
+    let spool = backend_client::find_spool_with_record(tag_id);
+
+    let mut state = SYNC_STATE.lock().unwrap();
+    state.placement = spool.map(|(info, record)| {
+        let end = info.id.iter().position(|&b| b == 0).unwrap_or(info.id.len());
+        let spool_id = String::from_utf8_lossy(&info.id[..end]).to_string();
+        info!("Auto-sync armed for spool {} (inventory {}g)", spool_id, info.weight_current);
+        Placement {
+            tag_id: tag_id.to_string(),
+            spool_id,
+            inventory_weight: info.weight_current,
+            record,
+            settle_ref: None,
+            synced: false,
+        }
+    });
+}
+
+/// Tag removed from the scale - disarm auto-sync
+pub fn on_tag_removed() {
+    let mut state = SYNC_STATE.lock().unwrap();
+    state.placement = None;
+}
+
+/// Check whether the reading has settled and post it (call from the network thread)
+pub fn poll(weight: f32, stable: bool) {
+    let pending = {
+        let mut state = SYNC_STATE.lock().unwrap();
+        let placement = match state.placement.as_mut() {
+            Some(p) if !p.synced => p,
+            _ => return,
+        };
+
+        if !stable || weight < MIN_WEIGHT_G {
+            placement.settle_ref = None;
+            return;
+        }
+
+        let settle_ref = placement.settle_ref;
+        match settle_ref {
+            Some((reference, _)) if (weight - reference).abs() > HYSTERESIS_G => {
+                // Moved outside the band - restart the settle timer
+                placement.settle_ref = Some((weight, Instant::now()));
+                return;
+            }
+            Some((_, since)) if since.elapsed().as_millis() >= SETTLE_TIME_MS => {}
+            Some(_) => return,
+            None => {
+                placement.settle_ref = Some((weight, Instant::now()));
+                return;
+            }
+        }
+
+        let measured = weight.round() as i32;
+        placement.synced = true; // Once per placement, even if nothing changed
+        if (measured - placement.inventory_weight).abs() < MIN_CHANGE_G {
+            return;
+        }
+        (placement.spool_id.clone(), placement.record, measured)
+    };
+
+    let (spool_id, previous, measured) = pending;
+    info!("Auto-sync: spool {} {}g -> {}g", spool_id, previous.weight_current.unwrap_or(0), measured);
+
+    if !backend_client::sync_spool_weight(&spool_id, measured) {
+        warn!("Auto-sync failed for spool {}", spool_id);
+        // Allow a retry on the next settle
+        let mut state = SYNC_STATE.lock().unwrap();
+        if let Some(p) = state.placement.as_mut().filter(|p| p.spool_id == spool_id) {
+            p.synced = false;
+            p.settle_ref = None;
+        }
+        return;
+    }
+
+    let mut state = SYNC_STATE.lock().unwrap();
+    if let Some(p) = state.placement.as_mut().filter(|p| p.spool_id == spool_id) {
+        p.inventory_weight = measured;
+    }
+    let seq = state.next_seq;
+    state.next_seq = state.next_seq.wrapping_add(1).max(1);
+    state.last_event = Some(SyncEvent {
+        seq,
+        spool_id,
+        previous,
+        synced_weight: measured,
+        at: Instant::now(),
+        undo: Undo::None,
+    });
+}
+
+/// Run an undo queued by the UI (call from the network thread)
+pub fn poll_undo() {
+    let (seq, spool_id, previous) = {
+        let state = SYNC_STATE.lock().unwrap();
+        match state.last_event.as_ref() {
+            Some(e) if matches!(e.undo, Undo::Pending) => (e.seq, e.spool_id.clone(), e.previous),
+            _ => return,
+        }
+    };
+    match previous.weight_current {
+        Some(weight) => info!("Auto-sync undo: spool {} back to {}g", spool_id, weight),
+        None => info!("Auto-sync undo: spool {} back to no weight", spool_id),
+    }
+
+    // HTTP call outside the lock
+    let restored = backend_client::restore_spool_weight(&spool_id, &previous);
+    if !restored {
+        warn!("Auto-sync undo failed for spool {}", spool_id);
+    }
+
+    let mut state = SYNC_STATE.lock().unwrap();
+    if let Some(e) = state.last_event.as_mut().filter(|e| e.seq == seq) {
+        e.undo = if restored { Undo::Done(Instant::now()) } else { Undo::Failed(Instant::now()) };
+    }
+    if !restored {
+        return;
+    }
+    if let (Some(p), Some(weight)) = (state.placement.as_mut().filter(|p| p.spool_id == spool_id),
+                                      previous.weight_current) {
+        p.inventory_weight = weight;
+    }
+}
+
+// =============================================================================
+// C-callable FFI Functions
+// =============================================================================
+
+/// C-compatible auto-sync event for the UI confirmation
+#[repr(C)]
+pub struct WeightSyncEventC {
+    pub seq: u32,               // Increments per sync (0 = none)
+    pub spool_id: [u8; 64],     // Spool UUID
+    pub previous_weight: i32,   // Inventory weight before sync (grams, 0 = none)
+    pub synced_weight: i32,     // Weight posted (grams)
+    pub had_weight: bool,       // The spool had a weight before the sync
+    pub undo_state: u8,         // WEIGHT_SYNC_UNDO_* (none, pending, done, failed)
+}
+
+/// Get the most recent auto-sync event
+/// Returns true if an event within the undo window, or an undo in progress or
+/// just finished, is available
+#[no_mangle]
+pub extern "C" fn weight_sync_get_event(out: *mut WeightSyncEventC) -> bool {
+    if out.is_null() {
+        return false;
+    }
+
+    let state = SYNC_STATE.lock().unwrap();
+    let event = match state.last_event.as_ref() {
+        Some(e) if e.visible() => e,
+        _ => return false,
+    };
+
+    let out = unsafe { &mut *out };
+    out.seq = event.seq;
+    out.spool_id = [0; 64];
+    let bytes = event.spool_id.as_bytes();
+    let len = bytes.len().min(out.spool_id.len() - 1);
+    out.spool_id[..len].copy_from_slice(&bytes[..len]);
+    out.previous_weight = event.previous.weight_current.unwrap_or(0);
+    out.synced_weight = event.synced_weight;
+    out.had_weight = event.previous.weight_current.is_some();
+    out.undo_state = event.undo.code();
+    true
+}
+
+/// Queue a revert of the last auto-sync to the previous inventory tracking
+/// (also "no weight" for a spool weighed for the first time); the network
+/// thread runs it and the event's undo_state reports the outcome
+/// Returns 0 if queued, -1 if the undo window has passed
+#[no_mangle]
+pub extern "C" fn weight_sync_undo(seq: u32) -> c_int {
+    let mut state = SYNC_STATE.lock().unwrap();
+    match state.last_event.as_mut() {
+        Some(e) if e.seq == seq
+            && matches!(e.undo, Undo::None)
+            && e.at.elapsed().as_millis() < UNDO_WINDOW_MS =>
+        {
+            e.undo = Undo::Pending;
+            0
+        }
+        _ => -1,
+    }
+}
+
+/// Dismiss the confirmation for an auto-sync event (a queued undo still runs)
+#[no_mangle]
+pub extern "C" fn weight_sync_dismiss(seq: u32) {
+    let mut state = SYNC_STATE.lock().unwrap();
+    if state.last_event.as_ref().is_some_and(|e| e.seq == seq && !matches!(e.undo, Undo::Pending)) {
+        state.last_event = None;
+    }
+}
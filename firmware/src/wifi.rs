@@ -186,23 +186,3 @@ pub fn format_ip(ip: [u8; 4]) -> String<16> {
 pub fn wifi_init_help() {
     info!("WiFi initialization helper - see wifi.rs for setup code");
 }
-
-// =============================================================================
-// Configuration Portal (Future)
-// =============================================================================
-
-/// Start configuration portal (AP mode) for initial setup.
-///
-/// When WiFi credentials are not configured, the device starts an AP
-/// named "SpoolBuddy-XXXX" with a captive portal for configuration.
-pub async fn start_config_portal() {
-    info!("Starting WiFi config portal (not yet implemented)");
-
-    // TODO: Implement AP mode for configuration
-    // 1. Start AP with name "SpoolBuddy-XXXX" (last 4 of MAC)
-    // 2. Start DNS server redirecting all queries to self
-    // 3. Start HTTP server with configuration page
-    // 4. Accept WiFi credentials and server URL
-    // 5. Store in NVS
-    // 6. Restart in station mode
-}
@@ -1,21 +1,185 @@
 //! WiFi Manager with C-callable interface
 //!
 //! Provides async WiFi connection with status polling for UI integration.
-//! The connection runs in a background thread to avoid blocking the UI.
-//! Credentials are persisted to NVS for auto-reconnect on boot.
+//! A supervisor thread owns connecting: it listens for WiFi events, reconnects
+//! with exponential backoff when the AP drops and keeps state and RSSI live.
+//! Callers only queue commands, so the UI never blocks on a connect.
+//! Credentials are kept in the settings store for auto-reconnect on boot.
 
 use esp_idf_hal::modem::Modem;
 use esp_idf_svc::eventloop::EspSystemEventLoop;
-use esp_idf_svc::nvs::{EspDefaultNvsPartition, EspNvs};
-use esp_idf_svc::wifi::{AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi};
+use esp_idf_svc::nvs::EspDefaultNvsPartition;
+use esp_idf_svc::wifi::{
+    AccessPointConfiguration, AuthMethod, BlockingWifi, ClientConfiguration, Configuration, EspWifi,
+    PmfConfiguration, ScanMethod, ScanSortMethod, WifiEvent,
+};
 use log::{info, warn, error};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::ffi::{CStr, c_char, c_int};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
 use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::settings::{self, DeviceSettings};
+
+/// Maximum number of saved networks
+pub const MAX_SAVED_NETWORKS: usize = 5;
+
+/// CA of the WPA2-Enterprise authentication server (NUL-terminated PEM from
+/// `SPOOLBUDDY_EAP_CA` at build time, empty without it)
+static EAP_CA: &[u8] = include_bytes!(concat!(env!("OUT_DIR"), "/eap_ca.pem"));
+
+/// Authentication for a saved network
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WifiAuth {
+    /// Open if no password, otherwise WPA2 or better
+    #[default]
+    Auto,
+    Open,
+    Wpa2Personal,
+    /// WPA3-only (SAE, management frame protection required)
+    Wpa3Personal,
+    /// WPA2/WPA3 transition mode
+    Wpa2Wpa3Personal,
+    /// WPA2-Enterprise (PEAP/MSCHAPv2)
+    Wpa2Enterprise,
+}
+
+impl WifiAuth {
+    /// Code used in the C interface
+    pub fn code(self) -> u8 {
+        match self {
+            WifiAuth::Auto => 0,
+            WifiAuth::Open => 1,
+            WifiAuth::Wpa2Personal => 2,
+            WifiAuth::Wpa3Personal => 3,
+            WifiAuth::Wpa2Wpa3Personal => 4,
+            WifiAuth::Wpa2Enterprise => 5,
+        }
+    }
+
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(WifiAuth::Auto),
+            1 => Some(WifiAuth::Open),
+            2 => Some(WifiAuth::Wpa2Personal),
+            3 => Some(WifiAuth::Wpa3Personal),
+            4 => Some(WifiAuth::Wpa2Wpa3Personal),
+            5 => Some(WifiAuth::Wpa2Enterprise),
+            _ => None,
+        }
+    }
+}
+
+/// Saved network (list order is priority, index 0 = highest)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedNetwork {
+    pub ssid: String,
+    /// PSK, or the EAP password for enterprise networks
+    pub password: String,
+    pub auth: WifiAuth,
+    /// EAP outer identity (enterprise only, falls back to the username)
+    pub eap_identity: String,
+    /// EAP inner username (enterprise only)
+    pub eap_username: String,
+    /// Join without verifying the authentication server (enterprise only,
+    /// needed when the build has no EAP CA)
+    pub eap_allow_unverified: bool,
+    /// Only join this access point
+    pub bssid: Option<[u8; 6]>,
+    /// SSID is not broadcast - probe for it by name
+    pub hidden: bool,
+}
+
+impl SavedNetwork {
+    /// Personal/open network with just a password
+    pub fn new(ssid: &str, password: &str) -> Self {
+        SavedNetwork {
+            ssid: ssid.to_string(),
+            password: password.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Check lengths and required fields before saving or connecting
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ssid.is_empty() || self.ssid.len() > 32 {
+            return Err("SSID must be 1-32 characters".to_string());
+        }
+        if self.password.len() > 64 {
+            return Err("Password too long".to_string());
+        }
+        if self.eap_identity.len() > 63 || self.eap_username.len() > 63 {
+            return Err("EAP identity/username too long".to_string());
+        }
+        match self.auth {
+            WifiAuth::Wpa2Enterprise if self.eap_username.is_empty() => {
+                Err("Enterprise networks need a username".to_string())
+            }
+            WifiAuth::Wpa2Enterprise if EAP_CA.is_empty() && !self.eap_allow_unverified => Err(
+                "No EAP CA in this build - allow an unverified server to join this network".to_string(),
+            ),
+            WifiAuth::Wpa2Personal | WifiAuth::Wpa3Personal | WifiAuth::Wpa2Wpa3Personal
+                if self.password.len() < 8 =>
+            {
+                Err("WPA password must be at least 8 characters".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// IPv4 settings for the station interface (applies to every network)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct IpConfig {
+    /// Use the addresses below instead of DHCP
+    pub use_static: bool,
+    pub ip: [u8; 4],
+    pub netmask: [u8; 4],
+    pub gateway: [u8; 4],
+    /// DNS servers (0.0.0.0 = not set)
+    pub dns_primary: [u8; 4],
+    pub dns_secondary: [u8; 4],
+}
+
+impl IpConfig {
+    /// Check a static configuration is usable (DHCP needs nothing)
+    pub fn validate(&self) -> Result<(), String> {
+        if !self.use_static {
+            return Ok(());
+        }
+        if self.ip == [0; 4] || self.ip[0] >= 224 {
+            return Err("Invalid IP address".to_string());
+        }
+        let mask = u32::from_be_bytes(self.netmask);
+        // Netmask must be a run of ones followed by zeros
+        if mask == 0 || mask.leading_ones() + mask.trailing_zeros() != 32 {
+            return Err("Invalid netmask".to_string());
+        }
+        let ip = u32::from_be_bytes(self.ip);
+        let host = ip & !mask;
+        if host == 0 || host == !mask {
+            return Err("IP address is the network or broadcast address".to_string());
+        }
+        if self.gateway != [0; 4] && (u32::from_be_bytes(self.gateway) & mask) != (ip & mask) {
+            return Err("Gateway is not in the IP address's subnet".to_string());
+        }
+        Ok(())
+    }
+}
 
-// NVS keys for WiFi credentials
-const NVS_NAMESPACE: &str = "wifi";
-const NVS_KEY_SSID: &str = "ssid";
-const NVS_KEY_PASSWORD: &str = "password";
+/// Parse a dotted IPv4 address ("" parses as 0.0.0.0)
+pub fn parse_ipv4(s: &str) -> Option<[u8; 4]> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Some([0; 4]);
+    }
+    s.parse::<std::net::Ipv4Addr>().ok().map(|ip| ip.octets())
+}
 
 /// WiFi connection state
 #[derive(Debug, Clone, PartialEq)]
@@ -30,17 +194,21 @@ pub enum WifiState {
 /// Global WiFi manager state
 struct WifiManager {
     state: WifiState,
-    ssid: String,
-    password: String,
-    // WiFi handle stored after init - using Option to handle initial state
-    wifi: Option<BlockingWifi<EspWifi<'static>>>,
-    // NVS partition for storing credentials
-    nvs: Option<EspDefaultNvsPartition>,
+    // Network being joined or joined (empty SSID if none)
+    current: SavedNetwork,
+    // Known networks in priority order
+    networks: Vec<SavedNetwork>,
+    // DHCP or static IPv4 for the station
+    ip_config: IpConfig,
 }
 
 // Global WiFi manager - protected by mutex
+// Only holds state, so status queries never wait on the driver
 static WIFI_MANAGER: Mutex<Option<WifiManager>> = Mutex::new(None);
 
+// WiFi driver handle - locked only for short driver calls
+static WIFI_DRIVER: Mutex<Option<BlockingWifi<EspWifi<'static>>>> = Mutex::new(None);
+
 /// Initialize the WiFi subsystem (call once at startup)
 /// This sets up the WiFi hardware but doesn't connect yet
 pub fn init_wifi_system(
@@ -53,202 +221,680 @@ pub fn init_wifi_system(
     // Leak modem to get 'static lifetime
     let modem: Modem<'static> = unsafe { std::mem::transmute(modem) };
 
-    let esp_wifi = EspWifi::new(modem, sysloop.clone(), nvs.clone())
+    let esp_wifi = EspWifi::new(modem, sysloop.clone(), nvs)
         .map_err(|e| format!("Failed to create EspWifi: {:?}", e))?;
 
     let wifi = BlockingWifi::wrap(esp_wifi, sysloop.clone())
         .map_err(|e| format!("Failed to wrap WiFi: {:?}", e))?;
+    *WIFI_DRIVER.lock().unwrap() = Some(wifi);
 
-    // Load saved credentials from NVS
-    let (saved_ssid, saved_password) = load_credentials_from_nvs(nvs.as_ref());
+    // Saved networks and IP settings
+    let (networks, ip_config) = settings::read(|s| (s.wifi.networks.clone(), s.wifi.ip));
+    for (i, net) in networks.iter().enumerate() {
+        info!("Saved WiFi network [{}]: {} ({:?}{})", i, net.ssid, net.auth,
+              if net.hidden { ", hidden" } else { "" });
+    }
+    if ip_config.use_static {
+        let ip = ip_config.ip;
+        info!("Static IP configured: {}.{}.{}.{}", ip[0], ip[1], ip[2], ip[3]);
+    }
+    let has_networks = !networks.is_empty();
 
-    let mut manager = WIFI_MANAGER.lock().unwrap();
-    *manager = Some(WifiManager {
+    *WIFI_MANAGER.lock().unwrap() = Some(WifiManager {
         state: WifiState::Disconnected,
-        ssid: saved_ssid.clone(),
-        password: saved_password.clone(),
-        wifi: Some(wifi),
-        nvs,
+        current: SavedNetwork::default(),
+        networks,
+        ip_config,
     });
+    settings::subscribe(on_settings_changed);
+
+    // Start the connection supervisor; disconnect events are forwarded to it
+    let (tx, rx) = mpsc::channel();
+    *SUPERVISOR_TX.lock().unwrap() = Some(tx);
+    let subscription = sysloop
+        .subscribe::<WifiEvent, _>(|event| {
+            if matches!(event, WifiEvent::StaDisconnected(..)) {
+                let _ = send_command(SupervisorCmd::LinkLost);
+            }
+        })
+        .map_err(|e| format!("Failed to subscribe to WiFi events: {:?}", e))?;
+    std::thread::Builder::new()
+        .name("wifi_supervisor".into())
+        .stack_size(8192)
+        .spawn(move || {
+            let _subscription = subscription; // Keep events flowing while we run
+            run_supervisor(rx);
+        })
+        .map_err(|e| format!("Failed to start WiFi supervisor: {:?}", e))?;
 
     info!("WiFi subsystem initialized");
 
-    // Auto-connect if we have saved credentials
-    if !saved_ssid.is_empty() {
-        info!("Found saved WiFi credentials, auto-connecting to: {}", saved_ssid);
-        drop(manager); // Release lock before calling start_connect
-        let _ = start_connect(&saved_ssid, &saved_password);
+    // Auto-connect to the best saved network
+    if has_networks {
+        let _ = connect_best();
     }
 
     Ok(())
 }
 
-/// Load WiFi credentials from NVS
-fn load_credentials_from_nvs(nvs: Option<&EspDefaultNvsPartition>) -> (String, String) {
-    let Some(nvs_partition) = nvs else {
-        return (String::new(), String::new());
+/// Save the manager's network list
+fn save_networks() {
+    let manager_guard = WIFI_MANAGER.lock().unwrap();
+    let Some(manager) = manager_guard.as_ref() else {
+        return;
     };
+    let networks = manager.networks.clone();
+    drop(manager_guard); // Settings subscribers lock the manager again
 
-    let Ok(nvs) = EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) else {
-        warn!("Failed to open NVS namespace for reading");
-        return (String::new(), String::new());
-    };
+    let count = networks.len();
+    match settings::update(|s| s.wifi.networks = networks) {
+        Ok(()) => info!("Saved {} WiFi network(s)", count),
+        Err(e) => error!("Failed to save WiFi networks: {}", e),
+    }
+}
 
-    let mut ssid_buf = [0u8; 64];
-    let mut password_buf = [0u8; 64];
+/// Pick up networks and IP settings changed through the settings store
+/// (e.g. a restored backup). The current connection is kept; the new list
+/// is used from the next connect.
+fn on_settings_changed(old: &DeviceSettings, new: &DeviceSettings) {
+    if old.wifi == new.wifi {
+        return;
+    }
+    let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+    if let Some(manager) = manager_guard.as_mut() {
+        manager.networks = new.wifi.networks.clone();
+        manager.ip_config = new.wifi.ip;
+    }
+}
 
-    let ssid = match nvs.get_str(NVS_KEY_SSID, &mut ssid_buf) {
-        Ok(Some(s)) => s.to_string(),
-        _ => String::new(),
-    };
+/// Add a network or update its settings. New networks go to the top of the
+/// list (most recently added is preferred until reordered).
+pub fn add_network(network: SavedNetwork) -> Result<(), String> {
+    network.validate()?;
+    {
+        let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+        let manager = manager_guard.as_mut().ok_or("WiFi not initialized")?;
+        if let Some(net) = manager.networks.iter_mut().find(|n| n.ssid == network.ssid) {
+            *net = network;
+        } else {
+            if manager.networks.len() >= MAX_SAVED_NETWORKS {
+                // Make room by dropping the lowest priority network
+                if let Some(dropped) = manager.networks.pop() {
+                    info!("Saved network list full, forgetting: {}", dropped.ssid);
+                }
+            }
+            manager.networks.insert(0, network);
+        }
+    }
+    save_networks();
+    Ok(())
+}
 
-    let password = match nvs.get_str(NVS_KEY_PASSWORD, &mut password_buf) {
-        Ok(Some(s)) => s.to_string(),
-        _ => String::new(),
-    };
+/// Forget a saved network by index
+pub fn remove_network(index: usize) -> Result<(), String> {
+    {
+        let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+        let manager = manager_guard.as_mut().ok_or("WiFi not initialized")?;
+        if index >= manager.networks.len() {
+            return Err("Invalid network index".to_string());
+        }
+        let removed = manager.networks.remove(index);
+        info!("Forgot WiFi network: {}", removed.ssid);
+    }
+    save_networks();
+    Ok(())
+}
 
-    if !ssid.is_empty() {
-        info!("Loaded saved WiFi SSID: {}", ssid);
+/// Move a saved network to a new priority position
+pub fn move_network(from: usize, to: usize) -> Result<(), String> {
+    {
+        let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+        let manager = manager_guard.as_mut().ok_or("WiFi not initialized")?;
+        let len = manager.networks.len();
+        if from >= len || to >= len {
+            return Err("Invalid network index".to_string());
+        }
+        let net = manager.networks.remove(from);
+        manager.networks.insert(to, net);
     }
+    save_networks();
+    Ok(())
+}
 
-    (ssid, password)
+/// Saved networks in priority order
+pub fn saved_networks() -> Vec<SavedNetwork> {
+    let manager_guard = WIFI_MANAGER.lock().unwrap();
+    manager_guard.as_ref().map(|m| m.networks.clone()).unwrap_or_default()
 }
 
-/// Save WiFi credentials to NVS
-fn save_credentials_to_nvs(ssid: &str, password: &str) {
+// ============================================================================
+// Station IP configuration
+// ============================================================================
+
+/// Current station IPv4 settings
+pub fn ip_config() -> IpConfig {
     let manager_guard = WIFI_MANAGER.lock().unwrap();
-    let Some(manager) = manager_guard.as_ref() else {
-        return;
-    };
-    let Some(nvs_partition) = manager.nvs.as_ref() else {
-        warn!("No NVS partition available for saving credentials");
-        return;
-    };
+    manager_guard.as_ref().map(|m| m.ip_config).unwrap_or_default()
+}
 
-    let nvs_clone = nvs_partition.clone();
-    drop(manager_guard); // Release lock before NVS operations
+/// Validate, persist and apply new station IPv4 settings. Takes effect
+/// immediately if the station is running, otherwise on the next connect.
+pub fn set_ip_config(config: IpConfig) -> Result<(), String> {
+    config.validate()?;
 
-    let Ok(nvs) = EspNvs::new(nvs_clone, NVS_NAMESPACE, true) else {
-        error!("Failed to open NVS namespace for writing");
-        return;
-    };
+    {
+        let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+        let manager = manager_guard.as_mut().ok_or("WiFi not initialized")?;
+        manager.ip_config = config;
+    }
+    settings::update(|s| s.wifi.ip = config)?;
 
-    if let Err(e) = nvs.set_str(NVS_KEY_SSID, ssid) {
-        error!("Failed to save SSID to NVS: {:?}", e);
-        return;
+    let mut driver_guard = WIFI_DRIVER.lock().unwrap();
+    if let Some(wifi) = driver_guard.as_mut() {
+        if wifi.is_started().unwrap_or(false) {
+            apply_ip_config(wifi, &config)?;
+        }
     }
+    info!("Station IP mode: {}", if config.use_static { "static" } else { "DHCP" });
+    Ok(())
+}
 
-    if let Err(e) = nvs.set_str(NVS_KEY_PASSWORD, password) {
-        error!("Failed to save password to NVS: {:?}", e);
-        return;
+/// Configure the station netif for DHCP or the static addresses
+fn apply_ip_config(wifi: &BlockingWifi<EspWifi<'static>>, config: &IpConfig) -> Result<(), String> {
+    use esp_idf_svc::handle::RawHandle;
+    use esp_idf_sys::*;
+
+    let netif = wifi.wifi().sta_netif().handle();
+
+    unsafe {
+        if !config.use_static {
+            let err = esp_netif_dhcpc_start(netif);
+            if err != ESP_OK && err != ESP_ERR_ESP_NETIF_DHCP_ALREADY_STARTED {
+                return Err(format!("Failed to start DHCP client: {}", err));
+            }
+            return Ok(());
+        }
+
+        let err = esp_netif_dhcpc_stop(netif);
+        if err != ESP_OK && err != ESP_ERR_ESP_NETIF_DHCP_ALREADY_STOPPED {
+            return Err(format!("Failed to stop DHCP client: {}", err));
+        }
+
+        // lwIP keeps addresses in network byte order, i.e. octets in memory order
+        let info = esp_netif_ip_info_t {
+            ip: esp_ip4_addr_t { addr: u32::from_ne_bytes(config.ip) },
+            netmask: esp_ip4_addr_t { addr: u32::from_ne_bytes(config.netmask) },
+            gw: esp_ip4_addr_t { addr: u32::from_ne_bytes(config.gateway) },
+        };
+        let err = esp_netif_set_ip_info(netif, &info);
+        if err != ESP_OK {
+            return Err(format!("Failed to set static IP: {}", err));
+        }
+
+        let servers = [
+            (esp_netif_dns_type_t_ESP_NETIF_DNS_MAIN, config.dns_primary),
+            (esp_netif_dns_type_t_ESP_NETIF_DNS_BACKUP, config.dns_secondary),
+        ];
+        for (kind, server) in servers {
+            if server == [0; 4] {
+                continue;
+            }
+            let mut dns: esp_netif_dns_info_t = std::mem::zeroed();
+            dns.ip.type_ = ESP_IPADDR_TYPE_V4 as u8;
+            dns.ip.u_addr.ip4.addr = u32::from_ne_bytes(server);
+            let err = esp_netif_set_dns_info(netif, kind, &mut dns);
+            if err != ESP_OK {
+                return Err(format!("Failed to set DNS server: {}", err));
+            }
+        }
     }
+    Ok(())
+}
 
-    info!("WiFi credentials saved to NVS");
+/// Order saved networks for connecting: those seen in the scan first,
+/// strongest signal first (priority breaks ties), then the rest by priority.
+/// Hidden networks never show up by name, so `visible` should include the
+/// result of probing for them (see probe_hidden).
+fn rank_networks(networks: &[SavedNetwork], visible: &[(String, i8)]) -> Vec<SavedNetwork> {
+    let mut ranked: Vec<(Option<i8>, usize, &SavedNetwork)> = networks
+        .iter()
+        .enumerate()
+        .map(|(priority, net)| {
+            let rssi = visible.iter().find(|(ssid, _)| *ssid == net.ssid).map(|&(_, r)| r);
+            (rssi, priority, net)
+        })
+        .collect();
+    ranked.sort_by(|a, b| match (a.0, b.0) {
+        (Some(ra), Some(rb)) => rb.cmp(&ra).then(a.1.cmp(&b.1)),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.1.cmp(&b.1),
+    });
+    ranked.into_iter().map(|(_, _, net)| net.clone()).collect()
 }
 
-/// Start WiFi connection (non-blocking, runs in background)
-fn start_connect(ssid: &str, password: &str) -> Result<(), String> {
-    let ssid_owned = ssid.to_string();
-    let password_owned = password.to_string();
+/// Scan and connect to the strongest known network, falling back through
+/// the rest of the list and retrying with backoff (non-blocking)
+pub fn connect_best() -> Result<(), String> {
+    send_command(SupervisorCmd::ConnectBest)
+}
 
-    // Update state to Connecting
-    {
-        let mut manager_guard = WIFI_MANAGER.lock().unwrap();
-        let manager = manager_guard.as_mut().ok_or("WiFi not initialized")?;
-        manager.state = WifiState::Connecting;
-        manager.ssid = ssid_owned.clone();
-        manager.password = password_owned.clone();
+/// Start WiFi connection (non-blocking, runs in the supervisor)
+fn start_connect(network: SavedNetwork) -> Result<(), String> {
+    network.validate()?;
+
+    info!("Starting WiFi connection to: {} ({:?})", network.ssid, network.auth);
+    // Report Connecting right away so the UI doesn't wait for the supervisor tick
+    set_state(WifiState::Connecting, Some(&network));
+    send_command(SupervisorCmd::Connect(network))
+}
+
+/// Set the WiFi state (and the network it refers to, if given)
+fn set_state(state: WifiState, network: Option<&SavedNetwork>) {
+    let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+    if let Some(manager) = manager_guard.as_mut() {
+        manager.state = state;
+        if let Some(net) = network {
+            manager.current = net.clone();
+        }
     }
+}
 
-    info!("Starting WiFi connection to: {}", ssid_owned);
+// ============================================================================
+// Connection supervisor
+// ============================================================================
 
-    // Do the connection in the current context (we'll make it truly async later if needed)
-    // For now, we'll do a blocking connect but update state properly
-    let result = do_connect(&ssid_owned, &password_owned);
+/// Time allowed for association and DHCP before an attempt fails
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Supervisor tick (also the connect poll interval)
+const TICK: Duration = Duration::from_millis(250);
+
+/// RSSI refresh interval while connected
+const RSSI_REFRESH: Duration = Duration::from_secs(5);
+
+/// Reconnect backoff bounds (doubles per failed round)
+const BACKOFF_MIN: Duration = Duration::from_secs(1);
+const BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// Commands for the supervisor thread
+enum SupervisorCmd {
+    /// Connect to a specific network (from the UI) - no retries on failure
+    Connect(SavedNetwork),
+    /// Connect to the best saved network, retrying with backoff
+    ConnectBest,
+    /// Station lost its AP (from the WiFi event loop)
+    LinkLost,
+    /// Stop connecting (user disconnect)
+    Stop,
+}
 
-    // Update state based on result
-    {
-        let mut manager_guard = WIFI_MANAGER.lock().unwrap();
-        if let Some(manager) = manager_guard.as_mut() {
-            match result {
-                Ok((ip, rssi)) => {
-                    manager.state = WifiState::Connected { ip, rssi };
-                    info!("WiFi connected! IP: {}.{}.{}.{} RSSI: {}dBm", ip[0], ip[1], ip[2], ip[3], rssi);
+static SUPERVISOR_TX: Mutex<Option<Sender<SupervisorCmd>>> = Mutex::new(None);
+
+fn send_command(cmd: SupervisorCmd) -> Result<(), String> {
+    let tx_guard = SUPERVISOR_TX.lock().unwrap();
+    let tx = tx_guard.as_ref().ok_or("WiFi not initialized")?;
+    tx.send(cmd).map_err(|_| "WiFi supervisor not running".to_string())
+}
+
+/// Connection attempt in progress
+struct Attempt {
+    network: SavedNetwork,
+    started: Instant,
+}
+
+struct Supervisor {
+    /// Networks still to try in this round
+    candidates: VecDeque<SavedNetwork>,
+    attempt: Option<Attempt>,
+    /// Retry with backoff once a round fails (off for manual connects)
+    auto_retry: bool,
+    /// Consecutive failed rounds
+    failures: u32,
+    retry_at: Option<Instant>,
+    last_rssi: Instant,
+}
+
+fn run_supervisor(rx: Receiver<SupervisorCmd>) {
+    let mut sup = Supervisor {
+        candidates: VecDeque::new(),
+        attempt: None,
+        auto_retry: false,
+        failures: 0,
+        retry_at: None,
+        last_rssi: Instant::now(),
+    };
+
+    loop {
+        match rx.recv_timeout(TICK) {
+            Ok(cmd) => sup.handle(cmd),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+        sup.tick();
+    }
+}
+
+impl Supervisor {
+    fn reset(&mut self, auto_retry: bool) {
+        self.candidates.clear();
+        self.attempt = None;
+        self.auto_retry = auto_retry;
+        self.failures = 0;
+        self.retry_at = None;
+    }
+
+    fn handle(&mut self, cmd: SupervisorCmd) {
+        match cmd {
+            SupervisorCmd::Connect(net) => {
+                self.reset(false);
+                self.candidates.push_back(net);
+            }
+            SupervisorCmd::ConnectBest => {
+                self.reset(true);
+                self.candidates = ranked_candidates();
+                if self.candidates.is_empty() {
+                    warn!("No saved WiFi networks to connect to");
+                }
+            }
+            SupervisorCmd::LinkLost => {
+                // Our own attempts and user disconnects also raise this event
+                if !matches!(get_state(), WifiState::Connected { .. }) {
+                    return;
                 }
-                Err(ref e) => {
-                    manager.state = WifiState::Error(e.clone());
-                    warn!("WiFi connection failed: {}", e);
+                let current = {
+                    let manager_guard = WIFI_MANAGER.lock().unwrap();
+                    manager_guard.as_ref().map(|m| m.current.clone())
+                };
+                let Some(current) = current else { return };
+                warn!("WiFi link to '{}' lost, reconnecting", current.ssid);
+                set_state(WifiState::Disconnected, None);
+                // Rejoin the same AP first; later rounds re-rank all saved networks
+                self.reset(true);
+                self.candidates.push_back(current);
+            }
+            SupervisorCmd::Stop => self.reset(false),
+        }
+    }
+
+    fn tick(&mut self) {
+        if let Some(attempt) = self.attempt.as_ref() {
+            match connected_ip() {
+                Some(ip) => {
+                    let network = attempt.network.clone();
+                    self.attempt = None;
+                    self.on_connected(network, ip);
+                }
+                None if attempt.started.elapsed() >= CONNECT_TIMEOUT => {
+                    let ssid = attempt.network.ssid.clone();
+                    self.attempt = None;
+                    self.on_failed(format!("Timed out connecting to {}", ssid));
+                }
+                None => {}
+            }
+            return;
+        }
+
+        if let Some(net) = self.candidates.pop_front() {
+            self.begin(net);
+            return;
+        }
+
+        if self.retry_at.is_some_and(|at| Instant::now() >= at) {
+            self.retry_at = None;
+            self.candidates = ranked_candidates();
+            return;
+        }
+
+        // Keep RSSI current while connected
+        if self.last_rssi.elapsed() >= RSSI_REFRESH {
+            self.last_rssi = Instant::now();
+            if let Some(rssi) = read_rssi() {
+                let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+                if let Some(manager) = manager_guard.as_mut() {
+                    if let WifiState::Connected { rssi: ref mut current, .. } = manager.state {
+                        *current = rssi;
+                    }
                 }
             }
         }
     }
 
-    // Save credentials to NVS after successful connection
-    if result.is_ok() {
-        save_credentials_to_nvs(&ssid_owned, &password_owned);
+    /// Configure the station and kick off a non-blocking connect
+    fn begin(&mut self, network: SavedNetwork) {
+        info!("Connecting to WiFi: {}", network.ssid);
+        set_state(WifiState::Connecting, Some(&network));
+
+        match start_station(&network) {
+            Ok(()) => {
+                self.attempt = Some(Attempt {
+                    network,
+                    started: Instant::now(),
+                });
+            }
+            Err(e) => self.on_failed(e),
+        }
+    }
+
+    fn on_connected(&mut self, network: SavedNetwork, ip: [u8; 4]) {
+        let rssi = read_rssi().unwrap_or(0);
+        set_state(WifiState::Connected { ip, rssi }, Some(&network));
+        info!("WiFi connected! IP: {}.{}.{}.{} RSSI: {}dBm", ip[0], ip[1], ip[2], ip[3], rssi);
+
+        self.candidates.clear();
+        self.failures = 0;
+        self.retry_at = None;
+        self.last_rssi = Instant::now();
+
+        // Remember the network after a successful connection
+        let known = saved_networks().iter().any(|n| *n == network);
+        if !known {
+            let _ = add_network(network);
+        }
     }
 
-    result.map(|_| ())
+    fn on_failed(&mut self, reason: String) {
+        warn!("WiFi connection failed: {}", reason);
+        if !self.candidates.is_empty() {
+            return; // Try the next candidate on the next tick
+        }
+
+        if self.auto_retry {
+            let delay = backoff(self.failures);
+            self.failures = self.failures.saturating_add(1);
+            self.retry_at = Some(Instant::now() + delay);
+            info!("Retrying WiFi in {}s", delay.as_secs());
+        }
+        set_state(WifiState::Error(reason), None);
+    }
 }
 
-/// Actually perform the WiFi connection (blocking)
-fn do_connect(ssid: &str, password: &str) -> Result<([u8; 4], i8), String> {
-    let mut manager_guard = WIFI_MANAGER.lock().unwrap();
-    let manager = manager_guard.as_mut().ok_or("WiFi not initialized")?;
+/// Backoff for the given number of failed rounds
+fn backoff(failures: u32) -> Duration {
+    BACKOFF_MIN
+        .saturating_mul(1u32 << failures.min(6))
+        .min(BACKOFF_MAX)
+}
 
-    let wifi = manager.wifi.as_mut().ok_or("WiFi handle not available")?;
+/// Saved networks ordered for a connection round
+fn ranked_candidates() -> VecDeque<SavedNetwork> {
+    let networks = saved_networks();
+    if networks.is_empty() {
+        return VecDeque::new();
+    }
+    let mut visible = scan_networks();
+    for net in networks.iter().filter(|n| n.hidden) {
+        if let Some(rssi) = probe_hidden(&net.ssid) {
+            visible.push((net.ssid.clone(), rssi));
+        }
+    }
+    rank_networks(&networks, &visible).into()
+}
 
-    // Configure WiFi
-    let config = Configuration::Client(ClientConfiguration {
-        ssid: ssid.try_into().map_err(|_| "SSID too long")?,
-        bssid: None,
-        auth_method: if password.is_empty() { AuthMethod::None } else { AuthMethod::WPA2Personal },
+/// Directed scan for a hidden SSID (probe requests carry the name, so the
+/// AP answers even though it doesn't broadcast it). Returns the best RSSI.
+fn probe_hidden(ssid: &str) -> Option<i8> {
+    let mut driver_guard = WIFI_DRIVER.lock().unwrap();
+    let wifi = driver_guard.as_mut()?;
+    if !wifi.is_started().unwrap_or(false) {
+        return None;
+    }
+
+    let scan_config = esp_idf_svc::wifi::config::ScanConfig {
+        ssid: Some(ssid.try_into().ok()?),
+        show_hidden: true,
+        ..Default::default()
+    };
+    let driver = wifi.wifi_mut().driver_mut();
+    if let Err(e) = driver.start_scan(&scan_config, true) {
+        warn!("Probe for hidden network '{}' failed: {:?}", ssid, e);
+        return None;
+    }
+    let results = driver.get_scan_result().ok()?;
+    results.iter().map(|ap| ap.signal_strength).max()
+}
+
+/// Set the station config (keeping the SoftAP if it is running) and start
+/// connecting without waiting for the result
+fn start_station(network: &SavedNetwork) -> Result<(), String> {
+    let ip_config = ip_config();
+    let mut driver_guard = WIFI_DRIVER.lock().unwrap();
+    let wifi = driver_guard.as_mut().ok_or("WiFi handle not available")?;
+
+    // auth_method is the weakest mode accepted; WPA3 needs PMF
+    let (auth_method, pmf_cfg) = match network.auth {
+        WifiAuth::Auto if network.password.is_empty() => (AuthMethod::None, PmfConfiguration::NotCapable),
+        WifiAuth::Auto | WifiAuth::Wpa2Personal => {
+            (AuthMethod::WPA2Personal, PmfConfiguration::Capable { required: false })
+        }
+        WifiAuth::Open => (AuthMethod::None, PmfConfiguration::NotCapable),
+        WifiAuth::Wpa3Personal => (AuthMethod::WPA3Personal, PmfConfiguration::Capable { required: true }),
+        WifiAuth::Wpa2Wpa3Personal => {
+            (AuthMethod::WPA2WPA3Personal, PmfConfiguration::Capable { required: false })
+        }
+        WifiAuth::Wpa2Enterprise => {
+            (AuthMethod::WPA2Enterprise, PmfConfiguration::Capable { required: false })
+        }
+    };
+    // EAP credentials go to the supplicant, not the station config
+    let password = match network.auth {
+        WifiAuth::Wpa2Enterprise | WifiAuth::Open => "",
+        _ => network.password.as_str(),
+    };
+    configure_enterprise(network)?;
+
+    let client = ClientConfiguration {
+        ssid: network.ssid.as_str().try_into().map_err(|_| "SSID too long")?,
+        bssid: network.bssid,
+        auth_method,
         password: password.try_into().map_err(|_| "Password too long")?,
         channel: None,
-        ..Default::default()
-    });
+        // Hidden APs only answer directed probes, so check every channel
+        // rather than stopping at the first match
+        scan_method: if network.hidden {
+            ScanMethod::CompleteScan(ScanSortMethod::Signal)
+        } else {
+            ScanMethod::FastScan
+        },
+        pmf_cfg,
+    };
+    let config = match wifi.get_configuration() {
+        Ok(Configuration::Mixed(_, ap)) => Configuration::Mixed(client, ap),
+        _ => Configuration::Client(client),
+    };
+
+    // Leave the current AP first (e.g. switching networks from the UI)
+    if wifi.is_connected().unwrap_or(false) {
+        let _ = wifi.wifi_mut().disconnect();
+    }
 
     wifi.set_configuration(&config)
         .map_err(|e| format!("Failed to set config: {:?}", e))?;
 
-    // Start WiFi
-    wifi.start()
-        .map_err(|e| format!("Failed to start WiFi: {:?}", e))?;
+    if !wifi.is_started().unwrap_or(false) {
+        wifi.start()
+            .map_err(|e| format!("Failed to start WiFi: {:?}", e))?;
+    }
+    apply_ip_config(wifi, &ip_config)?;
 
-    // Connect
-    wifi.connect()
-        .map_err(|e| format!("Failed to connect: {:?}", e))?;
+    // EspWifi::connect only starts association; the supervisor polls for the IP
+    wifi.wifi_mut().connect()
+        .map_err(|e| format!("Failed to connect: {:?}", e))
+}
 
-    // Wait for IP
-    wifi.wait_netif_up()
-        .map_err(|e| format!("Failed to get IP: {:?}", e))?;
+/// Whether joining this network skips verifying the authentication server
+fn is_unverified(network: &SavedNetwork) -> bool {
+    network.auth == WifiAuth::Wpa2Enterprise && EAP_CA.is_empty()
+}
 
-    // Get IP address
-    let ip_info = wifi.wifi().sta_netif().get_ip_info()
-        .map_err(|e| format!("Failed to get IP info: {:?}", e))?;
+/// Set up (or turn off) WPA2-Enterprise in the supplicant for this network.
+/// PEAP/MSCHAPv2, checking the server against the build's EAP CA; without
+/// one only networks that opted in (`eap_allow_unverified`) get here.
+fn configure_enterprise(network: &SavedNetwork) -> Result<(), String> {
+    use esp_idf_sys::*;
 
-    let ip = ip_info.ip;
-    let ip_bytes = [ip.octets()[0], ip.octets()[1], ip.octets()[2], ip.octets()[3]];
+    if network.auth != WifiAuth::Wpa2Enterprise {
+        unsafe { esp_wifi_sta_enterprise_disable() };
+        return Ok(());
+    }
 
-    // Get RSSI (signal strength)
-    let rssi = get_current_rssi_internal(wifi);
+    let identity = if network.eap_identity.is_empty() {
+        &network.eap_username
+    } else {
+        &network.eap_identity
+    };
+    let check = |err: esp_err_t, what: &str| {
+        if err == ESP_OK {
+            Ok(())
+        } else {
+            Err(format!("Failed to set EAP {}: {}", what, err))
+        }
+    };
 
-    Ok((ip_bytes, rssi))
+    unsafe {
+        esp_eap_client_clear_identity();
+        esp_eap_client_clear_username();
+        esp_eap_client_clear_password();
+        esp_eap_client_clear_ca_cert();
+        if is_unverified(network) {
+            warn!("Joining {} without verifying the authentication server", network.ssid);
+        } else {
+            check(esp_eap_client_set_ca_cert(EAP_CA.as_ptr(), EAP_CA.len() as i32), "CA certificate")?;
+        }
+        check(esp_eap_client_set_identity(identity.as_ptr(), identity.len() as i32), "identity")?;
+        check(
+            esp_eap_client_set_username(network.eap_username.as_ptr(), network.eap_username.len() as i32),
+            "username",
+        )?;
+        check(
+            esp_eap_client_set_password(network.password.as_ptr(), network.password.len() as i32),
+            "password",
+        )?;
+        check(esp_wifi_sta_enterprise_enable(), "enable")?;
+    }
+    Ok(())
 }
 
-/// Get current RSSI from WiFi driver (internal helper)
-fn get_current_rssi_internal(wifi: &mut BlockingWifi<EspWifi<'static>>) -> i8 {
-    // Try to get scan info for the connected AP
-    match wifi.wifi_mut().driver_mut().get_scan_result() {
-        Ok(results) => {
-            // Find the connected SSID in scan results
-            if let Some(ap) = results.first() {
-                return ap.signal_strength;
-            }
-        }
-        Err(_) => {}
+/// Station IP once associated and DHCP has completed
+fn connected_ip() -> Option<[u8; 4]> {
+    let driver_guard = WIFI_DRIVER.lock().unwrap();
+    let wifi = driver_guard.as_ref()?;
+    if !wifi.is_connected().unwrap_or(false) {
+        return None;
+    }
+    let netif = wifi.wifi().sta_netif();
+    if !netif.is_up().unwrap_or(false) {
+        return None;
     }
-    -50 // Default moderate signal if we can't get it
+    let ip = netif.get_ip_info().ok()?.ip;
+    (!ip.is_unspecified()).then(|| ip.octets())
+}
+
+/// RSSI of the AP the station is associated with
+fn read_rssi() -> Option<i8> {
+    let mut info: esp_idf_sys::wifi_ap_record_t = unsafe { std::mem::zeroed() };
+    let err = unsafe { esp_idf_sys::esp_wifi_sta_get_ap_info(&mut info) };
+    (err == esp_idf_sys::ESP_OK).then_some(info.rssi)
 }
 
 /// Get current WiFi state
@@ -260,6 +906,110 @@ fn get_state() -> WifiState {
     }
 }
 
+/// Whether any network is saved or set by a connect attempt
+pub fn has_credentials() -> bool {
+    let manager_guard = WIFI_MANAGER.lock().unwrap();
+    manager_guard.as_ref().is_some_and(|m| !m.networks.is_empty() || !m.current.ssid.is_empty())
+}
+
+/// Persist credentials without connecting (e.g. from the setup portal)
+pub fn store_credentials(ssid: &str, password: &str) {
+    if let Err(e) = add_network(SavedNetwork::new(ssid, password)) {
+        warn!("Failed to store WiFi credentials: {}", e);
+    }
+}
+
+/// MAC address of the SoftAP interface
+pub fn ap_mac() -> Option<[u8; 6]> {
+    let driver_guard = WIFI_DRIVER.lock().unwrap();
+    let wifi = driver_guard.as_ref()?;
+    wifi.wifi().ap_netif().get_mac().ok()
+}
+
+/// Start the SoftAP (open network) alongside the station interface
+/// Returns the AP's IP address
+pub fn start_access_point(ap_ssid: &str) -> Result<[u8; 4], String> {
+    let mut driver_guard = WIFI_DRIVER.lock().unwrap();
+    let wifi = driver_guard.as_mut().ok_or("WiFi not initialized")?;
+
+    // Keep the station config so a saved network can still connect
+    let client = match wifi.get_configuration() {
+        Ok(Configuration::Client(c)) | Ok(Configuration::Mixed(c, _)) => c,
+        _ => ClientConfiguration::default(),
+    };
+    let ap = AccessPointConfiguration {
+        ssid: ap_ssid.try_into().map_err(|_| "AP SSID too long")?,
+        auth_method: AuthMethod::None,
+        channel: 1,
+        max_connections: 4,
+        ..Default::default()
+    };
+
+    wifi.set_configuration(&Configuration::Mixed(client, ap))
+        .map_err(|e| format!("Failed to set AP config: {:?}", e))?;
+    if !wifi.is_started().unwrap_or(false) {
+        wifi.start()
+            .map_err(|e| format!("Failed to start WiFi: {:?}", e))?;
+    }
+
+    let ip = wifi.wifi().ap_netif().get_ip_info()
+        .map_err(|e| format!("Failed to get AP IP info: {:?}", e))?
+        .ip
+        .octets();
+
+    info!("SoftAP '{}' started at {}.{}.{}.{}", ap_ssid, ip[0], ip[1], ip[2], ip[3]);
+    Ok(ip)
+}
+
+/// Stop the SoftAP and return to station-only mode
+pub fn stop_access_point() -> Result<(), String> {
+    let mut driver_guard = WIFI_DRIVER.lock().unwrap();
+    let wifi = driver_guard.as_mut().ok_or("WiFi not initialized")?;
+
+    let client = match wifi.get_configuration() {
+        Ok(Configuration::Mixed(c, _)) => c,
+        _ => return Ok(()), // AP not running
+    };
+    wifi.set_configuration(&Configuration::Client(client))
+        .map_err(|e| format!("Failed to leave AP mode: {:?}", e))?;
+    info!("SoftAP stopped");
+    Ok(())
+}
+
+/// Scan for networks and return (SSID, RSSI), strongest first
+pub fn scan_networks() -> Vec<(String, i8)> {
+    let mut driver_guard = WIFI_DRIVER.lock().unwrap();
+    let Some(wifi) = driver_guard.as_mut() else {
+        return Vec::new();
+    };
+
+    // Scanning needs the driver started (e.g. on boot, before any connect)
+    if !wifi.is_started().unwrap_or(false) {
+        let config = Configuration::Client(ClientConfiguration::default());
+        if let Err(e) = wifi.set_configuration(&config).and_then(|_| wifi.start()) {
+            warn!("Failed to start WiFi for scan: {:?}", e);
+            return Vec::new();
+        }
+    }
+
+    let mut networks: Vec<(String, i8)> = match wifi.scan() {
+        Ok(results) => results
+            .iter()
+            .filter(|ap| !ap.ssid.is_empty())
+            .map(|ap| (ap.ssid.to_string(), ap.signal_strength))
+            .collect(),
+        Err(e) => {
+            warn!("WiFi scan failed: {:?}", e);
+            return Vec::new();
+        }
+    };
+    networks.sort_by(|a, b| b.1.cmp(&a.1));
+    // Keep only the strongest entry per SSID
+    let mut seen = std::collections::HashSet::new();
+    networks.retain(|(ssid, _)| seen.insert(ssid.clone()));
+    networks
+}
+
 // ============================================================================
 // C-callable interface
 // ============================================================================
@@ -328,7 +1078,7 @@ pub extern "C" fn wifi_connect(ssid: *const c_char, password: *const c_char) ->
         }
     };
 
-    match start_connect(ssid_str, password_str) {
+    match start_connect(SavedNetwork::new(ssid_str, password_str)) {
         Ok(_) => 0,
         Err(e) => {
             error!("wifi_connect failed: {}", e);
@@ -378,41 +1128,52 @@ pub extern "C" fn wifi_get_status(status: *mut WifiStatus) {
     }
 }
 
-/// Disconnect from WiFi and clear saved credentials
+/// Disconnect from WiFi and forget the current network
+/// Other saved networks are kept
 /// Returns 0 on success, -1 on error
 #[no_mangle]
 pub extern "C" fn wifi_disconnect() -> c_int {
-    let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+    // Stop the supervisor first so the disconnect event isn't treated as a dropped link
+    if send_command(SupervisorCmd::Stop).is_err() {
+        return -1;
+    }
 
-    if let Some(manager) = manager_guard.as_mut() {
-        if let Some(wifi) = manager.wifi.as_mut() {
-            // Stop the WiFi completely to prevent auto-reconnect
-            match wifi.stop() {
-                Ok(_) => {
-                    manager.state = WifiState::Disconnected;
-                    manager.ssid.clear();
-                    manager.password.clear();
-                    info!("WiFi stopped and disconnected");
-
-                    // Clear saved credentials from NVS to prevent auto-reconnect on boot
-                    if let Some(nvs_partition) = manager.nvs.as_ref() {
-                        if let Ok(nvs) = EspNvs::new(nvs_partition.clone(), NVS_NAMESPACE, true) {
-                            let _ = nvs.remove(NVS_KEY_SSID);
-                            let _ = nvs.remove(NVS_KEY_PASSWORD);
-                            info!("WiFi credentials cleared from NVS");
-                        }
-                    }
-                    return 0;
-                }
-                Err(e) => {
-                    error!("WiFi stop failed: {:?}", e);
-                    return -1;
-                }
-            }
+    let ssid = {
+        let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+        let Some(manager) = manager_guard.as_mut() else {
+            return -1;
+        };
+        manager.state = WifiState::Disconnected;
+        std::mem::take(&mut manager.current).ssid
+    };
+
+    {
+        let mut driver_guard = WIFI_DRIVER.lock().unwrap();
+        let Some(wifi) = driver_guard.as_mut() else {
+            return -1;
+        };
+        // Stop the WiFi completely to prevent auto-reconnect
+        if let Err(e) = wifi.stop() {
+            error!("WiFi stop failed: {:?}", e);
+            return -1;
         }
     }
+    info!("WiFi stopped and disconnected");
 
-    -1
+    // Forget this network so it isn't auto-joined on boot
+    let changed = {
+        let mut manager_guard = WIFI_MANAGER.lock().unwrap();
+        manager_guard.as_mut().is_some_and(|manager| {
+            let before = manager.networks.len();
+            manager.networks.retain(|n| n.ssid != ssid);
+            manager.networks.len() != before
+        })
+    };
+    if changed {
+        save_networks();
+        info!("WiFi network '{}' forgotten", ssid);
+    }
+    0
 }
 
 /// Check if WiFi is connected (Rust API)
@@ -437,8 +1198,8 @@ pub extern "C" fn wifi_get_ssid(buf: *mut c_char, buf_len: c_int) -> c_int {
 
     let manager_guard = WIFI_MANAGER.lock().unwrap();
     match manager_guard.as_ref() {
-        Some(manager) if !manager.ssid.is_empty() => {
-            let ssid = &manager.ssid;
+        Some(manager) if !manager.current.ssid.is_empty() => {
+            let ssid = &manager.current.ssid;
             let copy_len = std::cmp::min(ssid.len(), (buf_len - 1) as usize);
             unsafe {
                 std::ptr::copy_nonoverlapping(ssid.as_ptr(), buf as *mut u8, copy_len);
@@ -453,6 +1214,14 @@ pub extern "C" fn wifi_get_ssid(buf: *mut c_char, buf_len: c_int) -> c_int {
     }
 }
 
+/// Check if the network being joined (or joined) skips verifying its
+/// authentication server (enterprise network in a build without an EAP CA)
+#[no_mangle]
+pub extern "C" fn wifi_is_unverified() -> bool {
+    let manager_guard = WIFI_MANAGER.lock().unwrap();
+    manager_guard.as_ref().is_some_and(|m| !m.current.ssid.is_empty() && is_unverified(&m.current))
+}
+
 /// Scan for WiFi networks
 /// Fills the results array with up to max_results entries
 /// Returns the number of networks found, or -1 on error
@@ -462,19 +1231,11 @@ pub extern "C" fn wifi_scan(results: *mut WifiScanResult, max_results: c_int) ->
         return -1;
     }
 
-    let mut manager_guard = WIFI_MANAGER.lock().unwrap();
-    let manager = match manager_guard.as_mut() {
-        Some(m) => m,
-        None => {
-            error!("wifi_scan: WiFi not initialized");
-            return -1;
-        }
-    };
-
-    let wifi = match manager.wifi.as_mut() {
+    let mut driver_guard = WIFI_DRIVER.lock().unwrap();
+    let wifi = match driver_guard.as_mut() {
         Some(w) => w,
         None => {
-            error!("wifi_scan: WiFi handle not available");
+            error!("wifi_scan: WiFi not initialized");
             return -1;
         }
     };
@@ -558,343 +1319,289 @@ pub extern "C" fn wifi_get_rssi() -> i8 {
 }
 
 // ============================================================================
-// Printer Discovery via UDP (Bambu SSDP-like protocol)
+// Saved networks (C interface)
 // ============================================================================
 
-/// Discovered printer info for C interface
+/// Saved network entry for C interface
 #[repr(C)]
-pub struct PrinterDiscoveryResult {
-    /// Printer name (null-terminated)
-    pub name: [c_char; 64],
-    /// Serial number (null-terminated)
-    pub serial: [c_char; 32],
-    /// IP address as string (null-terminated)
-    pub ip: [c_char; 16],
-    /// Model name (null-terminated)
-    pub model: [c_char; 32],
-}
-
-/// Discover Bambu printers on the network via UDP broadcast
-/// Fills the results array with up to max_results entries
-/// Returns the number of printers found, or -1 on error
-#[no_mangle]
-pub extern "C" fn printer_discover(results: *mut PrinterDiscoveryResult, max_results: c_int) -> c_int {
-    use std::net::{UdpSocket, SocketAddr, Ipv4Addr};
-    use std::time::Duration;
+pub struct SavedWifiNetwork {
+    /// SSID (null-terminated)
+    pub ssid: [c_char; 33],
+    /// Whether a password is stored
+    pub has_password: bool,
+    /// Whether this is the currently connected network
+    pub connected: bool,
+    /// Auth: 0=Auto, 1=Open, 2=WPA2, 3=WPA3, 4=WPA2/WPA3, 5=WPA2-Enterprise
+    pub auth_mode: u8,
+    /// Whether the network is hidden
+    pub hidden: bool,
+    /// Whether the network is pinned to one access point
+    pub bssid_pinned: bool,
+    /// Whether joining skips verifying the authentication server
+    pub unverified: bool,
+}
 
-    if results.is_null() || max_results <= 0 {
-        return -1;
-    }
+/// Full connection settings for C interface
+#[repr(C)]
+pub struct WifiConnectParams {
+    /// SSID (null-terminated)
+    pub ssid: [c_char; 33],
+    /// PSK or EAP password (null-terminated)
+    pub password: [c_char; 65],
+    /// Auth: 0=Auto, 1=Open, 2=WPA2, 3=WPA3, 4=WPA2/WPA3, 5=WPA2-Enterprise
+    pub auth_mode: u8,
+    /// EAP outer identity, empty to use the username (null-terminated)
+    pub eap_identity: [c_char; 64],
+    /// EAP username (null-terminated)
+    pub eap_username: [c_char; 64],
+    /// Join without verifying the authentication server (builds without an EAP CA)
+    pub eap_allow_unverified: bool,
+    /// Access point to pin to (used when pin_bssid is set)
+    pub bssid: [u8; 6],
+    pub pin_bssid: bool,
+    /// SSID is not broadcast
+    pub hidden: bool,
+}
 
-    // Check if WiFi is connected
-    if !matches!(get_state(), WifiState::Connected { .. }) {
-        error!("printer_discover: WiFi not connected");
-        return -1;
+/// Convert C connection settings to a SavedNetwork
+fn network_from_params(params: *const WifiConnectParams) -> Option<SavedNetwork> {
+    if params.is_null() {
+        return None;
     }
-
-    info!("Starting printer discovery...");
-
-    // Create UDP socket
-    let socket = match UdpSocket::bind("0.0.0.0:0") {
-        Ok(s) => s,
-        Err(e) => {
-            error!("Failed to create UDP socket: {:?}", e);
-            return -1;
+    let params = unsafe { &*params };
+    let field = |buf: &[c_char], what: &str| -> Option<String> {
+        // Never read past the array, even if the caller forgot the terminator
+        let bytes: Vec<u8> = buf.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+        match String::from_utf8(bytes) {
+            Ok(s) => Some(s),
+            Err(_) => {
+                error!("Invalid {} string", what);
+                None
+            }
         }
     };
+    let Some(auth) = WifiAuth::from_code(params.auth_mode) else {
+        error!("Invalid WiFi auth mode: {}", params.auth_mode);
+        return None;
+    };
 
-    // Enable broadcast
-    if let Err(e) = socket.set_broadcast(true) {
-        error!("Failed to enable broadcast: {:?}", e);
-        return -1;
-    }
+    Some(SavedNetwork {
+        ssid: field(&params.ssid, "SSID")?,
+        password: field(&params.password, "password")?,
+        auth,
+        eap_identity: field(&params.eap_identity, "EAP identity")?,
+        eap_username: field(&params.eap_username, "EAP username")?,
+        eap_allow_unverified: params.eap_allow_unverified,
+        bssid: params.pin_bssid.then_some(params.bssid),
+        hidden: params.hidden,
+    })
+}
 
-    // Set receive timeout (2 seconds)
-    if let Err(e) = socket.set_read_timeout(Some(Duration::from_secs(2))) {
-        error!("Failed to set socket timeout: {:?}", e);
+/// Start WiFi connection with full settings (auth mode, EAP, BSSID, hidden)
+/// Returns 0 if connection started, -1 on error
+#[no_mangle]
+pub extern "C" fn wifi_connect_ex(params: *const WifiConnectParams) -> c_int {
+    let Some(network) = network_from_params(params) else {
         return -1;
+    };
+    match start_connect(network) {
+        Ok(_) => 0,
+        Err(e) => {
+            error!("wifi_connect_ex failed: {}", e);
+            -1
+        }
     }
+}
 
-    // Bambu discovery message (SSDP-like M-SEARCH)
-    // Bambu printers respond to SSDP on UDP 2021
-    let discover_msg = b"M-SEARCH * HTTP/1.1\r\nHOST: 239.255.255.250:1900\r\nMAN: \"ssdp:discover\"\r\nMX: 2\r\nST: urn:bambulab-com:device:3dprinter:1\r\n\r\n";
-
-    // Send broadcast to port 2021 (Bambu discovery port)
-    let broadcast_addr: SocketAddr = (Ipv4Addr::BROADCAST, 2021).into();
-    if let Err(e) = socket.send_to(discover_msg, broadcast_addr) {
-        error!("Failed to send discovery broadcast: {:?}", e);
+/// Add a network with full settings to the saved list (or update it)
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn wifi_saved_add_ex(params: *const WifiConnectParams) -> c_int {
+    let Some(network) = network_from_params(params) else {
         return -1;
+    };
+    match add_network(network) {
+        Ok(()) => 0,
+        Err(e) => {
+            error!("wifi_saved_add_ex failed: {}", e);
+            -1
+        }
     }
+}
 
-    // Also try multicast address that Bambu uses
-    let multicast_addr: SocketAddr = (Ipv4Addr::new(239, 255, 255, 250), 2021).into();
-    let _ = socket.send_to(discover_msg, multicast_addr);
-
-    info!("Discovery broadcast sent, waiting for responses...");
-
-    // Receive responses
-    let mut count = 0;
-    let mut buf = [0u8; 1024];
-
-    loop {
-        if count >= max_results as usize {
-            break;
-        }
-
-        match socket.recv_from(&mut buf) {
-            Ok((len, addr)) => {
-                info!("Received {} bytes from {}", len, addr);
-
-                // Parse the response
-                if let Some(printer_info) = parse_printer_response(&buf[..len], &addr.to_string()) {
-                    unsafe {
-                        let result = &mut *results.add(count);
-
-                        // Copy name
-                        let name_bytes = printer_info.0.as_bytes();
-                        let name_len = std::cmp::min(name_bytes.len(), 63);
-                        std::ptr::copy_nonoverlapping(name_bytes.as_ptr(), result.name.as_mut_ptr() as *mut u8, name_len);
-                        result.name[name_len] = 0;
-
-                        // Copy serial
-                        let serial_bytes = printer_info.1.as_bytes();
-                        let serial_len = std::cmp::min(serial_bytes.len(), 31);
-                        std::ptr::copy_nonoverlapping(serial_bytes.as_ptr(), result.serial.as_mut_ptr() as *mut u8, serial_len);
-                        result.serial[serial_len] = 0;
-
-                        // Copy IP
-                        let ip_bytes = printer_info.2.as_bytes();
-                        let ip_len = std::cmp::min(ip_bytes.len(), 15);
-                        std::ptr::copy_nonoverlapping(ip_bytes.as_ptr(), result.ip.as_mut_ptr() as *mut u8, ip_len);
-                        result.ip[ip_len] = 0;
-
-                        // Copy model
-                        let model_bytes = printer_info.3.as_bytes();
-                        let model_len = std::cmp::min(model_bytes.len(), 31);
-                        std::ptr::copy_nonoverlapping(model_bytes.as_ptr(), result.model.as_mut_ptr() as *mut u8, model_len);
-                        result.model[model_len] = 0;
-                    }
-                    count += 1;
-                    info!("Found printer: {} ({}) at {}", printer_info.0, printer_info.1, printer_info.2);
-                }
-            }
-            Err(e) => {
-                // Timeout or other error - stop listening
-                if e.kind() == std::io::ErrorKind::WouldBlock || e.kind() == std::io::ErrorKind::TimedOut {
-                    info!("Discovery timeout, found {} printers", count);
-                } else {
-                    error!("Error receiving: {:?}", e);
-                }
-                break;
-            }
+/// Parse a C string argument, logging on failure
+fn c_str_arg<'a>(ptr: *const c_char, what: &str) -> Option<&'a str> {
+    if ptr.is_null() {
+        return Some("");
+    }
+    match unsafe { CStr::from_ptr(ptr) }.to_str() {
+        Ok(s) => Some(s),
+        Err(_) => {
+            error!("Invalid {} string", what);
+            None
         }
     }
-
-    count as c_int
 }
 
-/// Parse Bambu printer discovery response
-/// Returns (name, serial, ip, model) if valid
-fn parse_printer_response(data: &[u8], source_ip: &str) -> Option<(String, String, String, String)> {
-    // Extract IP from source address (remove port)
-    let ip = source_ip.split(':').next().unwrap_or(source_ip).to_string();
+/// Get number of saved networks
+#[no_mangle]
+pub extern "C" fn wifi_saved_count() -> c_int {
+    saved_networks().len() as c_int
+}
 
-    // Log raw bytes for debugging (first 100 bytes as hex)
-    let hex_preview: String = data.iter().take(100).map(|b| format!("{:02x}", b)).collect::<Vec<_>>().join(" ");
-    info!("Raw response from {} ({} bytes): {}", ip, data.len(), hex_preview);
+/// Get a saved network by priority index (0 = highest)
+/// Returns 0 on success, -1 if the index is invalid
+#[no_mangle]
+pub extern "C" fn wifi_saved_get(index: c_int, out: *mut SavedWifiNetwork) -> c_int {
+    if out.is_null() || index < 0 {
+        return -1;
+    }
+    let networks = saved_networks();
+    let Some(net) = networks.get(index as usize) else {
+        return -1;
+    };
 
-    let text = match std::str::from_utf8(data) {
-        Ok(t) => t,
-        Err(e) => {
-            warn!("Response is not valid UTF-8: {:?}", e);
-            return None;
+    let connected_ssid = {
+        let manager_guard = WIFI_MANAGER.lock().unwrap();
+        match manager_guard.as_ref() {
+            Some(m) if matches!(m.state, WifiState::Connected { .. }) => m.current.ssid.clone(),
+            _ => String::new(),
         }
     };
 
-    // Log full response for debugging
-    info!("Text response from {}: {}", ip, text);
-
-    let mut serial = String::new();
-    let mut model = String::new();
-    let mut name = String::new();
-
-    // Bambu printers respond with JSON containing printer info
-    // Common fields: "dev_sn", "sn", "name", "product_name", "dev_name", "machine_name"
-
-    // Try multiple field names for serial
-    for key in &["\"dev_sn\"", "\"sn\"", "\"serial\""] {
-        if serial.is_empty() {
-            if let Some(pos) = text.find(key) {
-                if let Some(value) = extract_json_string_value(&text[pos..]) {
-                    if !value.is_empty() {
-                        serial = value;
-                        info!("Found serial from {}: {}", key, serial);
-                    }
-                }
-            }
-        }
+    unsafe {
+        let out = &mut *out;
+        let bytes = net.ssid.as_bytes();
+        let len = std::cmp::min(bytes.len(), 32);
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), out.ssid.as_mut_ptr() as *mut u8, len);
+        out.ssid[len] = 0;
+        out.has_password = !net.password.is_empty();
+        out.connected = net.ssid == connected_ssid;
+        out.auth_mode = net.auth.code();
+        out.hidden = net.hidden;
+        out.bssid_pinned = net.bssid.is_some();
+        out.unverified = is_unverified(net);
     }
+    0
+}
 
-    // Try multiple field names for model/product
-    for key in &["\"product_name\"", "\"model\"", "\"dev_product_name\"", "\"machine_type\""] {
-        if model.is_empty() {
-            if let Some(pos) = text.find(key) {
-                if let Some(value) = extract_json_string_value(&text[pos..]) {
-                    if !value.is_empty() {
-                        model = value;
-                        info!("Found model from {}: {}", key, model);
-                    }
-                }
-            }
-        }
+/// Add a network to the saved list (or update its password)
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn wifi_saved_add(ssid: *const c_char, password: *const c_char) -> c_int {
+    if ssid.is_null() {
+        return -1;
     }
-
-    // Try multiple field names for printer name
-    for key in &["\"dev_name\"", "\"machine_name\"", "\"name\""] {
-        if name.is_empty() {
-            if let Some(pos) = text.find(key) {
-                if let Some(value) = extract_json_string_value(&text[pos..]) {
-                    if !value.is_empty() {
-                        name = value;
-                        info!("Found name from {}: {}", key, name);
-                    }
-                }
-            }
+    let (Some(ssid), Some(password)) = (c_str_arg(ssid, "SSID"), c_str_arg(password, "password")) else {
+        return -1;
+    };
+    match add_network(SavedNetwork::new(ssid, password)) {
+        Ok(()) => 0,
+        Err(e) => {
+            error!("wifi_saved_add failed: {}", e);
+            -1
         }
     }
+}
 
-    // Parse SSDP/HTTP headers from Bambu printers
-    // Format: "HeaderName: value" or "HeaderName.bambu.com: value"
-    for line in text.lines() {
-        let line = line.trim();
-
-        // Skip empty lines
-        if line.is_empty() {
-            continue;
-        }
-
-        // USN header contains the serial number directly (e.g., "USN: 00M09C411500579")
-        if line.to_uppercase().starts_with("USN:") && serial.is_empty() {
-            let usn = line[4..].trim();
-            // Bambu sends raw serial, but handle uuid: prefix too just in case
-            if let Some(uuid_part) = usn.strip_prefix("uuid:") {
-                if let Some(serial_end) = uuid_part.find("::") {
-                    serial = uuid_part[..serial_end].to_string();
-                } else {
-                    serial = uuid_part.to_string();
-                }
-            } else {
-                // Raw serial number
-                serial = usn.to_string();
-            }
-            info!("Found serial from USN: {}", serial);
-        }
-
-        // DevModel.bambu.com: BL-P001 (model code)
-        if line.starts_with("DevModel.bambu.com:") && model.is_empty() {
-            model = line["DevModel.bambu.com:".len()..].trim().to_string();
-            info!("Found model from DevModel.bambu.com: {}", model);
+/// Remove a saved network by index
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn wifi_saved_remove(index: c_int) -> c_int {
+    if index < 0 {
+        return -1;
+    }
+    match remove_network(index as usize) {
+        Ok(()) => 0,
+        Err(e) => {
+            error!("wifi_saved_remove failed: {}", e);
+            -1
         }
+    }
+}
 
-        // DevName.bambu.com: X1C-2 (printer name)
-        if line.starts_with("DevName.bambu.com:") && name.is_empty() {
-            name = line["DevName.bambu.com:".len()..].trim().to_string();
-            info!("Found name from DevName.bambu.com: {}", name);
+/// Move a saved network from one priority position to another
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn wifi_saved_move(from: c_int, to: c_int) -> c_int {
+    if from < 0 || to < 0 {
+        return -1;
+    }
+    match move_network(from as usize, to as usize) {
+        Ok(()) => 0,
+        Err(e) => {
+            error!("wifi_saved_move failed: {}", e);
+            -1
         }
+    }
+}
 
-        // Fallback: generic MODEL header
-        if (line.to_uppercase().starts_with("MODEL:") || line.to_uppercase().starts_with("X-MODEL:")) && model.is_empty() {
-            let colon_pos = line.find(':').unwrap_or(0);
-            model = line[colon_pos + 1..].trim().to_string();
-        }
+/// Station IPv4 settings for C interface
+#[repr(C)]
+pub struct WifiIpConfig {
+    /// Use the static addresses below instead of DHCP
+    pub use_static: bool,
+    pub ip: [u8; 4],
+    pub netmask: [u8; 4],
+    pub gateway: [u8; 4],
+    /// DNS servers (0.0.0.0 = not set)
+    pub dns_primary: [u8; 4],
+    pub dns_secondary: [u8; 4],
+}
 
-        // Fallback: FRIENDLY-NAME header
-        if (line.to_uppercase().starts_with("FRIENDLY-NAME:") || line.to_uppercase().starts_with("X-FRIENDLY-NAME:")) && name.is_empty() {
-            let colon_pos = line.find(':').unwrap_or(0);
-            name = line[colon_pos + 1..].trim().to_string();
-        }
+/// Get the station IPv4 settings
+#[no_mangle]
+pub extern "C" fn wifi_get_ip_config(out: *mut WifiIpConfig) {
+    if out.is_null() {
+        return;
+    }
+    let config = ip_config();
+    unsafe {
+        *out = WifiIpConfig {
+            use_static: config.use_static,
+            ip: config.ip,
+            netmask: config.netmask,
+            gateway: config.gateway,
+            dns_primary: config.dns_primary,
+            dns_secondary: config.dns_secondary,
+        };
     }
+}
 
-    // Generate default name if not found
-    if name.is_empty() {
-        if !serial.is_empty() && !model.is_empty() {
-            // Use last 6 chars of serial for short name
-            let short_serial = if serial.len() > 6 {
-                &serial[serial.len() - 6..]
-            } else {
-                &serial
-            };
-            name = format!("{} ({})", model, short_serial);
-        } else if !model.is_empty() {
-            name = format!("{} at {}", model, ip);
-        } else if !serial.is_empty() {
-            let short = &serial[serial.len().saturating_sub(6)..];
-            name = format!("Printer {}", short);
-        } else {
-            name = format!("Bambu Printer at {}", ip);
-        }
-    }
-
-    // Map Bambu model codes to friendly names
-    // Reference: https://github.com/bambulab/BambuStudio/tree/master/resources/printers
-    let friendly_model = match model.as_str() {
-        // X1 Series
-        "BL-P001" => "X1 Carbon",
-        "BL-P002" => "X1",
-        "C13" => "X1E",
-        // P1 Series
-        "C11" => "P1P",
-        "C12" => "P1S",
-        // A1 Series
-        "N1" => "A1 Mini",
-        "N2S" => "A1",
-        // P2 Series
-        "N7" => "P2S",
-        // H2 Series
-        "O1C" | "O1C2" => "H2C",
-        "O1D" => "H2D",
-        "O1E" => "H2D Pro",
-        "O1S" => "H2S",
-        "" => "Bambu Printer",
-        other => other, // Keep unknown codes as-is
+/// Save and apply the station IPv4 settings
+/// Returns 0 on success, -1 if invalid or not applied
+#[no_mangle]
+pub extern "C" fn wifi_set_ip_config(config: *const WifiIpConfig) -> c_int {
+    if config.is_null() {
+        return -1;
+    }
+    let config = unsafe { &*config };
+    let config = IpConfig {
+        use_static: config.use_static,
+        ip: config.ip,
+        netmask: config.netmask,
+        gateway: config.gateway,
+        dns_primary: config.dns_primary,
+        dns_secondary: config.dns_secondary,
     };
-    let model = friendly_model.to_string();
-
-    info!("Final parsed: name='{}', serial='{}', model='{}', ip='{}'", name, serial, model, ip);
-    Some((name, serial, ip, model))
-}
-
-/// Extract a JSON string value from text starting at a key
-/// Input: "\"key\": \"value\"..." or "\"key\":\"value\"..."
-/// Returns: Some("value") or None
-fn extract_json_string_value(text: &str) -> Option<String> {
-    // Find the colon after the key
-    let colon_pos = text.find(':')?;
-    let after_colon = &text[colon_pos + 1..];
-
-    // Find the opening quote
-    let quote_start = after_colon.find('"')?;
-    let value_start = quote_start + 1;
-    let remaining = &after_colon[value_start..];
-
-    // Find the closing quote (handle escaped quotes)
-    let mut end_pos = 0;
-    let mut chars = remaining.chars().peekable();
-    while let Some(c) = chars.next() {
-        if c == '\\' {
-            // Skip escaped character
-            chars.next();
-            end_pos += 2;
-        } else if c == '"' {
-            break;
-        } else {
-            end_pos += c.len_utf8();
+    match set_ip_config(config) {
+        Ok(()) => 0,
+        Err(e) => {
+            error!("wifi_set_ip_config failed: {}", e);
+            -1
         }
     }
+}
 
-    if end_pos > 0 || remaining.starts_with('"') {
-        Some(remaining[..end_pos].to_string())
-    } else {
-        None
+/// Connect to the strongest saved network in range
+/// Returns 0 on success, -1 on error
+#[no_mangle]
+pub extern "C" fn wifi_connect_best() -> c_int {
+    match connect_best() {
+        Ok(()) => 0,
+        Err(e) => {
+            warn!("wifi_connect_best failed: {}", e);
+            -1
+        }
     }
 }
@@ -0,0 +1,191 @@
+#!/usr/bin/env python3
+"""
+SpoolBuddy Firmware Signing Tool
+
+Signs OTA images with Ed25519. The device only installs images whose
+signature verifies against the public key compiled into the firmware
+(SPOOLBUDDY_OTA_PUBLIC_KEY at build time). The signed message is
+
+    tag || 0x00 || version || 0x00 || sha256(image)
+
+with tag "spoolbuddy-esp32" or "spoolbuddy-nfc-bridge", so an image can't be
+installed as the other kind or announced as a different version. ESP32
+images are signed with the version from their app descriptor, bridge images
+with the version from the file name (nfc-bridge-<version>.bin) or --version.
+
+Usage:
+    python sign_firmware.py keygen signing.key
+        Create a private key and print the public key for the build
+    python sign_firmware.py sign signing.key spoolbuddy-1.0.0.bin
+        Write spoolbuddy-1.0.0.bin.sig next to the image
+    python sign_firmware.py sign --bridge signing.key nfc-bridge-2.1.0.bin
+        Sign a Pico NFC bridge image (not an ESP32 app image)
+    python sign_firmware.py sign --bridge --version 2.1.0 signing.key bridge.bin
+        Sign a bridge image whose file name has no version
+
+Build with the key:
+    SPOOLBUDDY_OTA_PUBLIC_KEY=<public key hex> cargo build --release
+
+Upload the .sig file together with the image (backend releases directory,
+or the signature field of /api/firmware/upload).
+
+Requirements:
+    pip install cryptography
+"""
+
+import argparse
+import hashlib
+import re
+import struct
+import sys
+from pathlib import Path
+
+try:
+    from cryptography.hazmat.primitives import serialization
+    from cryptography.hazmat.primitives.asymmetric.ed25519 import Ed25519PrivateKey
+except ImportError:
+    print("Error: cryptography not installed. Run: pip install cryptography")
+    sys.exit(1)
+
+FIRMWARE_TAG = b"spoolbuddy-esp32"
+BRIDGE_TAG = b"spoolbuddy-nfc-bridge"
+
+# esp_app_desc_t follows the 24-byte image header and the first segment header
+APP_DESC_OFFSET = 32
+APP_DESC_MAGIC = 0xABCD5432
+APP_DESC_VERSION_OFFSET = APP_DESC_OFFSET + 16
+
+
+def normalize_version(version: str) -> str:
+    """PEP 440 short pre-releases (0.1.0b2) in the semver form (0.1.0-beta.2)."""
+    version = version.strip().lstrip("v")
+    match = re.fullmatch(r"(\d+\.\d+\.\d+)(a|b|rc)(\d+)", version)
+    if match:
+        label = {"a": "alpha", "b": "beta", "rc": "rc"}[match.group(2)]
+        return f"{match.group(1)}-{label}.{match.group(3)}"
+    return version
+
+
+def app_desc_version(image: bytes) -> str | None:
+    """Version string from an ESP32 app image's app descriptor."""
+    if len(image) < APP_DESC_VERSION_OFFSET + 32:
+        return None
+    (magic,) = struct.unpack_from("<I", image, APP_DESC_OFFSET)
+    if magic != APP_DESC_MAGIC:
+        return None
+    raw = image[APP_DESC_VERSION_OFFSET : APP_DESC_VERSION_OFFSET + 32]
+    return raw.split(b"\0", 1)[0].decode("ascii", errors="replace")
+
+
+def file_name_version(image_path: Path, prefix: str) -> str | None:
+    """Version from a <prefix><version>.bin file name."""
+    if image_path.suffix != ".bin" or not image_path.stem.startswith(prefix):
+        return None
+    return image_path.stem.removeprefix(prefix) or None
+
+
+def public_key_hex(private_key: Ed25519PrivateKey) -> str:
+    raw = private_key.public_key().public_bytes(
+        encoding=serialization.Encoding.Raw,
+        format=serialization.PublicFormat.Raw,
+    )
+    return raw.hex()
+
+
+def load_private_key(path: Path) -> Ed25519PrivateKey:
+    key = serialization.load_pem_private_key(path.read_bytes(), password=None)
+    if not isinstance(key, Ed25519PrivateKey):
+        print(f"Error: {path} is not an Ed25519 private key")
+        sys.exit(1)
+    return key
+
+
+def keygen(key_path: Path):
+    if key_path.exists():
+        print(f"Error: {key_path} already exists")
+        sys.exit(1)
+
+    private_key = Ed25519PrivateKey.generate()
+    pem = private_key.private_bytes(
+        encoding=serialization.Encoding.PEM,
+        format=serialization.PrivateFormat.PKCS8,
+        encryption_algorithm=serialization.NoEncryption(),
+    )
+    key_path.write_bytes(pem)
+    key_path.chmod(0o600)
+
+    print(f"Private key written to {key_path} (keep it out of the repository)")
+    print(f"SPOOLBUDDY_OTA_PUBLIC_KEY={public_key_hex(private_key)}")
+
+
+def sign(key_path: Path, image_path: Path, bridge: bool = False, version: str | None = None):
+    private_key = load_private_key(key_path)
+    image = image_path.read_bytes()
+    if not image:
+        print(f"Error: {image_path} is empty")
+        sys.exit(1)
+
+    if bridge:
+        tag = BRIDGE_TAG
+        # The display checks the version the backend announces, taken from the file name
+        named = file_name_version(image_path, "nfc-bridge-")
+        if version is None:
+            version = named
+        if version is None:
+            print(f"Error: no version in {image_path.name} (name it nfc-bridge-<version>.bin or use --version)")
+            sys.exit(1)
+        if named is not None and named != version:
+            print(f"Error: --version {version} doesn't match the file name version {named}")
+            sys.exit(1)
+    else:
+        tag = FIRMWARE_TAG
+        if image[0] != 0xE9:
+            print(f"Error: {image_path} is not an ESP32 app image (use --bridge for NFC bridge images)")
+            sys.exit(1)
+        # The display checks the version written in the image itself
+        embedded = app_desc_version(image)
+        if not embedded:
+            print(f"Error: {image_path} has no app descriptor version")
+            sys.exit(1)
+        for other in (version, file_name_version(image_path, "spoolbuddy-")):
+            if other is not None and normalize_version(other) != normalize_version(embedded):
+                print(f"Error: image is version {embedded}, not {other}")
+                sys.exit(1)
+        version = embedded
+
+    digest = hashlib.sha256(image).digest()
+    signature = private_key.sign(tag + b"\0" + version.encode() + b"\0" + digest)
+
+    sig_path = image_path.with_name(image_path.name + ".sig")
+    sig_path.write_text(signature.hex() + "\n")
+
+    print(f"Image:      {image_path} ({len(image)} bytes)")
+    print(f"Kind:       {tag.decode()}")
+    print(f"Version:    {version}")
+    print(f"SHA-256:    {digest.hex()}")
+    print(f"Public key: {public_key_hex(private_key)}")
+    print(f"Signature written to {sig_path}")
+
+
+def main():
+    parser = argparse.ArgumentParser(description="Sign SpoolBuddy OTA images")
+    sub = parser.add_subparsers(dest="command", required=True)
+
+    p_keygen = sub.add_parser("keygen", help="Create a signing key")
+    p_keygen.add_argument("key", type=Path, help="Private key file to create")
+
+    p_sign = sub.add_parser("sign", help="Sign a firmware image")
+    p_sign.add_argument("--bridge", action="store_true", help="Pico NFC bridge image")
+    p_sign.add_argument("--version", help="Image version (default: from the image or file name)")
+    p_sign.add_argument("key", type=Path, help="Private key file")
+    p_sign.add_argument("image", type=Path, help="Firmware .bin file")
+
+    args = parser.parse_args()
+    if args.command == "keygen":
+        keygen(args.key)
+    else:
+        sign(args.key, args.image, args.bridge, args.version)
+
+
+if __name__ == "__main__":
+    main()
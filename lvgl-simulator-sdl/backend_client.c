@@ -40,6 +40,9 @@ static int g_tag_spool_weight = 0;
 static char g_tag_type[32] = "";
 static char g_tag_slicer_filament[32] = "";
 
+// Spool last matched by spool_get_by_tag_full (for net weight estimate)
+static SpoolInfo g_active_spool = {0};
+
 // Staging state - separate from raw NFC tag detection
 // UI should use staging_is_active() for popup control
 static bool g_staging_active = false;
@@ -862,6 +865,28 @@ int time_is_synced(void) {
     return 1;  // Simulator always has valid time
 }
 
+static int g_clock_24h = 1;
+
+int time_format_clock(char *buf, int buf_len) {
+    time_t now = time(NULL);
+    struct tm *tm = localtime(&now);
+    if (!buf || buf_len <= 0 || !tm) return -1;
+    if (g_clock_24h) {
+        return snprintf(buf, buf_len, "%02d:%02d", tm->tm_hour, tm->tm_min);
+    }
+    return snprintf(buf, buf_len, "%d:%02d %s", (tm->tm_hour + 11) % 12 + 1, tm->tm_min,
+                    tm->tm_hour < 12 ? "AM" : "PM");
+}
+
+int time_is_24h(void) {
+    return g_clock_24h;
+}
+
+int time_set_24h(int enabled) {
+    g_clock_24h = enabled ? 1 : 0;
+    return 0;
+}
+
 // =============================================================================
 // Spool Inventory API
 // =============================================================================
@@ -980,8 +1005,12 @@ bool spool_get_by_tag_full(const char *tag_id, SpoolInfo *info) {
                     field = cJSON_GetObjectItem(spool, "tag_type");
                     if (field && field->valuestring) strncpy(info->tag_type, field->valuestring, sizeof(info->tag_type) - 1);
 
+                    field = cJSON_GetObjectItem(spool, "core_weight");
+                    info->core_weight = (field && cJSON_IsNumber(field) && field->valueint > 0) ? field->valueint : 250;
+
                     info->valid = true;
                     found = true;
+                    g_active_spool = *info;
                     break;
                 }
             }
@@ -1452,6 +1481,56 @@ bool spool_sync_weight(const char *spool_id, int weight) {
     return success;
 }
 
+// Net weight estimate (firmware does this in spool_weight.rs; PLA density assumed here)
+bool filament_get_estimate(FilamentEstimateC *out) {
+    if (!out) return false;
+    memset(out, 0, sizeof(FilamentEstimateC));
+    out->gross_weight = g_scale_weight;
+    if (!g_active_spool.valid) return false;
+
+    out->core_weight = g_active_spool.core_weight;
+    out->net_weight = g_scale_weight - g_active_spool.core_weight;
+    if (out->net_weight < 0) out->net_weight = 0;
+    // 1.75mm PLA: 1.24 g/cm3 * pi * 0.0875^2 cm2 * 100 cm = ~2.98 g/m
+    out->remaining_length_m = out->net_weight / 2.98f;
+    if (g_active_spool.label_weight > 0) {
+        out->fill_pct = (int32_t)(out->net_weight * 100 / g_active_spool.label_weight);
+        if (out->fill_pct > 100) out->fill_pct = 100;
+    }
+    out->valid = true;
+    return true;
+}
+
+// Auto weight sync stubs (firmware-only workflow)
+bool weight_sync_get_event(WeightSyncEventC *out) {
+    if (out) memset(out, 0, sizeof(WeightSyncEventC));
+    return false;
+}
+
+int weight_sync_undo(uint32_t seq) {
+    (void)seq;
+    return -1;
+}
+
+void weight_sync_dismiss(uint32_t seq) {
+    (void)seq;
+}
+
+// Weight history stub (firmware-only)
+bool weight_history_get_estimate(ConsumptionEstimateC *out) {
+    if (out) memset(out, 0, sizeof(ConsumptionEstimateC));
+    return false;
+}
+
+bool weight_history_get_runout_alert(RunoutAlertC *out) {
+    if (out) memset(out, 0, sizeof(RunoutAlertC));
+    return false;
+}
+
+void weight_history_dismiss_runout(uint32_t seq) {
+    (void)seq;
+}
+
 // =============================================================================
 // AMS Slot Assignment functions
 // =============================================================================
@@ -2526,6 +2605,11 @@ int wifi_get_ssid(char *buf, int buf_len) {
     return strlen(buf);
 }
 
+// Simulated networks are never enterprise
+bool wifi_is_unverified(void) {
+    return false;
+}
+
 int wifi_connect(const char *ssid, const char *password) {
     (void)password;
     printf("[sim] WiFi connect: %s\n", ssid);
@@ -2565,6 +2649,59 @@ int wifi_scan(WifiScanResult *results, int max_results) {
     return 2;
 }
 
+// Station IP settings (matching ui_internal.h) - kept in memory only
+typedef struct {
+    bool use_static;
+    uint8_t ip[4];
+    uint8_t netmask[4];
+    uint8_t gateway[4];
+    uint8_t dns_primary[4];
+    uint8_t dns_secondary[4];
+} WifiIpConfig;
+
+static WifiIpConfig g_wifi_ip_config = {0};
+
+void wifi_get_ip_config(WifiIpConfig *out) {
+    if (out) *out = g_wifi_ip_config;
+}
+
+int wifi_set_ip_config(const WifiIpConfig *config) {
+    if (!config) return -1;
+    if (config->use_static && config->ip[0] == 0) return -1;
+    g_wifi_ip_config = *config;
+    printf("[sim] IP settings: %s\n", config->use_static ? "static" : "DHCP");
+    return 0;
+}
+
+// Setup portal - only tracks whether it is "running"
+static bool g_config_portal_active = false;
+
+int config_portal_start(void) {
+    printf("[sim] Setup portal started\n");
+    g_config_portal_active = true;
+    return 0;
+}
+
+void config_portal_stop(void) {
+    printf("[sim] Setup portal stopped\n");
+    g_config_portal_active = false;
+}
+
+bool config_portal_is_active(void) {
+    return g_config_portal_active;
+}
+
+int config_portal_get_ssid(char *buf, int buf_len) {
+    if (!buf || buf_len <= 0) return -1;
+    if (!g_config_portal_active) {
+        buf[0] = '\0';
+        return -1;
+    }
+    strncpy(buf, "SpoolBuddy-SIM0", buf_len - 1);
+    buf[buf_len - 1] = '\0';
+    return strlen(buf);
+}
+
 // =============================================================================
 // Printer Management API
 // =============================================================================
@@ -190,6 +190,9 @@ const uint8_t* backend_get_cover_data(uint32_t *size_out);
 // Time functions (simulator provides system time)
 int time_get_hhmm(void);
 int time_is_synced(void);
+int time_format_clock(char *buf, int buf_len);
+int time_is_24h(void);
+int time_set_24h(int enabled);
 
 // =============================================================================
 // Functions implemented in ui.c (simulator's own implementation)
@@ -214,6 +217,7 @@ typedef struct {
     int weight_current;
     char slicer_filament[32];
     char tag_type[32];
+    int core_weight;        // Empty spool weight (grams)
     bool valid;
 } SpoolInfo;
 
@@ -317,6 +321,68 @@ bool spool_link_tag(const char *spool_id, const char *tag_id, const char *tag_ty
 // Returns true on success, false on failure
 bool spool_sync_weight(const char *spool_id, int weight);
 
+// Net filament estimate (matches firmware FilamentEstimateC)
+typedef struct {
+    float gross_weight;
+    int32_t core_weight;
+    float net_weight;
+    float remaining_length_m;
+    int32_t fill_pct;
+    bool valid;
+} FilamentEstimateC;
+
+// Net weight/length for the spool last looked up by spool_get_by_tag_full
+bool filament_get_estimate(FilamentEstimateC *out);
+
+// Auto weight sync undo progress (matches firmware WeightSyncUndo)
+typedef enum {
+    WEIGHT_SYNC_UNDO_NONE = 0,
+    WEIGHT_SYNC_UNDO_PENDING = 1,
+    WEIGHT_SYNC_UNDO_DONE = 2,
+    WEIGHT_SYNC_UNDO_FAILED = 3,
+} WeightSyncUndo;
+
+// Auto weight sync event (matches firmware WeightSyncEventC)
+typedef struct {
+    uint32_t seq;
+    char spool_id[64];
+    int32_t previous_weight;
+    int32_t synced_weight;
+    bool had_weight;
+    uint8_t undo_state;
+} WeightSyncEventC;
+
+// Auto weight sync runs in firmware only - simulator never reports an event
+bool weight_sync_get_event(WeightSyncEventC *out);
+int weight_sync_undo(uint32_t seq);
+void weight_sync_dismiss(uint32_t seq);
+
+// Consumption estimate (matches firmware ConsumptionEstimateC)
+typedef struct {
+    float rate_g_per_hour;
+    float net_weight;
+    int32_t time_to_empty_min;
+    int32_t print_remaining_min;
+    int32_t sample_count;
+    bool runout_predicted;
+    bool valid;
+} ConsumptionEstimateC;
+
+// Weight history lives in firmware only - simulator never has an estimate
+bool weight_history_get_estimate(ConsumptionEstimateC *out);
+
+// Runout alert (matches firmware RunoutAlertC)
+typedef struct {
+    uint32_t seq;
+    char printer_name[32];
+    char slot[16];
+    int32_t time_to_empty_min;
+    int32_t print_remaining_min;
+} RunoutAlertC;
+
+bool weight_history_get_runout_alert(RunoutAlertC *out);
+void weight_history_dismiss_runout(uint32_t seq);
+
 // =============================================================================
 // OTA functions (mocked in simulator - implemented in sim_mocks.c)
 // =============================================================================
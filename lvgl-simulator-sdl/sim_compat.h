@@ -61,9 +61,16 @@ void wifi_get_status(WifiStatus *status);
 int wifi_disconnect(void);
 int wifi_is_connected(void);
 int wifi_get_ssid(char *buf, int buf_len);
+bool wifi_is_unverified(void);
 int wifi_scan(WifiScanResult *results, int max_results);
 int8_t wifi_get_rssi(void);
 
+// Setup portal
+int config_portal_start(void);
+void config_portal_stop(void);
+bool config_portal_is_active(void);
+int config_portal_get_ssid(char *buf, int buf_len);
+
 // =============================================================================
 // Printer Discovery Mock
 // =============================================================================
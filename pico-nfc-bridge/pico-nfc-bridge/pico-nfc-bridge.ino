@@ -5,10 +5,14 @@
  * Supports:
  * - MIFARE Classic 1K (Bambu Lab tags) with HKDF key derivation
  * - NTAG (SpoolEase/OpenPrintTag with NDEF)
+ * - Firmware updates pushed by the ESP32 over I2C (needs a flash layout with
+ *   a filesystem, e.g. "2MB (Sketch: 1MB, FS: 1MB)"; applied on reboot)
  */
 
 #include <SPI.h>
 #include <Wire.h>
+#include <LittleFS.h>
+#include <Updater.h>
 #include <SHA256.h>  // From Crypto library by Rhys Weatherley
 
 #define PN5180_NSS   17
@@ -19,11 +23,32 @@
 #define I2C_SCL      5
 #define I2C_ADDR     0x55
 
+// Bridge firmware version (reported by CMD_GET_FW_VERSION)
+#define FW_VERSION_MAJOR 2
+#define FW_VERSION_MINOR 1
+#define FW_VERSION_PATCH 0
+
 // I2C Commands
 #define CMD_GET_STATUS          0x00
 #define CMD_GET_PRODUCT_VERSION 0x01
 #define CMD_SCAN_TAG            0x10
+#define CMD_GET_FW_VERSION      0x02
 #define CMD_READ_TAG_DATA       0x20  // New: Read tag blocks/pages
+#define CMD_UPDATE_BEGIN        0x30  // [seq, size(4), sha256(32)]
+#define CMD_UPDATE_WRITE        0x31  // [seq, offset(4), data...]
+#define CMD_UPDATE_END          0x32  // [seq] - verify, stage and reboot
+#define CMD_UPDATE_ABORT        0x33  // [seq]
+
+// Update status codes
+#define UPDATE_OK               0
+#define UPDATE_ERR_STATE        1     // No update in progress
+#define UPDATE_ERR_OFFSET       2     // Chunk not at the expected offset
+#define UPDATE_ERR_FLASH        3     // Updater/filesystem error
+#define UPDATE_ERR_HASH         4     // SHA-256 mismatch
+#define UPDATE_ERR_SIZE         5     // Bad size or incomplete image
+
+// Max data bytes per CMD_UPDATE_WRITE
+#define UPDATE_CHUNK_SIZE       128
 
 // Tag types (from SAK byte)
 #define TAG_TYPE_UNKNOWN        0
@@ -34,7 +59,7 @@
 // Response buffer - needs to be larger for tag data
 volatile uint8_t respBuffer[200];
 volatile uint8_t respLength = 0;
-volatile uint8_t cmdBuffer[64];
+volatile uint8_t cmdBuffer[6 + UPDATE_CHUNK_SIZE];
 volatile uint8_t cmdLength = 0;
 volatile bool cmdReady = false;
 
@@ -98,6 +123,18 @@ void logSeqStart(const char* msg) {
 // Scan protection - after CMD_SCAN finds a tag, skip background scans briefly
 uint32_t scanProtectionUntil = 0;
 
+// Firmware update state (background scans pause while an update is open)
+bool updateActive = false;
+uint32_t updateSize = 0;
+uint32_t updateWritten = 0;
+uint8_t updateExpectedHash[32];
+SHA256 updateHash;
+uint8_t updateTail[UPDATE_CHUNK_SIZE];  // Last chunk, held back until the hash matched
+uint8_t updateTailLen = 0;
+uint32_t updateLastActivity = 0;
+const uint32_t UPDATE_TIMEOUT_MS = 30000;  // Abandoned updates are discarded
+uint32_t rebootAt = 0;            // Reboot into the staged image (0 = none)
+
 // ============================================================================
 // HKDF Key Derivation for Bambu Lab tags
 // ============================================================================
@@ -1128,6 +1165,108 @@ bool scanTag() {
     return false;
 }
 
+// ============================================================================
+// Firmware Update
+// ============================================================================
+
+uint32_t readU32(const volatile uint8_t* p) {
+    return (uint32_t)p[0] | ((uint32_t)p[1] << 8) | ((uint32_t)p[2] << 16) | ((uint32_t)p[3] << 24);
+}
+
+void writeU32(volatile uint8_t* p, uint32_t value) {
+    p[0] = value & 0xFF;
+    p[1] = (value >> 8) & 0xFF;
+    p[2] = (value >> 16) & 0xFF;
+    p[3] = (value >> 24) & 0xFF;
+}
+
+void abortUpdate() {
+    if (updateActive) {
+        Update.end(false);  // Discards the image (the tail is never written)
+        updateActive = false;
+        logSeq("Update aborted");
+    }
+}
+
+uint8_t updateBegin(uint32_t size, const volatile uint8_t* hash) {
+    abortUpdate();
+    if (size == 0) return UPDATE_ERR_SIZE;
+
+    if (!LittleFS.begin() || !Update.begin(size)) {
+        logSeq("Update begin failed");
+        return UPDATE_ERR_FLASH;
+    }
+
+    for (int i = 0; i < 32; i++) updateExpectedHash[i] = hash[i];
+    updateHash.reset();
+    updateSize = size;
+    updateWritten = 0;
+    updateTailLen = 0;
+    updateActive = true;
+    updateLastActivity = millis();
+    logSeqStart("Update started: ");
+    Serial.print(size);
+    Serial.println(" bytes");
+    return UPDATE_OK;
+}
+
+// Writes must be sequential; a repeated chunk (lost response) is acknowledged
+// without writing it again
+uint8_t updateWrite(uint32_t offset, const volatile uint8_t* data, uint8_t len) {
+    if (!updateActive) return UPDATE_ERR_STATE;
+    updateLastActivity = millis();
+    if (offset + len == updateWritten) return UPDATE_OK;
+    if (offset != updateWritten) return UPDATE_ERR_OFFSET;
+    if (updateWritten + len > updateSize) return UPDATE_ERR_SIZE;
+
+    uint8_t chunk[UPDATE_CHUNK_SIZE];
+    for (int i = 0; i < len; i++) chunk[i] = data[i];
+    if (updateWritten + len == updateSize) {
+        // Keeps the image incomplete, so a hash mismatch can still discard it
+        memcpy(updateTail, chunk, len);
+        updateTailLen = len;
+    } else if (Update.write(chunk, len) != len) {
+        logSeq("Update write failed");
+        abortUpdate();
+        return UPDATE_ERR_FLASH;
+    }
+    updateHash.update(chunk, len);
+    updateWritten += len;
+    return UPDATE_OK;
+}
+
+// Check the SHA-256 and stage the image; the bootloader copies it on reboot
+uint8_t updateEnd() {
+    if (!updateActive) return UPDATE_ERR_STATE;
+    if (updateWritten != updateSize) {
+        abortUpdate();
+        return UPDATE_ERR_SIZE;
+    }
+
+    uint8_t hash[32];
+    updateHash.finalize(hash, 32);
+    if (memcmp(hash, updateExpectedHash, 32) != 0) {
+        logSeq("Update SHA-256 mismatch");
+        abortUpdate();
+        return UPDATE_ERR_HASH;
+    }
+
+    if (Update.write(updateTail, updateTailLen) != updateTailLen) {
+        logSeq("Update write failed");
+        abortUpdate();
+        return UPDATE_ERR_FLASH;
+    }
+    updateActive = false;
+    if (!Update.end()) {
+        logSeq("Update end failed");
+        return UPDATE_ERR_FLASH;
+    }
+
+    logSeq("Update verified, rebooting");
+    rebootAt = millis() + 500;  // Let the ESP32 read the response first
+    return UPDATE_OK;
+}
+
 // ============================================================================
 // I2C Command Processing
 // ============================================================================
@@ -1160,8 +1299,47 @@ void processCommand() {
             respLength = 3;
             break;
 
+        case CMD_GET_FW_VERSION:
+            respBuffer[0] = 0;
+            respBuffer[1] = FW_VERSION_MAJOR;
+            respBuffer[2] = FW_VERSION_MINOR;
+            respBuffer[3] = FW_VERSION_PATCH;
+            respLength = 4;
+            break;
+
+        case CMD_UPDATE_BEGIN:
+            respBuffer[0] = (cmdLength >= 38) ? updateBegin(readU32(&cmdBuffer[2]), &cmdBuffer[6])
+                                               : UPDATE_ERR_SIZE;
+            respLength = 1;
+            break;
+
+        case CMD_UPDATE_WRITE:
+            if (cmdLength < 6) {
+                respBuffer[0] = UPDATE_ERR_SIZE;
+            } else {
+                respBuffer[0] = updateWrite(readU32(&cmdBuffer[2]), &cmdBuffer[6], cmdLength - 6);
+            }
+            // [status, bytes written so far] lets the ESP32 resync after errors
+            writeU32(&respBuffer[1], updateWritten);
+            respLength = 5;
+            break;
+
+        case CMD_UPDATE_END:
+            respBuffer[0] = updateEnd();
+            respLength = 1;
+            break;
+
+        case CMD_UPDATE_ABORT:
+            abortUpdate();
+            respBuffer[0] = UPDATE_OK;
+            respLength = 1;
+            break;
+
         case CMD_SCAN_TAG:
-            if (scanTag()) {
+            if (updateActive) {
+                respBuffer[0] = 1;  // No scanning while updating
+                respLength = 1;
+            } else if (scanTag()) {
                 respBuffer[0] = 0;  // Success
                 respBuffer[1] = tagUidLen;
                 memcpy((void*)&respBuffer[2], tagUid, tagUidLen);
@@ -1252,14 +1430,15 @@ void i2cReceive(int n) {
     Serial.print(n);
     Serial.print(" bytes: ");
     cmdLength = 0;
-    while (Wire.available() && cmdLength < 64) {
+    while (Wire.available() && cmdLength < sizeof(cmdBuffer)) {
         cmdBuffer[cmdLength++] = Wire.read();
     }
-    for (int i = 0; i < cmdLength; i++) {
+    // Only the header of update chunks
+    for (int i = 0; i < cmdLength && i < 8; i++) {
         Serial.print(cmdBuffer[i], HEX);
         Serial.print(" ");
     }
-    Serial.println();
+    Serial.println(cmdLength > 8 ? "..." : "");
     cmdReady = true;
 }
 
@@ -1284,7 +1463,13 @@ void setup() {
     pinMode(LED_BUILTIN, OUTPUT);
     Serial.begin(115200);
     delay(2000);
-    Serial.println("Pico NFC Bridge v2.0 starting...");
+    Serial.print("Pico NFC Bridge v");
+    Serial.print(FW_VERSION_MAJOR);
+    Serial.print(".");
+    Serial.print(FW_VERSION_MINOR);
+    Serial.print(".");
+    Serial.print(FW_VERSION_PATCH);
+    Serial.println(" starting...");
     Serial.println("Features: MIFARE Classic + NTAG + Bambu HKDF");
 
     pinMode(PN5180_NSS, OUTPUT);
@@ -1358,6 +1543,9 @@ void loop() {
         if (processingCommand) {
 
             Serial.println("(skip scan - cmd)");
+        } else if (updateActive) {
+
+            Serial.println("(skip scan - update)");
         } else if (millis() < scanProtectionUntil) {
 
             Serial.println("(skip scan - protected)");
@@ -1391,4 +1579,16 @@ void loop() {
         processCommand();
         cmdReady = false;
     }
+
+    // The ESP32 rebooted or gave up mid-update
+    if (updateActive && millis() - updateLastActivity > UPDATE_TIMEOUT_MS) {
+        Serial.println("Update timed out");
+        abortUpdate();
+    }
+
+    if (rebootAt != 0 && (int32_t)(millis() - rebootAt) >= 0) {
+        Serial.println("Rebooting into new firmware...");
+        Serial.flush();
+        rp2040.reboot();
+    }
 }
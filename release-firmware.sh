@@ -68,6 +68,12 @@ set_version() {
     echo_info "Updated Cargo.toml version to $cargo_version"
 }
 
+# Size of the ota_0 app slot in partitions.csv (bytes)
+app_slot_size() {
+    local size=$(awk -F, '$1 ~ /^ota_0/ { gsub(/ /, "", $5); print $5 }' "$FIRMWARE_DIR/partitions.csv")
+    echo $((size))
+}
+
 # Create and push git tag
 push_release() {
     local version="$1"
@@ -178,6 +184,16 @@ main() {
         exit 1
     fi
 
+    # The image must fit an OTA app slot
+    SLOT_SIZE=$(app_slot_size)
+    BYTES=$(wc -c < "$OUTPUT_PATH" | tr -d ' ')
+    if [ "$BYTES" -gt "$SLOT_SIZE" ]; then
+        echo_error "Image is $BYTES bytes, larger than the $SLOT_SIZE byte OTA app slot"
+        rm -f "$OUTPUT_PATH"
+        exit 1
+    fi
+    echo_info "Image uses $((BYTES * 100 / SLOT_SIZE))% of the OTA app slot"
+
     # Delete old versions
     for old_file in "$RELEASES_DIR"/spoolbuddy-*.bin; do
         if [ -f "$old_file" ] && [ "$old_file" != "$OUTPUT_PATH" ]; then